@@ -0,0 +1,67 @@
+"""Collect the signed updater bundles of a desktop build and write latest.json.
+
+Usage: updater_manifest.py <artifacts dir> <output dir> <release tag>
+
+Each build artifact (ragkit-desktop-<name>) holds the updater bundle of one platform
+and its .sig file. The bundles are copied to the output directory under names that
+don't collide between platforms, next to the latest.json manifest that the in-app
+updater reads from the release.
+"""
+
+import json
+import shutil
+import sys
+from datetime import datetime, timezone
+from pathlib import Path
+
+REPOSITORY = "henribesnard/ragkit"
+
+# Build artifact name -> (updater platform, updater bundle suffix)
+PLATFORMS = {
+    "linux-x64": ("linux-x86_64", ".AppImage"),
+    "windows-x64": ("windows-x86_64", "-setup.exe"),
+    "macos-x64": ("darwin-x86_64", ".app.tar.gz"),
+    "macos-arm64": ("darwin-aarch64", ".app.tar.gz"),
+}
+
+
+def find_bundle(directory: Path, suffix: str) -> Path | None:
+    for path in sorted(directory.rglob(f"*{suffix}")):
+        if path.with_name(path.name + ".sig").exists():
+            return path
+    return None
+
+
+def main() -> None:
+    artifacts, output, tag = Path(sys.argv[1]), Path(sys.argv[2]), sys.argv[3]
+    config = json.loads(Path("desktop/src-tauri/tauri.conf.json").read_text())
+    output.mkdir(parents=True, exist_ok=True)
+
+    platforms = {}
+    for name, (platform, suffix) in PLATFORMS.items():
+        bundle = find_bundle(artifacts / f"ragkit-desktop-{name}", suffix)
+        if bundle is None:
+            print(f"No signed updater bundle for {name}, skipping")
+            continue
+        asset = f"ragkit-desktop-{name}{suffix}"
+        shutil.copyfile(bundle, output / asset)
+        platforms[platform] = {
+            "signature": bundle.with_name(bundle.name + ".sig").read_text().strip(),
+            "url": f"https://github.com/{REPOSITORY}/releases/download/{tag}/{asset}",
+        }
+
+    if not platforms:
+        sys.exit("No signed updater bundles found; is TAURI_SIGNING_PRIVATE_KEY set?")
+
+    manifest = {
+        "version": config["version"],
+        "notes": f"RAGKIT Desktop {config['version']}",
+        "pub_date": datetime.now(timezone.utc).strftime("%Y-%m-%dT%H:%M:%SZ"),
+        "platforms": platforms,
+    }
+    (output / "latest.json").write_text(json.dumps(manifest, indent=2) + "\n")
+    print(f"Wrote latest.json for {', '.join(sorted(platforms))}")
+
+
+if __name__ == "__main__":
+    main()
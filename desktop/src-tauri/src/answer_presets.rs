@@ -0,0 +1,172 @@
+//! Answer length and style presets.
+//!
+//! A preset is a named set of style instructions (e.g. "answer in bullet points") sent
+//! with a query, so users don't have to repeat them in every question. Built-in presets
+//! cover the common cases; user presets are stored in answer_presets.json. A query uses
+//! its own preset, or the default preset from the preferences.
+
+use crate::commands::QueryParams;
+use crate::{preferences, store};
+use serde::{Deserialize, Serialize};
+
+const STATE_FILE: &str = "answer_presets.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnswerPreset {
+    pub id: String,
+    pub name: String,
+    /// Style instructions sent to the LLM with the question
+    pub instructions: String,
+    /// Upper bound on the answer length, added to the instructions
+    pub max_words: Option<u32>,
+    #[serde(default)]
+    pub builtin: bool,
+}
+
+fn builtin(id: &str, name: &str, instructions: &str, max_words: Option<u32>) -> AnswerPreset {
+    AnswerPreset {
+        id: id.to_string(),
+        name: name.to_string(),
+        instructions: instructions.to_string(),
+        max_words,
+        builtin: true,
+    }
+}
+
+fn builtin_presets() -> Vec<AnswerPreset> {
+    vec![
+        builtin(
+            "concise",
+            "Concise",
+            "Answer directly in a few sentences, without preamble.",
+            Some(80),
+        ),
+        builtin(
+            "detailed",
+            "Detailed",
+            "Give a thorough answer that explains the reasoning and covers every relevant \
+             point from the sources.",
+            None,
+        ),
+        builtin(
+            "bullet_points",
+            "Bullet points",
+            "Answer as a bulleted list of short, self-contained points.",
+            None,
+        ),
+        builtin(
+            "executive_summary",
+            "Executive summary",
+            "Write an executive summary: start with a one-sentence conclusion, then the key \
+             facts and their implications, and end with recommended next steps if any.",
+            Some(200),
+        ),
+    ]
+}
+
+fn all_presets() -> Vec<AnswerPreset> {
+    let mut presets = builtin_presets();
+    presets.extend(store::load::<Vec<AnswerPreset>>(STATE_FILE));
+    presets
+}
+
+impl AnswerPreset {
+    fn full_instructions(&self) -> String {
+        match self.max_words {
+            Some(max) => format!("{} Use at most {} words.", self.instructions.trim(), max),
+            None => self.instructions.trim().to_string(),
+        }
+    }
+}
+
+/// Turn the query's preset (or the default preset) into answer instructions, unless the
+/// caller already gave explicit instructions.
+pub fn apply(params: &mut QueryParams) {
+    if params.answer_instructions.is_some() {
+        return;
+    }
+    let Some(id) = params
+        .preset
+        .clone()
+        .or_else(|| preferences::load().default_answer_preset)
+    else {
+        return;
+    };
+    match all_presets().into_iter().find(|p| p.id == id) {
+        Some(preset) => params.answer_instructions = Some(preset.full_instructions()),
+        None => tracing::warn!("Unknown answer preset: {}", id),
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// List the built-in and user answer presets
+#[tauri::command]
+pub async fn list_answer_presets() -> Result<Vec<AnswerPreset>, String> {
+    Ok(all_presets())
+}
+
+/// Create an answer preset, or update it when `id` is given
+#[tauri::command]
+pub async fn save_answer_preset(
+    id: Option<String>,
+    name: String,
+    instructions: String,
+    max_words: Option<u32>,
+) -> Result<AnswerPreset, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Preset name cannot be empty".into());
+    }
+    if instructions.trim().is_empty() {
+        return Err("Preset instructions cannot be empty".into());
+    }
+    if let Some(id) = &id {
+        if builtin_presets().iter().any(|p| &p.id == id) {
+            return Err(format!("Built-in preset {} cannot be modified", id));
+        }
+    }
+
+    let mut presets: Vec<AnswerPreset> = store::load(STATE_FILE);
+    let saved = match id {
+        Some(id) => {
+            let existing = presets
+                .iter_mut()
+                .find(|p| p.id == id)
+                .ok_or_else(|| format!("Preset not found: {}", id))?;
+            existing.name = name;
+            existing.instructions = instructions;
+            existing.max_words = max_words;
+            existing.clone()
+        }
+        None => {
+            let created = AnswerPreset {
+                id: uuid::Uuid::new_v4().to_string(),
+                name,
+                instructions,
+                max_words,
+                builtin: false,
+            };
+            presets.push(created.clone());
+            created
+        }
+    };
+
+    store::save(STATE_FILE, &presets).map_err(|e| e.to_string())?;
+    Ok(saved)
+}
+
+/// Delete a user answer preset
+#[tauri::command]
+pub async fn delete_answer_preset(id: String) -> Result<bool, String> {
+    let mut presets: Vec<AnswerPreset> = store::load(STATE_FILE);
+    let before = presets.len();
+    presets.retain(|p| p.id != id);
+    if presets.len() == before {
+        return Ok(false);
+    }
+    store::save(STATE_FILE, &presets).map_err(|e| e.to_string())?;
+    Ok(true)
+}
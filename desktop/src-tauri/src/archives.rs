@@ -0,0 +1,147 @@
+//! Unpacking of .zip and .tar(.gz) archives found during imports.
+//!
+//! With `extract_archives` set on an import, each archive is unpacked under
+//! `~/.ragkit/tmp/unpack/<id>/` and its supported members are ingested in its place.
+//! Entries escaping the target directory, links, and archives unpacking to more than
+//! `MAX_UNPACKED_BYTES` or `MAX_ENTRIES` entries are refused. Nested archives are not
+//! unpacked. The ingestion job removes the directory when it finishes; the janitor
+//! sweeps the ones left by a crash.
+
+use crate::files::{self, extension_of};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+/// Refuse archives unpacking to more than this, to guard against zip bombs.
+const MAX_UNPACKED_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+const MAX_ENTRIES: usize = 10_000;
+
+/// Result of the archive members of an ingestion job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveResult {
+    pub path: String,
+    /// Supported files found in the archive
+    pub files: usize,
+    pub added: usize,
+    pub failed: usize,
+    pub duplicates: usize,
+    /// Why the archive could not be unpacked
+    pub error: Option<String>,
+    /// Directory the archive was unpacked to
+    #[serde(skip)]
+    pub unpacked_to: Option<PathBuf>,
+}
+
+/// Whether a file is an archive that can be unpacked.
+pub fn is_archive(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    matches!(extension_of(path).as_str(), "zip" | "tar" | "tgz") || name.ends_with(".tar.gz")
+}
+
+/// Path of an entry under `dir`, refusing absolute paths and `..` components.
+fn safe_join(dir: &Path, entry: &Path) -> Option<PathBuf> {
+    let mut path = dir.to_path_buf();
+    for component in entry.components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (path != dir).then_some(path)
+}
+
+/// Copy an entry to `target`, counting its bytes against the unpacked size limit.
+fn write_entry(reader: &mut impl Read, target: &Path, unpacked: &mut u64) -> Result<()> {
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let remaining = MAX_UNPACKED_BYTES.saturating_sub(*unpacked);
+    let mut file = std::fs::File::create(target)?;
+    // One byte over the limit is enough to know it was exceeded
+    let written = std::io::copy(&mut reader.take(remaining + 1), &mut file)?;
+    *unpacked += written;
+    if *unpacked > MAX_UNPACKED_BYTES {
+        return Err(anyhow!(
+            "Archive unpacks to more than {} MB",
+            MAX_UNPACKED_BYTES / (1024 * 1024)
+        ));
+    }
+    Ok(())
+}
+
+fn unpack_zip(path: &Path, dir: &Path) -> Result<()> {
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(path)?)
+        .map_err(|e| anyhow!("Not a valid zip archive: {}", e))?;
+    if archive.len() > MAX_ENTRIES {
+        return Err(anyhow!("Archive has more than {} entries", MAX_ENTRIES));
+    }
+    let mut unpacked = 0;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        if entry.is_dir() || entry.is_symlink() {
+            continue;
+        }
+        let Some(target) = entry.enclosed_name().and_then(|name| safe_join(dir, &name)) else {
+            tracing::warn!("Skipping unsafe archive entry {}", entry.name());
+            continue;
+        };
+        write_entry(&mut entry, &target, &mut unpacked)?;
+    }
+    Ok(())
+}
+
+fn unpack_tar(reader: impl Read, dir: &Path) -> Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    let mut unpacked = 0;
+    for (i, entry) in archive.entries()?.enumerate() {
+        if i >= MAX_ENTRIES {
+            return Err(anyhow!("Archive has more than {} entries", MAX_ENTRIES));
+        }
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry.path()?.into_owned();
+        let Some(target) = safe_join(dir, &name) else {
+            tracing::warn!("Skipping unsafe archive entry {}", name.display());
+            continue;
+        };
+        write_entry(&mut entry, &target, &mut unpacked)?;
+    }
+    Ok(())
+}
+
+/// Unpack an archive to a new directory under `~/.ragkit/tmp/unpack/` and list its
+/// supported files. The caller removes the directory when done.
+pub fn unpack(path: &Path) -> Result<(PathBuf, Vec<PathBuf>)> {
+    let dir = crate::store::tmp_dir()
+        .join("unpack")
+        .join(uuid::Uuid::new_v4().to_string());
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| anyhow!("Failed to create {}: {}", dir.display(), e))?;
+
+    let result = match extension_of(path).as_str() {
+        "zip" => unpack_zip(path, &dir),
+        "tar" => std::fs::File::open(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| unpack_tar(file, &dir)),
+        _ => std::fs::File::open(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| unpack_tar(flate2::read::GzDecoder::new(file), &dir)),
+    };
+    if let Err(e) = result {
+        let _ = std::fs::remove_dir_all(&dir);
+        return Err(anyhow!("Failed to unpack {}: {}", path.display(), e));
+    }
+
+    let members = files::collect_files(&dir, true, &[])
+        .into_iter()
+        .filter(|member| files::is_supported(member))
+        .collect();
+    Ok((dir, members))
+}
@@ -2,94 +2,574 @@
 //!
 //! In production: launches the bundled ragkit-backend sidecar (PyInstaller executable).
 //! In development: launches `python -m ragkit.desktop.main` directly.
+//! In remote mode: connects to an existing RAGKIT server instead of launching anything.
+//!
+//! A launched backend that exits unexpectedly is restarted with exponential backoff,
+//! whatever the launch mode.
 
+use crate::backend_environments::BackendLaunch;
+use crate::capabilities::Feature;
+use crate::startup::{self, StartupPhase};
+use crate::store;
 use anyhow::{anyhow, Result};
-use std::sync::atomic::{AtomicU16, Ordering};
-use std::time::Duration;
-use tauri::AppHandle;
-use tokio::sync::Mutex;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU32, AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::{oneshot, Mutex};
 use tokio::time::sleep;
 
 // Global state for backend process
 static BACKEND_PORT: AtomicU16 = AtomicU16::new(0);
 
+/// Set while the app is deliberately stopping the backend, so the supervisor
+/// doesn't mistake the shutdown for a crash.
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+static RESTART_ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+/// Set once the app has started its exit sequence.
+static EXITING: AtomicBool = AtomicBool::new(false);
+/// Set when the sidecar process has terminated.
+static SIDECAR_EXITED: AtomicBool = AtomicBool::new(false);
+/// Incremented for each backend launch, so the supervisor of a replaced process
+/// stands down.
+static BACKEND_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+const MAX_RESTART_ATTEMPTS: u32 = 5;
+const RESTART_BASE_DELAY: Duration = Duration::from_secs(1);
+const RESTART_MAX_DELAY: Duration = Duration::from_secs(30);
+/// A backend that ran at least this long before crashing gets a fresh retry budget.
+const STABLE_UPTIME: Duration = Duration::from_secs(60);
+/// How often a backend launched as a plain process is checked for exit.
+const PROCESS_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Longest the app waits for the backend to stop before exiting anyway.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(90);
+/// Longest in-progress work may take to reach a checkpoint before the backend stops.
+const CHECKPOINT_TIMEOUT: Duration = Duration::from_secs(60);
+/// Time the backend gets to exit after `/shutdown` before it is killed.
+const BACKEND_EXIT_GRACE: Duration = Duration::from_secs(10);
+/// Health checks slower than this report the backend as degraded.
+const DEGRADED_LATENCY: Duration = Duration::from_secs(2);
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+/// Consecutive failed health checks before the backend is reported as down.
+const DOWN_AFTER_FAILURES: u32 = 3;
+/// Longest the app waits for a remote backend to answer when connecting.
+const REMOTE_CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+const REMOTE_FILE: &str = "remote_backend.json";
+
+/// Payload of the `backend-restarted` event.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BackendRestartEvent {
+    pub attempt: u32,
+    pub exit_code: Option<i32>,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
 /// Holds either a sidecar child or a tokio process child.
 enum BackendChild {
     Sidecar(tauri_plugin_shell::process::CommandChild),
     Process(tokio::process::Child),
 }
 
+impl BackendChild {
+    fn pid(&self) -> Option<u32> {
+        match self {
+            BackendChild::Sidecar(c) => Some(c.pid()),
+            BackendChild::Process(c) => c.id(),
+        }
+    }
+
+    async fn kill(self) {
+        match self {
+            BackendChild::Sidecar(c) => {
+                let _ = c.kill();
+            }
+            BackendChild::Process(mut c) => {
+                let _ = c.kill().await;
+            }
+        }
+    }
+}
+
 static BACKEND_CHILD: Mutex<Option<BackendChild>> = Mutex::const_new(None);
 
+/// Last status reported by the health watchdog.
+static LAST_STATUS: std::sync::Mutex<Option<BackendStatusEvent>> = std::sync::Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+    Healthy,
+    Degraded,
+    Down,
+}
+
+/// Payload of the `backend-status` event.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BackendStatusEvent {
+    pub state: HealthState,
+    pub latency_ms: Option<u64>,
+    /// Resident memory of the backend process, when it can be measured
+    pub memory_bytes: Option<u64>,
+    pub error: Option<String>,
+    pub checked_at: String,
+}
+
+/// HTTP client shared by the shell's outbound requests (providers, connectors, cloud
+/// drives, feeds) so connections are pooled and kept alive. It only trusts the default
+/// roots and the CA bundle of the preferences.
+static HTTP_CLIENT: RwLock<Option<reqwest::Client>> = RwLock::new(None);
+/// HTTP client of backend calls, which also trusts the certificates of the remote
+/// backend. Rebuilt when the remote backend (and so its TLS settings) changes.
+static BACKEND_CLIENT: RwLock<Option<reqwest::Client>> = RwLock::new(None);
+
+/// A RAGKIT server the app uses instead of starting its own backend.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RemoteBackend {
+    pub url: String,
+    /// PEM certificate to trust, for servers using a private certificate authority
+    pub ca_cert_path: Option<String>,
+    /// Accept any server certificate (self-signed test servers only)
+    #[serde(default)]
+    pub accept_invalid_certs: bool,
+}
+
+/// Remote backend the app is configured to use, if any.
+static REMOTE: std::sync::Mutex<Option<RemoteBackend>> = std::sync::Mutex::new(None);
+/// Set while connected to the remote backend.
+static REMOTE_CONNECTED: AtomicBool = AtomicBool::new(false);
+
+/// Cancellation handles of in-flight requests, keyed by caller-provided request id, with
+/// the token of the call that registered them.
+static IN_FLIGHT: std::sync::Mutex<BTreeMap<String, (u64, oneshot::Sender<()>)>> =
+    std::sync::Mutex::new(BTreeMap::new());
+static IN_FLIGHT_TOKENS: AtomicU64 = AtomicU64::new(0);
+
+/// Build an HTTP client from the preferences, with the TLS settings of a remote backend.
+fn build_client(remote: Option<&RemoteBackend>) -> Result<reqwest::Client> {
+    let prefs = crate::preferences::load();
+    let mut builder = reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(prefs.connect_timeout_secs))
+        .pool_idle_timeout(Duration::from_secs(90))
+        .pool_max_idle_per_host(16)
+        .tcp_keepalive(Duration::from_secs(60));
+    builder = crate::proxy::configure(builder, &prefs)?;
+
+    if let Some(remote) = remote {
+        if let Some(path) = &remote.ca_cert_path {
+            let pem = std::fs::read(path)
+                .map_err(|e| anyhow!("Failed to read certificate {}: {}", path, e))?;
+            let certificate = reqwest::Certificate::from_pem(&pem)
+                .map_err(|e| anyhow!("Invalid certificate {}: {}", path, e))?;
+            builder = builder.add_root_certificate(certificate);
+        }
+        if remote.accept_invalid_certs {
+            tracing::warn!("Accepting invalid TLS certificates from {}", remote.url);
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+    }
+
+    builder
+        .build()
+        .map_err(|e| anyhow!("Failed to build HTTP client: {}", e))
+}
+
+/// Drop the HTTP clients so the next request builds them from the preferences.
+pub fn reset_http_client() {
+    *HTTP_CLIENT.write().unwrap() = None;
+    *BACKEND_CLIENT.write().unwrap() = None;
+}
+
+/// Get the shared HTTP client for requests that don't go to the backend, building it on
+/// first use from the preferences.
+pub fn http_client() -> reqwest::Client {
+    if let Some(client) = HTTP_CLIENT.read().unwrap().as_ref() {
+        return client.clone();
+    }
+    let client = build_client(None).unwrap_or_else(|e| {
+        tracing::error!("{}, using defaults", e);
+        reqwest::Client::new()
+    });
+    HTTP_CLIENT.write().unwrap().get_or_insert(client).clone()
+}
+
+/// Get the HTTP client for backend requests, building it on first use from the
+/// preferences and the remote backend.
+pub fn backend_client() -> reqwest::Client {
+    if let Some(client) = BACKEND_CLIENT.read().unwrap().as_ref() {
+        return client.clone();
+    }
+    let remote = REMOTE.lock().unwrap().clone();
+    let client = build_client(remote.as_ref()).unwrap_or_else(|e| {
+        tracing::error!("{}, using defaults", e);
+        reqwest::Client::new()
+    });
+    BACKEND_CLIENT.write().unwrap().get_or_insert(client).clone()
+}
+
+// ============================================================================
+// Authentication
+// ============================================================================
+
+const AUTH_FILE: &str = "backend_auth.json";
+const REFRESH_PATH: &str = "/api/auth/refresh";
+/// Tokens are refreshed this long before they expire, so requests don't race the expiry.
+const REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// Tokens attached to every backend request, loaded when the backend starts.
+static AUTH: RwLock<Option<AuthTokens>> = RwLock::new(None);
+/// Serializes token refreshes, so concurrent 401s only refresh once.
+static REFRESHING: Mutex<()> = Mutex::const_new(());
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AuthTokens {
+    pub access_token: String,
+    /// Used to get a new access token when it expires
+    pub refresh_token: Option<String>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Response of the backend's token refresh endpoint.
+#[derive(Debug, serde::Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+    /// Lifetime of the access token, in seconds
+    expires_in: Option<u64>,
+}
+
+impl AuthTokens {
+    fn new(access_token: String, refresh_token: Option<String>, expires_in: Option<u64>) -> Self {
+        AuthTokens {
+            access_token,
+            refresh_token,
+            expires_at: expires_in
+                .map(|secs| chrono::Utc::now() + chrono::Duration::seconds(secs as i64)),
+        }
+    }
+
+    fn expires_soon(&self) -> bool {
+        self.expires_at.is_some_and(|at| {
+            at - chrono::Duration::from_std(REFRESH_MARGIN).unwrap_or_default()
+                <= chrono::Utc::now()
+        })
+    }
+}
+
+/// The backend rejected a request as unauthenticated (HTTP 401) and the token could
+/// not be refreshed. The UI is told through a `backend-auth-required` event so it can
+/// prompt the user to log in again.
+#[derive(Debug)]
+pub struct AuthError(pub String);
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Authentication required: {}", self.0)
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+/// Payload of the `backend-auth-required` event.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AuthRequiredEvent {
+    pub message: String,
+    pub backend_url: String,
+}
+
+fn load_auth() {
+    *AUTH.write().unwrap() = store::load(AUTH_FILE);
+}
+
+fn save_auth(tokens: Option<AuthTokens>) -> Result<()> {
+    store::save(AUTH_FILE, &tokens)?;
+    *AUTH.write().unwrap() = tokens;
+    Ok(())
+}
+
+fn access_token() -> Option<String> {
+    AUTH.read()
+        .unwrap()
+        .as_ref()
+        .map(|tokens| tokens.access_token.clone())
+}
+
+/// Attach the backend access token to a request built outside `backend_request`.
+pub fn authorize(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    match access_token() {
+        Some(token) => request.bearer_auth(token),
+        None => request,
+    }
+}
+
+/// Exchange the refresh token for a new access token, unless another request already
+/// replaced `stale`. Returns whether a newer token is available.
+async fn refresh_tokens(stale: Option<&str>) -> bool {
+    let _guard = REFRESHING.lock().await;
+    let Some(tokens) = AUTH.read().unwrap().clone() else {
+        return false;
+    };
+    if stale.is_some_and(|stale| stale != tokens.access_token) {
+        return true;
+    }
+    let Some(refresh_token) = tokens.refresh_token else {
+        return false;
+    };
+
+    let response = backend_client()
+        .post(format!("{}{}", get_backend_url(), REFRESH_PATH))
+        .json(&serde_json::json!({ "refresh_token": refresh_token }))
+        .timeout(TimeoutTier::Fast.timeout())
+        .send()
+        .await;
+    let refreshed = match response {
+        Ok(response) if response.status().is_success() => response
+            .json::<TokenResponse>()
+            .await
+            .map_err(|e| format!("invalid response: {}", e)),
+        Ok(response) => Err(format!("status {}", response.status())),
+        Err(e) => Err(e.to_string()),
+    };
+    match refreshed {
+        Ok(response) => {
+            let tokens = AuthTokens::new(
+                response.access_token,
+                response.refresh_token.or(Some(refresh_token)),
+                response.expires_in,
+            );
+            if let Err(e) = save_auth(Some(tokens)) {
+                tracing::warn!("Failed to save the refreshed backend token: {}", e);
+            }
+            tracing::info!("Refreshed the backend access token");
+            true
+        }
+        Err(e) => {
+            tracing::warn!("Failed to refresh the backend access token: {}", e);
+            false
+        }
+    }
+}
+
+/// Refresh the access token ahead of its expiry.
+async fn refresh_if_expiring() {
+    let expiring = AUTH
+        .read()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|tokens| tokens.expires_soon() && tokens.refresh_token.is_some());
+    if expiring {
+        let stale = access_token();
+        refresh_tokens(stale.as_deref()).await;
+    }
+}
+
+fn auth_failed(message: String) -> anyhow::Error {
+    if let Some(app) = startup::app() {
+        let _ = app.emit(
+            "backend-auth-required",
+            AuthRequiredEvent {
+                message: message.clone(),
+                backend_url: get_backend_url(),
+            },
+        );
+    }
+    anyhow::Error::new(AuthError(message))
+}
+
+/// Whether the backend is available, as a local process or a connected remote server.
+pub fn is_running() -> bool {
+    BACKEND_PORT.load(Ordering::Relaxed) > 0 || REMOTE_CONNECTED.load(Ordering::Relaxed)
+}
+
+/// Whether the app uses a remote backend instead of its own.
+pub fn is_remote() -> bool {
+    REMOTE.lock().unwrap().is_some()
+}
+
 /// Get the backend API base URL.
 pub fn get_backend_url() -> String {
+    if let Some(remote) = REMOTE.lock().unwrap().as_ref() {
+        return remote.url.clone();
+    }
     let port = BACKEND_PORT.load(Ordering::Relaxed);
     format!("http://127.0.0.1:{}", port)
 }
 
-/// Start the Python backend process.
+/// Start the Python backend process, or connect to the configured remote backend.
+///
+/// A selected backend environment takes precedence over the default launch and the
+/// remote backend.
 pub async fn start_backend(app: &AppHandle) -> Result<()> {
+    SHUTTING_DOWN.store(false, Ordering::Relaxed);
+    load_auth();
+    let environment = crate::backend_environments::selected();
+    let launch = environment.as_ref().map(|e| &e.launch);
+    let remote = match launch {
+        Some(BackendLaunch::Remote(remote)) => Some(remote.clone()),
+        Some(_) => None,
+        None => store::load::<Option<RemoteBackend>>(REMOTE_FILE),
+    };
+    if let Some(remote) = remote {
+        return connect_remote(app, remote).await;
+    }
     let port = find_available_port().await?;
     BACKEND_PORT.store(port, Ordering::Relaxed);
 
-    tracing::info!("Starting backend on port {}", port);
+    let data_dir = environment.as_ref().and_then(|e| e.resolved_data_dir());
+    match &environment {
+        Some(environment) => tracing::info!(
+            "Starting backend environment {} on port {}",
+            environment.name,
+            port
+        ),
+        None => tracing::info!("Starting backend on port {}", port),
+    }
 
-    let child = if cfg!(debug_assertions) {
-        start_dev_backend(port).await?
-    } else {
-        start_sidecar_backend(app, port)?
+    let generation = BACKEND_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let child = match launch {
+        Some(BackendLaunch::PythonModule {
+            python,
+            module,
+            working_dir,
+        }) => {
+            let mut command = tokio::process::Command::new(python.as_deref().unwrap_or("python"));
+            command.args(["-m", module.as_str()]);
+            if let Some(dir) = working_dir {
+                command.current_dir(dir);
+            }
+            start_process_backend(command, port, data_dir.as_deref())?
+        }
+        Some(BackendLaunch::Sidecar { path: Some(path) }) => {
+            let command = tokio::process::Command::new(path);
+            start_process_backend(command, port, data_dir.as_deref())?
+        }
+        Some(_) => start_sidecar_backend(app, port, data_dir.as_deref(), generation)?,
+        None if cfg!(debug_assertions) => start_dev_backend(port).await?,
+        None => start_sidecar_backend(app, port, None, generation)?,
     };
+    startup::record(StartupPhase::BackendSpawned);
 
+    let is_process = matches!(child, BackendChild::Process(_));
     {
         let mut guard = BACKEND_CHILD.lock().await;
         *guard = Some(child);
     }
+    // The sidecar is supervised through its output events
+    if is_process {
+        supervise_process(app.clone(), generation);
+    }
 
-    wait_for_backend(port, Duration::from_secs(30)).await?;
+    wait_for_backend(&get_backend_url(), Duration::from_secs(30)).await?;
+    startup::record(StartupPhase::BackendReady);
     tracing::info!("Backend started successfully on port {}", port);
+    crate::capabilities::refresh().await;
+    crate::providers::sync().await;
+    crate::jobs::resume_checkpointed(app).await;
     Ok(())
 }
 
+/// Remote mode: use an existing RAGKIT server instead of launching a backend.
+async fn connect_remote(app: &AppHandle, remote: RemoteBackend) -> Result<()> {
+    tracing::info!("Connecting to remote backend at {}", remote.url);
+    let client = build_client(Some(&remote))?;
+    *REMOTE.lock().unwrap() = Some(remote);
+    *BACKEND_CLIENT.write().unwrap() = Some(client);
+
+    wait_for_backend(&get_backend_url(), REMOTE_CONNECT_TIMEOUT).await?;
+    REMOTE_CONNECTED.store(true, Ordering::Relaxed);
+    startup::record(StartupPhase::BackendReady);
+    tracing::info!("Connected to remote backend");
+    crate::capabilities::refresh().await;
+    crate::providers::sync().await;
+    crate::jobs::resume_checkpointed(app).await;
+    Ok(())
+}
+
+/// Data directory passed to the backend: the guest directory, then the environment's,
+/// then the one of config.toml.
+fn backend_data_dir(environment_dir: Option<&Path>) -> Result<Option<PathBuf>> {
+    if let Some(dir) = crate::guest::data_dir() {
+        return Ok(Some(dir.to_path_buf()));
+    }
+    let dir = environment_dir
+        .map(Path::to_path_buf)
+        .or_else(|| crate::config::get().data_dir.as_ref().map(|d| d.value.clone()));
+    if let Some(dir) = &dir {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| anyhow!("Failed to create data directory {}: {}", dir.display(), e))?;
+    }
+    Ok(dir)
+}
+
 /// Development mode: launch via system Python.
 async fn start_dev_backend(port: u16) -> Result<BackendChild> {
     tracing::info!("DEV MODE: launching python -m ragkit.desktop.main");
-    let child = tokio::process::Command::new("python")
-        .args(["-m", "ragkit.desktop.main", "--port", &port.to_string()])
-        .kill_on_drop(true)
+    let mut command = tokio::process::Command::new("python");
+    command.args(["-m", "ragkit.desktop.main"]);
+    start_process_backend(command, port, None)
+}
+
+/// Launch a backend as a plain child process.
+fn start_process_backend(
+    mut command: tokio::process::Command,
+    port: u16,
+    data_dir: Option<&Path>,
+) -> Result<BackendChild> {
+    command
+        .args(["--port", &port.to_string()])
+        .kill_on_drop(true);
+    if let Some(dir) = backend_data_dir(data_dir)? {
+        command.env("RAGKIT_DATA_DIR", dir);
+    }
+    command.envs(crate::proxy::backend_env(&crate::preferences::load()));
+    let child = command
         .spawn()
-        .map_err(|e| anyhow!("Failed to spawn dev backend: {}", e))?;
+        .map_err(|e| anyhow!("Failed to spawn backend process: {}", e))?;
     Ok(BackendChild::Process(child))
 }
 
 /// Production mode: launch the bundled sidecar executable.
-fn start_sidecar_backend(app: &AppHandle, port: u16) -> Result<BackendChild> {
+fn start_sidecar_backend(
+    app: &AppHandle,
+    port: u16,
+    data_dir: Option<&Path>,
+    generation: u64,
+) -> Result<BackendChild> {
     use tauri_plugin_shell::ShellExt;
 
     tracing::info!("PRODUCTION: launching ragkit-backend sidecar");
 
-    let sidecar_cmd = app
+    let mut sidecar_cmd = app
         .shell()
         .sidecar("ragkit-backend")
         .map_err(|e| anyhow!("Failed to create sidecar command: {}", e))?
         .args(["--port", &port.to_string()]);
+    if let Some(dir) = backend_data_dir(data_dir)? {
+        sidecar_cmd = sidecar_cmd.env("RAGKIT_DATA_DIR", dir);
+    }
+    sidecar_cmd = sidecar_cmd.envs(crate::proxy::backend_env(&crate::preferences::load()));
 
     let (mut rx, child) = sidecar_cmd
         .spawn()
         .map_err(|e| anyhow!("Failed to spawn sidecar: {}", e))?;
+    SIDECAR_EXITED.store(false, Ordering::Relaxed);
 
-    // Log sidecar output in a background task
+    // Log sidecar output in a background task and supervise its exit
+    let app = app.clone();
+    let spawned_at = Instant::now();
     tauri::async_runtime::spawn(async move {
         use tauri_plugin_shell::process::CommandEvent;
         while let Some(event) = rx.recv().await {
             match event {
-                CommandEvent::Stdout(line) => {
-                    tracing::info!("[backend stdout] {}", String::from_utf8_lossy(&line));
-                }
-                CommandEvent::Stderr(line) => {
-                    tracing::warn!("[backend stderr] {}", String::from_utf8_lossy(&line));
-                }
+                CommandEvent::Stdout(line) => forward_backend_line(&line, "stdout"),
+                CommandEvent::Stderr(line) => forward_backend_line(&line, "stderr"),
                 CommandEvent::Terminated(payload) => {
                     tracing::info!("[backend] terminated with code: {:?}", payload.code);
+                    SIDECAR_EXITED.store(true, Ordering::Relaxed);
+                    backend_exited(app, generation, payload.code, spawned_at).await;
                     break;
                 }
                 CommandEvent::Error(err) => {
@@ -104,61 +584,348 @@ fn start_sidecar_backend(app: &AppHandle, port: u16) -> Result<BackendChild> {
     Ok(BackendChild::Sidecar(child))
 }
 
+/// A structured log line emitted by the backend's JSON log formatter.
+#[derive(Debug, serde::Deserialize)]
+struct BackendLogLine {
+    level: String,
+    message: String,
+    #[serde(default)]
+    module: Option<String>,
+    #[serde(default)]
+    line: Option<u32>,
+    #[serde(default)]
+    request_id: Option<String>,
+    #[serde(default)]
+    exception: Option<String>,
+}
+
+/// Re-emit a line of sidecar output as a tracing event.
+///
+/// JSON log lines keep their level, module, and request id as structured fields so
+/// backend entries can be filtered like shell entries; anything else is logged as-is.
+fn forward_backend_line(raw: &[u8], stream: &str) {
+    startup::record(StartupPhase::BackendFirstOutput);
+    let text = String::from_utf8_lossy(raw);
+    let text = text.trim_end();
+
+    let Ok(entry) = serde_json::from_str::<BackendLogLine>(text) else {
+        if stream == "stderr" {
+            tracing::warn!("[backend stderr] {}", text);
+        } else {
+            tracing::info!("[backend stdout] {}", text);
+        }
+        return;
+    };
+
+    let module = entry.module.as_deref().unwrap_or("backend");
+    let request_id = entry.request_id.as_deref().unwrap_or("");
+    let line = entry.line.unwrap_or(0);
+    let message = match &entry.exception {
+        Some(exception) => format!("{}\n{}", entry.message, exception),
+        None => entry.message,
+    };
+
+    macro_rules! forward {
+        ($level:ident) => {
+            tracing::$level!(
+                target: "backend",
+                module = module,
+                line = line,
+                request_id = request_id,
+                "{}",
+                message
+            )
+        };
+    }
+
+    match entry.level.to_uppercase().as_str() {
+        "CRITICAL" | "ERROR" => forward!(error),
+        "WARNING" | "WARN" => forward!(warn),
+        "DEBUG" => forward!(debug),
+        "TRACE" => forward!(trace),
+        _ => forward!(info),
+    }
+}
+
+/// Watch a backend launched as a plain process, restarting it if it exits.
+fn supervise_process(app: AppHandle, generation: u64) {
+    let spawned_at = Instant::now();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            sleep(PROCESS_POLL_INTERVAL).await;
+            if BACKEND_GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            let status = match BACKEND_CHILD.lock().await.as_mut() {
+                Some(BackendChild::Process(child)) => child.try_wait(),
+                // Stopped or replaced
+                _ => return,
+            };
+            match status {
+                Ok(None) => {}
+                Ok(Some(status)) => {
+                    tracing::info!("[backend] terminated with code: {:?}", status.code());
+                    backend_exited(app, generation, status.code(), spawned_at).await;
+                    return;
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to check the backend process: {}", e);
+                    return;
+                }
+            }
+        }
+    });
+}
+
+/// Restart the backend after its process exited, unless the app stopped or replaced
+/// it or it exited cleanly.
+async fn backend_exited(
+    app: AppHandle,
+    generation: u64,
+    exit_code: Option<i32>,
+    spawned_at: Instant,
+) {
+    if exit_code == Some(0)
+        || SHUTTING_DOWN.load(Ordering::Relaxed)
+        || BACKEND_GENERATION.load(Ordering::SeqCst) != generation
+    {
+        return;
+    }
+    if spawned_at.elapsed() >= STABLE_UPTIME {
+        RESTART_ATTEMPTS.store(0, Ordering::Relaxed);
+    }
+    restart_backend(app, exit_code).await;
+}
+
+/// Restart a crashed backend with exponential backoff, giving up after
+/// `MAX_RESTART_ATTEMPTS` consecutive failures. A launch that fails counts as a failure
+/// like a crash.
+async fn restart_backend(app: AppHandle, exit_code: Option<i32>) {
+    loop {
+        let attempt = RESTART_ATTEMPTS.fetch_add(1, Ordering::Relaxed) + 1;
+
+        if attempt > MAX_RESTART_ATTEMPTS {
+            tracing::error!(
+                "Backend failed {} times in a row, giving up on automatic restart",
+                MAX_RESTART_ATTEMPTS
+            );
+            let _ = app.emit(
+                "backend-restarted",
+                BackendRestartEvent {
+                    attempt,
+                    exit_code,
+                    success: false,
+                    error: Some("Maximum restart attempts reached".to_string()),
+                },
+            );
+            return;
+        }
+
+        let delay = RESTART_BASE_DELAY
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(RESTART_MAX_DELAY);
+        tracing::warn!(
+            "Backend crashed (code {:?}), restarting in {:?} (attempt {}/{})",
+            exit_code,
+            delay,
+            attempt,
+            MAX_RESTART_ATTEMPTS
+        );
+        sleep(delay).await;
+
+        // The user may have closed the app while we were waiting.
+        if SHUTTING_DOWN.load(Ordering::Relaxed) {
+            return;
+        }
+
+        // Drop the handle of the dead process before spawning a new one.
+        BACKEND_CHILD.lock().await.take();
+
+        let result = start_backend(&app).await;
+        if let Err(e) = &result {
+            tracing::error!("Backend restart failed: {}", e);
+            // Stop what the failed launch started, without its supervisor restarting it
+            BACKEND_GENERATION.fetch_add(1, Ordering::SeqCst);
+            if let Some(child) = BACKEND_CHILD.lock().await.take() {
+                child.kill().await;
+            }
+        }
+        let success = result.is_ok();
+        let _ = app.emit(
+            "backend-restarted",
+            BackendRestartEvent {
+                attempt,
+                exit_code,
+                success,
+                error: result.err().map(|e| e.to_string()),
+            },
+        );
+        if success {
+            return;
+        }
+    }
+}
+
+/// Wait up to `BACKEND_EXIT_GRACE` for the backend process to exit on its own.
+async fn wait_for_exit(child: &mut BackendChild) -> bool {
+    match child {
+        BackendChild::Sidecar(_) => {
+            let deadline = Instant::now() + BACKEND_EXIT_GRACE;
+            while !SIDECAR_EXITED.load(Ordering::Relaxed) {
+                if Instant::now() >= deadline {
+                    return false;
+                }
+                sleep(Duration::from_millis(100)).await;
+            }
+            true
+        }
+        BackendChild::Process(c) => tokio::time::timeout(BACKEND_EXIT_GRACE, c.wait())
+            .await
+            .is_ok(),
+    }
+}
+
 /// Stop the backend process.
+///
+/// In-progress work is checkpointed first, and the backend gets time to exit after
+/// `/shutdown` so it is only killed if it hangs, not in the middle of a write. A remote
+/// backend is only disconnected, never shut down.
 pub async fn stop_backend(_app: &AppHandle) {
     tracing::info!("Stopping backend");
+    if tokio::time::timeout(CHECKPOINT_TIMEOUT, crate::shutdown::checkpoint_all())
+        .await
+        .is_err()
+    {
+        tracing::warn!(
+            "In-progress work did not reach a checkpoint within {} seconds",
+            CHECKPOINT_TIMEOUT.as_secs()
+        );
+    }
+    SHUTTING_DOWN.store(true, Ordering::Relaxed);
 
     // Try graceful HTTP shutdown first
     let port = BACKEND_PORT.load(Ordering::Relaxed);
-    if port > 0 {
-        let shutdown_url = format!("http://127.0.0.1:{}/shutdown", port);
-        if let Ok(client) = reqwest::Client::builder()
+    let requested = port > 0
+        && crate::capabilities::supports(Feature::GracefulShutdown)
+        && backend_client()
+            .post(format!("http://127.0.0.1:{}/shutdown", port))
             .timeout(Duration::from_secs(5))
-            .build()
-        {
-            let _ = client.post(&shutdown_url).send().await;
-        }
-        sleep(Duration::from_millis(500)).await;
-    }
+            .send()
+            .await
+            .is_ok();
 
-    // Force kill
     let mut guard = BACKEND_CHILD.lock().await;
-    if let Some(child) = guard.take() {
-        match child {
-            BackendChild::Sidecar(c) => {
-                let _ = c.kill();
-            }
-            BackendChild::Process(mut c) => {
-                let _ = c.kill().await;
-            }
+    if let Some(mut child) = guard.take() {
+        if requested && wait_for_exit(&mut child).await {
+            tracing::info!("Backend exited gracefully");
+        } else {
+            // Force kill
+            tracing::warn!("Backend did not exit gracefully, killing it");
+            child.kill().await;
         }
     }
 
     BACKEND_PORT.store(0, Ordering::Relaxed);
+    REMOTE_CONNECTED.store(false, Ordering::Relaxed);
     tracing::info!("Backend stopped");
 }
 
+/// Stop the backend and start it again with the current configuration, which may
+/// target another backend: the tokens of the previous one are forgotten.
+pub async fn relaunch(app: &AppHandle) -> Result<()> {
+    stop_backend(app).await;
+    *REMOTE.lock().unwrap() = None;
+    *BACKEND_CLIENT.write().unwrap() = None;
+    save_auth(None)?;
+    start_backend(app).await
+}
+
+/// Stop the backend, then exit the app.
+///
+/// Emits `app-shutting-down` so the UI can show a "Shutting down…" state while the
+/// backend stops. Exits after `SHUTDOWN_TIMEOUT` even if the backend hangs. Calls made
+/// while the exit sequence is already running are ignored.
+pub async fn shutdown_and_exit(app: AppHandle) {
+    if EXITING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let _ = app.emit("app-shutting-down", ());
+    if tokio::time::timeout(SHUTDOWN_TIMEOUT, stop_backend(&app))
+        .await
+        .is_err()
+    {
+        tracing::warn!(
+            "Backend did not stop within {} seconds, exiting anyway",
+            SHUTDOWN_TIMEOUT.as_secs()
+        );
+    }
+    app.exit(0);
+}
+
+/// Where the backend may listen, from `RAGKIT_BACKEND_PORT` or the preferences.
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum PortPolicy {
+    Fixed { port: u16 },
+    Range { min: u16, max: u16 },
+}
+
+fn port_policy() -> Result<PortPolicy> {
+    if let Some(port) = &crate::config::get().backend_port {
+        return Ok(PortPolicy::Fixed { port: port.value });
+    }
+
+    let prefs = crate::preferences::load();
+    if let Some(port) = prefs.backend_port.filter(|p| *p > 0) {
+        return Ok(PortPolicy::Fixed { port });
+    }
+    if prefs.backend_port_min == 0 || prefs.backend_port_min > prefs.backend_port_max {
+        return Err(anyhow!(
+            "Invalid backend port range {}-{}",
+            prefs.backend_port_min,
+            prefs.backend_port_max
+        ));
+    }
+    Ok(PortPolicy::Range {
+        min: prefs.backend_port_min,
+        max: prefs.backend_port_max,
+    })
+}
+
 /// Find an available port.
 async fn find_available_port() -> Result<u16> {
-    for port in 8100..8200 {
-        let addr = format!("127.0.0.1:{}", port);
-        if tokio::net::TcpListener::bind(&addr).await.is_ok() {
-            return Ok(port);
+    match port_policy()? {
+        PortPolicy::Fixed { port } => {
+            tokio::net::TcpListener::bind(format!("127.0.0.1:{}", port))
+                .await
+                .map_err(|e| anyhow!("Configured backend port {} is unavailable: {}", port, e))?;
+            Ok(port)
+        }
+        PortPolicy::Range { min, max } => {
+            for port in min..=max {
+                let addr = format!("127.0.0.1:{}", port);
+                if tokio::net::TcpListener::bind(&addr).await.is_ok() {
+                    return Ok(port);
+                }
+            }
+            Err(anyhow!("No available port found in range {}-{}", min, max))
         }
     }
-    Err(anyhow!("No available port found in range 8100-8199"))
 }
 
 /// Wait for the backend /health endpoint to respond.
-async fn wait_for_backend(port: u16, timeout: Duration) -> Result<()> {
-    let health_url = format!("http://127.0.0.1:{}/health", port);
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(2))
-        .build()?;
+async fn wait_for_backend(base_url: &str, timeout: Duration) -> Result<()> {
+    let health_url = format!("{}/health", base_url);
+    let client = backend_client();
 
     let start = std::time::Instant::now();
     while start.elapsed() < timeout {
-        match client.get(&health_url).send().await {
+        match authorize(client.get(&health_url))
+            .timeout(Duration::from_secs(2))
+            .send()
+            .await
+        {
             Ok(resp) if resp.status().is_success() => return Ok(()),
             _ => sleep(Duration::from_millis(250)).await,
         }
@@ -170,33 +937,779 @@ async fn wait_for_backend(port: u16, timeout: Duration) -> Result<()> {
     ))
 }
 
+/// Resident memory of a process, read from `/proc` on Linux.
+#[cfg(target_os = "linux")]
+fn process_memory(pid: u32) -> Option<u64> {
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    let kb = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn process_memory(_pid: u32) -> Option<u64> {
+    None
+}
+
+/// Ping `/health` once. Returns the latency, or why the check failed.
+async fn check_health() -> (Option<Duration>, Option<String>) {
+    let start = Instant::now();
+    match authorize(backend_client().get(format!("{}/health", get_backend_url())))
+        .timeout(HEALTH_CHECK_TIMEOUT)
+        .send()
+        .await
+    {
+        Ok(resp) if resp.status().is_success() => (Some(start.elapsed()), None),
+        Ok(resp) => (
+            Some(start.elapsed()),
+            Some(format!("Health check returned {}", resp.status())),
+        ),
+        Err(e) => (None, Some(format!("Health check failed: {}", e))),
+    }
+}
+
+/// Ping the backend every `health_check_interval_secs` and emit `backend-status`.
+///
+/// A slow or failing check reports the backend as degraded; it is reported as down once
+/// `DOWN_AFTER_FAILURES` checks in a row fail, or when no backend process is running.
+/// Checks are skipped while the backend is being stopped.
+pub fn start_health_watchdog(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let interval =
+            Duration::from_secs(crate::preferences::load().health_check_interval_secs.max(1));
+        let mut failures: u32 = 0;
+        loop {
+            sleep(interval).await;
+            if SHUTTING_DOWN.load(Ordering::Relaxed) || EXITING.load(Ordering::Relaxed) {
+                continue;
+            }
+
+            let (latency, error) = if is_running() {
+                check_health().await
+            } else {
+                (None, Some("Backend is not running".to_string()))
+            };
+            failures = if error.is_some() { failures + 1 } else { 0 };
+
+            let state = if !is_running() || failures >= DOWN_AFTER_FAILURES {
+                HealthState::Down
+            } else if error.is_some() || latency.is_some_and(|l| l > DEGRADED_LATENCY) {
+                HealthState::Degraded
+            } else {
+                HealthState::Healthy
+            };
+            let pid = BACKEND_CHILD.lock().await.as_ref().and_then(|c| c.pid());
+
+            let event = BackendStatusEvent {
+                state,
+                latency_ms: latency.map(|l| l.as_millis() as u64),
+                memory_bytes: pid.and_then(process_memory),
+                error,
+                checked_at: chrono::Utc::now().to_rfc3339(),
+            };
+
+            let previous = LAST_STATUS.lock().unwrap().replace(event.clone());
+            if previous.is_none_or(|p| p.state != state) {
+                tracing::info!("Backend status: {:?}", state);
+            }
+            let _ = app.emit("backend-status", &event);
+        }
+    });
+}
+
+/// How long a backend request may take, by kind of endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutTier {
+    /// Health checks, settings, logs, API keys
+    Fast,
+    /// Everything not classified otherwise
+    Standard,
+    /// Queries and answer verification, bounded by LLM generation time
+    Slow,
+    /// Ingestion, re-embedding, and other bulk work
+    VerySlow,
+}
+
+impl TimeoutTier {
+    /// Classify a backend endpoint.
+    pub fn for_endpoint(method: &reqwest::Method, path: &str) -> Self {
+        let path = path.split('?').next().unwrap_or(path);
+        if path == "/health"
+            || path.starts_with("/api/settings")
+            || path.starts_with("/api/logs")
+            || path.starts_with("/api/keys")
+            || path == "/api/ollama/status"
+        {
+            TimeoutTier::Fast
+        } else if path.starts_with("/api/query")
+            || path == "/api/retrieve"
+            || path.ends_with("/verify")
+            || path.ends_with("/regenerate")
+            // Editing a message answers it again
+            || (*method == reqwest::Method::PUT
+                && path.starts_with("/api/conversations/")
+                && path.contains("/messages/"))
+        {
+            TimeoutTier::Slow
+        } else if (*method == reqwest::Method::POST
+            && (path.ends_with("/documents")
+                || path.ends_with("/documents/upload")
+                || path.ends_with("/folders")))
+            || path == "/api/ollama/pull"
+            || path.ends_with("/reembed")
+            || path.ends_with("/export")
+            || path.ends_with("/import")
+            || path == "/api/transcribe"
+        {
+            TimeoutTier::VerySlow
+        } else {
+            TimeoutTier::Standard
+        }
+    }
+
+    /// Timeout of this tier from the preferences.
+    pub fn timeout(self) -> Duration {
+        let prefs = crate::preferences::load();
+        let secs = match self {
+            TimeoutTier::Fast => prefs.fast_timeout_secs,
+            TimeoutTier::Standard => prefs.request_timeout_secs,
+            TimeoutTier::Slow => prefs.query_timeout_secs,
+            TimeoutTier::VerySlow => prefs.long_timeout_secs,
+        };
+        Duration::from_secs(secs.max(1))
+    }
+}
+
+/// Longest delay between two retries of a backend request.
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(5);
+
+/// Why a backend request failed, as far as retrying is concerned.
+enum Failure {
+    /// The request never reached the backend (refused, reset during connect, or no
+    /// backend listening yet), typically while it is starting or restarting.
+    Unreachable(String),
+    /// The backend answered that it is temporarily unavailable (502/503/504).
+    Unavailable(BackendError),
+    /// The backend rejected the access token (401); retried once after a refresh.
+    Unauthorized(String),
+    /// The LLM or embedding provider is rate limiting requests (429, or a quota error
+    /// relayed by the backend); retried once the provider allows it.
+    RateLimited {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+    /// Any other error, including genuine API errors; never retried.
+    Fatal(anyhow::Error),
+}
+
+fn is_idempotent(method: &reqwest::Method) -> bool {
+    use reqwest::Method;
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS
+    )
+}
+
+/// Exponential backoff with up to 50% random jitter, so requests queued during warmup
+/// don't all hit the backend at the same instant.
+fn retry_delay(base: Duration, attempt: u32) -> Duration {
+    let delay = base
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(RETRY_MAX_DELAY);
+    let jitter = uuid::Uuid::new_v4().as_u128() % 1000;
+    delay + delay.mul_f64(jitter as f64 / 2000.0)
+}
+
+// ============================================================================
+// Provider rate limits
+// ============================================================================
+
+/// Delay before retrying a rate-limited request when the provider gives none, doubled
+/// for each further attempt.
+const RATE_LIMIT_DEFAULT_DELAY: Duration = Duration::from_secs(10);
+/// Non-idempotent endpoints that save nothing when the provider fails, so retrying a
+/// rate-limited request can't duplicate a conversation turn. Other POSTs (regeneration,
+/// streaming queries, titles) are reported to the user instead of retried.
+const RATE_LIMIT_RETRYABLE: [&str; 2] = ["/api/query", "/api/retrieve"];
+
+/// Whether a rate-limited request may be sent again automatically.
+fn may_retry_rate_limited(method: &reqwest::Method, path: &str) -> bool {
+    let path = path.split('?').next().unwrap_or(path);
+    is_idempotent(method) || RATE_LIMIT_RETRYABLE.contains(&path)
+}
+
+/// Phrases of provider errors that mean "slow down", as relayed by the backend.
+const RATE_LIMIT_MARKERS: [&str; 6] = [
+    "rate limit",
+    "rate_limit",
+    "ratelimit",
+    "too many requests",
+    "resource_exhausted",
+    "quota",
+];
+
+/// A provider rate-limited a request and the shell stopped waiting for it. The error
+/// message starts with "Rate limited" so the UI can tell it from other failures.
+#[derive(Debug)]
+pub struct RateLimitError {
+    pub message: String,
+    pub retry_after: Option<Duration>,
+}
+
+impl std::fmt::Display for RateLimitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.retry_after {
+            Some(delay) => write!(
+                f,
+                "Rate limited, retry in {} seconds: {}",
+                delay.as_secs().max(1),
+                self.message
+            ),
+            None => write!(f, "Rate limited: {}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for RateLimitError {}
+
+/// The backend answered with an error status other than a rate limit.
+#[derive(Debug)]
+pub struct BackendError {
+    pub status: u16,
+    pub message: String,
+}
+
+impl BackendError {
+    /// Whether the error comes from the backend or a provider rather than the request
+    /// itself (5xx, including provider failures relayed by the backend).
+    pub fn is_server_error(&self) -> bool {
+        self.status >= 500
+    }
+}
+
+impl std::fmt::Display for BackendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = reqwest::StatusCode::from_u16(self.status)
+            .map_or_else(|_| self.status.to_string(), |s| s.to_string());
+        write!(f, "Backend error ({}): {}", status, self.message)
+    }
+}
+
+impl std::error::Error for BackendError {}
+
+/// Payload of the `provider-rate-limited` event, sent each time a request is
+/// rate-limited so the UI can show a countdown until `retry_at`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RateLimitedEvent {
+    pub path: String,
+    pub message: String,
+    /// Whether the shell retries the request by itself
+    pub retrying: bool,
+    pub retry_after_secs: Option<u64>,
+    pub retry_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub attempt: u32,
+    pub max_attempts: u32,
+}
+
+/// `Retry-After` header, in seconds or as an HTTP date.
+fn retry_after_header(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let value = headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(secs) = value.parse::<f64>() {
+        return Some(Duration::from_secs_f64(secs.max(0.0)));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    (at.with_timezone(&chrono::Utc) - chrono::Utc::now())
+        .to_std()
+        .ok()
+}
+
+/// Delay given in the error body: a `retry_after` field (seconds), or a provider
+/// message such as "Please try again in 20s" or "try again in 850ms".
+fn retry_after_body(text: &str) -> Option<Duration> {
+    if let Ok(body) = serde_json::from_str::<serde_json::Value>(text) {
+        let secs = body["retry_after"]
+            .as_f64()
+            .or_else(|| body["detail"]["retry_after"].as_f64());
+        if let Some(secs) = secs {
+            return Some(Duration::from_secs_f64(secs.max(0.0)));
+        }
+    }
+    let lower = text.to_lowercase();
+    let rest = &lower[lower.find("try again in ")? + "try again in ".len()..];
+    let number_end = rest
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(rest.len());
+    let value: f64 = rest[..number_end].parse().ok()?;
+    let unit = rest[number_end..].trim_start();
+    let secs = if unit.starts_with("ms") {
+        value / 1000.0
+    } else if unit.starts_with('m') && !unit.starts_with("mi") {
+        value * 60.0
+    } else {
+        value
+    };
+    Some(Duration::from_secs_f64(secs.max(0.0)))
+}
+
+/// Whether a failed response is a provider rate limit, and how long to wait if known.
+fn rate_limit(
+    status: reqwest::StatusCode,
+    headers: &reqwest::header::HeaderMap,
+    text: &str,
+) -> Option<Option<Duration>> {
+    let lower = text.to_lowercase();
+    let limited = status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        || (status.is_server_error() && RATE_LIMIT_MARKERS.iter().any(|m| lower.contains(m)));
+    limited.then(|| retry_after_header(headers).or_else(|| retry_after_body(text)))
+}
+
+fn emit_rate_limited(event: RateLimitedEvent) {
+    if let Some(app) = startup::app() {
+        let _ = app.emit("provider-rate-limited", event);
+    }
+}
+
+/// Body of a backend request.
+enum Payload {
+    Json(serde_json::Value),
+    Multipart(Vec<MultipartFile>),
+}
+
+/// A file field of a `multipart/form-data` request.
+#[derive(Debug, Clone)]
+pub struct MultipartFile {
+    pub field: String,
+    pub filename: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Multipart forms are consumed when sent, so one is built for each attempt.
+fn multipart_form(files: &[MultipartFile]) -> reqwest::multipart::Form {
+    use reqwest::multipart::{Form, Part};
+    files.iter().fold(Form::new(), |form, file| {
+        form.part(
+            file.field.clone(),
+            Part::bytes(file.bytes.clone()).file_name(file.filename.clone()),
+        )
+    })
+}
+
+async fn send_once(
+    method: reqwest::Method,
+    path: &str,
+    body: Option<&Payload>,
+) -> std::result::Result<reqwest::Response, Failure> {
+    if !is_running() {
+        return Err(Failure::Unreachable("Backend is not running".to_string()));
+    }
+
+    // The port changes when the backend restarts, so resolve it for each attempt
+    let url = format!("{}{}", get_backend_url(), path);
+    let tier = TimeoutTier::for_endpoint(&method, path);
+    let timeout = tier.timeout();
+    let mut request = authorize(backend_client().request(method, &url)).timeout(timeout);
+    match body {
+        Some(Payload::Json(body)) => request = request.json(body),
+        Some(Payload::Multipart(files)) => request = request.multipart(multipart_form(files)),
+        None => {}
+    }
+
+    let response = request.send().await.map_err(|e| {
+        if e.is_connect() {
+            Failure::Unreachable(format!("Backend unreachable: {}", e))
+        } else if e.is_timeout() {
+            Failure::Fatal(anyhow!(
+                "Request timed out after {} seconds ({:?} timeout tier, configurable in preferences)",
+                timeout.as_secs(),
+                tier
+            ))
+        } else {
+            Failure::Fatal(anyhow!("Request failed: {}", e))
+        }
+    })?;
+
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let headers = response.headers().clone();
+    let text = response.text().await.unwrap_or_default();
+    if let Some(retry_after) = rate_limit(status, &headers, &text) {
+        return Err(Failure::RateLimited {
+            message: text,
+            retry_after,
+        });
+    }
+    let error = BackendError {
+        status: status.as_u16(),
+        message: text,
+    };
+    match status.as_u16() {
+        401 => Err(Failure::Unauthorized(error.to_string())),
+        502..=504 => Err(Failure::Unavailable(error)),
+        _ => Err(Failure::Fatal(anyhow::Error::new(error))),
+    }
+}
+
+/// Send an HTTP request to the backend and return the raw response once its status is
+/// known to be successful. Used directly for streaming endpoints.
+///
+/// Requests that fail because the backend is still warming up (connection refused,
+/// nothing listening yet) are retried with jittered backoff; so are idempotent requests
+/// answered with 502/503/504. Genuine API errors are returned immediately.
+pub async fn backend_send(
+    method: reqwest::Method,
+    path: &str,
+    body: Option<serde_json::Value>,
+) -> Result<reqwest::Response> {
+    send_with_retries(method, path, body.map(Payload::Json)).await
+}
+
+/// Offline mode: refuse a request that would reach a cloud provider. The settings are
+/// read with `send_once`, since `send_with_retries` would check them again.
+pub async fn check_offline(
+    method: &reqwest::Method,
+    path: &str,
+    body: Option<&serde_json::Value>,
+) -> Result<()> {
+    if !crate::network::is_offline() || !crate::network::uses_providers(method, path) {
+        return Ok(());
+    }
+    let settings = match send_once(reqwest::Method::GET, "/api/settings", None).await {
+        Ok(response) => response
+            .json::<crate::commands::Settings>()
+            .await
+            .map_err(|e| anyhow!("Offline mode could not check the providers: {}", e))?,
+        Err(_) => {
+            return Err(anyhow!(
+                "Offline mode could not check the providers: the settings are unavailable"
+            ))
+        }
+    };
+    crate::network::check_request(settings, body).map_err(|e| anyhow!(e))
+}
+
+async fn send_with_retries(
+    method: reqwest::Method,
+    path: &str,
+    body: Option<Payload>,
+) -> Result<reqwest::Response> {
+    let json = match &body {
+        Some(Payload::Json(body)) => Some(body),
+        _ => None,
+    };
+    check_offline(&method, path, json).await?;
+    let prefs = crate::preferences::load();
+    let attempts = prefs.request_retry_attempts.max(1);
+    let base_delay = Duration::from_millis(prefs.request_retry_base_delay_ms);
+    let retry_unavailable = prefs.retry_non_idempotent || is_idempotent(&method);
+
+    let mut attempt = 1;
+    let mut refreshed = false;
+    let mut rate_limited = 0;
+    loop {
+        refresh_if_expiring().await;
+        let token = access_token();
+        let error = match send_once(method.clone(), path, body.as_ref()).await {
+            Ok(response) => return Ok(response),
+            Err(Failure::Fatal(e)) => return Err(e),
+            Err(Failure::Unauthorized(message)) => {
+                if !refreshed && refresh_tokens(token.as_deref()).await {
+                    refreshed = true;
+                    continue;
+                }
+                return Err(auth_failed(message));
+            }
+            Err(Failure::RateLimited {
+                message,
+                retry_after,
+            }) => {
+                rate_limited += 1;
+                let delay = retry_after.unwrap_or_else(|| {
+                    RATE_LIMIT_DEFAULT_DELAY.saturating_mul(2u32.saturating_pow(rate_limited - 1))
+                });
+                let retrying = may_retry_rate_limited(&method, path)
+                    && rate_limited <= prefs.rate_limit_retries
+                    && delay <= Duration::from_secs(prefs.rate_limit_max_wait_secs)
+                    && !EXITING.load(Ordering::Relaxed);
+                emit_rate_limited(RateLimitedEvent {
+                    path: path.to_string(),
+                    message: message.clone(),
+                    retrying,
+                    retry_after_secs: Some(delay.as_secs()),
+                    retry_at: chrono::Duration::from_std(delay)
+                        .ok()
+                        .map(|d| chrono::Utc::now() + d),
+                    attempt: rate_limited,
+                    max_attempts: prefs.rate_limit_retries,
+                });
+                if !retrying {
+                    return Err(anyhow::Error::new(RateLimitError {
+                        message,
+                        retry_after,
+                    }));
+                }
+                tracing::info!(
+                    "{} {} rate-limited by the provider, retrying in {} s ({}/{})",
+                    method,
+                    path,
+                    delay.as_secs(),
+                    rate_limited,
+                    prefs.rate_limit_retries
+                );
+                sleep(delay).await;
+                continue;
+            }
+            Err(Failure::Unavailable(error)) if !retry_unavailable => {
+                return Err(anyhow::Error::new(error))
+            }
+            Err(Failure::Unavailable(error)) => anyhow::Error::new(error),
+            Err(Failure::Unreachable(message)) => anyhow!(message),
+        };
+        if attempt >= attempts || EXITING.load(Ordering::Relaxed) {
+            return Err(error);
+        }
+
+        let delay = retry_delay(base_delay, attempt);
+        tracing::debug!(
+            "{} {} failed ({}), retrying in {} ms (attempt {}/{})",
+            method,
+            path,
+            error,
+            delay.as_millis(),
+            attempt + 1,
+            attempts
+        );
+        sleep(delay).await;
+        attempt += 1;
+    }
+}
+
 /// Make an HTTP request to the backend.
 pub async fn backend_request<T: serde::de::DeserializeOwned>(
     method: reqwest::Method,
     path: &str,
     body: Option<serde_json::Value>,
 ) -> Result<T> {
-    let url = format!("{}{}", get_backend_url(), path);
-    let client = reqwest::Client::new();
+    backend_send(method, path, body)
+        .await?
+        .json::<T>()
+        .await
+        .map_err(|e| anyhow!("Failed to parse response: {}", e))
+}
 
-    let mut request = client.request(method, &url);
-    if let Some(body) = body {
-        request = request.json(&body);
+/// Make a `multipart/form-data` request to the backend, e.g. to upload file content.
+pub async fn backend_request_multipart<T: serde::de::DeserializeOwned>(
+    method: reqwest::Method,
+    path: &str,
+    files: Vec<MultipartFile>,
+) -> Result<T> {
+    send_with_retries(method, path, Some(Payload::Multipart(files)))
+        .await?
+        .json::<T>()
+        .await
+        .map_err(|e| anyhow!("Failed to parse response: {}", e))
+}
+
+/// Run a backend request that can be aborted with [`cancel_request`].
+///
+/// Cancelling drops the request future, which closes the HTTP connection. Requests
+/// without an id run to completion as usual; an id already in flight is refused.
+pub async fn cancellable<T>(
+    request_id: Option<&str>,
+    request: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    let Some(request_id) = request_id else {
+        return request.await;
+    };
+
+    let token = IN_FLIGHT_TOKENS.fetch_add(1, Ordering::Relaxed);
+    let (tx, rx) = oneshot::channel();
+    {
+        let mut in_flight = IN_FLIGHT.lock().unwrap();
+        if in_flight.contains_key(request_id) {
+            return Err(anyhow!("Request {} is already running", request_id));
+        }
+        in_flight.insert(request_id.to_string(), (token, tx));
+    }
+
+    let result = tokio::select! {
+        result = request => result,
+        Ok(()) = rx => Err(anyhow!("Request cancelled")),
+    };
+
+    // The entry may already belong to a later call reusing the id after a cancel
+    let mut in_flight = IN_FLIGHT.lock().unwrap();
+    if in_flight.get(request_id).is_some_and(|(t, _)| *t == token) {
+        in_flight.remove(request_id);
+    }
+    result
+}
+
+/// Abort an in-flight request registered with [`cancellable`].
+///
+/// Returns false if no request with this id is running.
+pub fn cancel_request(request_id: &str) -> bool {
+    match IN_FLIGHT.lock().unwrap().remove(request_id) {
+        Some((_, tx)) => tx.send(()).is_ok(),
+        None => false,
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BackendInfo {
+    pub running: bool,
+    pub remote: bool,
+    /// Selected backend environment, `None` for the default launch
+    pub environment: Option<String>,
+    pub url: Option<String>,
+    pub port: Option<u16>,
+    pub port_policy: Option<PortPolicy>,
+}
+
+/// Get the last status reported by the backend health watchdog
+#[tauri::command]
+pub async fn get_backend_status() -> Result<Option<BackendStatusEvent>, String> {
+    Ok(LAST_STATUS.lock().unwrap().clone())
+}
+
+/// Get the active backend URL and port configuration
+#[tauri::command]
+pub async fn get_backend_info() -> Result<BackendInfo, String> {
+    let running = is_running();
+    let remote = is_remote();
+    Ok(BackendInfo {
+        running,
+        remote,
+        environment: crate::backend_environments::selected().map(|e| e.name),
+        url: running.then(get_backend_url),
+        port: (running && !remote).then(|| BACKEND_PORT.load(Ordering::Relaxed)),
+        port_policy: port_policy().ok(),
+    })
+}
+
+/// Use an existing RAGKIT server as the backend, stopping the local one
+///
+/// The server must answer its health check with the given settings before the app
+/// switches to it.
+#[tauri::command]
+pub async fn connect_remote_backend(
+    app: AppHandle,
+    url: String,
+    token: Option<String>,
+    ca_cert_path: Option<String>,
+    accept_invalid_certs: Option<bool>,
+) -> Result<BackendInfo, String> {
+    let parsed = reqwest::Url::parse(url.trim()).map_err(|e| format!("Invalid URL: {}", e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err("The backend URL must use http or https".to_string());
+    }
+    crate::network::check_url(parsed.as_str())?;
+    let token = token.filter(|t| !t.trim().is_empty());
+    let remote = RemoteBackend {
+        url: parsed.as_str().trim_end_matches('/').to_string(),
+        ca_cert_path: ca_cert_path.filter(|p| !p.trim().is_empty()),
+        accept_invalid_certs: accept_invalid_certs.unwrap_or(false),
+    };
+    if token.is_some() && parsed.scheme() == "http" {
+        tracing::warn!("Sending the access token of {} over plain HTTP", remote.url);
     }
 
+    let client = build_client(Some(&remote)).map_err(|e| e.to_string())?;
+    let mut request = client.get(format!("{}/health", remote.url));
+    if let Some(token) = &token {
+        request = request.bearer_auth(token);
+    }
     let response = request
+        .timeout(REMOTE_CONNECT_TIMEOUT)
         .send()
         .await
-        .map_err(|e| anyhow!("Request failed: {}", e))?;
-
+        .map_err(|e| format!("Could not reach {}: {}", remote.url, e))?;
     if !response.status().is_success() {
-        let status = response.status();
-        let text = response.text().await.unwrap_or_default();
-        return Err(anyhow!("Backend error ({}): {}", status, text));
+        return Err(format!(
+            "{} answered its health check with {}",
+            remote.url,
+            response.status()
+        ));
     }
 
-    response
-        .json::<T>()
-        .await
-        .map_err(|e| anyhow!("Failed to parse response: {}", e))
+    stop_backend(&app).await;
+    store::save(REMOTE_FILE, &Some(&remote)).map_err(|e| e.to_string())?;
+    // Never send the previous backend's tokens to this one
+    save_auth(token.map(|token| AuthTokens::new(token, None, None))).map_err(|e| e.to_string())?;
+    start_backend(&app).await.map_err(|e| e.to_string())?;
+    get_backend_info().await
+}
+
+/// Stop using the remote backend and start the local one again
+#[tauri::command]
+pub async fn disconnect_remote_backend(app: AppHandle) -> Result<BackendInfo, String> {
+    if !is_remote() {
+        return get_backend_info().await;
+    }
+    stop_backend(&app).await;
+    store::save(REMOTE_FILE, &None::<RemoteBackend>).map_err(|e| e.to_string())?;
+    *REMOTE.lock().unwrap() = None;
+    *BACKEND_CLIENT.write().unwrap() = None;
+    // The remote backend's tokens mean nothing to the local one
+    save_auth(None).map_err(|e| e.to_string())?;
+    start_backend(&app).await.map_err(|e| e.to_string())?;
+    get_backend_info().await
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AuthStatus {
+    pub authenticated: bool,
+    pub can_refresh: bool,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Get whether backend requests are authenticated
+#[tauri::command]
+pub async fn get_backend_auth_status() -> Result<AuthStatus, String> {
+    let tokens = AUTH.read().unwrap().clone();
+    Ok(AuthStatus {
+        authenticated: tokens.is_some(),
+        can_refresh: tokens.as_ref().is_some_and(|t| t.refresh_token.is_some()),
+        expires_at: tokens.and_then(|t| t.expires_at),
+    })
+}
+
+/// Set the tokens sent with every backend request, e.g. after logging in
+#[tauri::command]
+pub async fn set_backend_auth(
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_in: Option<u64>,
+) -> Result<(), String> {
+    if access_token.trim().is_empty() {
+        return Err("Access token cannot be empty".to_string());
+    }
+    let refresh_token = refresh_token.filter(|t| !t.trim().is_empty());
+    save_auth(Some(AuthTokens::new(
+        access_token,
+        refresh_token,
+        expires_in,
+    )))
+    .map_err(|e| e.to_string())
+}
+
+/// Forget the backend tokens (log out)
+#[tauri::command]
+pub async fn clear_backend_auth() -> Result<(), String> {
+    save_auth(None).map_err(|e| e.to_string())
 }
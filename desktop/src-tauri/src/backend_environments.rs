@@ -0,0 +1,176 @@
+//! Named backend environments for development and testing.
+//!
+//! Besides the default launch (the bundled sidecar, or `python -m` in debug builds),
+//! developers can register launch configurations (a Python module from a checkout, a
+//! locally built sidecar, or a remote URL) and switch between them without rebuilding
+//! the shell. Local environments get their own data directory, so test data never
+//! ends up in the real knowledge bases.
+
+use crate::backend::{self, BackendInfo, RemoteBackend};
+use crate::store;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+const STATE_FILE: &str = "backend_environments.json";
+
+/// How an environment's backend is started.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BackendLaunch {
+    /// `python -m <module>`, e.g. from a development checkout
+    PythonModule {
+        /// Interpreter, `python` from the PATH by default
+        python: Option<String>,
+        #[serde(default = "default_module")]
+        module: String,
+        working_dir: Option<String>,
+    },
+    /// A backend executable, the bundled sidecar when no path is given
+    Sidecar { path: Option<String> },
+    /// An existing RAGKIT server
+    Remote(RemoteBackend),
+}
+
+fn default_module() -> String {
+    "ragkit.desktop.main".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendEnvironment {
+    pub name: String,
+    pub launch: BackendLaunch,
+    /// Data directory of local backends, `~/.ragkit/environments/<name>` by default
+    pub data_dir: Option<String>,
+}
+
+impl BackendEnvironment {
+    /// Data directory the backend of this environment uses, `None` for remote ones.
+    pub fn resolved_data_dir(&self) -> Option<PathBuf> {
+        if matches!(self.launch, BackendLaunch::Remote(_)) {
+            return None;
+        }
+        Some(match &self.data_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => store::ragkit_dir().join("environments").join(&self.name),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackendEnvironments {
+    pub environments: Vec<BackendEnvironment>,
+    /// Environment in use, the default launch when `None`
+    pub selected: Option<String>,
+}
+
+/// The environment the backend should be started in, if one is selected.
+pub fn selected() -> Option<BackendEnvironment> {
+    let state: BackendEnvironments = store::load(STATE_FILE);
+    let name = state.selected?;
+    let environment = state.environments.into_iter().find(|e| e.name == name);
+    if environment.is_none() {
+        tracing::warn!(
+            "Backend environment {} no longer exists, using the default",
+            name
+        );
+    }
+    environment
+}
+
+fn validate(environment: &BackendEnvironment) -> Result<(), String> {
+    let name = environment.name.trim();
+    if name.is_empty() {
+        return Err("The environment needs a name".to_string());
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Err(format!(
+            "Invalid environment name {}: use letters, digits, '-', '_' and '.'",
+            name
+        ));
+    }
+    match &environment.launch {
+        BackendLaunch::PythonModule { module, .. } if module.trim().is_empty() => {
+            Err("The Python module to run is empty".to_string())
+        }
+        BackendLaunch::Sidecar { path: Some(path) } if !std::path::Path::new(path).is_file() => {
+            Err(format!("Backend executable not found: {}", path))
+        }
+        BackendLaunch::Remote(remote) => reqwest::Url::parse(&remote.url)
+            .map(|_| ())
+            .map_err(|e| format!("Invalid URL {}: {}", remote.url, e)),
+        _ => Ok(()),
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// List the backend environments and the selected one
+#[tauri::command]
+pub async fn list_backend_environments() -> Result<BackendEnvironments, String> {
+    Ok(store::load(STATE_FILE))
+}
+
+/// Add a backend environment, or replace the one with the same name
+///
+/// Changes to the selected environment apply the next time the backend starts.
+#[tauri::command]
+pub async fn save_backend_environment(
+    mut environment: BackendEnvironment,
+) -> Result<BackendEnvironment, String> {
+    environment.name = environment.name.trim().to_string();
+    validate(&environment)?;
+    let mut state: BackendEnvironments = store::load(STATE_FILE);
+    match state
+        .environments
+        .iter_mut()
+        .find(|e| e.name == environment.name)
+    {
+        Some(existing) => *existing = environment.clone(),
+        None => state.environments.push(environment.clone()),
+    }
+    store::save(STATE_FILE, &state).map_err(|e| e.to_string())?;
+    Ok(environment)
+}
+
+/// Delete a backend environment; its data directory is kept
+#[tauri::command]
+pub async fn delete_backend_environment(name: String) -> Result<(), String> {
+    let mut state: BackendEnvironments = store::load(STATE_FILE);
+    if state.selected.as_deref() == Some(name.as_str()) {
+        return Err(format!(
+            "{} is in use; switch to another environment first",
+            name
+        ));
+    }
+    state.environments.retain(|e| e.name != name);
+    store::save(STATE_FILE, &state).map_err(|e| e.to_string())
+}
+
+/// Restart the backend in an environment, or with the default launch when `name` is null
+#[tauri::command]
+pub async fn select_backend_environment(
+    app: AppHandle,
+    name: Option<String>,
+) -> Result<BackendInfo, String> {
+    let mut state: BackendEnvironments = store::load(STATE_FILE);
+    if let Some(name) = &name {
+        if !state.environments.iter().any(|e| &e.name == name) {
+            return Err(format!("Unknown backend environment: {}", name));
+        }
+    }
+    tracing::info!(
+        "Switching to backend environment {}",
+        name.as_deref().unwrap_or("default")
+    );
+    state.selected = name;
+    store::save(STATE_FILE, &state).map_err(|e| e.to_string())?;
+    backend::relaunch(&app).await.map_err(|e| e.to_string())?;
+    backend::get_backend_info().await
+}
@@ -0,0 +1,275 @@
+//! Backend capability negotiation.
+//!
+//! The shell and the backend are upgraded independently, so the installed backend may
+//! not serve every endpoint the shell knows about. Once the backend is up, its OpenAPI
+//! schema is fetched and mapped onto the optional features below; commands check
+//! [`supports`] to fall back or fail with a clear message, and the UI reads
+//! `get_backend_capabilities` to hide what is unavailable.
+
+use crate::backend::backend_request;
+use reqwest::Method;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Capabilities of the running backend, `None` until its schema has been read.
+static CAPABILITIES: Mutex<Option<BackendCapabilities>> = Mutex::new(None);
+
+/// Optional backend features, each tied to the endpoint that provides it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    QueryStream,
+    QueryCancel,
+    AnswerVerification,
+    DocumentUpload,
+    DocumentContent,
+    DocumentDelete,
+    Reembedding,
+    SourceContext,
+    GracefulShutdown,
+    DocumentRecommendations,
+    AnswerRegeneration,
+    MessageFeedback,
+    ConversationTitles,
+    ConversationUpdate,
+    KnowledgeBaseUpdate,
+    ChunkHashes,
+    KbSettings,
+    Retrieval,
+    MessageEdit,
+    ConversationFork,
+    DocumentHashes,
+    OcrStatus,
+    Transcription,
+    Chunks,
+    ChunkUpdate,
+    DocumentTags,
+    SimilarDocuments,
+    CustomProviders,
+    IndexRefresh,
+}
+
+impl Feature {
+    const ALL: [Feature; 29] = [
+        Feature::QueryStream,
+        Feature::QueryCancel,
+        Feature::AnswerVerification,
+        Feature::DocumentUpload,
+        Feature::DocumentContent,
+        Feature::DocumentDelete,
+        Feature::Reembedding,
+        Feature::SourceContext,
+        Feature::GracefulShutdown,
+        Feature::DocumentRecommendations,
+        Feature::AnswerRegeneration,
+        Feature::MessageFeedback,
+        Feature::ConversationTitles,
+        Feature::ConversationUpdate,
+        Feature::KnowledgeBaseUpdate,
+        Feature::ChunkHashes,
+        Feature::KbSettings,
+        Feature::Retrieval,
+        Feature::MessageEdit,
+        Feature::ConversationFork,
+        Feature::DocumentHashes,
+        Feature::OcrStatus,
+        Feature::Transcription,
+        Feature::Chunks,
+        Feature::ChunkUpdate,
+        Feature::DocumentTags,
+        Feature::SimilarDocuments,
+        Feature::CustomProviders,
+        Feature::IndexRefresh,
+    ];
+
+    /// Method and path of the endpoint, with path parameters written `{}`.
+    fn endpoint(self) -> (&'static str, &'static str) {
+        match self {
+            Feature::QueryStream => ("POST", "/api/query/stream"),
+            Feature::QueryCancel => ("POST", "/api/query/{}/cancel"),
+            Feature::AnswerVerification => ("POST", "/api/messages/{}/verify"),
+            Feature::DocumentUpload => ("POST", "/api/knowledge-bases/{}/documents/upload"),
+            Feature::DocumentContent => ("GET", "/api/knowledge-bases/{}/documents/{}/content"),
+            Feature::DocumentDelete => ("DELETE", "/api/knowledge-bases/{}/documents/{}"),
+            Feature::Reembedding => ("POST", "/api/knowledge-bases/{}/reembed"),
+            Feature::SourceContext => ("POST", "/api/sources/context"),
+            Feature::GracefulShutdown => ("POST", "/shutdown"),
+            Feature::DocumentRecommendations => ("POST", "/api/conversations/{}/recommendations"),
+            Feature::AnswerRegeneration => ("POST", "/api/conversations/{}/messages/{}/regenerate"),
+            Feature::MessageFeedback => ("POST", "/api/messages/{}/feedback"),
+            Feature::ConversationTitles => ("POST", "/api/conversations/{}/title"),
+            Feature::ConversationUpdate => ("PATCH", "/api/conversations/{}"),
+            Feature::KnowledgeBaseUpdate => ("PATCH", "/api/knowledge-bases/{}"),
+            Feature::ChunkHashes => ("GET", "/api/knowledge-bases/{}/chunks/hashes"),
+            Feature::KbSettings => ("PUT", "/api/knowledge-bases/{}/settings"),
+            Feature::Retrieval => ("POST", "/api/retrieve"),
+            Feature::MessageEdit => ("PUT", "/api/conversations/{}/messages/{}"),
+            Feature::ConversationFork => ("POST", "/api/conversations/{}/fork"),
+            Feature::DocumentHashes => ("GET", "/api/knowledge-bases/{}/documents/hashes"),
+            Feature::OcrStatus => ("GET", "/api/ocr/status"),
+            Feature::Transcription => ("POST", "/api/transcribe"),
+            Feature::Chunks => ("GET", "/api/knowledge-bases/{}/chunks"),
+            Feature::ChunkUpdate => ("PATCH", "/api/chunks/{}"),
+            Feature::DocumentTags => ("PUT", "/api/documents/{}/tags"),
+            Feature::SimilarDocuments => ("GET", "/api/documents/{}/similar"),
+            Feature::CustomProviders => ("PUT", "/api/providers/custom/{}"),
+            Feature::IndexRefresh => ("POST", "/api/knowledge-bases/{}/refresh-index"),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Feature::QueryStream => "streaming answers",
+            Feature::QueryCancel => "query cancellation",
+            Feature::AnswerVerification => "answer verification",
+            Feature::DocumentUpload => "document uploads",
+            Feature::DocumentContent => "document content",
+            Feature::DocumentDelete => "document deletion",
+            Feature::Reembedding => "re-embedding",
+            Feature::SourceContext => "source context",
+            Feature::GracefulShutdown => "graceful shutdown",
+            Feature::DocumentRecommendations => "document recommendations",
+            Feature::AnswerRegeneration => "answer regeneration",
+            Feature::MessageFeedback => "answer feedback",
+            Feature::ConversationTitles => "conversation titles",
+            Feature::ConversationUpdate => "conversation renaming",
+            Feature::KnowledgeBaseUpdate => "knowledge base editing",
+            Feature::ChunkHashes => "chunk hashes",
+            Feature::KbSettings => "knowledge base settings",
+            Feature::Retrieval => "retrieval-only queries",
+            Feature::MessageEdit => "message editing",
+            Feature::ConversationFork => "conversation forking",
+            Feature::DocumentHashes => "document hashes",
+            Feature::OcrStatus => "OCR status",
+            Feature::Transcription => "audio and video transcription",
+            Feature::Chunks => "chunk listing",
+            Feature::ChunkUpdate => "chunk editing",
+            Feature::DocumentTags => "document tags",
+            Feature::SimilarDocuments => "similar documents",
+            Feature::CustomProviders => "custom LLM providers",
+            Feature::IndexRefresh => "deferred index refresh",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackendCapabilities {
+    /// Version reported by the backend's schema
+    pub backend_version: Option<String>,
+    pub features: BTreeMap<Feature, bool>,
+    /// Number of endpoints the backend serves
+    pub endpoint_count: usize,
+    pub checked_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Replace path parameters with `{}` so `/a/{kb_id}` matches `/a/{}`.
+fn normalize(path: &str) -> String {
+    let mut normalized = String::with_capacity(path.len());
+    let mut in_param = false;
+    for c in path.chars() {
+        match c {
+            '{' => {
+                in_param = true;
+                normalized.push_str("{}");
+            }
+            '}' => in_param = false,
+            _ if !in_param => normalized.push(c),
+            _ => {}
+        }
+    }
+    normalized
+}
+
+fn from_schema(schema: &serde_json::Value) -> BackendCapabilities {
+    let mut endpoints = Vec::new();
+    if let Some(paths) = schema["paths"].as_object() {
+        for (path, operations) in paths {
+            let path = normalize(path);
+            for method in operations.as_object().into_iter().flat_map(|o| o.keys()) {
+                endpoints.push((method.to_uppercase(), path.clone()));
+            }
+        }
+    }
+    let features = Feature::ALL
+        .into_iter()
+        .map(|feature| {
+            let (method, path) = feature.endpoint();
+            let available = endpoints.iter().any(|(m, p)| m == method && p == path);
+            (feature, available)
+        })
+        .collect();
+    BackendCapabilities {
+        backend_version: schema["info"]["version"].as_str().map(String::from),
+        features,
+        endpoint_count: endpoints.len(),
+        checked_at: chrono::Utc::now(),
+    }
+}
+
+/// Read the capabilities of the backend that just started.
+///
+/// If the schema can't be read, capabilities stay unknown and every feature is assumed
+/// available, as before negotiation existed.
+pub async fn refresh() {
+    let capabilities =
+        match backend_request::<serde_json::Value>(Method::GET, "/openapi.json", None).await {
+            Ok(schema) => Some(from_schema(&schema)),
+            Err(e) => {
+                tracing::warn!("Could not read the backend capabilities: {}", e);
+                None
+            }
+        };
+    if let Some(capabilities) = &capabilities {
+        let missing: Vec<&str> = capabilities
+            .features
+            .iter()
+            .filter(|(_, available)| !**available)
+            .map(|(feature, _)| feature.label())
+            .collect();
+        tracing::info!(
+            "Backend version {} ({} endpoints){}",
+            capabilities.backend_version.as_deref().unwrap_or("unknown"),
+            capabilities.endpoint_count,
+            if missing.is_empty() {
+                String::new()
+            } else {
+                format!(", unsupported: {}", missing.join(", "))
+            }
+        );
+    }
+    *CAPABILITIES.lock().unwrap() = capabilities;
+}
+
+/// Whether the running backend provides a feature. Unknown capabilities count as
+/// supported.
+pub fn supports(feature: Feature) -> bool {
+    CAPABILITIES
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|c| c.features.get(&feature).copied())
+        .unwrap_or(true)
+}
+
+/// Fail with a readable message when the backend lacks a feature.
+pub fn require(feature: Feature) -> Result<(), String> {
+    if supports(feature) {
+        Ok(())
+    } else {
+        Err(format!(
+            "The installed backend does not support {}; update it to use this feature",
+            feature.label()
+        ))
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Get the features supported by the running backend, or null if not known yet
+#[tauri::command]
+pub async fn get_backend_capabilities() -> Result<Option<BackendCapabilities>, String> {
+    Ok(CAPABILITIES.lock().unwrap().clone())
+}
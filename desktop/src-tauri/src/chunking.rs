@@ -0,0 +1,178 @@
+//! Chunk previews.
+//!
+//! Mirrors the backend's chunkers closely enough to tune chunking before an expensive
+//! embedding run. Tokens are whitespace-separated words, as the backend counts them
+//! without tiktoken; with tiktoken installed its chunks hold somewhat fewer words. The
+//! `fixed` strategy reproduces the backend's windows exactly. The `semantic` strategy
+//! splits on embedding similarity in the backend, which the shell can't compute, so the
+//! preview packs whole sentences up to the chunk size and is marked approximate.
+
+use crate::extraction::{ExtractedDocument, Section};
+use serde::Serialize;
+
+/// Chunk sizes accepted by the backend, in tokens.
+const CHUNK_SIZE_RANGE: std::ops::RangeInclusive<i32> = 50..=2000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PreviewChunk {
+    pub index: usize,
+    /// Character offsets of the chunk in the previewed text (end exclusive)
+    pub start: usize,
+    pub end: usize,
+    /// Chunk content, as the backend would store it
+    pub content: String,
+    pub token_count: usize,
+    /// Heading the chunk starts under, if the document has sections
+    pub section: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChunkPreview {
+    #[serde(flatten)]
+    pub document: ExtractedDocument,
+    pub chunk_strategy: String,
+    pub chunk_size: i32,
+    pub chunk_overlap: i32,
+    pub chunks: Vec<PreviewChunk>,
+    pub total_tokens: usize,
+    /// Set when the boundaries only approximate the backend's
+    pub approximate: bool,
+}
+
+/// A whitespace-separated token, with its character offsets.
+struct Token<'a> {
+    text: &'a str,
+    start: usize,
+    end: usize,
+}
+
+fn tokens(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut current: Option<(usize, usize)> = None;
+    for (chars, (byte, c)) in text.char_indices().enumerate() {
+        match (c.is_whitespace(), current) {
+            (false, None) => current = Some((byte, chars)),
+            (true, Some((start_byte, start))) => {
+                tokens.push(Token {
+                    text: &text[start_byte..byte],
+                    start,
+                    end: chars,
+                });
+                current = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some((start_byte, start)) = current {
+        tokens.push(Token {
+            text: &text[start_byte..],
+            start,
+            end: start + text[start_byte..].chars().count(),
+        });
+    }
+    tokens
+}
+
+fn section_at(sections: &[Section], offset: usize) -> Option<String> {
+    sections
+        .iter()
+        .take_while(|s| s.offset <= offset)
+        .last()
+        .map(|s| s.title.clone())
+}
+
+fn chunk_of(index: usize, tokens: &[Token], sections: &[Section]) -> PreviewChunk {
+    let start = tokens.first().map_or(0, |t| t.start);
+    PreviewChunk {
+        index,
+        start,
+        end: tokens.last().map_or(0, |t| t.end),
+        content: tokens.iter().map(|t| t.text).collect::<Vec<_>>().join(" "),
+        token_count: tokens.len(),
+        section: section_at(sections, start),
+    }
+}
+
+/// Windows of `size` tokens, each starting `size - overlap` tokens after the previous.
+fn fixed(tokens: &[Token], size: usize, overlap: usize, sections: &[Section]) -> Vec<PreviewChunk> {
+    let step = size.saturating_sub(overlap).max(1);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < tokens.len() {
+        let end = (start + size).min(tokens.len());
+        chunks.push(chunk_of(chunks.len(), &tokens[start..end], sections));
+        if end >= tokens.len() {
+            break;
+        }
+        start += step;
+    }
+    chunks
+}
+
+/// Whole sentences packed up to `size` tokens; longer sentences get a chunk of their own.
+fn by_sentences(tokens: &[Token], size: usize, sections: &[Section]) -> Vec<PreviewChunk> {
+    let mut chunks = Vec::new();
+    let mut chunk_start = 0;
+    let mut sentence_start = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        let sentence_end = token.text.ends_with(['.', '!', '?']) || i + 1 == tokens.len();
+        if !sentence_end {
+            continue;
+        }
+        // Close the chunk before a sentence that would overflow it
+        if i + 1 - chunk_start > size && sentence_start > chunk_start {
+            chunks.push(chunk_of(
+                chunks.len(),
+                &tokens[chunk_start..sentence_start],
+                sections,
+            ));
+            chunk_start = sentence_start;
+        }
+        sentence_start = i + 1;
+    }
+    if chunk_start < tokens.len() {
+        chunks.push(chunk_of(chunks.len(), &tokens[chunk_start..], sections));
+    }
+    chunks
+}
+
+/// Split an extracted document into chunks as the backend would.
+pub fn preview(
+    document: ExtractedDocument,
+    strategy: &str,
+    size: i32,
+    overlap: i32,
+) -> Result<ChunkPreview, String> {
+    if !CHUNK_SIZE_RANGE.contains(&size) {
+        return Err(format!(
+            "The chunk size must be between {} and {}",
+            CHUNK_SIZE_RANGE.start(),
+            CHUNK_SIZE_RANGE.end()
+        ));
+    }
+    if overlap < 0 || overlap >= size {
+        return Err("The chunk overlap must be between 0 and the chunk size".to_string());
+    }
+
+    let tokens = tokens(&document.text);
+    let (chunks, approximate) = match strategy {
+        "fixed" => (
+            fixed(&tokens, size as usize, overlap as usize, &document.sections),
+            false,
+        ),
+        "semantic" => (
+            by_sentences(&tokens, size as usize, &document.sections),
+            true,
+        ),
+        other => return Err(format!("Unknown chunk strategy: {}", other)),
+    };
+    Ok(ChunkPreview {
+        total_tokens: tokens.len(),
+        chunk_strategy: strategy.to_string(),
+        chunk_size: size,
+        chunk_overlap: overlap,
+        chunks,
+        approximate,
+        document,
+    })
+}
@@ -0,0 +1,328 @@
+//! Chunk browser.
+//!
+//! `list_chunks` pages through the chunks of a knowledge base, or of one document, so
+//! users can audit exactly what got indexed. Chunks come from the backend's chunk
+//! listing with their embedding metadata; older backends fall back to the passages of
+//! the shell's lexical index, which have none. Each chunk is checked for the usual
+//! signs of a failed extraction (empty or tiny chunks, garbled text, no embedding) so
+//! the UI can flag them. Flagged chunks can then be fixed with `update_chunk`, which
+//! has the backend re-embed the new text, or left out of retrieval with
+//! `exclude_chunk`, without re-ingesting the document.
+
+use crate::backend::backend_request;
+use crate::capabilities::{self, Feature};
+use crate::kb_history::{self, KbChange};
+use crate::kb_snapshots::ChunkSource;
+use crate::lexical_index;
+use anyhow::Result;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 500;
+/// Chunks shorter than this, in characters, are flagged.
+const MIN_CHUNK_CHARS: usize = 20;
+/// Share of characters that are neither letters, digits, whitespace, nor common
+/// punctuation above which a chunk is flagged as garbled.
+const MAX_GARBLED_RATIO: f64 = 0.3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkEmbedding {
+    pub model: Option<String>,
+    pub dimensions: Option<u32>,
+    /// L2 norm of the vector; zero means the embedding failed
+    pub norm: Option<f64>,
+}
+
+/// A chunk as listed by the backend.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BackendChunk {
+    pub id: String,
+    #[serde(default)]
+    pub kb_id: Option<String>,
+    pub document_id: String,
+    #[serde(default)]
+    pub filename: Option<String>,
+    pub index: usize,
+    pub text: String,
+    /// Whitespace-separated words, as the backend counts tokens
+    #[serde(default)]
+    pub token_count: Option<usize>,
+    #[serde(default)]
+    pub embedding: Option<ChunkEmbedding>,
+    /// Extraction metadata, e.g. page or section
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+    /// Left out of retrieval
+    #[serde(default)]
+    pub excluded: bool,
+    /// Text changed by hand since ingestion
+    #[serde(default)]
+    pub edited: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BackendChunkPage {
+    pub chunks: Vec<BackendChunk>,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkIssue {
+    Empty,
+    TooShort,
+    /// Mostly symbols or replacement characters, typical of a broken text layer
+    Garbled,
+    MissingEmbedding,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Chunk {
+    pub id: String,
+    pub document_id: String,
+    pub filename: Option<String>,
+    pub index: usize,
+    pub text: String,
+    pub char_count: usize,
+    pub token_count: usize,
+    pub embedding: Option<ChunkEmbedding>,
+    pub metadata: serde_json::Value,
+    pub excluded: bool,
+    pub edited: bool,
+    pub issues: Vec<ChunkIssue>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChunkPage {
+    pub source: ChunkSource,
+    pub chunks: Vec<Chunk>,
+    /// Chunks matching the filters
+    pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
+}
+
+/// Tokens of a text as the backend counts them without tiktoken.
+pub fn token_count(text: &str) -> usize {
+    text.split_whitespace().count()
+}
+
+/// One page of the backend's chunk listing.
+pub async fn fetch_page(
+    kb_id: &str,
+    doc_id: Option<&str>,
+    search: Option<&str>,
+    offset: usize,
+    limit: usize,
+) -> Result<BackendChunkPage> {
+    let mut query = vec![
+        ("offset".to_string(), offset.to_string()),
+        ("limit".to_string(), limit.to_string()),
+    ];
+    if let Some(doc_id) = doc_id {
+        query.push(("document_id".to_string(), doc_id.to_string()));
+    }
+    if let Some(search) = search {
+        query.push(("search".to_string(), search.to_string()));
+    }
+    let query = reqwest::Url::parse_with_params("http://localhost/", &query)?
+        .query()
+        .unwrap_or_default()
+        .to_string();
+    backend_request::<BackendChunkPage>(
+        Method::GET,
+        &format!("/api/knowledge-bases/{}/chunks?{}", kb_id, query),
+        None,
+    )
+    .await
+}
+
+fn issues(text: &str, embedding: Option<&ChunkEmbedding>, source: ChunkSource) -> Vec<ChunkIssue> {
+    let mut issues = Vec::new();
+    let chars = text.trim().chars().count();
+    if chars == 0 {
+        issues.push(ChunkIssue::Empty);
+    } else if chars < MIN_CHUNK_CHARS {
+        issues.push(ChunkIssue::TooShort);
+    }
+    let odd = text
+        .chars()
+        .filter(|c| {
+            *c == char::REPLACEMENT_CHARACTER
+                || !(c.is_alphanumeric() || c.is_whitespace() || ".,;:!?'\"()-–—%/".contains(*c))
+        })
+        .count();
+    if chars > 0 && odd as f64 / chars as f64 > MAX_GARBLED_RATIO {
+        issues.push(ChunkIssue::Garbled);
+    }
+    // Local passages never have embeddings: only flag the backend's chunks
+    if source == ChunkSource::Backend && embedding.is_none_or(|e| e.norm == Some(0.0)) {
+        issues.push(ChunkIssue::MissingEmbedding);
+    }
+    issues
+}
+
+fn to_chunk(chunk: BackendChunk, source: ChunkSource) -> Chunk {
+    Chunk {
+        issues: issues(&chunk.text, chunk.embedding.as_ref(), source),
+        char_count: chunk.text.chars().count(),
+        token_count: chunk
+            .token_count
+            .unwrap_or_else(|| token_count(&chunk.text)),
+        id: chunk.id,
+        document_id: chunk.document_id,
+        filename: chunk.filename,
+        index: chunk.index,
+        text: chunk.text,
+        embedding: chunk.embedding,
+        metadata: chunk.metadata,
+        excluded: chunk.excluded,
+        edited: chunk.edited,
+    }
+}
+
+/// Passages of the lexical index, filtered like the backend listing.
+fn local_chunks(kb_id: &str, doc_id: Option<&str>, search: Option<&str>) -> Vec<BackendChunk> {
+    let search = search.map(str::to_lowercase);
+    lexical_index::document_passages(kb_id)
+        .into_iter()
+        .filter(|(document_id, _)| doc_id.is_none_or(|id| id == document_id))
+        .flat_map(|(document_id, passages)| {
+            passages
+                .into_iter()
+                .enumerate()
+                .map(move |(index, text)| BackendChunk {
+                    id: format!("{}:{}", document_id, index),
+                    kb_id: Some(kb_id.to_string()),
+                    document_id: document_id.clone(),
+                    filename: None,
+                    index,
+                    text,
+                    token_count: None,
+                    embedding: None,
+                    metadata: serde_json::Value::Null,
+                    excluded: false,
+                    edited: false,
+                })
+        })
+        .filter(|c| {
+            search
+                .as_ref()
+                .is_none_or(|s| c.text.to_lowercase().contains(s))
+        })
+        .collect()
+}
+
+/// Apply a change to a chunk, returning the chunk as the backend now stores it.
+async fn patch_chunk(chunk_id: &str, change: serde_json::Value) -> Result<BackendChunk, String> {
+    capabilities::require(Feature::ChunkUpdate)?;
+    backend_request::<BackendChunk>(
+        Method::PATCH,
+        &format!("/api/chunks/{}", chunk_id),
+        Some(change),
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// List the chunks of a knowledge base or document, a page at a time
+///
+/// `page` starts at 1; `search` keeps the chunks containing the text.
+#[tauri::command]
+pub async fn list_chunks(
+    kb_id: String,
+    doc_id: Option<String>,
+    page: Option<usize>,
+    page_size: Option<usize>,
+    search: Option<String>,
+) -> Result<ChunkPage, String> {
+    let page = page.unwrap_or(1).max(1);
+    let page_size = page_size
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let offset = (page - 1) * page_size;
+    let search = search.filter(|s| !s.trim().is_empty());
+
+    if capabilities::supports(Feature::Chunks) {
+        match fetch_page(
+            &kb_id,
+            doc_id.as_deref(),
+            search.as_deref(),
+            offset,
+            page_size,
+        )
+        .await
+        {
+            Ok(listing) => {
+                return Ok(ChunkPage {
+                    source: ChunkSource::Backend,
+                    chunks: listing
+                        .chunks
+                        .into_iter()
+                        .map(|c| to_chunk(c, ChunkSource::Backend))
+                        .collect(),
+                    total: listing.total,
+                    page,
+                    page_size,
+                })
+            }
+            Err(e) => tracing::warn!("Listing the local index instead: {}", e),
+        }
+    }
+
+    let chunks = local_chunks(&kb_id, doc_id.as_deref(), search.as_deref());
+    Ok(ChunkPage {
+        source: ChunkSource::LocalIndex,
+        total: chunks.len(),
+        chunks: chunks
+            .into_iter()
+            .skip(offset)
+            .take(page_size)
+            .map(|c| to_chunk(c, ChunkSource::LocalIndex))
+            .collect(),
+        page,
+        page_size,
+    })
+}
+
+/// Replace the text of a chunk, e.g. to fix garbled OCR; the backend re-embeds it
+#[tauri::command]
+pub async fn update_chunk(chunk_id: String, text: String) -> Result<Chunk, String> {
+    if text.trim().is_empty() {
+        return Err("A chunk can't be emptied: exclude it instead".to_string());
+    }
+    let chunk = patch_chunk(&chunk_id, json!({ "text": text })).await?;
+    if let Some(kb_id) = &chunk.kb_id {
+        kb_history::record(
+            kb_id,
+            KbChange::ChunkEdited {
+                chunk_id: chunk.id.clone(),
+                document_id: chunk.document_id.clone(),
+            },
+        );
+    }
+    Ok(to_chunk(chunk, ChunkSource::Backend))
+}
+
+/// Leave a chunk (e.g. a repeated header or footer) out of retrieval, or bring it back
+#[tauri::command]
+pub async fn exclude_chunk(chunk_id: String, excluded: bool) -> Result<Chunk, String> {
+    let chunk = patch_chunk(&chunk_id, json!({ "excluded": excluded })).await?;
+    if let Some(kb_id) = &chunk.kb_id {
+        kb_history::record(
+            kb_id,
+            KbChange::ChunkExcluded {
+                chunk_id: chunk.id.clone(),
+                document_id: chunk.document_id.clone(),
+                excluded,
+            },
+        );
+    }
+    Ok(to_chunk(chunk, ChunkSource::Backend))
+}
@@ -0,0 +1,177 @@
+//! Citation export.
+//!
+//! `export_citations` turns the sources of an answer into citations that can be pasted
+//! into a paper: BibTeX entries, Markdown footnotes, or CSL-JSON for reference managers
+//! like Zotero. A source is cited once per page, with its page number when the backend
+//! reports one.
+
+use crate::commands::{self, Source};
+use crate::printing;
+use serde::Deserialize;
+use serde_json::json;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CitationFormat {
+    Bibtex,
+    /// Markdown footnote definitions (`[^1]: …`)
+    Footnotes,
+    CslJson,
+}
+
+/// A cited file and page, with the first chunk cited from it.
+struct Citation<'a> {
+    key: String,
+    title: String,
+    filename: &'a str,
+    page: Option<u32>,
+    chunk: &'a str,
+}
+
+fn title_of(filename: &str) -> String {
+    Path::new(filename)
+        .file_stem()
+        .map(|s| s.to_string_lossy().replace(['_', '-'], " "))
+        .unwrap_or_else(|| filename.to_string())
+}
+
+/// One citation per (file, page), in the order the sources were cited, with unique keys.
+fn citations(sources: &[Source]) -> Vec<Citation<'_>> {
+    let mut citations: Vec<Citation> = Vec::new();
+    for source in sources {
+        if citations
+            .iter()
+            .any(|c| c.filename == source.filename && c.page == source.page)
+        {
+            continue;
+        }
+        let title = title_of(&source.filename);
+        let mut key: String = title
+            .to_lowercase()
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect();
+        if key.is_empty() {
+            key = "source".to_string();
+        }
+        if let Some(page) = source.page {
+            key = format!("{}_p{}", key, page);
+        }
+        let base = key.clone();
+        let mut n = 1;
+        while citations.iter().any(|c| c.key == key) {
+            n += 1;
+            key = format!("{}_{}", base, n);
+        }
+        citations.push(Citation {
+            key,
+            title,
+            filename: &source.filename,
+            page: source.page,
+            chunk: &source.chunk,
+        });
+    }
+    citations
+}
+
+/// Escape the characters BibTeX treats specially inside braces.
+fn escape_bibtex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '{' | '}' | '&' | '%' | '$' | '#' | '_' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn bibtex(citations: &[Citation]) -> String {
+    citations
+        .iter()
+        .map(|c| {
+            let mut entry = format!(
+                "@misc{{{},\n  title = {{{}}},\n  howpublished = {{{}}},\n",
+                c.key,
+                escape_bibtex(&c.title),
+                escape_bibtex(c.filename)
+            );
+            if let Some(page) = c.page {
+                entry.push_str(&format!("  pages = {{{}}},\n", page));
+            }
+            entry.push('}');
+            entry
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn footnotes(citations: &[Citation]) -> String {
+    citations
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            let page = c.page.map(|p| format!(", p. {}", p)).unwrap_or_default();
+            format!(
+                "[^{}]: {}{}. \"{}\"",
+                i + 1,
+                c.filename,
+                page,
+                printing::excerpt(c.chunk)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn csl_json(citations: &[Citation]) -> Result<String, String> {
+    let items: Vec<serde_json::Value> = citations
+        .iter()
+        .map(|c| {
+            let mut item = json!({
+                "id": c.key,
+                "type": "document",
+                "title": c.title,
+                "source": c.filename,
+            });
+            if let Some(page) = c.page {
+                item["page"] = json!(page.to_string());
+            }
+            item
+        })
+        .collect();
+    serde_json::to_string_pretty(&items).map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Export the sources of an answer as BibTeX, Markdown footnotes, or CSL-JSON
+#[tauri::command]
+pub async fn export_citations(
+    conv_id: String,
+    message_id: String,
+    format: CitationFormat,
+) -> Result<String, String> {
+    let message = commands::get_messages(conv_id)
+        .await?
+        .into_iter()
+        .find(|m| m.id == message_id)
+        .ok_or_else(|| format!("Message not found: {}", message_id))?;
+    let sources = message.sources.unwrap_or_default();
+    if sources.is_empty() {
+        return Err("This message has no sources to cite".to_string());
+    }
+    let citations = citations(&sources);
+    match format {
+        CitationFormat::Bibtex => Ok(bibtex(&citations)),
+        CitationFormat::Footnotes => Ok(footnotes(&citations)),
+        CitationFormat::CslJson => csl_json(&citations),
+    }
+}
@@ -0,0 +1,115 @@
+//! Clipboard ingestion and reading.
+//!
+//! `add_clipboard_content` turns the text on the clipboard into a Markdown document, so
+//! a selected passage or a copied table becomes part of a knowledge base instantly.
+//! Tables copied from spreadsheets arrive as tab-separated rows and are converted to
+//! Markdown tables. The document is saved under `~/.ragkit/clipboard/` and ingested
+//! like any other file. The quick-ask window reads the clipboard to offer asking about
+//! the copied text.
+
+use crate::{files, jobs, store};
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+/// Longest clipboard text handed to the quick-ask window, in characters.
+const QUICK_ASK_MAX_CHARS: usize = 4000;
+
+/// Text on the clipboard, if any.
+pub fn read_text(app: &AppHandle) -> Option<String> {
+    app.clipboard()
+        .read_text()
+        .ok()
+        .filter(|text| !text.trim().is_empty())
+}
+
+/// Convert tab-separated rows (a table copied from a spreadsheet) to a Markdown table.
+/// Other text is returned unchanged.
+fn tables_to_markdown(text: &str) -> String {
+    let rows: Vec<Vec<&str>> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.split('\t').collect())
+        .collect();
+    let columns = rows.first().map_or(0, Vec::len);
+    if rows.len() < 2 || columns < 2 || rows.iter().any(|row| row.len() != columns) {
+        return text.to_string();
+    }
+
+    let row = |cells: &[&str]| {
+        let cells: Vec<String> = cells
+            .iter()
+            .map(|cell| cell.trim().replace('|', "\\|"))
+            .collect();
+        format!("| {} |\n", cells.join(" | "))
+    };
+    let mut table = row(&rows[0]);
+    table.push_str(&format!("|{}\n", " --- |".repeat(columns)));
+    for cells in &rows[1..] {
+        table.push_str(&row(cells));
+    }
+    table
+}
+
+/// File name for a clipboard document: the title, or the first words of the text.
+fn file_name(title: Option<&str>, text: &str) -> String {
+    let source = title
+        .map(str::to_string)
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| {
+            let words: Vec<&str> = text.split_whitespace().take(8).collect();
+            format!(
+                "Clipboard {} {}",
+                chrono::Local::now().format("%Y-%m-%d %H-%M"),
+                words.join(" ")
+            )
+        });
+    files::safe_file_name(&source, "Clipboard")
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Add the text on the clipboard to a knowledge base as a new document
+///
+/// Returns an ingestion job id; progress is reported through `ingestion-progress`
+/// events.
+#[tauri::command]
+pub async fn add_clipboard_content(
+    app: AppHandle,
+    kb_id: String,
+    title: Option<String>,
+) -> Result<String, String> {
+    let text = read_text(&app).ok_or("The clipboard contains no text")?;
+    let name = file_name(title.as_deref(), &text);
+    let mut content = tables_to_markdown(&text);
+    if let Some(title) = title.filter(|t| !t.trim().is_empty()) {
+        content = format!("# {}\n\n{}", title.trim(), content);
+    }
+
+    let dir = store::ragkit_dir().join("clipboard");
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let mut path = dir.join(format!("{}.md", name));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{} ({}).md", name, n));
+        n += 1;
+    }
+    std::fs::write(&path, content).map_err(|e| e.to_string())?;
+    tracing::info!("Saved clipboard content to {}", path.display());
+
+    let paths = vec![path.to_string_lossy().into_owned()];
+    Ok(jobs::start_job(&app, kb_id, paths, jobs::IngestOptions::default()).await)
+}
+
+/// Read the text on the clipboard, shortened for the quick-ask window
+#[tauri::command]
+pub async fn read_clipboard_text(app: AppHandle) -> Result<Option<String>, String> {
+    Ok(read_text(&app).map(|text| {
+        let text = text.trim();
+        match text.char_indices().nth(QUICK_ASK_MAX_CHARS) {
+            Some((cut, _)) => format!("{}…", &text[..cut]),
+            None => text.to_string(),
+        }
+    }))
+}
@@ -0,0 +1,540 @@
+//! Google Drive and OneDrive folder sync.
+//!
+//! `link_cloud_drive` signs in with the OAuth device flow and reports the code to enter
+//! through the returned authorization; the outcome arrives as a `cloud-drive-linked`
+//! event. `sync_cloud_folder` then mirrors a remote folder, recursively, into a
+//! knowledge base: supported files are downloaded under `~/.ragkit/cloud/`, new and
+//! modified ones are ingested, and files deleted remotely are removed. Google Docs,
+//! Sheets and Slides are exported to .docx or PDF first. The remote modification time
+//! of each file and the document created for it are kept in cloud_folders.json, so
+//! each sync only downloads what changed.
+
+use crate::backend::{http_client, TimeoutTier};
+use crate::jobs::{self, FileStatus, IngestOptions};
+use crate::oauth::{self, DeviceAuthorization, OAuthProvider};
+use crate::{commands, files, store};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+
+const STATE_FILE: &str = "cloud_folders.json";
+const GOOGLE_DRIVE_API: &str = "https://www.googleapis.com/drive/v3";
+const GRAPH_API: &str = "https://graph.microsoft.com/v1.0";
+const GOOGLE_FOLDER: &str = "application/vnd.google-apps.folder";
+
+/// Google Workspace files and the format they are exported to.
+const GOOGLE_EXPORTS: &[(&str, &str, &str)] = &[
+    (
+        "application/vnd.google-apps.document",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "docx",
+    ),
+    (
+        "application/vnd.google-apps.spreadsheet",
+        "application/pdf",
+        "pdf",
+    ),
+    (
+        "application/vnd.google-apps.presentation",
+        "application/pdf",
+        "pdf",
+    ),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloudProvider {
+    GoogleDrive,
+    OneDrive,
+}
+
+impl CloudProvider {
+    const ALL: [CloudProvider; 2] = [Self::GoogleDrive, Self::OneDrive];
+
+    fn oauth(self) -> OAuthProvider {
+        match self {
+            Self::GoogleDrive => OAuthProvider::Google,
+            Self::OneDrive => OAuthProvider::Microsoft,
+        }
+    }
+
+    fn scope(self) -> &'static str {
+        match self {
+            Self::GoogleDrive => "https://www.googleapis.com/auth/drive.readonly",
+            Self::OneDrive => "Files.Read.All offline_access",
+        }
+    }
+
+    fn dir_name(self) -> &'static str {
+        match self {
+            Self::GoogleDrive => "google_drive",
+            Self::OneDrive => "onedrive",
+        }
+    }
+}
+
+/// Payload of the `cloud-drive-linked` event, sent when a device authorization ends.
+#[derive(Debug, Clone, Serialize)]
+pub struct CloudDriveLinkedEvent {
+    pub provider: CloudProvider,
+    pub linked: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CloudFile {
+    path: String,
+    modified: String,
+    document_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CloudFolder {
+    kb_id: String,
+    provider: CloudProvider,
+    folder_id: String,
+    /// Synced files by remote id
+    files: BTreeMap<String, CloudFile>,
+    last_synced_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CloudSyncResult {
+    pub kb_id: String,
+    pub provider: CloudProvider,
+    pub folder_id: String,
+    /// Supported files in the remote folder
+    pub files: usize,
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    pub duplicates: usize,
+    pub failed: usize,
+    pub job_id: Option<String>,
+}
+
+/// A file of the remote folder.
+struct RemoteFile {
+    id: String,
+    /// Path below the synced folder, with the extension it is saved under
+    relative_path: PathBuf,
+    modified: String,
+    download_url: String,
+}
+
+fn load() -> Vec<CloudFolder> {
+    store::load(STATE_FILE)
+}
+
+fn save(folders: &[CloudFolder]) -> Result<(), String> {
+    store::save(STATE_FILE, &folders).map_err(|e| e.to_string())
+}
+
+/// Make a remote name safe as a single path component.
+fn sanitize(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let name = name.trim().trim_matches('.');
+    if name.is_empty() {
+        "_".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+async fn get_json(token: &str, url: &str) -> Result<Value> {
+    let response = http_client()
+        .get(url)
+        .bearer_auth(token)
+        .timeout(TimeoutTier::Standard.timeout())
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let text = response.text().await.unwrap_or_default();
+        return Err(anyhow!("{}: {}", status, text));
+    }
+    Ok(response.json().await?)
+}
+
+async fn list_google(token: &str, folder_id: &str) -> Result<Vec<RemoteFile>> {
+    let mut files = Vec::new();
+    let mut folders = vec![(folder_id.to_string(), PathBuf::new())];
+    while let Some((folder, prefix)) = folders.pop() {
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut url = reqwest::Url::parse(&format!("{}/files", GOOGLE_DRIVE_API))?;
+            url.query_pairs_mut()
+                .append_pair(
+                    "q",
+                    &format!(
+                        "'{}' in parents and trashed = false",
+                        folder.replace('\'', "")
+                    ),
+                )
+                .append_pair(
+                    "fields",
+                    "nextPageToken,files(id,name,mimeType,modifiedTime)",
+                )
+                .append_pair("pageSize", "1000")
+                .append_pair("supportsAllDrives", "true")
+                .append_pair("includeItemsFromAllDrives", "true");
+            if let Some(page_token) = &page_token {
+                url.query_pairs_mut().append_pair("pageToken", page_token);
+            }
+            let body = get_json(token, url.as_str()).await?;
+            for item in body["files"].as_array().into_iter().flatten() {
+                let (Some(id), Some(name), Some(mime)) = (
+                    item["id"].as_str(),
+                    item["name"].as_str(),
+                    item["mimeType"].as_str(),
+                ) else {
+                    continue;
+                };
+                if mime == GOOGLE_FOLDER {
+                    folders.push((id.to_string(), prefix.join(sanitize(name))));
+                    continue;
+                }
+                let (name, download_url) =
+                    match GOOGLE_EXPORTS.iter().find(|(kind, _, _)| *kind == mime) {
+                        Some((_, export, extension)) => (
+                            format!("{}.{}", name, extension),
+                            format!(
+                                "{}/files/{}/export?mimeType={}",
+                                GOOGLE_DRIVE_API, id, export
+                            ),
+                        ),
+                        // Other Workspace files (forms, drawings...) can't be downloaded
+                        None if mime.starts_with("application/vnd.google-apps.") => continue,
+                        None => (
+                            name.to_string(),
+                            format!(
+                                "{}/files/{}?alt=media&supportsAllDrives=true",
+                                GOOGLE_DRIVE_API, id
+                            ),
+                        ),
+                    };
+                let relative_path = prefix.join(sanitize(&name));
+                if !files::is_supported(&relative_path) {
+                    continue;
+                }
+                files.push(RemoteFile {
+                    id: id.to_string(),
+                    relative_path,
+                    modified: item["modifiedTime"].as_str().unwrap_or("").to_string(),
+                    download_url,
+                });
+            }
+            match body["nextPageToken"].as_str() {
+                Some(next) => page_token = Some(next.to_string()),
+                None => break,
+            }
+        }
+    }
+    Ok(files)
+}
+
+async fn list_onedrive(token: &str, folder_id: &str) -> Result<Vec<RemoteFile>> {
+    let mut files = Vec::new();
+    let mut folders = vec![(folder_id.to_string(), PathBuf::new())];
+    while let Some((folder, prefix)) = folders.pop() {
+        let mut url = Some(format!(
+            "{}/me/drive/items/{}/children?$select=id,name,file,folder,lastModifiedDateTime",
+            GRAPH_API, folder
+        ));
+        while let Some(page) = url.take() {
+            let body = get_json(token, &page).await?;
+            for item in body["value"].as_array().into_iter().flatten() {
+                let (Some(id), Some(name)) = (item["id"].as_str(), item["name"].as_str()) else {
+                    continue;
+                };
+                if item["folder"].is_object() {
+                    folders.push((id.to_string(), prefix.join(sanitize(name))));
+                    continue;
+                }
+                let relative_path = prefix.join(sanitize(name));
+                if !item["file"].is_object() || !files::is_supported(&relative_path) {
+                    continue;
+                }
+                files.push(RemoteFile {
+                    id: id.to_string(),
+                    relative_path,
+                    modified: item["lastModifiedDateTime"]
+                        .as_str()
+                        .unwrap_or("")
+                        .to_string(),
+                    download_url: format!("{}/me/drive/items/{}/content", GRAPH_API, id),
+                });
+            }
+            url = body["@odata.nextLink"].as_str().map(String::from);
+        }
+    }
+    Ok(files)
+}
+
+async fn download(token: &str, file: &RemoteFile, target: &Path) -> Result<()> {
+    let response = http_client()
+        .get(&file.download_url)
+        .bearer_auth(token)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(anyhow!("{}", status));
+    }
+    let bytes = response.bytes().await?;
+    if let Some(parent) = target.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(target, &bytes).await?;
+    Ok(())
+}
+
+/// The provider to sync with: the one given, or the only linked one.
+fn resolve_provider(provider: Option<CloudProvider>) -> Result<CloudProvider, String> {
+    if let Some(provider) = provider {
+        return Ok(provider);
+    }
+    let linked: Vec<CloudProvider> = CloudProvider::ALL
+        .into_iter()
+        .filter(|p| oauth::is_linked(p.oauth()))
+        .collect();
+    match linked.as_slice() {
+        [provider] => Ok(*provider),
+        [] => Err("No cloud drive is linked".to_string()),
+        _ => Err("Several cloud drives are linked: choose a provider".to_string()),
+    }
+}
+
+/// Forget the cloud folders synced into a deleted knowledge base.
+pub fn remove_kb(kb_id: &str) {
+    let mut folders = load();
+    let before = folders.len();
+    folders.retain(|f| f.kb_id != kb_id);
+    if folders.len() != before {
+        if let Err(e) = save(&folders) {
+            tracing::warn!("Failed to save cloud folders: {}", e);
+        }
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Start linking a Google Drive or OneDrive account
+///
+/// Returns the code to enter on the verification page. The app keeps polling in the
+/// background and sends `cloud-drive-linked` once the user has approved or refused.
+#[tauri::command]
+pub async fn link_cloud_drive(
+    app: AppHandle,
+    provider: CloudProvider,
+) -> Result<DeviceAuthorization, String> {
+    crate::guest::ensure_not_guest("Cloud drives cannot be linked in guest mode")?;
+    crate::network::ensure_online("Linking a cloud drive")?;
+    let authorization = oauth::start_device_flow(provider.oauth(), provider.scope())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let pending = authorization.clone();
+    tauri::async_runtime::spawn(async move {
+        let outcome = oauth::complete_device_flow(&pending, provider.scope()).await;
+        if let Err(e) = &outcome {
+            tracing::warn!("Linking {:?} failed: {}", provider, e);
+        }
+        let _ = app.emit(
+            "cloud-drive-linked",
+            CloudDriveLinkedEvent {
+                provider,
+                linked: outcome.is_ok(),
+                error: outcome.err().map(|e| e.to_string()),
+            },
+        );
+    });
+    Ok(authorization)
+}
+
+/// Unlink a cloud drive; documents already synced stay in their knowledge bases
+#[tauri::command]
+pub async fn unlink_cloud_drive(provider: CloudProvider) -> Result<bool, String> {
+    oauth::unlink(provider.oauth()).map_err(|e| e.to_string())
+}
+
+/// Sync a Google Drive or OneDrive folder into a knowledge base
+///
+/// `remote_folder_id` is the folder's id in the drive (`root` for the whole OneDrive).
+/// `provider` may be omitted when a single drive is linked.
+#[tauri::command]
+pub async fn sync_cloud_folder(
+    app: AppHandle,
+    kb_id: String,
+    remote_folder_id: String,
+    provider: Option<CloudProvider>,
+) -> Result<CloudSyncResult, String> {
+    crate::network::ensure_online("Syncing a cloud drive")?;
+    let provider = resolve_provider(provider)?;
+    let token = oauth::access_token(provider.oauth())
+        .await
+        .map_err(|e| e.to_string())?;
+    let remote = match provider {
+        CloudProvider::GoogleDrive => list_google(&token, &remote_folder_id).await,
+        CloudProvider::OneDrive => list_onedrive(&token, &remote_folder_id).await,
+    }
+    .map_err(|e| format!("Failed to list the folder: {}", e))?;
+
+    let mut folder = load()
+        .into_iter()
+        .find(|f| f.kb_id == kb_id && f.provider == provider && f.folder_id == remote_folder_id)
+        .unwrap_or_else(|| CloudFolder {
+            kb_id: kb_id.clone(),
+            provider,
+            folder_id: remote_folder_id.clone(),
+            files: BTreeMap::new(),
+            last_synced_at: None,
+        });
+    let mut result = CloudSyncResult {
+        kb_id: kb_id.clone(),
+        provider,
+        folder_id: remote_folder_id.clone(),
+        files: remote.len(),
+        added: 0,
+        updated: 0,
+        removed: 0,
+        duplicates: 0,
+        failed: 0,
+        job_id: None,
+    };
+
+    // Files deleted or moved out of the folder
+    let gone: Vec<String> = folder
+        .files
+        .keys()
+        .filter(|id| !remote.iter().any(|f| &f.id == *id))
+        .cloned()
+        .collect();
+    for id in gone {
+        let file = &folder.files[&id];
+        match commands::delete_document(kb_id.clone(), file.document_id.clone()).await {
+            Ok(_) => {
+                let _ = std::fs::remove_file(&file.path);
+                folder.files.remove(&id);
+                result.removed += 1;
+            }
+            Err(e) => tracing::warn!("Failed to remove {} from KB {}: {}", file.path, kb_id, e),
+        }
+    }
+
+    let dir = store::ragkit_dir()
+        .join("cloud")
+        .join(provider.dir_name())
+        .join(sanitize(&remote_folder_id));
+    let mut by_path: BTreeMap<String, &RemoteFile> = BTreeMap::new();
+    for file in &remote {
+        if folder
+            .files
+            .get(&file.id)
+            .is_some_and(|synced| synced.modified == file.modified)
+        {
+            continue;
+        }
+        let target = dir.join(&file.relative_path);
+        match download(&token, file, &target).await {
+            Ok(()) => {
+                by_path.insert(target.to_string_lossy().into_owned(), file);
+            }
+            Err(e) => {
+                tracing::warn!("Failed to download {}: {}", file.relative_path.display(), e);
+                result.failed += 1;
+            }
+        }
+    }
+
+    if !by_path.is_empty() {
+        let paths: Vec<String> = by_path.keys().cloned().collect();
+        let job_id = jobs::start_job(&app, kb_id.clone(), paths, IngestOptions::default()).await;
+        let ingested = jobs::wait_for_job(&job_id)
+            .await
+            .map(|job| job.files)
+            .unwrap_or_default();
+        result.job_id = Some(job_id);
+
+        for file in ingested {
+            let Some(remote_file) = by_path.get(&file.path) else {
+                continue;
+            };
+            let document_id = match (file.status, file.document_id, file.duplicate_of) {
+                (FileStatus::Added, Some(document_id), _) => document_id,
+                (FileStatus::Duplicate, _, Some(duplicate_of)) => {
+                    // Touched without changes: the synced document is still current
+                    match folder
+                        .files
+                        .get_mut(&remote_file.id)
+                        .filter(|f| f.document_id == duplicate_of.document_id)
+                    {
+                        Some(synced) => synced.modified = remote_file.modified.clone(),
+                        None => result.duplicates += 1,
+                    }
+                    continue;
+                }
+                _ => {
+                    result.failed += 1;
+                    continue;
+                }
+            };
+            let synced = CloudFile {
+                path: file.path.clone(),
+                modified: remote_file.modified.clone(),
+                document_id,
+            };
+            match folder.files.insert(remote_file.id.clone(), synced) {
+                // The previous version is only removed once the new one is in
+                Some(previous) => {
+                    if let Err(e) =
+                        commands::delete_document(kb_id.clone(), previous.document_id).await
+                    {
+                        tracing::warn!(
+                            "Failed to remove the previous version of {}: {}",
+                            file.path,
+                            e
+                        );
+                    }
+                    if previous.path != file.path {
+                        let _ = std::fs::remove_file(&previous.path);
+                    }
+                    result.updated += 1;
+                }
+                None => result.added += 1,
+            }
+        }
+    }
+
+    folder.last_synced_at = Some(Utc::now());
+    let mut folders = load();
+    folders.retain(|f| {
+        !(f.kb_id == kb_id && f.provider == provider && f.folder_id == remote_folder_id)
+    });
+    folders.push(folder);
+    save(&folders)?;
+    tracing::info!(
+        "Synced {:?} folder {} into KB {}: {} added, {} updated, {} removed, {} failed",
+        provider,
+        remote_folder_id,
+        kb_id,
+        result.added,
+        result.updated,
+        result.removed,
+        result.failed
+    );
+    Ok(result)
+}
@@ -0,0 +1,294 @@
+//! Collections for organizing knowledge bases.
+//!
+//! Collections are folders of knowledge bases (e.g. one per client or project) that can
+//! be nested, ordered, and colored. They only exist in the shell: the backend still
+//! sees a flat list of knowledge bases. A knowledge base belongs to at most one
+//! collection; the others are shown unfiled.
+
+use crate::store;
+use serde::{Deserialize, Serialize};
+
+const STATE_FILE: &str = "kb_collections.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Collection {
+    pub id: String,
+    pub name: String,
+    /// `#rrggbb` color of the collection in the sidebar
+    pub color: Option<String>,
+    /// Enclosing collection, `None` at the top level
+    pub parent_id: Option<String>,
+    /// Position among the collections with the same parent
+    pub position: usize,
+    /// Knowledge bases of the collection, in display order
+    pub kb_ids: Vec<String>,
+    pub created_at: String,
+}
+
+fn load() -> Vec<Collection> {
+    let mut collections: Vec<Collection> = store::load(STATE_FILE);
+    collections.sort_by(|a, b| {
+        a.parent_id
+            .cmp(&b.parent_id)
+            .then(a.position.cmp(&b.position))
+    });
+    collections
+}
+
+fn save(collections: &[Collection]) -> Result<(), String> {
+    store::save(STATE_FILE, &collections).map_err(|e| e.to_string())
+}
+
+fn validate_color(color: Option<String>) -> Result<Option<String>, String> {
+    let Some(color) = color
+        .map(|c| c.trim().to_lowercase())
+        .filter(|c| !c.is_empty())
+    else {
+        return Ok(None);
+    };
+    let valid = color.len() == 7
+        && color.starts_with('#')
+        && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+    if valid {
+        Ok(Some(color))
+    } else {
+        Err(format!("Invalid color {} (expected #rrggbb)", color))
+    }
+}
+
+/// Whether `id` is `ancestor` or nested inside it.
+fn is_within(collections: &[Collection], id: &str, ancestor: &str) -> bool {
+    let mut current = Some(id.to_string());
+    // Bounded by the number of collections in case the stored tree has a cycle
+    for _ in 0..=collections.len() {
+        match current {
+            Some(ref c) if c == ancestor => return true,
+            Some(c) => {
+                current = collections
+                    .iter()
+                    .find(|x| x.id == c)
+                    .and_then(|x| x.parent_id.clone())
+            }
+            None => return false,
+        }
+    }
+    false
+}
+
+/// Renumber the collections under `parent_id`, placing `moved` at `position`.
+fn reorder(
+    collections: &mut [Collection],
+    parent_id: Option<&str>,
+    moved: Option<&str>,
+    position: Option<usize>,
+) {
+    let mut siblings: Vec<String> = collections
+        .iter()
+        .filter(|c| c.parent_id.as_deref() == parent_id && Some(c.id.as_str()) != moved)
+        .map(|c| c.id.clone())
+        .collect();
+    if let Some(moved) = moved {
+        let position = position.unwrap_or(siblings.len()).min(siblings.len());
+        siblings.insert(position, moved.to_string());
+    }
+    for collection in collections.iter_mut() {
+        if let Some(position) = siblings.iter().position(|id| *id == collection.id) {
+            collection.position = position;
+        }
+    }
+}
+
+/// Drop a deleted knowledge base from its collection.
+pub fn forget_kb(kb_id: &str) {
+    let mut collections = load();
+    let mut changed = false;
+    for collection in &mut collections {
+        let before = collection.kb_ids.len();
+        collection.kb_ids.retain(|id| id != kb_id);
+        changed |= collection.kb_ids.len() != before;
+    }
+    if changed {
+        if let Err(e) = save(&collections) {
+            tracing::warn!("Failed to update KB collections: {}", e);
+        }
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// List KB collections, ordered by parent and position
+#[tauri::command]
+pub async fn list_kb_collections() -> Result<Vec<Collection>, String> {
+    Ok(load())
+}
+
+/// Create a KB collection, at the end of its parent
+#[tauri::command]
+pub async fn create_kb_collection(
+    name: String,
+    color: Option<String>,
+    parent_id: Option<String>,
+) -> Result<Collection, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Collection name cannot be empty".into());
+    }
+    let color = validate_color(color)?;
+    let mut collections = load();
+    if let Some(parent_id) = &parent_id {
+        if !collections.iter().any(|c| &c.id == parent_id) {
+            return Err(format!("Collection not found: {}", parent_id));
+        }
+    }
+
+    let collection = Collection {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        color,
+        position: collections
+            .iter()
+            .filter(|c| c.parent_id == parent_id)
+            .count(),
+        parent_id,
+        kb_ids: Vec::new(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    collections.push(collection.clone());
+    save(&collections)?;
+    Ok(collection)
+}
+
+/// Rename or recolor a KB collection
+#[tauri::command]
+pub async fn update_kb_collection(
+    id: String,
+    name: String,
+    color: Option<String>,
+) -> Result<Collection, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Collection name cannot be empty".into());
+    }
+    let color = validate_color(color)?;
+    let mut collections = load();
+    let collection = collections
+        .iter_mut()
+        .find(|c| c.id == id)
+        .ok_or_else(|| format!("Collection not found: {}", id))?;
+    collection.name = name;
+    collection.color = color;
+    let updated = collection.clone();
+    save(&collections)?;
+    Ok(updated)
+}
+
+/// Move a KB collection under another parent (or to the top level) at a position
+#[tauri::command]
+pub async fn move_kb_collection(
+    id: String,
+    parent_id: Option<String>,
+    position: Option<usize>,
+) -> Result<Vec<Collection>, String> {
+    let mut collections = load();
+    let previous_parent = collections
+        .iter()
+        .find(|c| c.id == id)
+        .ok_or_else(|| format!("Collection not found: {}", id))?
+        .parent_id
+        .clone();
+    if let Some(parent_id) = &parent_id {
+        if !collections.iter().any(|c| &c.id == parent_id) {
+            return Err(format!("Collection not found: {}", parent_id));
+        }
+        if is_within(&collections, parent_id, &id) {
+            return Err("A collection cannot be moved inside itself".into());
+        }
+    }
+
+    if let Some(collection) = collections.iter_mut().find(|c| c.id == id) {
+        collection.parent_id = parent_id.clone();
+    }
+    reorder(&mut collections, previous_parent.as_deref(), None, None);
+    reorder(&mut collections, parent_id.as_deref(), Some(&id), position);
+    save(&collections)?;
+    Ok(load())
+}
+
+/// Delete a KB collection; its knowledge bases and sub-collections move to its parent
+#[tauri::command]
+pub async fn delete_kb_collection(id: String) -> Result<bool, String> {
+    let mut collections = load();
+    let Some(index) = collections.iter().position(|c| c.id == id) else {
+        return Ok(false);
+    };
+    let removed = collections.remove(index);
+
+    let mut moved = Vec::new();
+    for collection in &mut collections {
+        if collection.parent_id.as_deref() == Some(id.as_str()) {
+            collection.parent_id = removed.parent_id.clone();
+            moved.push(collection.id.clone());
+        }
+    }
+    if let Some(parent_id) = &removed.parent_id {
+        if let Some(parent) = collections.iter_mut().find(|c| &c.id == parent_id) {
+            parent.kb_ids.extend(removed.kb_ids);
+        }
+    }
+    // Sub-collections take the place of the deleted one, keeping their order
+    let mut siblings: Vec<(usize, String)> = collections
+        .iter()
+        .filter(|c| c.parent_id == removed.parent_id)
+        .map(|c| {
+            let rank = if moved.contains(&c.id) {
+                removed.position + c.position
+            } else if c.position > removed.position {
+                c.position - 1 + moved.len()
+            } else {
+                c.position
+            };
+            (rank, c.id.clone())
+        })
+        .collect();
+    siblings.sort();
+    for (position, (_, sibling)) in siblings.iter().enumerate() {
+        if let Some(collection) = collections.iter_mut().find(|c| &c.id == sibling) {
+            collection.position = position;
+        }
+    }
+
+    save(&collections)?;
+    Ok(true)
+}
+
+/// Put a knowledge base in a collection at a position, or unfile it
+#[tauri::command]
+pub async fn move_knowledge_base(
+    kb_id: String,
+    collection_id: Option<String>,
+    position: Option<usize>,
+) -> Result<Vec<Collection>, String> {
+    let mut collections = load();
+    if let Some(collection_id) = &collection_id {
+        if !collections.iter().any(|c| &c.id == collection_id) {
+            return Err(format!("Collection not found: {}", collection_id));
+        }
+    }
+
+    for collection in &mut collections {
+        collection.kb_ids.retain(|id| *id != kb_id);
+    }
+    if let Some(collection) = collections
+        .iter_mut()
+        .find(|c| Some(&c.id) == collection_id.as_ref())
+    {
+        let position = position
+            .unwrap_or(collection.kb_ids.len())
+            .min(collection.kb_ids.len());
+        collection.kb_ids.insert(position, kb_id);
+    }
+    save(&collections)?;
+    Ok(load())
+}
@@ -1,9 +1,34 @@
 //! Tauri commands that proxy to the Python backend.
 
-use crate::backend::backend_request;
+use crate::answer_presets;
+use crate::backend::{
+    backend_request, backend_request_multipart, backend_send, cancel_request, cancellable,
+    MultipartFile,
+};
+use crate::archives::ArchiveResult;
+use crate::capabilities::{self, Feature};
+use crate::dedup::{self, DuplicateOf};
+use crate::document_flags::{self, SourceWarning};
+use crate::failover::{query_with_failover, ProviderTarget};
+use crate::feedback::{self, Feedback};
+use crate::hooks::{self, HookEvent};
+use crate::jobs::IngestOptions;
+use crate::kb_history::{self, KbChange};
+use crate::latency_budget::{self, PipelineTrim, StageTimings};
+use crate::response_format::ResponseFormat;
+use crate::streaming::SseReader;
+use crate::{
+    collections, conversation_archive, conversation_forks, conversation_models, conversation_pins,
+    conversation_titles, document_tags, file_filters, file_types, files, guest, jobs, kb_settings,
+    lexical_index, os_search, preferences, prompt_templates, provenance, recommendations,
+    network, reembedding, response_format, sources, startup, usage, watcher,
+};
+use anyhow::anyhow;
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::path::PathBuf;
+use tauri::AppHandle;
 
 // ============================================================================
 // Response Types
@@ -16,7 +41,7 @@ pub struct HealthCheckResponse {
     pub error: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KnowledgeBase {
     pub id: String,
     pub name: String,
@@ -30,12 +55,42 @@ pub struct KnowledgeBase {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+pub struct Document {
+    pub id: String,
+    pub filename: String,
+    /// File size in bytes
+    pub size: Option<u64>,
+    pub chunk_count: i32,
+    pub ingested_at: String,
+    /// Ingestion status reported by the backend (e.g. "ready", "processing", "failed")
+    pub status: String,
+    /// Why ingestion failed, when it did
+    #[serde(default)]
+    pub error_message: Option<String>,
+    /// Tags such as `year:2024`, set with `set_document_tags`
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Conversation {
     pub id: String,
     pub kb_id: Option<String>,
     pub title: Option<String>,
     pub created_at: String,
     pub updated_at: String,
+    /// Provider and model pinned to this conversation, set by the shell
+    #[serde(default)]
+    pub model_override: Option<ProviderTarget>,
+    /// Hidden from the conversation list, set by the shell
+    #[serde(default)]
+    pub archived: bool,
+    /// Conversation this one was forked from
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    /// Listed before the other conversations, set by the shell
+    #[serde(default)]
+    pub pinned: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -47,6 +102,18 @@ pub struct Message {
     pub sources: Option<Vec<Source>>,
     pub latency_ms: Option<i32>,
     pub created_at: String,
+    /// Rating given to the answer, if any
+    #[serde(default)]
+    pub feedback: Option<Feedback>,
+    /// Prompt tokens sent to the LLM, estimated when the backend doesn't report them
+    #[serde(default)]
+    pub prompt_tokens: Option<u32>,
+    /// Tokens generated in the answer
+    #[serde(default)]
+    pub completion_tokens: Option<u32>,
+    /// Estimated cost of the answer in USD, when the model's price is known
+    #[serde(default)]
+    pub estimated_cost: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -54,6 +121,20 @@ pub struct Source {
     pub filename: String,
     pub chunk: String,
     pub score: f32,
+    /// SHA-256 of the source file when it was ingested
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    #[serde(default)]
+    pub ingested_at: Option<String>,
+    /// Tags of the source's document
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Page of the chunk, for paginated formats like PDF
+    #[serde(default)]
+    pub page: Option<u32>,
+    /// Index of the source this chunk nearly duplicates, set by the shell
+    #[serde(default)]
+    pub duplicate_of: Option<usize>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -61,19 +142,175 @@ pub struct QueryResponse {
     pub answer: String,
     pub sources: Vec<Source>,
     pub latency_ms: i32,
+    /// Provider that produced the answer when a failover chain was used
+    pub served_by: Option<String>,
+    /// Number of sources marked as near-duplicates by the shell
+    #[serde(default, alias = "duplicates_suppressed")]
+    pub duplicates_marked: usize,
+    /// Warnings about sources flagged as sensitive or outdated
+    #[serde(default)]
+    pub warnings: Vec<SourceWarning>,
+    /// Version of the knowledge base the answer was generated against
+    #[serde(default)]
+    pub kb_version: Option<u64>,
+    /// Id of the stored answer message, used to rate it
+    #[serde(default)]
+    pub message_id: Option<String>,
+    /// Rating given to the answer; set when a regenerated answer keeps its message
+    #[serde(default)]
+    pub feedback: Option<Feedback>,
+    /// Stage durations reported by the backend
+    #[serde(default)]
+    pub timings: Option<StageTimings>,
+    /// Pipeline stages trimmed to stay within the latency budget
+    #[serde(default)]
+    pub trimmed: Vec<PipelineTrim>,
+    /// Whether the answer took longer than the latency budget, when one is set
+    #[serde(default)]
+    pub budget_exceeded: Option<bool>,
+    /// Prompt tokens sent to the LLM, estimated when the backend doesn't report them
+    #[serde(default)]
+    pub prompt_tokens: Option<u32>,
+    /// Tokens generated in the answer
+    #[serde(default)]
+    pub completion_tokens: Option<u32>,
+    /// Estimated cost of the answer in USD, when the model's price is known
+    #[serde(default)]
+    pub estimated_cost: Option<f64>,
+    /// JSON value of a JSON or table answer, parsed by the shell
+    #[serde(default)]
+    pub structured: Option<serde_json::Value>,
+}
+
+/// A single server-sent event from `/api/query/stream`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum QueryStreamEvent {
+    Token { content: String },
+    Done(Box<QueryResponse>),
+    Error { message: String },
+}
+
+/// Payload of the `query-token` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryTokenEvent {
+    pub conversation_id: String,
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimVerdict {
+    Supported,
+    PartiallySupported,
+    Unsupported,
+}
+
+/// Verification of one sentence of an answer against the cited chunks.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SentenceVerification {
+    pub text: String,
+    /// Character offsets of the sentence within the answer
+    pub start: usize,
+    pub end: usize,
+    pub verdict: ClaimVerdict,
+    /// Indexes into the message's sources that support the sentence
+    #[serde(default)]
+    pub supporting_sources: Vec<usize>,
+    pub explanation: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+pub struct AnswerVerification {
+    pub message_id: String,
+    pub sentences: Vec<SentenceVerification>,
+    /// Share of sentences fully supported by the sources (0-1)
+    pub supported_ratio: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Retriever {
+    Semantic,
+    Lexical,
+    Rerank,
+}
+
+/// Score and rank a retriever gave a chunk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetrieverScore {
+    pub retriever: Retriever,
+    pub score: f64,
+    /// 1-based rank among that retriever's results
+    pub rank: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetrievedChunk {
+    pub chunk_id: Option<String>,
+    pub document_id: Option<String>,
+    pub filename: String,
+    pub content: String,
+    /// Final score used to order the chunks
+    pub score: f64,
+    #[serde(default)]
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+    /// Retrievers that contributed the chunk
+    #[serde(default)]
+    pub retrievers: Vec<RetrieverScore>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetrievalResult {
+    pub chunks: Vec<RetrievedChunk>,
+    /// Whether the chunks come from the shell's lexical index because the backend can't
+    /// retrieve without answering
+    #[serde(default)]
+    pub local: bool,
+    #[serde(default)]
+    pub latency_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AddFolderFailure {
     pub path: String,
     pub error: String,
+    /// The file is a scan or an image and OCR is disabled
+    #[serde(default)]
+    pub needs_ocr: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AddFolderResponse {
     pub added: Vec<String>,
     pub failed: Vec<AddFolderFailure>,
     pub total_processed: usize,
+    /// Archives unpacked during the import, with the results of their members
+    #[serde(default)]
+    pub archives: Vec<ArchiveResult>,
+    /// Files skipped because their content is already in the knowledge base
+    #[serde(default)]
+    pub skipped_duplicates: Vec<SkippedDuplicate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedDuplicate {
+    pub path: String,
+    pub duplicate_of: DuplicateOf,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RejectedPath {
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DroppedPathsResponse {
+    /// Ingestion job started for the accepted files, if any
+    pub job_id: Option<String>,
+    pub accepted: Vec<String>,
+    pub rejected: Vec<RejectedPath>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -145,11 +382,82 @@ pub struct CreateKnowledgeBaseParams {
     pub embedding_model: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct QueryParams {
     pub kb_id: String,
     pub conversation_id: String,
     pub question: String,
+    /// Caller-chosen id used to cancel the query with `cancel_query`
+    pub request_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub llm_provider: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub llm_model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embedding_fallbacks: Option<Vec<ProviderTarget>>,
+    /// Answer preset id, resolved by the shell into `answer_instructions`
+    #[serde(default, skip_serializing)]
+    pub preset: Option<String>,
+    /// Style and length instructions for the answer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub answer_instructions: Option<String>,
+    /// Prompt template id, resolved by the shell into `system_prompt`
+    #[serde(default, skip_serializing)]
+    pub template_id: Option<String>,
+    /// System prompt for the LLM, replacing the backend's default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    /// Number of chunks to retrieve, overriding the settings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<i32>,
+    /// Whether to rerank retrieved chunks, overriding the settings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rerank_enabled: Option<bool>,
+    /// Weight of semantic versus lexical retrieval (0 to 1), overriding the settings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub semantic_weight: Option<f64>,
+    /// LLM sampling temperature, overriding the settings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    /// Only retrieve from documents with these tags (e.g. `year:2024`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tag_filter: Vec<String>,
+    /// Format of the answer; Markdown when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+}
+
+/// Retrieval and generation overrides for a single question.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueryOptions {
+    pub top_k: Option<i32>,
+    pub rerank_enabled: Option<bool>,
+    pub semantic_weight: Option<f64>,
+    pub temperature: Option<f64>,
+}
+
+impl QueryOptions {
+    fn validate(&self) -> Result<(), String> {
+        if self.top_k.is_some_and(|k| !(1..=100).contains(&k)) {
+            return Err("top_k must be between 1 and 100".to_string());
+        }
+        if self.semantic_weight.is_some_and(|w| !(0.0..=1.0).contains(&w)) {
+            return Err("The semantic weight must be between 0 and 1".to_string());
+        }
+        if self.temperature.is_some_and(|t| !(0.0..=2.0).contains(&t)) {
+            return Err("The temperature must be between 0 and 2".to_string());
+        }
+        Ok(())
+    }
+
+    /// Set the overrides on a query, replacing those it already has.
+    fn apply(self, params: &mut QueryParams) {
+        params.top_k = self.top_k.or(params.top_k);
+        params.rerank_enabled = self.rerank_enabled.or(params.rerank_enabled);
+        params.semantic_weight = self.semantic_weight.or(params.semantic_weight);
+        params.temperature = self.temperature.or(params.temperature);
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -159,6 +467,12 @@ pub struct AddFolderParams {
     pub folder_path: String,
     pub recursive: bool,
     pub file_types: Vec<String>,
+    /// Preset from `get_supported_file_types`, used when `file_types` is empty
+    #[serde(default)]
+    pub file_type_preset: Option<String>,
+    /// Archive extraction and OCR
+    #[serde(flatten)]
+    pub options: IngestOptions,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -192,64 +506,328 @@ pub async fn health_check() -> Result<HealthCheckResponse, String> {
 /// List all knowledge bases
 #[tauri::command]
 pub async fn list_knowledge_bases() -> Result<Vec<KnowledgeBase>, String> {
-    backend_request(Method::GET, "/api/knowledge-bases", None)
-        .await
-        .map_err(|e| e.to_string())
+    let knowledge_bases: Vec<KnowledgeBase> =
+        backend_request(Method::GET, "/api/knowledge-bases", None)
+            .await
+            .map_err(|e| e.to_string())?;
+    startup::cache_knowledge_bases(&knowledge_bases);
+    os_search::sync_knowledge_bases(&knowledge_bases);
+    Ok(knowledge_bases)
 }
 
 /// Create a new knowledge base
 #[tauri::command]
 pub async fn create_knowledge_base(params: CreateKnowledgeBaseParams) -> Result<KnowledgeBase, String> {
-    backend_request(
+    let knowledge_base: KnowledgeBase = backend_request(
         Method::POST,
         "/api/knowledge-bases",
         Some(serde_json::to_value(&params).unwrap()),
     )
     .await
-    .map_err(|e| e.to_string())
+    .map_err(|e| e.to_string())?;
+    kb_history::record(&knowledge_base.id, KbChange::Created);
+    Ok(knowledge_base)
+}
+
+/// Rename a knowledge base or edit its description, keeping its index
+///
+/// Fields left null are unchanged; an empty description clears it.
+#[tauri::command]
+pub async fn update_knowledge_base(
+    kb_id: String,
+    name: Option<String>,
+    description: Option<String>,
+) -> Result<KnowledgeBase, String> {
+    capabilities::require(Feature::KnowledgeBaseUpdate)?;
+    let mut changes = serde_json::Map::new();
+    if let Some(name) = name {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("The knowledge base name is empty".to_string());
+        }
+        let taken = list_knowledge_bases()
+            .await?
+            .iter()
+            .any(|kb| kb.id != kb_id && kb.name.eq_ignore_ascii_case(name));
+        if taken {
+            return Err(format!("A knowledge base is already named {}", name));
+        }
+        changes.insert("name".to_string(), json!(name));
+    }
+    if let Some(description) = description {
+        let description = description.trim();
+        changes.insert(
+            "description".to_string(),
+            json!((!description.is_empty()).then_some(description)),
+        );
+    }
+    if changes.is_empty() {
+        return Err("Nothing to update".to_string());
+    }
+
+    let knowledge_base: KnowledgeBase = backend_request(
+        Method::PATCH,
+        &format!("/api/knowledge-bases/{}", kb_id),
+        Some(serde_json::Value::Object(changes)),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    // Refresh the startup cache and the OS search entries with the new name
+    if let Err(e) = list_knowledge_bases().await {
+        tracing::warn!("Failed to refresh knowledge bases after an update: {}", e);
+    }
+    Ok(knowledge_base)
 }
 
 /// Delete a knowledge base
 #[tauri::command]
 pub async fn delete_knowledge_base(kb_id: String) -> Result<bool, String> {
-    backend_request(
+    let deleted = backend_request(
         Method::DELETE,
         &format!("/api/knowledge-bases/{}", kb_id),
         None,
     )
     .await
-    .map_err(|e| e.to_string())
+    .map_err(|e| e.to_string())?;
+    kb_history::remove(&kb_id);
+    lexical_index::remove_kb(&kb_id);
+    provenance::remove_kb(&kb_id);
+    dedup::remove_kb(&kb_id);
+    kb_settings::remove(&kb_id);
+    prompt_templates::remove_kb(&kb_id);
+    watcher::forget_kb(&kb_id).await;
+    crate::scheduler::forget_kb(&kb_id).await;
+    crate::feeds::forget_kb(&kb_id).await;
+    collections::forget_kb(&kb_id);
+    crate::cloud_drive::remove_kb(&kb_id);
+    crate::connectors::remove_kb(&kb_id);
+    Ok(deleted)
 }
 
-/// Add documents to a knowledge base
+/// List the documents of a knowledge base
 #[tauri::command]
-pub async fn add_documents(kb_id: String, paths: Vec<String>) -> Result<(), String> {
-    backend_request::<serde_json::Value>(
-        Method::POST,
+pub async fn list_documents(kb_id: String) -> Result<Vec<Document>, String> {
+    backend_request(
+        Method::GET,
         &format!("/api/knowledge-bases/{}/documents", kb_id),
-        Some(json!({ "paths": paths })),
+        None,
     )
     .await
-    .map(|_| ())
     .map_err(|e| e.to_string())
 }
 
-/// Add a folder to a knowledge base
+/// Delete a document and its chunks from a knowledge base
 #[tauri::command]
-pub async fn add_folder(params: AddFolderParams) -> Result<AddFolderResponse, String> {
-    backend_request(
-        Method::POST,
-        &format!("/api/knowledge-bases/{}/folders", params.kb_id),
-        Some(json!({
-            "folder_path": params.folder_path,
-            "recursive": params.recursive,
-            "file_types": params.file_types,
-        })),
+pub async fn delete_document(kb_id: String, doc_id: String) -> Result<bool, String> {
+    // Looked up first so the history names the document rather than its id
+    let filename = list_documents(kb_id.clone())
+        .await
+        .ok()
+        .and_then(|docs| docs.into_iter().find(|d| d.id == doc_id))
+        .map_or_else(|| doc_id.clone(), |d| d.filename);
+
+    let deleted = backend_request(
+        Method::DELETE,
+        &format!("/api/knowledge-bases/{}/documents/{}", kb_id, doc_id),
+        None,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    kb_history::record(
+        &kb_id,
+        KbChange::DocumentsRemoved {
+            documents: vec![filename],
+        },
+    );
+    lexical_index::remove_document(&kb_id, &doc_id);
+    provenance::remove_document(&kb_id, &doc_id);
+    dedup::remove_document(&kb_id, &doc_id);
+    Ok(deleted)
+}
+
+/// Document text as returned by the backend.
+#[derive(Debug, Deserialize)]
+struct DocumentContent {
+    text: String,
+}
+
+/// Get the full text of a document.
+pub async fn document_text(kb_id: &str, doc_id: &str) -> Result<String, String> {
+    capabilities::require(Feature::DocumentContent)?;
+    backend_request::<DocumentContent>(
+        Method::GET,
+        &format!("/api/knowledge-bases/{}/documents/{}/content", kb_id, doc_id),
+        None,
     )
     .await
+    .map(|content| content.text)
     .map_err(|e| e.to_string())
 }
 
+#[derive(Debug, Deserialize)]
+struct UploadResponse {
+    added: Vec<String>,
+    #[serde(default)]
+    failed: Vec<AddFolderFailure>,
+}
+
+/// Upload file content to a knowledge base
+///
+/// For files that aren't on the backend's filesystem (pasted images, browser downloads).
+/// Returns the id of the new document.
+#[tauri::command]
+pub async fn upload_document(kb_id: String, filename: String, bytes: Vec<u8>) -> Result<String, String> {
+    let filename = std::path::Path::new(&filename)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .ok_or_else(|| format!("Invalid filename: {}", filename))?;
+    capabilities::require(Feature::DocumentUpload)?;
+    tracing::info!("Uploading {} ({} bytes) to KB {}", filename, bytes.len(), kb_id);
+    let stamp = provenance::stamp_bytes(&bytes);
+
+    let response: UploadResponse = backend_request_multipart(
+        Method::POST,
+        &format!("/api/knowledge-bases/{}/documents/upload", kb_id),
+        vec![MultipartFile {
+            field: "file".to_string(),
+            filename: filename.clone(),
+            bytes,
+        }],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let Some(document_id) = response.added.into_iter().next() else {
+        return Err(response
+            .failed
+            .into_iter()
+            .next()
+            .map(|f| f.error)
+            .unwrap_or_else(|| "Document could not be ingested".to_string()));
+    };
+    kb_history::record(
+        &kb_id,
+        KbChange::DocumentsAdded {
+            documents: vec![filename.clone()],
+        },
+    );
+    provenance::record_document(&kb_id, &document_id, &filename, None, stamp);
+    lexical_index::index_documents(kb_id, vec![(document_id.clone(), filename)]);
+    Ok(document_id)
+}
+
+/// Add documents to a knowledge base
+///
+/// Returns an ingestion job id immediately; progress is reported through
+/// `ingestion-progress` events. With `extract_archives`, .zip and .tar(.gz) files are
+/// unpacked and their supported members ingested. `ocr_enabled` and `ocr_language`
+/// override the OCR settings for these files.
+#[tauri::command]
+pub async fn add_documents(
+    app: AppHandle,
+    kb_id: String,
+    paths: Vec<String>,
+    extract_archives: Option<bool>,
+    ocr_enabled: Option<bool>,
+    ocr_language: Option<String>,
+) -> Result<String, String> {
+    let options = IngestOptions {
+        extract_archives: extract_archives.unwrap_or(false),
+        ocr_enabled,
+        ocr_language,
+    };
+    Ok(jobs::start_job(&app, kb_id, paths, options).await)
+}
+
+/// Add a folder to a knowledge base
+///
+/// Returns an ingestion job id immediately; the final `AddFolderResponse` is delivered
+/// with the last `ingestion-progress` event of the job.
+#[tauri::command]
+pub async fn add_folder(app: AppHandle, mut params: AddFolderParams) -> Result<String, String> {
+    if let Some(preset) = params.file_type_preset.as_deref() {
+        if params.file_types.is_empty() {
+            params.file_types = file_types::preset_extensions(preset)?;
+        }
+    }
+    watcher::remember_folder(&params).await;
+    jobs::start_folder_job(
+        &app,
+        params.kb_id,
+        PathBuf::from(params.folder_path),
+        params.recursive,
+        params.file_types,
+        params.options,
+    )
+    .await
+}
+
+/// Ingest files and folders dropped onto the window
+///
+/// Folders are expanded recursively and files with unsupported extensions are rejected
+/// before anything is sent to the backend.
+#[tauri::command]
+pub async fn ingest_dropped_paths(
+    app: AppHandle,
+    kb_id: String,
+    paths: Vec<String>,
+) -> Result<DroppedPathsResponse, String> {
+    let (accepted, rejected) = tauri::async_runtime::spawn_blocking(move || {
+        let filters = file_filters::load_filters();
+        let mut accepted = Vec::new();
+        let mut rejected = Vec::new();
+
+        for path in paths.into_iter().map(PathBuf::from) {
+            let (root, candidates) = if path.is_dir() {
+                (path.clone(), files::collect_files(&path, true, &[]))
+            } else if path.is_file() {
+                let root = path.parent().map(PathBuf::from).unwrap_or_default();
+                (root, vec![path])
+            } else {
+                rejected.push(RejectedPath {
+                    path: path.display().to_string(),
+                    reason: "File not found".to_string(),
+                });
+                continue;
+            };
+
+            for file in candidates {
+                let class = file_filters::classify(&filters, &root, &file);
+                if class != file_filters::FileClass::Accepted {
+                    rejected.push(RejectedPath {
+                        path: file.display().to_string(),
+                        reason: format!("Excluded ({:?})", class),
+                    });
+                } else if files::is_supported(&file) {
+                    accepted.push(file.display().to_string());
+                } else {
+                    rejected.push(RejectedPath {
+                        path: file.display().to_string(),
+                        reason: format!("Unsupported file type: .{}", files::extension_of(&file)),
+                    });
+                }
+            }
+        }
+
+        (accepted, rejected)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let job_id = if accepted.is_empty() {
+        None
+    } else {
+        let options = IngestOptions::default();
+        Some(jobs::start_job(&app, kb_id, accepted.clone(), options).await)
+    };
+
+    Ok(DroppedPathsResponse {
+        job_id,
+        accepted,
+        rejected,
+    })
+}
+
 /// Validate a knowledge base folder
 #[tauri::command]
 pub async fn validate_folder(path: String) -> Result<FolderValidationResult, String> {
@@ -266,16 +844,32 @@ pub async fn validate_folder(path: String) -> Result<FolderValidationResult, Str
     })
 }
 
-/// List conversations
+/// List conversations, without the archived ones unless `include_archived` is true
 #[tauri::command]
-pub async fn list_conversations(kb_id: Option<String>) -> Result<Vec<Conversation>, String> {
-    let path = match kb_id {
+pub async fn list_conversations(
+    kb_id: Option<String>,
+    include_archived: Option<bool>,
+) -> Result<Vec<Conversation>, String> {
+    let path = match &kb_id {
         Some(id) => format!("/api/conversations?kb_id={}", id),
         None => "/api/conversations".to_string(),
     };
-    backend_request(Method::GET, &path, None)
+    let mut conversations: Vec<Conversation> = backend_request(Method::GET, &path, None)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+    conversation_models::annotate(&mut conversations);
+    conversation_titles::annotate(&mut conversations);
+    conversation_archive::annotate(&mut conversations);
+    conversation_forks::annotate(&mut conversations);
+    conversation_pins::annotate(&mut conversations);
+    if kb_id.is_none() {
+        startup::cache_conversations(&conversations);
+        os_search::sync_conversations(&conversations);
+    }
+    if !include_archived.unwrap_or(false) {
+        conversations.retain(|c| !c.archived);
+    }
+    Ok(conversations)
 }
 
 /// Create a new conversation
@@ -293,39 +887,419 @@ pub async fn create_conversation(kb_id: Option<String>) -> Result<Conversation,
 /// Delete a conversation
 #[tauri::command]
 pub async fn delete_conversation(conv_id: String) -> Result<bool, String> {
-    backend_request(
+    let deleted = backend_request(
         Method::DELETE,
         &format!("/api/conversations/{}", conv_id),
         None,
     )
     .await
-    .map_err(|e| e.to_string())
+    .map_err(|e| e.to_string())?;
+    conversation_models::remove(&conv_id);
+    conversation_titles::remove(&conv_id);
+    conversation_archive::remove(&conv_id);
+    conversation_forks::remove(&conv_id);
+    conversation_pins::remove(&conv_id);
+    prompt_templates::remove_conversation(&conv_id);
+    Ok(deleted)
 }
 
 /// Get messages in a conversation
 #[tauri::command]
 pub async fn get_messages(conv_id: String) -> Result<Vec<Message>, String> {
-    backend_request(
+    let mut messages: Vec<Message> = backend_request(
         Method::GET,
         &format!("/api/conversations/{}/messages", conv_id),
         None,
     )
     .await
-    .map_err(|e| e.to_string())
+    .map_err(|e| e.to_string())?;
+    feedback::annotate(&mut messages);
+    usage::annotate(&mut messages);
+    Ok(messages)
+}
+
+/// Resolve the shell-side options of a question: the conversation's model, the
+/// knowledge base settings, the answer preset, the prompt template, the tag filters and
+/// the response format. Every path that asks the backend for an answer goes through it.
+fn prepare_query(params: &mut QueryParams) -> Result<(), String> {
+    conversation_models::apply(params);
+    kb_settings::apply(params);
+    answer_presets::apply(params);
+    prompt_templates::apply(params);
+    document_tags::apply(params)?;
+    response_format::apply(params);
+    Ok(())
 }
 
 /// Query the knowledge base
 #[tauri::command]
-pub async fn query(params: QueryParams) -> Result<QueryResponse, String> {
+pub async fn query(app: AppHandle, mut params: QueryParams) -> Result<QueryResponse, String> {
+    crate::reembedding::mark_activity();
+    crate::shortcuts::remember_kb(&params.kb_id);
+    prepare_query(&mut params)?;
+    let budget = latency_budget::apply(&mut params).await;
+    let model = usage::resolve_model(&params).await;
+    cancellable(params.request_id.as_deref(), query_with_failover(&params))
+        .await
+        .map(|response| latency_budget::finish(&params.kb_id, budget, response))
+        .map(|response| usage::record(&params, model.as_ref(), response))
+        .map(|response| postprocess_response(&params, response))
+        .inspect(|response| after_answer(&app, &params, response))
+        .map_err(|e| e.to_string())
+}
+
+/// Query the knowledge base with retrieval and generation overrides for this question
+/// only, leaving the settings untouched
+#[tauri::command]
+pub async fn query_with_options(
+    app: AppHandle,
+    mut params: QueryParams,
+    options: QueryOptions,
+) -> Result<QueryResponse, String> {
+    options.validate()?;
+    options.apply(&mut params);
+    query(app, params).await
+}
+
+/// Query the knowledge base, streaming answer tokens as `query-token` events.
+///
+/// Resolves with the complete response (answer and sources) once generation finishes.
+#[tauri::command]
+pub async fn query_stream(
+    app: AppHandle,
+    mut params: QueryParams,
+) -> Result<QueryResponse, String> {
+    crate::reembedding::mark_activity();
+    crate::shortcuts::remember_kb(&params.kb_id);
+    prepare_query(&mut params)?;
+    let budget = latency_budget::apply(&mut params).await;
+    let model = usage::resolve_model(&params).await;
+    lexical_index::emit_instant_results(&app, &params);
+    cancellable(params.request_id.as_deref(), async {
+        if !capabilities::supports(Feature::QueryStream) {
+            // Older backends only answer in one piece
+            return query_with_failover(&params).await;
+        }
+        let response = backend_send(
+            Method::POST,
+            "/api/query/stream",
+            Some(serde_json::to_value(&params).unwrap()),
+        )
+        .await?;
+        read_query_stream(&app, &params.conversation_id, response).await
+    })
+    .await
+    .map(|response| latency_budget::finish(&params.kb_id, budget, response))
+    .map(|response| usage::record(&params, model.as_ref(), response))
+    .map(|response| postprocess_response(&params, response))
+    .inspect(|response| after_answer(&app, &params, response))
+    .map_err(|e| e.to_string())
+}
+
+/// Apply shell-side filters to a query response.
+fn postprocess_response(params: &QueryParams, response: QueryResponse) -> QueryResponse {
+    let kb_id = params.kb_id.as_str();
+    let mut response = response_format::finish(params, response);
+    let prefs = preferences::load();
+    if prefs.suppress_duplicate_sources {
+        response.duplicates_marked = sources::mark_near_duplicates(
+            &mut response.sources,
+            prefs.duplicate_source_threshold,
+        );
+    }
+    provenance::annotate(kb_id, &mut response.sources);
+    response.warnings = document_flags::warnings_for(kb_id, &response.sources);
+    response.kb_version = Some(kb_history::current_version(kb_id));
+    response
+}
+
+/// Follow-ups of a saved answer: the conversation title, document recommendations and
+/// `answer_saved` hooks.
+fn after_answer(app: &AppHandle, params: &QueryParams, response: &QueryResponse) {
+    conversation_titles::title_after_answer(app, &params.conversation_id);
+    recommendations::emit_after_answer(app, &params.kb_id, &params.conversation_id);
+    if let Some(message_id) = &response.message_id {
+        provenance::record_answer(message_id, &params.kb_id, &response.sources);
+    }
+    let sources: Vec<&str> = response
+        .sources
+        .iter()
+        .map(|s| s.filename.as_str())
+        .collect();
+    hooks::fire(
+        HookEvent::AnswerSaved,
+        json!({
+            "kb_id": params.kb_id,
+            "conversation_id": params.conversation_id,
+            "message_id": response.message_id,
+            "question": params.question,
+            "answer": response.answer,
+            "sources": sources,
+        }),
+    );
+}
+
+/// Forward streamed tokens as events and return the final response.
+async fn read_query_stream(
+    app: &AppHandle,
+    conversation_id: &str,
+    response: reqwest::Response,
+) -> anyhow::Result<QueryResponse> {
+    let mut events = SseReader::new(response);
+
+    while let Some(data) = events
+        .next_event()
+        .await
+        .map_err(|e| anyhow!("Stream interrupted: {}", e))?
+    {
+        let event: QueryStreamEvent =
+            serde_json::from_str(&data).map_err(|e| anyhow!("Invalid stream event: {}", e))?;
+        match event {
+            QueryStreamEvent::Token { content } => {
+                crate::windows::emit_to_conversation(
+                    app,
+                    conversation_id,
+                    "query-token",
+                    QueryTokenEvent {
+                        conversation_id: conversation_id.to_string(),
+                        token: content,
+                    },
+                );
+            }
+            QueryStreamEvent::Done(response) => return Ok(*response),
+            QueryStreamEvent::Error { message } => return Err(anyhow!(message)),
+        }
+    }
+
+    Err(anyhow!("Stream ended before the answer was complete"))
+}
+
+/// Cancel a running query started with a `request_id`
+#[tauri::command]
+pub async fn cancel_query(request_id: String) -> Result<bool, String> {
+    let cancelled = cancel_request(&request_id);
+    if cancelled && capabilities::supports(Feature::QueryCancel) {
+        // Let the backend stop generating; the client side is already gone either way.
+        let _ = backend_request::<serde_json::Value>(
+            Method::POST,
+            &format!("/api/query/{}/cancel", request_id),
+            None,
+        )
+        .await;
+    }
+    Ok(cancelled)
+}
+
+/// Knowledge base a conversation belongs to.
+pub async fn conversation_kb(conv_id: &str) -> Result<String, String> {
+    list_conversations(None, Some(true))
+        .await?
+        .into_iter()
+        .find(|c| c.id == conv_id)
+        .ok_or_else(|| format!("Conversation not found: {}", conv_id))?
+        .kb_id
+        .ok_or_else(|| "The conversation is not linked to a knowledge base".to_string())
+}
+
+/// Answer an existing user turn again, with fresh retrieval and generation
+///
+/// `message_id` is the user message or the answer to regenerate. The new answer
+/// replaces the previous one unless `replace` is false, in which case it is appended as
+/// an alternative. Backends without regeneration ask the question again as a new turn.
+#[tauri::command]
+pub async fn regenerate_answer(
+    app: AppHandle,
+    conv_id: String,
+    message_id: String,
+    replace: Option<bool>,
+    request_id: Option<String>,
+) -> Result<QueryResponse, String> {
+    let messages = get_messages(conv_id.clone()).await?;
+    let index = messages
+        .iter()
+        .position(|m| m.id == message_id)
+        .ok_or_else(|| format!("Message not found: {}", message_id))?;
+    // An answer is regenerated from the question before it
+    let turn = messages[..=index]
+        .iter()
+        .rev()
+        .find(|m| m.role == "user")
+        .ok_or("No question to regenerate an answer for")?;
+
+    let mut params = QueryParams {
+        kb_id: conversation_kb(&conv_id).await?,
+        conversation_id: conv_id.clone(),
+        question: turn.content.clone(),
+        request_id,
+        ..Default::default()
+    };
+    if !capabilities::supports(Feature::AnswerRegeneration) {
+        return query(app, params).await;
+    }
+    prepare_query(&mut params)?;
+    let budget = latency_budget::apply(&mut params).await;
+    let model = usage::resolve_model(&params).await;
+
+    let mut body = serde_json::to_value(&params).unwrap();
+    body["replace"] = json!(replace.unwrap_or(true));
+    cancellable(
+        params.request_id.as_deref(),
+        backend_request::<QueryResponse>(
+            Method::POST,
+            &format!(
+                "/api/conversations/{}/messages/{}/regenerate",
+                conv_id, turn.id
+            ),
+            Some(body),
+        ),
+    )
+    .await
+    .map(|response| latency_budget::finish(&params.kb_id, budget, response))
+    .map(|response| usage::record(&params, model.as_ref(), response))
+    .map(|response| postprocess_response(&params, response))
+    .inspect(|response| after_answer(&app, &params, response))
+    .map_err(|e| e.to_string())
+}
+
+/// Edit a question and answer it again
+///
+/// The messages after the edited user turn are removed from the conversation, and the
+/// response is the new answer.
+#[tauri::command]
+pub async fn edit_message(
+    app: AppHandle,
+    conv_id: String,
+    message_id: String,
+    new_content: String,
+    request_id: Option<String>,
+) -> Result<QueryResponse, String> {
+    capabilities::require(Feature::MessageEdit)?;
+    let new_content = new_content.trim().to_string();
+    if new_content.is_empty() {
+        return Err("The message cannot be empty".to_string());
+    }
+    let messages = get_messages(conv_id.clone()).await?;
+    let message = messages
+        .iter()
+        .find(|m| m.id == message_id)
+        .ok_or_else(|| format!("Message not found: {}", message_id))?;
+    if message.role != "user" {
+        return Err("Only questions can be edited".to_string());
+    }
+
+    let mut params = QueryParams {
+        kb_id: conversation_kb(&conv_id).await?,
+        conversation_id: conv_id.clone(),
+        question: new_content,
+        request_id,
+        ..Default::default()
+    };
+    crate::reembedding::mark_activity();
+    prepare_query(&mut params)?;
+    let budget = latency_budget::apply(&mut params).await;
+    let model = usage::resolve_model(&params).await;
+
+    // The backend replaces the question, drops the turns after it and answers again
+    cancellable(
+        params.request_id.as_deref(),
+        backend_request::<QueryResponse>(
+            Method::PUT,
+            &format!("/api/conversations/{}/messages/{}", conv_id, message_id),
+            Some(serde_json::to_value(&params).unwrap()),
+        ),
+    )
+    .await
+    .map(|response| latency_budget::finish(&params.kb_id, budget, response))
+    .map(|response| usage::record(&params, model.as_ref(), response))
+    .map(|response| postprocess_response(&params, response))
+    .inspect(|response| after_answer(&app, &params, response))
+    .map_err(|e| e.to_string())
+}
+
+/// Check each sentence of an answer against its cited chunks
+///
+/// Returns per-sentence supported/unsupported annotations the UI can highlight.
+#[tauri::command]
+pub async fn verify_answer(message_id: String) -> Result<AnswerVerification, String> {
+    capabilities::require(Feature::AnswerVerification)?;
     backend_request(
         Method::POST,
-        "/api/query",
-        Some(serde_json::to_value(&params).unwrap()),
+        &format!("/api/messages/{}/verify", message_id),
+        None,
     )
     .await
     .map_err(|e| e.to_string())
 }
 
+/// Retrieve the chunks a question would get, without generating an answer
+///
+/// Shows each chunk's score and which retrievers contributed it, to debug why the LLM
+/// didn't get the right context. Backends without retrieval-only queries fall back to
+/// the shell's lexical index.
+#[tauri::command]
+pub async fn retrieve_chunks(
+    kb_id: String,
+    question: String,
+    top_k: Option<i32>,
+) -> Result<RetrievalResult, String> {
+    let started = std::time::Instant::now();
+    if capabilities::supports(Feature::Retrieval) {
+        let mut params = QueryParams {
+            kb_id: kb_id.clone(),
+            question,
+            top_k,
+            ..Default::default()
+        };
+        kb_settings::apply(&mut params);
+        let chunks: Vec<RetrievedChunk> = backend_request(
+            Method::POST,
+            "/api/retrieve",
+            Some(json!({
+                "kb_id": params.kb_id,
+                "question": params.question,
+                "top_k": params.top_k,
+                "rerank_enabled": params.rerank_enabled,
+                "semantic_weight": params.semantic_weight,
+            })),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+        return Ok(RetrievalResult {
+            chunks,
+            local: false,
+            latency_ms: started.elapsed().as_millis() as u64,
+        });
+    }
+
+    let limit = top_k.unwrap_or(10).max(1) as usize;
+    let chunks = tauri::async_runtime::spawn_blocking(move || {
+        lexical_index::search(&kb_id, &question, limit)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .into_iter()
+    .enumerate()
+    .map(|(i, result)| RetrievedChunk {
+        chunk_id: None,
+        document_id: Some(result.document_id),
+        filename: result.filename,
+        content: result.excerpt,
+        score: result.score,
+        metadata: serde_json::Map::new(),
+        retrievers: vec![RetrieverScore {
+            retriever: Retriever::Lexical,
+            score: result.score,
+            rank: Some(i + 1),
+        }],
+    })
+    .collect();
+    Ok(RetrievalResult {
+        chunks,
+        local: true,
+        latency_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
 /// Get settings
 #[tauri::command]
 pub async fn get_settings() -> Result<Settings, String> {
@@ -336,14 +1310,45 @@ pub async fn get_settings() -> Result<Settings, String> {
 
 /// Update settings
 #[tauri::command]
-pub async fn update_settings(settings: Settings) -> Result<Settings, String> {
-    backend_request(
+pub async fn update_settings(app: AppHandle, settings: Settings) -> Result<Settings, String> {
+    network::check_settings(&settings)?;
+    let previous = get_settings().await.ok();
+    let updated: Settings = backend_request(
         Method::PUT,
         "/api/settings",
         Some(serde_json::to_value(&settings).unwrap()),
     )
     .await
-    .map_err(|e| e.to_string())
+    .map_err(|e| e.to_string())?;
+
+    let fields = changed_fields(previous.as_ref(), &updated);
+    // Existing embeddings can't be compared with the new model's
+    if previous.is_some()
+        && fields
+            .iter()
+            .any(|f| f == "embedding_provider" || f == "embedding_model")
+    {
+        reembedding::on_embedding_model_changed(&app, &updated).await;
+    }
+    if !fields.is_empty() {
+        kb_history::record_global(KbChange::SettingsChanged { fields });
+    }
+    Ok(updated)
+}
+
+/// Top-level settings fields that differ between two versions (all of them when the
+/// previous settings are unknown).
+fn changed_fields(previous: Option<&Settings>, updated: &Settings) -> Vec<String> {
+    let to_map = |s: &Settings| match serde_json::to_value(s) {
+        Ok(serde_json::Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    };
+    let before = previous.map(to_map).unwrap_or_default();
+    to_map(updated)
+        .into_iter()
+        .filter(|(key, value)| before.get(key) != Some(value))
+        .map(|(key, _)| key)
+        .collect()
 }
 
 // ============================================================================
@@ -375,6 +1380,7 @@ pub async fn detect_environment() -> Result<serde_json::Value, String> {
 /// Set an API key
 #[tauri::command]
 pub async fn set_api_key(provider: String, api_key: String) -> Result<(), String> {
+    guest::ensure_not_guest("API keys cannot be changed in guest mode")?;
     backend_request::<serde_json::Value>(
         Method::POST,
         "/api/keys",
@@ -406,6 +1412,7 @@ pub async fn has_api_key(provider: String) -> Result<bool, String> {
 /// Delete an API key
 #[tauri::command]
 pub async fn delete_api_key(provider: String) -> Result<bool, String> {
+    guest::ensure_not_guest("API keys cannot be changed in guest mode")?;
     backend_request(
         Method::DELETE,
         &format!("/api/keys/{}", provider),
@@ -443,12 +1450,6 @@ pub struct InstallInstructions {
     pub all_platforms: std::collections::HashMap<String, String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct TestApiKeyResponse {
-    ok: bool,
-    error: Option<String>,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LogEntry {
     pub timestamp: String,
@@ -459,20 +1460,6 @@ pub struct LogEntry {
     pub exception: Option<String>,
 }
 
-#[tauri::command]
-pub async fn test_api_key(provider: String, api_key: String) -> Result<TestApiKeyResponse, String> {
-    backend_request(
-        Method::POST,
-        "/api/keys/test",
-        Some(serde_json::json!({
-            "provider": provider,
-            "api_key": api_key,
-        })),
-    )
-    .await
-    .map_err(|e| e.to_string())
-}
-
 #[tauri::command]
 pub async fn get_logs(limit: usize) -> Result<Vec<LogEntry>, String> {
     backend_request(
@@ -528,19 +1515,6 @@ pub async fn get_ollama_embedding_models() -> Result<serde_json::Value, String>
         .map_err(|e| e.to_string())
 }
 
-/// Pull (download) an Ollama model
-#[tauri::command]
-pub async fn pull_ollama_model(model_name: String) -> Result<(), String> {
-    backend_request::<serde_json::Value>(
-        Method::POST,
-        "/api/ollama/pull",
-        Some(json!({ "model_name": model_name })),
-    )
-    .await
-    .map(|_| ())
-    .map_err(|e| e.to_string())
-}
-
 /// Delete an Ollama model
 #[tauri::command]
 pub async fn delete_ollama_model(model_name: String) -> Result<(), String> {
@@ -0,0 +1,405 @@
+//! Startup configuration file.
+//!
+//! ~/.ragkit/config.toml holds the few settings needed before the shell can read its
+//! preferences or reach the backend, so that administrators can preset them on managed
+//! machines:
+//!
+//! ```toml
+//! backend_port = 8123
+//! data_dir = "D:/ragkit-data"
+//! log_level = "debug"
+//! proxy = "http://proxy.corp:3128"
+//! theme = "dark"
+//! ```
+//!
+//! Each key can be overridden by a `RAGKIT_*` environment variable. The file is read
+//! once at startup; invalid values are ignored with a warning. It always lives in
+//! ~/.ragkit, even when `data_dir` moves the rest of the data elsewhere.
+//! `get_effective_config` reports the value in use for each key and where it came from.
+
+use crate::{guest, preferences, store};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const CONFIG_FILE: &str = "config.toml";
+const LOG_LEVELS: &[&str] = &["error", "warn", "info", "debug", "trace"];
+const THEMES: &[&str] = &["light", "dark", "system"];
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ConfigFile {
+    backend_port: Option<u16>,
+    data_dir: Option<String>,
+    log_level: Option<String>,
+    proxy: Option<String>,
+    theme: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigSource {
+    Default,
+    /// config.toml
+    File,
+    /// A `RAGKIT_*` environment variable
+    Environment,
+    /// The shell preferences
+    Preferences,
+    /// The temporary directory of guest mode
+    Guest,
+}
+
+#[derive(Debug, Clone)]
+pub struct Sourced<T> {
+    pub value: T,
+    pub source: ConfigSource,
+}
+
+/// Values set by the configuration file or the environment.
+#[derive(Debug, Default)]
+pub struct Config {
+    pub backend_port: Option<Sourced<u16>>,
+    pub data_dir: Option<Sourced<PathBuf>>,
+    pub log_level: Option<Sourced<String>>,
+    pub proxy: Option<Sourced<reqwest::Url>>,
+    pub theme: Option<Sourced<String>>,
+    /// Whether config.toml exists
+    pub file_found: bool,
+    /// Invalid values and parse errors, logged once logging is set up
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigEntry {
+    pub key: &'static str,
+    pub value: serde_json::Value,
+    pub source: ConfigSource,
+    /// Environment variable overriding the key
+    pub env_var: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveConfig {
+    pub path: String,
+    pub file_found: bool,
+    pub entries: Vec<ConfigEntry>,
+    pub warnings: Vec<String>,
+}
+
+/// Path of config.toml, in ~/.ragkit whatever the data directory.
+pub fn path() -> PathBuf {
+    store::default_dir().join(CONFIG_FILE)
+}
+
+fn parse_port(value: &str) -> Option<u16> {
+    value.trim().parse::<u16>().ok().filter(|p| *p > 0)
+}
+
+fn parse_data_dir(value: &str) -> Option<PathBuf> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    match value
+        .strip_prefix("~/")
+        .or_else(|| value.strip_prefix("~\\"))
+    {
+        Some(rest) => Some(store::home_dir().join(rest)),
+        None => Some(PathBuf::from(value)),
+    }
+}
+
+fn parse_log_level(value: &str) -> Option<String> {
+    let value = value.trim().to_lowercase();
+    LOG_LEVELS.contains(&value.as_str()).then_some(value)
+}
+
+fn parse_theme(value: &str) -> Option<String> {
+    let value = value.trim().to_lowercase();
+    THEMES.contains(&value.as_str()).then_some(value)
+}
+
+/// Value of a key: the environment variable, then the file. Invalid values are skipped.
+fn layer<T>(
+    key: &str,
+    file_value: Option<String>,
+    env_var: &str,
+    parse: fn(&str) -> Option<T>,
+    warnings: &mut Vec<String>,
+) -> Option<Sourced<T>> {
+    if let Ok(value) = std::env::var(env_var) {
+        match parse(&value) {
+            Some(value) => {
+                return Some(Sourced {
+                    value,
+                    source: ConfigSource::Environment,
+                })
+            }
+            None => warnings.push(format!("Ignoring invalid {}: {}", env_var, value)),
+        }
+    }
+    let value = file_value?;
+    match parse(&value) {
+        Some(value) => Some(Sourced {
+            value,
+            source: ConfigSource::File,
+        }),
+        None => {
+            warnings.push(format!(
+                "Ignoring invalid {} in {}: {}",
+                key, CONFIG_FILE, value
+            ));
+            None
+        }
+    }
+}
+
+fn load() -> Config {
+    let path = path();
+    let mut warnings = Vec::new();
+    let content = std::fs::read_to_string(&path).ok();
+    let file = match content.as_deref().map(toml::from_str::<ConfigFile>) {
+        Some(Ok(file)) => file,
+        Some(Err(e)) => {
+            warnings.push(format!("Ignoring invalid {}: {}", path.display(), e));
+            ConfigFile::default()
+        }
+        None => ConfigFile::default(),
+    };
+
+    Config {
+        backend_port: layer(
+            "backend_port",
+            file.backend_port.map(|p| p.to_string()),
+            "RAGKIT_BACKEND_PORT",
+            parse_port,
+            &mut warnings,
+        ),
+        data_dir: layer(
+            "data_dir",
+            file.data_dir,
+            "RAGKIT_DATA_DIR",
+            parse_data_dir,
+            &mut warnings,
+        ),
+        log_level: layer(
+            "log_level",
+            file.log_level,
+            "RAGKIT_LOG_LEVEL",
+            parse_log_level,
+            &mut warnings,
+        ),
+        proxy: layer(
+            "proxy",
+            file.proxy,
+            "RAGKIT_PROXY",
+            crate::proxy::parse_proxy_url,
+            &mut warnings,
+        ),
+        theme: layer(
+            "theme",
+            file.theme,
+            "RAGKIT_THEME",
+            parse_theme,
+            &mut warnings,
+        ),
+        file_found: content.is_some(),
+        warnings,
+    }
+}
+
+/// The configuration read at startup.
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(load)
+}
+
+/// Set `data_dir` in config.toml, or remove it to use the default directory, keeping
+/// the other keys. Takes effect on the next start.
+pub fn save_data_dir(dir: Option<&Path>) -> Result<()> {
+    let path = path();
+    let mut table = match std::fs::read_to_string(&path) {
+        Ok(content) => content
+            .parse::<toml::Table>()
+            .map_err(|e| anyhow!("Invalid {}: {}", path.display(), e))?,
+        Err(_) => toml::Table::new(),
+    };
+    match dir {
+        Some(dir) => {
+            table.insert(
+                "data_dir".to_string(),
+                toml::Value::String(dir.display().to_string()),
+            );
+        }
+        None => {
+            table.remove("data_dir");
+        }
+    }
+
+    let parent = store::default_dir();
+    std::fs::create_dir_all(&parent)
+        .map_err(|e| anyhow!("Failed to create {}: {}", parent.display(), e))?;
+    let tmp_path = parent.join(format!("{}.tmp", CONFIG_FILE));
+    std::fs::write(&tmp_path, toml::to_string(&table)?)
+        .map_err(|e| anyhow!("Failed to write {}: {}", tmp_path.display(), e))?;
+    std::fs::rename(&tmp_path, &path)
+        .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+/// Maximum level of the log file (info by default).
+pub fn log_level() -> tracing::Level {
+    match get().log_level.as_ref().map(|l| l.value.as_str()) {
+        Some("error") => tracing::Level::ERROR,
+        Some("warn") => tracing::Level::WARN,
+        Some("debug") => tracing::Level::DEBUG,
+        Some("trace") => tracing::Level::TRACE,
+        _ => tracing::Level::INFO,
+    }
+}
+
+/// Log where the configuration came from and its invalid values.
+pub fn log_startup() {
+    let config = get();
+    if config.file_found {
+        tracing::info!("Configuration file: {}", path().display());
+    }
+    for warning in &config.warnings {
+        tracing::warn!("{}", warning);
+    }
+}
+
+/// Apply the configured theme to the main window, until the frontend applies the
+/// backend's.
+pub fn apply_theme(app: &tauri::AppHandle) {
+    use tauri::Manager;
+
+    let Some(theme) = &get().theme else {
+        return;
+    };
+    let theme = match theme.value.as_str() {
+        "light" => Some(tauri::Theme::Light),
+        "dark" => Some(tauri::Theme::Dark),
+        _ => None,
+    };
+    if let Some(window) = app.get_webview_window("main") {
+        if let Err(e) = window.set_theme(theme) {
+            tracing::warn!("Failed to set the window theme: {}", e);
+        }
+    }
+}
+
+fn entry<T: Serialize>(
+    key: &'static str,
+    env_var: &'static str,
+    configured: Option<&Sourced<T>>,
+    fallback: (serde_json::Value, ConfigSource),
+) -> ConfigEntry {
+    let (value, source) = match configured {
+        Some(sourced) => (
+            serde_json::to_value(&sourced.value).unwrap_or_default(),
+            sourced.source,
+        ),
+        None => fallback,
+    };
+    ConfigEntry {
+        key,
+        value,
+        source,
+        env_var,
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Get the value in use for each key of config.toml and where it came from
+#[tauri::command]
+pub async fn get_effective_config() -> Result<EffectiveConfig, String> {
+    let config = get();
+    let prefs = preferences::load();
+    let defaults = preferences::Preferences::default();
+
+    let port = match prefs.backend_port.filter(|p| *p > 0) {
+        Some(port) => (serde_json::json!(port), ConfigSource::Preferences),
+        None => {
+            let range = format!("{}-{}", prefs.backend_port_min, prefs.backend_port_max);
+            let source = if (prefs.backend_port_min, prefs.backend_port_max)
+                == (defaults.backend_port_min, defaults.backend_port_max)
+            {
+                ConfigSource::Default
+            } else {
+                ConfigSource::Preferences
+            };
+            (serde_json::json!(range), source)
+        }
+    };
+    let data_dir = store::ragkit_dir().display().to_string();
+    let data_dir_entry = match guest::data_dir() {
+        Some(_) => ConfigEntry {
+            key: "data_dir",
+            value: serde_json::json!(data_dir),
+            source: ConfigSource::Guest,
+            env_var: "RAGKIT_DATA_DIR",
+        },
+        None => entry(
+            "data_dir",
+            "RAGKIT_DATA_DIR",
+            config.data_dir.as_ref(),
+            (serde_json::json!(data_dir), ConfigSource::Default),
+        ),
+    };
+    let proxy_source = if prefs.proxy_mode == defaults.proxy_mode {
+        ConfigSource::Default
+    } else {
+        ConfigSource::Preferences
+    };
+    let proxy_mode = serde_json::to_value(prefs.proxy_mode).unwrap_or_default();
+    // Never report proxy credentials
+    let proxy = config.proxy.as_ref().map(|p| {
+        let mut url = p.value.clone();
+        let _ = url.set_username("");
+        let _ = url.set_password(None);
+        Sourced {
+            value: url.to_string(),
+            source: p.source,
+        }
+    });
+
+    Ok(EffectiveConfig {
+        path: path().display().to_string(),
+        file_found: config.file_found,
+        entries: vec![
+            entry(
+                "backend_port",
+                "RAGKIT_BACKEND_PORT",
+                config.backend_port.as_ref(),
+                port,
+            ),
+            data_dir_entry,
+            entry(
+                "log_level",
+                "RAGKIT_LOG_LEVEL",
+                config.log_level.as_ref(),
+                (serde_json::json!("info"), ConfigSource::Default),
+            ),
+            entry(
+                "proxy",
+                "RAGKIT_PROXY",
+                proxy.as_ref(),
+                (proxy_mode, proxy_source),
+            ),
+            entry(
+                "theme",
+                "RAGKIT_THEME",
+                config.theme.as_ref(),
+                (serde_json::json!("system"), ConfigSource::Default),
+            ),
+        ],
+        warnings: config.warnings.clone(),
+    })
+}
@@ -0,0 +1,627 @@
+//! Notion and Confluence connectors.
+//!
+//! A connector holds the credentials of a workspace: a Notion integration token, or a
+//! Confluence base URL with an API token (and the account email on Confluence Cloud).
+//! `sync_connector` pulls the pages edited since the last sync of the same knowledge
+//! base, writes each as Markdown under `~/.ragkit/connectors/<id>/`, and ingests them;
+//! the previous version of an updated page is removed once the new one is in. The sync
+//! cursor and the document created for each page are stored with the connector in
+//! connectors.json. Pages deleted upstream are not detected, since neither API reports
+//! them incrementally.
+
+use crate::backend::http_client;
+use crate::jobs::{self, FileStatus, IngestOptions};
+use crate::{commands, extraction, files, store};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+const STATE_FILE: &str = "connectors.json";
+const NOTION_API: &str = "https://api.notion.com/v1";
+const NOTION_VERSION: &str = "2022-06-28";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectorKind {
+    Notion,
+    Confluence,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectorCredentials {
+    /// Notion integration token, or Confluence API token / personal access token
+    pub token: String,
+    /// Confluence site, e.g. `https://example.atlassian.net/wiki`
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Confluence Cloud account email (Data Center tokens are used without one)
+    #[serde(default)]
+    pub email: Option<String>,
+    /// Only sync this Confluence space
+    #[serde(default)]
+    pub space_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SyncedPage {
+    document_id: String,
+    title: String,
+    edited_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SyncState {
+    /// Latest edit time among the synced pages
+    cursor: Option<DateTime<Utc>>,
+    pages: BTreeMap<String, SyncedPage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Connector {
+    id: String,
+    kind: ConnectorKind,
+    name: String,
+    credentials: ConnectorCredentials,
+    created_at: DateTime<Utc>,
+    /// Sync state per knowledge base
+    #[serde(default)]
+    syncs: BTreeMap<String, SyncState>,
+}
+
+/// A configured connector, without its credentials.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectorInfo {
+    pub id: String,
+    pub kind: ConnectorKind,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub synced_kbs: Vec<ConnectorKbSync>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectorKbSync {
+    pub kb_id: String,
+    pub cursor: Option<DateTime<Utc>>,
+    pub pages: usize,
+}
+
+impl From<&Connector> for ConnectorInfo {
+    fn from(connector: &Connector) -> Self {
+        Self {
+            id: connector.id.clone(),
+            kind: connector.kind,
+            name: connector.name.clone(),
+            created_at: connector.created_at,
+            synced_kbs: connector
+                .syncs
+                .iter()
+                .map(|(kb_id, state)| ConnectorKbSync {
+                    kb_id: kb_id.clone(),
+                    cursor: state.cursor,
+                    pages: state.pages.len(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectorSyncResult {
+    pub connector_id: String,
+    pub kb_id: String,
+    /// Pages edited since the previous sync
+    pub fetched: usize,
+    pub added: usize,
+    pub updated: usize,
+    /// Pages whose text is already in the knowledge base as another document
+    pub duplicates: usize,
+    pub failed: usize,
+    pub job_id: Option<String>,
+    pub cursor: Option<DateTime<Utc>>,
+}
+
+/// A page pulled from a workspace.
+struct RemotePage {
+    id: String,
+    title: String,
+    url: Option<String>,
+    edited_at: DateTime<Utc>,
+    markdown: String,
+}
+
+fn load() -> Vec<Connector> {
+    store::load(STATE_FILE)
+}
+
+fn save(connectors: &[Connector]) -> Result<(), String> {
+    store::save(STATE_FILE, &connectors).map_err(|e| e.to_string())
+}
+
+async fn check_response(response: reqwest::Response) -> Result<Value> {
+    let status = response.status();
+    if !status.is_success() {
+        let text = response.text().await.unwrap_or_default();
+        return Err(anyhow!("{}: {}", status, text));
+    }
+    Ok(response.json().await?)
+}
+
+// ============================================================================
+// Notion
+// ============================================================================
+
+fn notion_request(
+    credentials: &ConnectorCredentials,
+    method: reqwest::Method,
+    path: &str,
+) -> reqwest::RequestBuilder {
+    http_client()
+        .request(method, format!("{}{}", NOTION_API, path))
+        .bearer_auth(&credentials.token)
+        .header("Notion-Version", NOTION_VERSION)
+}
+
+fn plain_text(rich_text: &Value) -> String {
+    rich_text
+        .as_array()
+        .map(|parts| {
+            parts
+                .iter()
+                .filter_map(|p| p["plain_text"].as_str())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn notion_title(page: &Value) -> String {
+    page["properties"]
+        .as_object()
+        .and_then(|properties| {
+            properties
+                .values()
+                .find(|p| p["type"] == "title")
+                .map(|p| plain_text(&p["title"]))
+        })
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| "Untitled".to_string())
+}
+
+/// Markdown line of a block. Nested blocks (e.g. toggle contents) are not fetched.
+fn notion_block(block: &Value) -> Option<String> {
+    let kind = block["type"].as_str()?;
+    let text = plain_text(&block[kind]["rich_text"]);
+    Some(match kind {
+        "heading_1" => format!("# {}", text),
+        "heading_2" => format!("## {}", text),
+        "heading_3" => format!("### {}", text),
+        "bulleted_list_item" | "toggle" => format!("- {}", text),
+        "numbered_list_item" => format!("1. {}", text),
+        "to_do" => {
+            let checked = block[kind]["checked"].as_bool().unwrap_or(false);
+            format!("- [{}] {}", if checked { "x" } else { " " }, text)
+        }
+        "quote" | "callout" => format!("> {}", text),
+        "code" => {
+            let language = block[kind]["language"].as_str().unwrap_or("");
+            format!("```{}\n{}\n```", language, text)
+        }
+        "paragraph" if !text.trim().is_empty() => text,
+        _ => return None,
+    })
+}
+
+async fn notion_markdown(credentials: &ConnectorCredentials, page_id: &str) -> Result<String> {
+    let mut lines = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut path = format!("/blocks/{}/children?page_size=100", page_id);
+        if let Some(cursor) = &cursor {
+            path.push_str(&format!("&start_cursor={}", cursor));
+        }
+        let body = check_response(
+            notion_request(credentials, reqwest::Method::GET, &path)
+                .send()
+                .await?,
+        )
+        .await?;
+        if let Some(blocks) = body["results"].as_array() {
+            lines.extend(blocks.iter().filter_map(notion_block));
+        }
+        match body["next_cursor"].as_str() {
+            Some(next) if body["has_more"] == true => cursor = Some(next.to_string()),
+            _ => break,
+        }
+    }
+    Ok(lines.join("\n\n"))
+}
+
+/// Pages edited after `since`, newest first.
+async fn notion_pages(
+    credentials: &ConnectorCredentials,
+    since: Option<DateTime<Utc>>,
+) -> Result<Vec<RemotePage>> {
+    let mut pages = Vec::new();
+    let mut cursor: Option<String> = None;
+    'search: loop {
+        let mut query = json!({
+            "filter": { "property": "object", "value": "page" },
+            "sort": { "direction": "descending", "timestamp": "last_edited_time" },
+            "page_size": 100,
+        });
+        if let Some(cursor) = &cursor {
+            query["start_cursor"] = json!(cursor);
+        }
+        let body = check_response(
+            notion_request(credentials, reqwest::Method::POST, "/search")
+                .json(&query)
+                .send()
+                .await?,
+        )
+        .await?;
+
+        for page in body["results"].as_array().into_iter().flatten() {
+            let (Some(id), Some(edited_at)) = (
+                page["id"].as_str(),
+                page["last_edited_time"]
+                    .as_str()
+                    .and_then(|t| DateTime::parse_from_rfc3339(t).ok()),
+            ) else {
+                continue;
+            };
+            let edited_at = edited_at.with_timezone(&Utc);
+            // Sorted by edit time: everything after this was synced already
+            if since.is_some_and(|since| edited_at <= since) {
+                break 'search;
+            }
+            pages.push(RemotePage {
+                id: id.to_string(),
+                title: notion_title(page),
+                url: page["url"].as_str().map(String::from),
+                edited_at,
+                markdown: notion_markdown(credentials, id).await?,
+            });
+        }
+        match body["next_cursor"].as_str() {
+            Some(next) if body["has_more"] == true => cursor = Some(next.to_string()),
+            _ => break,
+        }
+    }
+    Ok(pages)
+}
+
+// ============================================================================
+// Confluence
+// ============================================================================
+
+fn confluence_request(
+    credentials: &ConnectorCredentials,
+    path: &str,
+) -> Result<reqwest::RequestBuilder> {
+    let base_url = credentials
+        .base_url
+        .as_deref()
+        .ok_or_else(|| anyhow!("The Confluence base URL is missing"))?
+        .trim_end_matches('/');
+    let request = http_client().get(format!("{}{}", base_url, path));
+    Ok(match &credentials.email {
+        Some(email) => request.basic_auth(email, Some(&credentials.token)),
+        None => request.bearer_auth(&credentials.token),
+    })
+}
+
+/// Pages edited after `since`.
+async fn confluence_pages(
+    credentials: &ConnectorCredentials,
+    since: Option<DateTime<Utc>>,
+) -> Result<Vec<RemotePage>> {
+    let mut cql = "type = page".to_string();
+    if let Some(space) = &credentials.space_key {
+        cql.push_str(&format!(" AND space = \"{}\"", space.replace('"', "")));
+    }
+    // CQL compares dates in the server's time zone: query from the day before and
+    // filter precisely below
+    if let Some(since) = since {
+        let day = (since - chrono::Duration::days(1)).format("%Y-%m-%d");
+        cql.push_str(&format!(" AND lastmodified >= \"{}\"", day));
+    }
+    cql.push_str(" ORDER BY lastmodified DESC");
+
+    let mut pages = Vec::new();
+    let mut start = 0;
+    loop {
+        let body = check_response(
+            confluence_request(credentials, "/rest/api/content/search")?
+                .query(&[
+                    ("cql", cql.as_str()),
+                    ("expand", "body.storage,version"),
+                    ("limit", "50"),
+                    ("start", &start.to_string()),
+                ])
+                .send()
+                .await?,
+        )
+        .await?;
+        let results = body["results"].as_array().cloned().unwrap_or_default();
+        for page in &results {
+            let (Some(id), Some(edited_at)) = (
+                page["id"].as_str(),
+                page["version"]["when"]
+                    .as_str()
+                    .and_then(|t| DateTime::parse_from_rfc3339(t).ok()),
+            ) else {
+                continue;
+            };
+            let edited_at = edited_at.with_timezone(&Utc);
+            if since.is_some_and(|since| edited_at <= since) {
+                continue;
+            }
+            let url = match (
+                credentials.base_url.as_deref(),
+                page["_links"]["webui"].as_str(),
+            ) {
+                (Some(base), Some(path)) => Some(format!("{}{}", base.trim_end_matches('/'), path)),
+                _ => None,
+            };
+            pages.push(RemotePage {
+                id: id.to_string(),
+                title: page["title"].as_str().unwrap_or("Untitled").to_string(),
+                url,
+                edited_at,
+                markdown: extraction::xhtml_to_markdown(
+                    page["body"]["storage"]["value"].as_str().unwrap_or(""),
+                ),
+            });
+        }
+        if results.is_empty() || body["_links"]["next"].is_null() {
+            break;
+        }
+        start += results.len();
+    }
+    Ok(pages)
+}
+
+// ============================================================================
+// Sync
+// ============================================================================
+
+/// Check the credentials, returning a name for the connector.
+async fn verify(kind: ConnectorKind, credentials: &ConnectorCredentials) -> Result<String> {
+    match kind {
+        ConnectorKind::Notion => {
+            let bot = check_response(
+                notion_request(credentials, reqwest::Method::GET, "/users/me")
+                    .send()
+                    .await?,
+            )
+            .await?;
+            let workspace = bot["bot"]["workspace_name"].as_str().unwrap_or("workspace");
+            Ok(format!("Notion ({})", workspace))
+        }
+        ConnectorKind::Confluence => {
+            check_response(
+                confluence_request(credentials, "/rest/api/space")?
+                    .query(&[("limit", "1")])
+                    .send()
+                    .await?,
+            )
+            .await?;
+            let site = credentials
+                .base_url
+                .as_deref()
+                .and_then(|url| reqwest::Url::parse(url).ok())
+                .and_then(|url| url.host_str().map(String::from))
+                .unwrap_or_else(|| "site".to_string());
+            Ok(format!("Confluence ({})", site))
+        }
+    }
+}
+
+/// Write a page as Markdown, returning its path. The page id keeps names unique.
+fn write_page(dir: &std::path::Path, page: &RemotePage) -> std::io::Result<PathBuf> {
+    let short_id: String = page
+        .id
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .take(8)
+        .collect();
+    let name = files::safe_file_name(&page.title, "Untitled");
+    let path = dir.join(format!("{} ({}).md", name, short_id));
+    let mut markdown = format!("---\ntitle: \"{}\"\n", page.title.replace('"', "'"));
+    markdown.push_str(&format!("date: {}\n", page.edited_at.to_rfc3339()));
+    if let Some(url) = &page.url {
+        markdown.push_str(&format!("source: {}\n", url));
+    }
+    markdown.push_str(&format!("---\n\n# {}\n\n{}\n", page.title, page.markdown));
+    std::fs::write(&path, markdown)?;
+    Ok(path)
+}
+
+/// Forget the sync state of a deleted knowledge base.
+pub fn remove_kb(kb_id: &str) {
+    let mut connectors = load();
+    let mut changed = false;
+    for connector in &mut connectors {
+        changed |= connector.syncs.remove(kb_id).is_some();
+    }
+    if changed {
+        if let Err(e) = save(&connectors) {
+            tracing::warn!("Failed to save connectors: {}", e);
+        }
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// List the configured connectors and the knowledge bases they sync
+#[tauri::command]
+pub async fn list_connectors() -> Result<Vec<ConnectorInfo>, String> {
+    Ok(load().iter().map(ConnectorInfo::from).collect())
+}
+
+/// Add a Notion or Confluence connector after checking its credentials
+#[tauri::command]
+pub async fn configure_connector(
+    kind: ConnectorKind,
+    credentials: ConnectorCredentials,
+) -> Result<ConnectorInfo, String> {
+    crate::guest::ensure_not_guest("Connectors cannot be configured in guest mode")?;
+    if credentials.token.trim().is_empty() {
+        return Err("The token is missing".to_string());
+    }
+    let name = verify(kind, &credentials)
+        .await
+        .map_err(|e| format!("The credentials were rejected: {}", e))?;
+
+    let connector = Connector {
+        id: uuid::Uuid::new_v4().to_string(),
+        kind,
+        name,
+        credentials,
+        created_at: Utc::now(),
+        syncs: BTreeMap::new(),
+    };
+    let mut connectors = load();
+    connectors.push(connector.clone());
+    save(&connectors)?;
+    Ok(ConnectorInfo::from(&connector))
+}
+
+/// Remove a connector; documents already synced stay in their knowledge bases
+#[tauri::command]
+pub async fn remove_connector(connector_id: String) -> Result<bool, String> {
+    let mut connectors = load();
+    let before = connectors.len();
+    connectors.retain(|c| c.id != connector_id);
+    if connectors.len() == before {
+        return Ok(false);
+    }
+    save(&connectors)?;
+    let _ = std::fs::remove_dir_all(store::ragkit_dir().join("connectors").join(&connector_id));
+    Ok(true)
+}
+
+/// Pull the pages edited since the last sync into a knowledge base
+#[tauri::command]
+pub async fn sync_connector(
+    app: AppHandle,
+    connector_id: String,
+    kb_id: String,
+) -> Result<ConnectorSyncResult, String> {
+    let connector = load()
+        .into_iter()
+        .find(|c| c.id == connector_id)
+        .ok_or_else(|| format!("Unknown connector: {}", connector_id))?;
+    match (connector.kind, &connector.credentials.base_url) {
+        (ConnectorKind::Confluence, Some(base_url)) => crate::network::check_url(base_url)?,
+        _ => crate::network::check_url(NOTION_API)?,
+    }
+    let mut state = connector.syncs.get(&kb_id).cloned().unwrap_or_default();
+
+    let pages = match connector.kind {
+        ConnectorKind::Notion => notion_pages(&connector.credentials, state.cursor).await,
+        ConnectorKind::Confluence => confluence_pages(&connector.credentials, state.cursor).await,
+    }
+    .map_err(|e| format!("Failed to read {}: {}", connector.name, e))?;
+
+    let mut result = ConnectorSyncResult {
+        connector_id: connector_id.clone(),
+        kb_id: kb_id.clone(),
+        fetched: pages.len(),
+        added: 0,
+        updated: 0,
+        duplicates: 0,
+        failed: 0,
+        job_id: None,
+        cursor: state.cursor,
+    };
+    if pages.is_empty() {
+        return Ok(result);
+    }
+
+    let dir = store::ragkit_dir().join("connectors").join(&connector_id);
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let mut by_path: BTreeMap<String, &RemotePage> = BTreeMap::new();
+    for page in &pages {
+        let path = write_page(&dir, page).map_err(|e| e.to_string())?;
+        by_path.insert(path.to_string_lossy().into_owned(), page);
+    }
+
+    let paths: Vec<String> = by_path.keys().cloned().collect();
+    let job_id = jobs::start_job(&app, kb_id.clone(), paths, IngestOptions::default()).await;
+    let files = jobs::wait_for_job(&job_id)
+        .await
+        .map(|job| job.files)
+        .unwrap_or_default();
+    result.job_id = Some(job_id);
+
+    for file in files {
+        let Some(page) = by_path.get(&file.path) else {
+            continue;
+        };
+        let document_id = match (file.status, file.document_id, file.duplicate_of) {
+            (FileStatus::Added, Some(document_id), _) => document_id,
+            (FileStatus::Duplicate, _, Some(duplicate_of)) => {
+                // Edited without changing its text: the synced document is still current
+                match state
+                    .pages
+                    .get_mut(&page.id)
+                    .filter(|p| p.document_id == duplicate_of.document_id)
+                {
+                    Some(synced) => synced.edited_at = page.edited_at,
+                    None => result.duplicates += 1,
+                }
+                continue;
+            }
+            _ => {
+                result.failed += 1;
+                continue;
+            }
+        };
+        let synced = SyncedPage {
+            document_id,
+            title: page.title.clone(),
+            edited_at: page.edited_at,
+        };
+        match state.pages.insert(page.id.clone(), synced) {
+            // The previous version is only removed once the new one is in
+            Some(previous) => {
+                if let Err(e) = commands::delete_document(kb_id.clone(), previous.document_id).await
+                {
+                    tracing::warn!(
+                        "Failed to remove the previous version of {}: {}",
+                        page.title,
+                        e
+                    );
+                }
+                result.updated += 1;
+            }
+            None => result.added += 1,
+        }
+    }
+    // Failed pages are retried at the next sync
+    if result.failed == 0 {
+        state.cursor = pages.iter().map(|p| p.edited_at).max().max(state.cursor);
+    }
+    result.cursor = state.cursor;
+
+    let mut connectors = load();
+    if let Some(entry) = connectors.iter_mut().find(|c| c.id == connector_id) {
+        entry.syncs.insert(kb_id.clone(), state);
+        save(&connectors)?;
+    }
+    tracing::info!(
+        "Synced {} into KB {}: {} added, {} updated, {} failed",
+        connector.name,
+        kb_id,
+        result.added,
+        result.updated,
+        result.failed
+    );
+    Ok(result)
+}
@@ -0,0 +1,60 @@
+//! Archived conversations.
+//!
+//! Archiving hides a conversation from the conversation list without deleting it. The
+//! backend has no notion of archiving, so the shell keeps the archived conversations
+//! and filters them out of `list_conversations` unless asked for them.
+
+use crate::commands::Conversation;
+use crate::store;
+use std::collections::BTreeMap;
+
+const STATE_FILE: &str = "conversation_archive.json";
+
+/// Archived conversations, with the time they were archived.
+type Archive = BTreeMap<String, chrono::DateTime<chrono::Utc>>;
+
+fn set_archived(conv_id: &str, archived: bool) -> Result<(), String> {
+    let mut archive: Archive = store::load(STATE_FILE);
+    let changed = if archived {
+        archive
+            .insert(conv_id.to_string(), chrono::Utc::now())
+            .is_none()
+    } else {
+        archive.remove(conv_id).is_some()
+    };
+    if changed {
+        store::save(STATE_FILE, &archive).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Fill in the archived flag of each conversation.
+pub fn annotate(conversations: &mut [Conversation]) {
+    let archive: Archive = store::load(STATE_FILE);
+    for conversation in conversations {
+        conversation.archived = archive.contains_key(&conversation.id);
+    }
+}
+
+/// Forget a deleted conversation.
+pub fn remove(conv_id: &str) {
+    if let Err(e) = set_archived(conv_id, false) {
+        tracing::warn!("Failed to unarchive {}: {}", conv_id, e);
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Hide a conversation from the conversation list
+#[tauri::command]
+pub async fn archive_conversation(conv_id: String) -> Result<(), String> {
+    set_archived(&conv_id, true)
+}
+
+/// Show an archived conversation in the conversation list again
+#[tauri::command]
+pub async fn unarchive_conversation(conv_id: String) -> Result<(), String> {
+    set_archived(&conv_id, false)
+}
@@ -0,0 +1,89 @@
+//! Conversation forks.
+//!
+//! Forking copies a conversation's history up to a message into a new conversation, so
+//! an alternative follow-up can be explored without losing the original thread. The
+//! backend copies the messages; the shell remembers where each fork came from, carries
+//! over the conversation's pinned model, and fills in `parent_id` when listing
+//! conversations.
+
+use crate::backend::backend_request;
+use crate::capabilities::{self, Feature};
+use crate::commands::{self, Conversation};
+use crate::{conversation_models, store};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+const STATE_FILE: &str = "conversation_forks.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ForkOrigin {
+    parent_id: String,
+    from_message_id: String,
+    forked_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Origin of each forked conversation, by conversation id.
+type Forks = BTreeMap<String, ForkOrigin>;
+
+/// Fill in the parent of each forked conversation, unless the backend already did.
+pub fn annotate(conversations: &mut [Conversation]) {
+    let forks: Forks = store::load(STATE_FILE);
+    for conversation in conversations {
+        if conversation.parent_id.is_none() {
+            conversation.parent_id = forks.get(&conversation.id).map(|f| f.parent_id.clone());
+        }
+    }
+}
+
+/// Forget the origin of a deleted conversation. Its forks keep their `parent_id`.
+pub fn remove(conv_id: &str) {
+    let mut forks: Forks = store::load(STATE_FILE);
+    if forks.remove(conv_id).is_some() {
+        if let Err(e) = store::save(STATE_FILE, &forks) {
+            tracing::warn!("Failed to remove fork origin of {}: {}", conv_id, e);
+        }
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Create a new conversation with the history of another up to and including a message
+#[tauri::command]
+pub async fn fork_conversation(
+    conv_id: String,
+    from_message_id: String,
+) -> Result<Conversation, String> {
+    capabilities::require(Feature::ConversationFork)?;
+    let messages = commands::get_messages(conv_id.clone()).await?;
+    if !messages.iter().any(|m| m.id == from_message_id) {
+        return Err(format!("Message not found: {}", from_message_id));
+    }
+
+    let mut fork: Conversation = backend_request(
+        Method::POST,
+        &format!("/api/conversations/{}/fork", conv_id),
+        Some(json!({ "from_message_id": from_message_id })),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut forks: Forks = store::load(STATE_FILE);
+    forks.insert(
+        fork.id.clone(),
+        ForkOrigin {
+            parent_id: conv_id.clone(),
+            from_message_id,
+            forked_at: chrono::Utc::now(),
+        },
+    );
+    store::save(STATE_FILE, &forks).map_err(|e| e.to_string())?;
+    conversation_models::copy(&conv_id, &fork.id);
+
+    fork.parent_id.get_or_insert(conv_id);
+    conversation_models::annotate(std::slice::from_mut(&mut fork));
+    Ok(fork)
+}
@@ -0,0 +1,86 @@
+//! Per-conversation LLM overrides.
+//!
+//! A conversation can be pinned to a specific provider and model (e.g. a cheap local
+//! model for quick questions) without touching the global settings. The shell stores
+//! the override and adds it to every query sent for that conversation.
+
+use crate::commands::{Conversation, QueryParams};
+use crate::failover::ProviderTarget;
+use crate::store;
+use std::collections::BTreeMap;
+
+const STATE_FILE: &str = "conversation_models.json";
+
+type Overrides = BTreeMap<String, ProviderTarget>;
+
+/// Use the conversation's model unless the caller already chose one for this query.
+pub fn apply(params: &mut QueryParams) {
+    if params.llm_provider.is_some() {
+        return;
+    }
+    let mut overrides: Overrides = store::load(STATE_FILE);
+    if let Some(target) = overrides.remove(&params.conversation_id) {
+        params.llm_provider = Some(target.provider);
+        params.llm_model = Some(target.model);
+    }
+}
+
+/// Fill in the model override of each conversation.
+pub fn annotate(conversations: &mut [Conversation]) {
+    let overrides: Overrides = store::load(STATE_FILE);
+    for conversation in conversations {
+        conversation.model_override = overrides.get(&conversation.id).cloned();
+    }
+}
+
+/// Pin a new conversation to the same model as the one it was created from.
+pub fn copy(from_conv_id: &str, to_conv_id: &str) {
+    let mut overrides: Overrides = store::load(STATE_FILE);
+    let Some(target) = overrides.get(from_conv_id).cloned() else {
+        return;
+    };
+    overrides.insert(to_conv_id.to_string(), target);
+    if let Err(e) = store::save(STATE_FILE, &overrides) {
+        tracing::warn!("Failed to copy model override to {}: {}", to_conv_id, e);
+    }
+}
+
+/// Forget the override of a deleted conversation.
+pub fn remove(conv_id: &str) {
+    let mut overrides: Overrides = store::load(STATE_FILE);
+    if overrides.remove(conv_id).is_some() {
+        if let Err(e) = store::save(STATE_FILE, &overrides) {
+            tracing::warn!("Failed to remove model override of {}: {}", conv_id, e);
+        }
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Get the model override of a conversation
+#[tauri::command]
+pub async fn get_conversation_model(conv_id: String) -> Result<Option<ProviderTarget>, String> {
+    let mut overrides: Overrides = store::load(STATE_FILE);
+    Ok(overrides.remove(&conv_id))
+}
+
+/// Pin a conversation to a provider and model, or clear the override with `None`
+#[tauri::command]
+pub async fn set_conversation_model(
+    conv_id: String,
+    target: Option<ProviderTarget>,
+) -> Result<Option<ProviderTarget>, String> {
+    let mut overrides: Overrides = store::load(STATE_FILE);
+    match &target {
+        Some(target) => {
+            overrides.insert(conv_id, target.clone());
+        }
+        None => {
+            overrides.remove(&conv_id);
+        }
+    }
+    store::save(STATE_FILE, &overrides).map_err(|e| e.to_string())?;
+    Ok(target)
+}
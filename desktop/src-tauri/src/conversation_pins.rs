@@ -0,0 +1,62 @@
+//! Pinned conversations.
+//!
+//! Pinned conversations are listed before the others, so frequently used threads stay
+//! at the top of long lists. Like archiving, pinning is kept by the shell only.
+
+use crate::commands::Conversation;
+use crate::store;
+use std::collections::BTreeMap;
+
+const STATE_FILE: &str = "conversation_pins.json";
+
+/// Pinned conversations, with the time they were pinned.
+type Pins = BTreeMap<String, chrono::DateTime<chrono::Utc>>;
+
+fn set_pinned(conv_id: &str, pinned: bool) -> Result<(), String> {
+    let mut pins: Pins = store::load(STATE_FILE);
+    let changed = if pinned {
+        // Pinning again keeps the conversation's place among the pinned ones
+        if pins.contains_key(conv_id) {
+            false
+        } else {
+            pins.insert(conv_id.to_string(), chrono::Utc::now());
+            true
+        }
+    } else {
+        pins.remove(conv_id).is_some()
+    };
+    if changed {
+        store::save(STATE_FILE, &pins).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Fill in the pinned flag of each conversation and move the pinned ones first, in the
+/// order they were pinned. The others keep their order.
+pub fn annotate(conversations: &mut [Conversation]) {
+    let pins: Pins = store::load(STATE_FILE);
+    for conversation in conversations.iter_mut() {
+        conversation.pinned = pins.contains_key(&conversation.id);
+    }
+    conversations.sort_by_key(|c| match pins.get(&c.id) {
+        Some(pinned_at) => (0, Some(*pinned_at)),
+        None => (1, None),
+    });
+}
+
+/// Forget a deleted conversation.
+pub fn remove(conv_id: &str) {
+    if let Err(e) = set_pinned(conv_id, false) {
+        tracing::warn!("Failed to unpin {}: {}", conv_id, e);
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Pin a conversation to the top of the conversation list, or unpin it
+#[tauri::command]
+pub async fn pin_conversation(conv_id: String, pinned: bool) -> Result<(), String> {
+    set_pinned(&conv_id, pinned)
+}
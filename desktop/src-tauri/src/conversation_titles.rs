@@ -0,0 +1,202 @@
+//! Conversation titles.
+//!
+//! Conversations are created without a title, and a list full of "Untitled" is hard
+//! to navigate. After the first exchange of an untitled conversation, the backend
+//! summarizes it into a short title. Backends without that endpoint get a title cut
+//! from the first question instead, which the shell keeps and fills in when listing
+//! conversations. Renaming works the same way: the backend saves the title when it can
+//! update conversations, the shell keeps it otherwise.
+
+use crate::backend::backend_request;
+use crate::capabilities::{self, Feature};
+use crate::commands::{self, Conversation};
+use crate::{store, windows};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+const STATE_FILE: &str = "conversation_titles.json";
+
+/// Longest title cut from a question, in characters.
+const MAX_TITLE_CHARS: usize = 60;
+
+/// Conversations whose title is being generated, so concurrent answers don't
+/// generate it twice.
+static GENERATING: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+type LocalTitles = BTreeMap<String, String>;
+
+#[derive(Debug, Deserialize)]
+struct TitleResponse {
+    title: String,
+}
+
+/// Payload of the `conversation-titled` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversationTitled {
+    pub conversation_id: String,
+    pub title: String,
+}
+
+/// First line of the question, cut at a word boundary.
+fn title_from_question(question: &str) -> Option<String> {
+    let line = question.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let mut title = String::new();
+    for word in &words {
+        let len = title.chars().count() + word.chars().count() + 1;
+        if len > MAX_TITLE_CHARS && !title.is_empty() {
+            return Some(format!("{}…", title.trim_end_matches([',', ';', ':'])));
+        }
+        if !title.is_empty() {
+            title.push(' ');
+        }
+        title.push_str(word);
+    }
+    Some(title.chars().take(MAX_TITLE_CHARS).collect())
+}
+
+async fn generate(conv_id: &str) -> Result<String, String> {
+    let messages = commands::get_messages(conv_id.to_string()).await?;
+    let first_question = messages.iter().find(|m| m.role == "user");
+    let answered = messages.iter().any(|m| m.role == "assistant");
+    let Some(first_question) = first_question.filter(|_| answered) else {
+        return Err("The conversation has no answered question yet".to_string());
+    };
+
+    if capabilities::supports(Feature::ConversationTitles) {
+        let generated = backend_request::<TitleResponse>(
+            Method::POST,
+            &format!("/api/conversations/{}/title", conv_id),
+            None,
+        )
+        .await;
+        match generated {
+            Ok(generated) if !generated.title.trim().is_empty() => {
+                return Ok(generated.title.trim().to_string())
+            }
+            Ok(_) => tracing::debug!("Backend returned an empty title for {}", conv_id),
+            Err(e) => tracing::debug!("Falling back to a local title: {}", e),
+        }
+    }
+
+    let title =
+        title_from_question(&first_question.content).ok_or("The first question is empty")?;
+    let mut titles: LocalTitles = store::load(STATE_FILE);
+    titles.insert(conv_id.to_string(), title.clone());
+    store::save(STATE_FILE, &titles).map_err(|e| e.to_string())?;
+    Ok(title)
+}
+
+/// Generate a title and tell the windows showing the conversation.
+async fn generate_and_emit(app: &AppHandle, conv_id: &str) -> Result<String, String> {
+    if !GENERATING
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashSet::new)
+        .insert(conv_id.to_string())
+    {
+        return Err("The title of this conversation is already being generated".to_string());
+    }
+    let result = generate(conv_id).await;
+    if let Some(generating) = GENERATING.lock().unwrap().as_mut() {
+        generating.remove(conv_id);
+    }
+
+    let title = result?;
+    tracing::info!("Titled conversation {}: {}", conv_id, title);
+    let event = ConversationTitled {
+        conversation_id: conv_id.to_string(),
+        title: title.clone(),
+    };
+    windows::emit_to_conversation(app, conv_id, "conversation-titled", event);
+    Ok(title)
+}
+
+/// Title a conversation that just got an answer if it has none yet, in the background.
+pub fn title_after_answer(app: &AppHandle, conv_id: &str) {
+    let app = app.clone();
+    let conv_id = conv_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        let untitled = commands::list_conversations(None, Some(true))
+            .await
+            .map(|conversations| {
+                conversations
+                    .iter()
+                    .any(|c| c.id == conv_id && c.title.is_none())
+            })
+            .unwrap_or(false);
+        if !untitled {
+            return;
+        }
+        if let Err(e) = generate_and_emit(&app, &conv_id).await {
+            tracing::debug!("No title for conversation {}: {}", conv_id, e);
+        }
+    });
+}
+
+/// Fill in the titles kept by the shell.
+pub fn annotate(conversations: &mut [Conversation]) {
+    let titles: LocalTitles = store::load(STATE_FILE);
+    for conversation in conversations {
+        if let Some(title) = titles.get(&conversation.id) {
+            conversation.title = Some(title.clone());
+        }
+    }
+}
+
+/// Forget the local title of a deleted conversation.
+pub fn remove(conv_id: &str) {
+    let mut titles: LocalTitles = store::load(STATE_FILE);
+    if titles.remove(conv_id).is_some() {
+        if let Err(e) = store::save(STATE_FILE, &titles) {
+            tracing::warn!("Failed to remove title of {}: {}", conv_id, e);
+        }
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Generate a title for a conversation from its first exchange
+#[tauri::command]
+pub async fn generate_conversation_title(
+    app: AppHandle,
+    conv_id: String,
+) -> Result<String, String> {
+    generate_and_emit(&app, &conv_id).await
+}
+
+/// Rename a conversation
+#[tauri::command]
+pub async fn rename_conversation(conv_id: String, title: String) -> Result<Conversation, String> {
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    if title.is_empty() {
+        return Err("The title is empty".to_string());
+    }
+
+    let mut titles: LocalTitles = store::load(STATE_FILE);
+    if capabilities::supports(Feature::ConversationUpdate) {
+        backend_request::<serde_json::Value>(
+            Method::PATCH,
+            &format!("/api/conversations/{}", conv_id),
+            Some(json!({ "title": title })),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+        titles.remove(&conv_id);
+    } else {
+        titles.insert(conv_id.clone(), title);
+    }
+    store::save(STATE_FILE, &titles).map_err(|e| e.to_string())?;
+
+    commands::list_conversations(None, Some(true))
+        .await?
+        .into_iter()
+        .find(|c| c.id == conv_id)
+        .ok_or_else(|| format!("Conversation not found: {}", conv_id))
+}
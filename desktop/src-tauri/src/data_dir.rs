@@ -0,0 +1,397 @@
+//! Moving the data directory.
+//!
+//! `set_data_dir` moves everything under ~/.ragkit (knowledge bases, conversations,
+//! settings, logs) to another location, e.g. a larger drive, in three steps reported
+//! with `data-dir-migration` events: the backend is stopped and the files are copied,
+//! the copies are read back and checked by hash, then `data_dir` is switched
+//! in config.toml and the app restarts on the new directory. Until the switch nothing
+//! changes for the app, so a failed copy or verification only removes the partial copy
+//! and restarts the backend. The old directory is kept unless asked otherwise.
+
+use crate::backend;
+use crate::config::{self, ConfigSource};
+use crate::{guest, store};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter};
+
+const CHUNK_SIZE: usize = 1024 * 1024;
+/// Minimum number of bytes between two progress events.
+const PROGRESS_STEP: u64 = 16 * 1024 * 1024;
+/// Top-level entries that aren't moved: scratch files, and config.toml, which always
+/// stays in ~/.ragkit.
+const SKIPPED: &[&str] = &["tmp", "config.toml", "config.toml.tmp"];
+
+static MIGRATING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationPhase {
+    Copying,
+    Verifying,
+    Switching,
+    Done,
+    Failed,
+}
+
+/// Payload of the `data-dir-migration` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationProgress {
+    pub phase: MigrationPhase,
+    pub target: String,
+    /// Bytes copied or verified in the current phase
+    pub bytes: u64,
+    pub total_bytes: u64,
+    pub files: usize,
+    pub total_files: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DataDirInfo {
+    pub path: String,
+    pub default_path: String,
+    pub source: ConfigSource,
+    /// Why the data directory can't be changed from the app, if it can't
+    pub locked_reason: Option<String>,
+}
+
+struct Reporter {
+    app: AppHandle,
+    progress: MigrationProgress,
+    last_emitted: u64,
+}
+
+impl Reporter {
+    fn start_phase(&mut self, phase: MigrationPhase) {
+        self.progress.phase = phase;
+        self.progress.bytes = 0;
+        self.progress.files = 0;
+        self.last_emitted = 0;
+        self.emit();
+    }
+
+    fn advance(&mut self, bytes: usize) {
+        self.progress.bytes += bytes as u64;
+        if self.progress.bytes - self.last_emitted >= PROGRESS_STEP {
+            self.last_emitted = self.progress.bytes;
+            self.emit();
+        }
+    }
+
+    fn file_done(&mut self) {
+        self.progress.files += 1;
+    }
+
+    fn emit(&self) {
+        let _ = self.app.emit("data-dir-migration", &self.progress);
+    }
+}
+
+fn locked_reason() -> Option<String> {
+    if guest::is_active() {
+        return Some("Guest mode uses a temporary data directory".to_string());
+    }
+    let configured = config::get().data_dir.as_ref();
+    if configured.is_some_and(|d| d.source == ConfigSource::Environment) {
+        return Some("The data directory is set by RAGKIT_DATA_DIR".to_string());
+    }
+    if MIGRATING.load(Ordering::Relaxed) {
+        return Some("The data directory is being moved".to_string());
+    }
+    None
+}
+
+/// Canonical form of a path that may not exist yet: the longest existing ancestor is
+/// canonicalized and the rest appended.
+fn canonical(path: &Path) -> Result<PathBuf> {
+    let mut existing = path;
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return Ok(rest.iter().rev().fold(canonical, |p, c| p.join(c)));
+        }
+        match (existing.file_name(), existing.parent()) {
+            (Some(name), Some(parent)) => {
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return Err(anyhow!("Invalid path: {}", path.display())),
+        }
+    }
+}
+
+/// Whether a directory holds nothing that would be overwritten by the migration.
+fn is_empty_target(dir: &Path) -> Result<bool> {
+    match std::fs::read_dir(dir) {
+        Ok(entries) => Ok(entries
+            .filter_map(|e| e.ok())
+            .all(|e| SKIPPED.contains(&e.file_name().to_string_lossy().as_ref()))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(true),
+        Err(e) => Err(anyhow!("Failed to read {}: {}", dir.display(), e)),
+    }
+}
+
+/// Files to move, relative to the data directory.
+fn list_files(root: &Path) -> Result<Vec<(PathBuf, u64)>> {
+    let mut files = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(relative) = pending.pop() {
+        let dir = root.join(&relative);
+        let entries = std::fs::read_dir(&dir)
+            .map_err(|e| anyhow!("Failed to read {}: {}", dir.display(), e))?;
+        for entry in entries {
+            let entry = entry?;
+            let name = entry.file_name();
+            if relative.as_os_str().is_empty() && SKIPPED.contains(&name.to_string_lossy().as_ref())
+            {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                pending.push(relative.join(&name));
+            } else if metadata.is_file() {
+                files.push((relative.join(&name), metadata.len()));
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Copy a file, returning the hash of the bytes read.
+fn copy_file(from: &Path, to: &Path, reporter: &mut Reporter) -> Result<blake3::Hash> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| anyhow!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let mut reader = std::fs::File::open(from)
+        .map_err(|e| anyhow!("Failed to read {}: {}", from.display(), e))?;
+    let mut writer = std::fs::File::create(to)
+        .map_err(|e| anyhow!("Failed to write {}: {}", to.display(), e))?;
+    let mut hasher = blake3::Hasher::new();
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    loop {
+        let read = reader
+            .read(&mut buffer)
+            .map_err(|e| anyhow!("Failed to read {}: {}", from.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        writer
+            .write_all(&buffer[..read])
+            .map_err(|e| anyhow!("Failed to write {}: {}", to.display(), e))?;
+        hasher.update(&buffer[..read]);
+        reporter.advance(read);
+    }
+    writer
+        .sync_all()
+        .map_err(|e| anyhow!("Failed to write {}: {}", to.display(), e))?;
+    Ok(hasher.finalize())
+}
+
+fn hash_file(path: &Path, reporter: &mut Reporter) -> Result<blake3::Hash> {
+    let mut reader = std::fs::File::open(path)
+        .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+    let mut hasher = blake3::Hasher::new();
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    loop {
+        let read = reader
+            .read(&mut buffer)
+            .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        reporter.advance(read);
+    }
+    Ok(hasher.finalize())
+}
+
+/// Copy the files, then check that each copy reads back as what was copied. The
+/// originals aren't hashed again: the log file keeps growing during the migration.
+fn copy_and_verify(source: &Path, target: &Path, reporter: &mut Reporter) -> Result<()> {
+    let files = list_files(source)?;
+    reporter.progress.total_files = files.len();
+    reporter.progress.total_bytes = files.iter().map(|(_, size)| size).sum();
+
+    reporter.start_phase(MigrationPhase::Copying);
+    std::fs::create_dir_all(target)
+        .map_err(|e| anyhow!("Failed to create {}: {}", target.display(), e))?;
+    let mut hashes = Vec::with_capacity(files.len());
+    for (relative, _) in &files {
+        hashes.push(copy_file(
+            &source.join(relative),
+            &target.join(relative),
+            reporter,
+        )?);
+        reporter.file_done();
+    }
+
+    reporter.start_phase(MigrationPhase::Verifying);
+    for ((relative, _), original) in files.iter().zip(hashes) {
+        if hash_file(&target.join(relative), reporter)? != original {
+            return Err(anyhow!(
+                "The copy of {} differs from the original",
+                relative.display()
+            ));
+        }
+        reporter.file_done();
+    }
+    Ok(())
+}
+
+/// Remove what the migration wrote to the target, keeping what was already there.
+fn remove_copy(source: &Path, target: &Path) {
+    let Ok(entries) = std::fs::read_dir(source) else {
+        return;
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let name = entry.file_name();
+        if SKIPPED.contains(&name.to_string_lossy().as_ref()) {
+            continue;
+        }
+        let path = target.join(&name);
+        let _ = if path.is_dir() {
+            std::fs::remove_dir_all(&path)
+        } else {
+            std::fs::remove_file(&path)
+        };
+    }
+}
+
+/// Delete the old data directory after a successful switch, keeping config.toml.
+fn remove_old(source: &Path) {
+    let Ok(entries) = std::fs::read_dir(source) else {
+        return;
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        if entry.file_name() == "config.toml" {
+            continue;
+        }
+        let path = entry.path();
+        let result = if path.is_dir() {
+            std::fs::remove_dir_all(&path)
+        } else {
+            std::fs::remove_file(&path)
+        };
+        if let Err(e) = result {
+            tracing::warn!("Failed to remove {}: {}", path.display(), e);
+        }
+    }
+    let _ = std::fs::remove_dir(source);
+}
+
+fn info() -> DataDirInfo {
+    DataDirInfo {
+        path: store::ragkit_dir().display().to_string(),
+        default_path: store::default_dir().display().to_string(),
+        source: match (guest::data_dir(), &config::get().data_dir) {
+            (Some(_), _) => ConfigSource::Guest,
+            (None, Some(dir)) => dir.source,
+            (None, None) => ConfigSource::Default,
+        },
+        locked_reason: locked_reason(),
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Get the data directory in use and whether it can be moved
+#[tauri::command]
+pub async fn get_data_dir() -> Result<DataDirInfo, String> {
+    Ok(info())
+}
+
+/// Move the data directory to `path` and restart the app on it, optionally deleting
+/// the old directory
+#[tauri::command]
+pub async fn set_data_dir(app: AppHandle, path: String, delete_old: bool) -> Result<(), String> {
+    if let Some(reason) = locked_reason() {
+        return Err(reason);
+    }
+    let requested = PathBuf::from(path.trim());
+    if !requested.is_absolute() || requested.components().any(|c| c == Component::ParentDir) {
+        return Err(format!(
+            "The data directory must be an absolute path: {}",
+            path
+        ));
+    }
+    let source = canonical(&store::ragkit_dir()).map_err(|e| e.to_string())?;
+    let target = canonical(&requested).map_err(|e| e.to_string())?;
+    if target == source {
+        return Err("This is already the data directory".to_string());
+    }
+    if target.starts_with(&source) || source.starts_with(&target) {
+        return Err("The new data directory can't contain or be inside the current one".into());
+    }
+    if !is_empty_target(&target).map_err(|e| e.to_string())? {
+        return Err(format!("{} is not empty", target.display()));
+    }
+    if MIGRATING.swap(true, Ordering::SeqCst) {
+        return Err("The data directory is being moved".to_string());
+    }
+
+    tracing::info!(
+        "Moving the data directory from {} to {}",
+        source.display(),
+        target.display()
+    );
+    let mut reporter = Reporter {
+        app: app.clone(),
+        progress: MigrationProgress {
+            phase: MigrationPhase::Copying,
+            target: target.display().to_string(),
+            bytes: 0,
+            total_bytes: 0,
+            files: 0,
+            total_files: 0,
+            error: None,
+        },
+        last_emitted: 0,
+    };
+    // Nothing may write to the data directory while it is copied
+    backend::stop_backend(&app).await;
+
+    let (copy_source, copy_target) = (source.clone(), target.clone());
+    let result = tokio::task::spawn_blocking(move || {
+        let result = copy_and_verify(&copy_source, &copy_target, &mut reporter);
+        (result, reporter)
+    })
+    .await;
+    let (result, mut reporter) = match result {
+        Ok(done) => done,
+        Err(e) => {
+            MIGRATING.store(false, Ordering::SeqCst);
+            return Err(e.to_string());
+        }
+    };
+
+    let result = result.and_then(|()| {
+        reporter.start_phase(MigrationPhase::Switching);
+        let default_dir = canonical(&store::default_dir())?;
+        config::save_data_dir((target != default_dir).then_some(target.as_path()))
+    });
+    if let Err(e) = result {
+        tracing::error!("Failed to move the data directory: {}", e);
+        remove_copy(&source, &target);
+        reporter.progress.error = Some(e.to_string());
+        reporter.start_phase(MigrationPhase::Failed);
+        MIGRATING.store(false, Ordering::SeqCst);
+        if let Err(e) = backend::start_backend(&app).await {
+            tracing::error!("Failed to restart the backend: {}", e);
+        }
+        return Err(e.to_string());
+    }
+
+    if delete_old {
+        remove_old(&source);
+    }
+    reporter.start_phase(MigrationPhase::Done);
+    tracing::info!("Data directory moved to {}, restarting", target.display());
+    app.restart();
+}
@@ -0,0 +1,127 @@
+//! Duplicate document detection.
+//!
+//! Before a file is ingested, the shell hashes its content with BLAKE3 and compares it
+//! with the documents already in the knowledge base: the hashes the backend reports,
+//! and those of the files the shell ingested itself (kept in dedup.json). A file whose
+//! content is already there, including twice in the same import, is skipped and listed
+//! in the job's `skipped_duplicates`.
+
+use crate::backend::backend_request;
+use crate::capabilities::{self, Feature};
+use crate::store;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+const STATE_FILE: &str = "dedup.json";
+
+/// Document a duplicate file has the same content as.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DuplicateOf {
+    pub document_id: String,
+    pub filename: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DocumentHash {
+    document_id: String,
+    filename: String,
+    /// BLAKE3 of the original file, as lowercase hex
+    hash: String,
+}
+
+/// Documents ingested by the shell, by knowledge base then content hash.
+type HashStore = BTreeMap<String, BTreeMap<String, DuplicateOf>>;
+
+/// BLAKE3 of a file's content, as lowercase hex.
+pub async fn hash_file(path: &str) -> Option<String> {
+    let path = path.to_string();
+    let hashed = tauri::async_runtime::spawn_blocking(move || {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update_reader(std::fs::File::open(&path)?)?;
+        Ok::<_, std::io::Error>(hasher.finalize().to_hex().to_string())
+    })
+    .await;
+    match hashed {
+        Ok(Ok(hash)) => Some(hash),
+        Ok(Err(e)) => {
+            tracing::warn!("Failed to hash a file for duplicate detection: {}", e);
+            None
+        }
+        Err(_) => None,
+    }
+}
+
+/// Content hashes of the documents already in a knowledge base.
+pub async fn known_hashes(kb_id: &str) -> HashMap<String, DuplicateOf> {
+    let mut known: HashMap<String, DuplicateOf> = store::load::<HashStore>(STATE_FILE)
+        .remove(kb_id)
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+    if capabilities::supports(Feature::DocumentHashes) {
+        match backend_request::<Vec<DocumentHash>>(
+            Method::GET,
+            &format!("/api/knowledge-bases/{}/documents/hashes", kb_id),
+            None,
+        )
+        .await
+        {
+            Ok(hashes) => known.extend(hashes.into_iter().map(|h| {
+                (
+                    h.hash,
+                    DuplicateOf {
+                        document_id: h.document_id,
+                        filename: h.filename,
+                    },
+                )
+            })),
+            Err(e) => tracing::debug!("Using the local document hashes of {}: {}", kb_id, e),
+        }
+    }
+    known
+}
+
+/// Remember the content hash of an ingested file.
+pub fn record(kb_id: &str, hash: &str, document_id: &str, path: &str) {
+    let filename = Path::new(path)
+        .file_name()
+        .map_or_else(|| path.to_string(), |n| n.to_string_lossy().into_owned());
+    let mut hashes: HashStore = store::load(STATE_FILE);
+    hashes.entry(kb_id.to_string()).or_default().insert(
+        hash.to_string(),
+        DuplicateOf {
+            document_id: document_id.to_string(),
+            filename,
+        },
+    );
+    if let Err(e) = store::save(STATE_FILE, &hashes) {
+        tracing::warn!("Failed to save document hashes: {}", e);
+    }
+}
+
+/// Forget the hash of a deleted document.
+pub fn remove_document(kb_id: &str, document_id: &str) {
+    let mut hashes: HashStore = store::load(STATE_FILE);
+    let Some(kb) = hashes.get_mut(kb_id) else {
+        return;
+    };
+    let before = kb.len();
+    kb.retain(|_, d| d.document_id != document_id);
+    if kb.len() != before {
+        if let Err(e) = store::save(STATE_FILE, &hashes) {
+            tracing::warn!("Failed to save document hashes: {}", e);
+        }
+    }
+}
+
+/// Forget the hashes of a deleted knowledge base.
+pub fn remove_kb(kb_id: &str) {
+    let mut hashes: HashStore = store::load(STATE_FILE);
+    if hashes.remove(kb_id).is_some() {
+        if let Err(e) = store::save(STATE_FILE, &hashes) {
+            tracing::warn!("Failed to save document hashes: {}", e);
+        }
+    }
+}
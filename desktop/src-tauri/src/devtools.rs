@@ -0,0 +1,118 @@
+//! Developer-mode REST console.
+//!
+//! Lets power users call arbitrary backend endpoints from inside the app, with an
+//! endpoint catalog built from the backend's OpenAPI schema. Disabled unless
+//! `developer_mode` is turned on in the preferences.
+
+use crate::backend::{authorize, backend_client, backend_request, get_backend_url, TimeoutTier};
+use crate::preferences;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EndpointInfo {
+    pub method: String,
+    pub path: String,
+    pub summary: Option<String>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DevResponse {
+    pub status: u16,
+    pub body: serde_json::Value,
+    pub latency_ms: u64,
+}
+
+fn ensure_developer_mode() -> Result<(), String> {
+    if preferences::load().developer_mode {
+        Ok(())
+    } else {
+        Err("Developer mode is disabled".to_string())
+    }
+}
+
+/// List backend endpoints from its OpenAPI schema
+#[tauri::command]
+pub async fn get_backend_endpoints() -> Result<Vec<EndpointInfo>, String> {
+    ensure_developer_mode()?;
+
+    let schema: serde_json::Value = backend_request(Method::GET, "/openapi.json", None)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut endpoints = Vec::new();
+    if let Some(paths) = schema["paths"].as_object() {
+        for (path, operations) in paths {
+            let Some(operations) = operations.as_object() else {
+                continue;
+            };
+            for (method, operation) in operations {
+                endpoints.push(EndpointInfo {
+                    method: method.to_uppercase(),
+                    path: path.clone(),
+                    summary: operation["summary"].as_str().map(String::from),
+                    tags: operation["tags"]
+                        .as_array()
+                        .map(|tags| {
+                            tags.iter()
+                                .filter_map(|t| t.as_str().map(String::from))
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                });
+            }
+        }
+    }
+
+    endpoints.sort_by(|a, b| a.path.cmp(&b.path).then(a.method.cmp(&b.method)));
+    Ok(endpoints)
+}
+
+/// Send a raw request to the backend (developer mode only)
+///
+/// Unlike other commands, non-2xx responses are returned rather than turned into errors
+/// so the console can display them.
+#[tauri::command]
+pub async fn dev_backend_request(
+    method: String,
+    path: String,
+    body: Option<serde_json::Value>,
+) -> Result<DevResponse, String> {
+    ensure_developer_mode()?;
+
+    let method = Method::from_bytes(method.to_uppercase().as_bytes())
+        .map_err(|_| format!("Invalid HTTP method: {}", method))?;
+    if !path.starts_with('/') {
+        return Err("Path must start with '/'".to_string());
+    }
+
+    crate::backend::check_offline(&method, &path, body.as_ref())
+        .await
+        .map_err(|e| e.to_string())?;
+    tracing::info!("[dev console] {} {}", method, path);
+
+    let url = format!("{}{}", get_backend_url(), path);
+    let timeout = TimeoutTier::for_endpoint(&method, &path).timeout();
+    let mut request = authorize(backend_client().request(method, &url)).timeout(timeout);
+    if let Some(body) = body {
+        request = request.json(&body);
+    }
+
+    let start = Instant::now();
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+    let status = response.status().as_u16();
+    let text = response.text().await.unwrap_or_default();
+    let latency_ms = start.elapsed().as_millis() as u64;
+
+    let body = serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text));
+    Ok(DevResponse {
+        status,
+        body,
+        latency_ms,
+    })
+}
@@ -0,0 +1,112 @@
+//! Caution flags on documents.
+//!
+//! Users can mark a document as sensitive or outdated, with an optional note. When a
+//! flagged document is among the sources of an answer, a warning is attached to the
+//! response so the user knows the answer leans on material flagged for caution.
+
+use crate::commands::Source;
+use crate::store;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const STATE_FILE: &str = "document_flags.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentFlag {
+    Sensitive,
+    Outdated,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DocumentFlags {
+    pub flags: Vec<DocumentFlag>,
+    pub note: Option<String>,
+}
+
+/// Flags per knowledge base, then per document filename.
+type FlagStore = BTreeMap<String, BTreeMap<String, DocumentFlags>>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceWarning {
+    pub filename: String,
+    pub flag: DocumentFlag,
+    pub note: Option<String>,
+    pub message: String,
+}
+
+fn warning_message(flag: DocumentFlag, filename: &str) -> String {
+    match flag {
+        DocumentFlag::Sensitive => {
+            format!("This answer uses \"{}\", which is flagged as sensitive.", filename)
+        }
+        DocumentFlag::Outdated => format!(
+            "This answer uses \"{}\", which is flagged as outdated and may no longer be accurate.",
+            filename
+        ),
+    }
+}
+
+/// Warnings for the flagged documents among `sources`, one per document and flag.
+pub fn warnings_for(kb_id: &str, sources: &[Source]) -> Vec<SourceWarning> {
+    let all: FlagStore = store::load(STATE_FILE);
+    let Some(documents) = all.get(kb_id) else {
+        return Vec::new();
+    };
+
+    let mut warnings: Vec<SourceWarning> = Vec::new();
+    for source in sources {
+        let Some(entry) = documents.get(&source.filename) else {
+            continue;
+        };
+        if warnings.iter().any(|w| w.filename == source.filename) {
+            continue;
+        }
+        warnings.extend(entry.flags.iter().map(|&flag| SourceWarning {
+            filename: source.filename.clone(),
+            flag,
+            note: entry.note.clone(),
+            message: warning_message(flag, &source.filename),
+        }));
+    }
+    warnings
+}
+
+/// Get the flagged documents of a knowledge base
+#[tauri::command]
+pub async fn get_document_flags(kb_id: String) -> Result<BTreeMap<String, DocumentFlags>, String> {
+    let mut all: FlagStore = store::load(STATE_FILE);
+    Ok(all.remove(&kb_id).unwrap_or_default())
+}
+
+/// Flag a document as sensitive and/or outdated. An empty flag list clears the entry.
+#[tauri::command]
+pub async fn set_document_flags(
+    kb_id: String,
+    filename: String,
+    flags: Vec<DocumentFlag>,
+    note: Option<String>,
+) -> Result<DocumentFlags, String> {
+    let mut all: FlagStore = store::load(STATE_FILE);
+    let documents = all.entry(kb_id.clone()).or_default();
+
+    let mut flags = flags;
+    flags.sort();
+    flags.dedup();
+    let entry = DocumentFlags {
+        flags,
+        note: note.filter(|n| !n.trim().is_empty()),
+    };
+
+    if entry.flags.is_empty() {
+        documents.remove(&filename);
+    } else {
+        documents.insert(filename, entry.clone());
+    }
+    if documents.is_empty() {
+        all.remove(&kb_id);
+    }
+
+    store::save(STATE_FILE, &all).map_err(|e| e.to_string())?;
+    Ok(entry)
+}
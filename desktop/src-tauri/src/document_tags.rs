@@ -0,0 +1,84 @@
+//! Document tags and tag-scoped queries.
+//!
+//! Tags are `key:value` pairs (e.g. `year:2024`, `department:legal`) or bare labels,
+//! stored by the backend with each document and returned on documents and sources.
+//! A query's `tag_filter` restricts retrieval to the documents carrying its tags: every
+//! key must match, and several values of one key match any of them. The shell
+//! normalizes tags so `Department: Legal` and `department:legal` are the same tag.
+
+use crate::backend::backend_request;
+use crate::capabilities::{self, Feature};
+use crate::commands::QueryParams;
+use reqwest::Method;
+use serde_json::json;
+
+const MAX_TAGS: usize = 32;
+const MAX_TAG_CHARS: usize = 64;
+
+/// Lowercase a tag and collapse its whitespace, checking `key:value` tags have both parts.
+fn normalize(tag: &str) -> Result<String, String> {
+    let collapse = |s: &str| s.split_whitespace().collect::<Vec<_>>().join(" ");
+    let tag = match tag.split_once(':') {
+        Some((key, value)) => {
+            let (key, value) = (collapse(key), collapse(value));
+            if key.is_empty() || value.is_empty() {
+                return Err(format!("Invalid tag \"{}\": expected key:value", tag));
+            }
+            format!("{}:{}", key, value)
+        }
+        None => collapse(tag),
+    }
+    .to_lowercase();
+    if tag.is_empty() {
+        return Err("Tags can't be empty".to_string());
+    }
+    if tag.chars().count() > MAX_TAG_CHARS {
+        return Err(format!(
+            "Tag \"{}\" is longer than {} characters",
+            tag, MAX_TAG_CHARS
+        ));
+    }
+    Ok(tag)
+}
+
+fn normalize_all(tags: &[String]) -> Result<Vec<String>, String> {
+    let mut tags = tags
+        .iter()
+        .map(|t| normalize(t))
+        .collect::<Result<Vec<_>, _>>()?;
+    tags.sort();
+    tags.dedup();
+    Ok(tags)
+}
+
+/// Normalize the tag filter of a query; backends without tags can't apply one.
+pub fn apply(params: &mut QueryParams) -> Result<(), String> {
+    if params.tag_filter.is_empty() {
+        return Ok(());
+    }
+    capabilities::require(Feature::DocumentTags)?;
+    params.tag_filter = normalize_all(&params.tag_filter)?;
+    Ok(())
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Replace the tags of a document. Returns the tags as stored.
+#[tauri::command]
+pub async fn set_document_tags(doc_id: String, tags: Vec<String>) -> Result<Vec<String>, String> {
+    capabilities::require(Feature::DocumentTags)?;
+    let tags = normalize_all(&tags)?;
+    if tags.len() > MAX_TAGS {
+        return Err(format!("A document can have at most {} tags", MAX_TAGS));
+    }
+    backend_request::<serde_json::Value>(
+        Method::PUT,
+        &format!("/api/documents/{}/tags", doc_id),
+        Some(json!({ "tags": tags })),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(tags)
+}
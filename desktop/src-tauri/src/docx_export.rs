@@ -0,0 +1,320 @@
+//! Conversation export to Word (DOCX).
+//!
+//! The document opens with a metadata page, then one heading per question followed by
+//! the answer, the quoted sources, and a footnote per citation. It is built with
+//! docx-rs using named styles (Title, Heading 1, Quote, ...) so it can be restyled
+//! with the organisation's Word template.
+
+use crate::backend::backend_request;
+use crate::commands::{self, Conversation, Message, Source};
+use crate::printing::excerpt;
+use anyhow::Result;
+use docx_rs::{
+    BorderType, BreakType, Docx, Footnote, LineSpacing, LineSpacingType, PageMargin, Paragraph,
+    ParagraphBorder, ParagraphBorderPosition, ParagraphBorders, Run, RunFonts, Style, StyleType,
+    VertAlignType,
+};
+use reqwest::Method;
+use std::io::Cursor;
+use std::path::PathBuf;
+
+/// A4 in twentieths of a point.
+const PAGE_SIZE: (u32, u32) = (11906, 16838);
+
+fn paragraph_style(id: &str, name: &str) -> Style {
+    Style::new(id, StyleType::Paragraph)
+        .name(name)
+        .based_on("Normal")
+        .next("Normal")
+        .q_format(true)
+}
+
+fn heading_style(id: &str, name: &str, level: usize, size: usize, color: &str) -> Style {
+    let mut style = paragraph_style(id, name)
+        .outline_lvl(level)
+        .bold()
+        .size(size)
+        .color(color);
+    style.paragraph_property = style.paragraph_property.keep_next(true);
+    style
+}
+
+fn styles() -> Vec<Style> {
+    let mut quote = paragraph_style("Quote", "Quote")
+        .indent(Some(567), None, Some(567), None)
+        .italic()
+        .color("404040");
+    quote.paragraph_property.borders = Some(
+        ParagraphBorders::with_empty().set(
+            ParagraphBorder::new(ParagraphBorderPosition::Left)
+                .val(BorderType::Single)
+                .size(12)
+                .space(8)
+                .color("A5A5A5"),
+        ),
+    );
+
+    let mut footnote_reference =
+        Style::new("FootnoteReference", StyleType::Character).name("footnote reference");
+    footnote_reference.run_property = footnote_reference
+        .run_property
+        .vert_align(VertAlignType::SuperScript);
+
+    vec![
+        paragraph_style("Title", "Title")
+            .line_spacing(LineSpacing::new().after(240))
+            .fonts(
+                RunFonts::new()
+                    .ascii("Calibri Light")
+                    .hi_ansi("Calibri Light"),
+            )
+            .size(48),
+        heading_style("Heading1", "heading 1", 0, 28, "1F3864")
+            .line_spacing(LineSpacing::new().before(360).after(120)),
+        heading_style("Heading2", "heading 2", 1, 24, "2F5496")
+            .line_spacing(LineSpacing::new().before(240).after(80)),
+        quote,
+        Style::new("MetadataLabel", StyleType::Paragraph)
+            .name("Metadata Label")
+            .based_on("Normal")
+            .bold()
+            .color("595959"),
+        Style::new("FootnoteText", StyleType::Paragraph)
+            .name("footnote text")
+            .based_on("Normal")
+            .line_spacing(
+                LineSpacing::new()
+                    .after(0)
+                    .line(240)
+                    .line_rule(LineSpacingType::Auto),
+            )
+            .size(18),
+        footnote_reference,
+    ]
+}
+
+/// Drop characters XML 1.0 cannot represent.
+fn xml_text(text: &str) -> String {
+    text.chars()
+        .filter(|c| matches!(c, '\t' | '\n' | '\r') || *c as u32 >= 0x20)
+        .collect()
+}
+
+/// A run for a piece of text, turning single line breaks into `<w:br/>`.
+fn text_run(text: &str) -> Run {
+    let mut run = Run::new();
+    for (i, line) in text.lines().enumerate() {
+        if i > 0 {
+            run = run.add_break(BreakType::TextWrapping);
+        }
+        run = run.add_text(xml_text(line));
+    }
+    run
+}
+
+fn paragraph(style: Option<&str>, text: &str) -> Paragraph {
+    let paragraph = Paragraph::new().add_run(text_run(text));
+    match style {
+        Some(style) => paragraph.style(style),
+        None => paragraph,
+    }
+}
+
+/// Body of the document, numbering footnotes in order of appearance as Word does.
+struct DocxBuilder {
+    docx: Docx,
+    footnotes: usize,
+}
+
+impl DocxBuilder {
+    fn new() -> Self {
+        let mut docx = Docx::new()
+            .default_fonts(
+                RunFonts::new()
+                    .ascii("Calibri")
+                    .hi_ansi("Calibri")
+                    .cs("Calibri"),
+            )
+            .default_size(22)
+            .default_line_spacing(
+                LineSpacing::new()
+                    .after(120)
+                    .line(276)
+                    .line_rule(LineSpacingType::Auto),
+            )
+            .page_size(PAGE_SIZE.0, PAGE_SIZE.1)
+            .page_margin(
+                PageMargin::new()
+                    .top(1440)
+                    .right(1440)
+                    .bottom(1440)
+                    .left(1440)
+                    .header(708)
+                    .footer(708)
+                    .gutter(0),
+            );
+        for style in styles() {
+            docx = docx.add_style(style);
+        }
+        Self { docx, footnotes: 0 }
+    }
+
+    fn add(&mut self, paragraph: Paragraph) {
+        self.docx = std::mem::take(&mut self.docx).add_paragraph(paragraph);
+    }
+
+    fn push(&mut self, style: Option<&str>, text: &str) {
+        self.add(paragraph(style, text));
+    }
+
+    fn footnote(&mut self, source: &Source) -> Run {
+        self.footnotes += 1;
+        let text = format!("{} (relevance {:.2})", source.filename, source.score);
+        let content = Paragraph::new()
+            .style("FootnoteText")
+            .add_run(
+                Run::new()
+                    .style("FootnoteReference")
+                    .add_text(self.footnotes.to_string()),
+            )
+            .add_run(Run::new().add_text(format!(" {}", xml_text(&text))));
+        Run::new().add_footnote_reference(Footnote::new().add_content(content))
+    }
+
+    fn metadata_page(&mut self, title: &str, fields: &[(&str, String)]) {
+        self.push(Some("Title"), title);
+        for (label, value) in fields {
+            self.add(
+                paragraph(Some("MetadataLabel"), &format!("{}:", label)).add_run(
+                    Run::new()
+                        .disable_bold()
+                        .add_text(format!(" {}", xml_text(value))),
+                ),
+            );
+        }
+        self.add(Paragraph::new().add_run(Run::new().add_break(BreakType::Page)));
+    }
+
+    /// An answer with a footnote per cited source, followed by the quoted sources.
+    fn answer(&mut self, message: &Message) {
+        let sources = message.sources.as_deref().unwrap_or_default();
+        let mut references: Vec<Run> = sources.iter().map(|s| self.footnote(s)).collect();
+
+        let paragraphs: Vec<&str> = message
+            .content
+            .split("\n\n")
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .collect();
+        let last = paragraphs.len().saturating_sub(1);
+        for (i, text) in paragraphs.iter().enumerate() {
+            let mut paragraph = paragraph(None, text);
+            if i == last {
+                for reference in references.drain(..) {
+                    paragraph = paragraph.add_run(reference);
+                }
+            }
+            self.add(paragraph);
+        }
+        if !references.is_empty() {
+            self.add(
+                references
+                    .into_iter()
+                    .fold(Paragraph::new(), Paragraph::add_run),
+            );
+        }
+
+        if !sources.is_empty() {
+            self.push(Some("Heading2"), "Sources");
+            for source in sources {
+                self.push(Some("Quote"), &format!("“{}”", excerpt(&source.chunk)));
+                self.push(None, &format!("— {}", source.filename));
+            }
+        }
+    }
+
+    fn build(self) -> Result<Vec<u8>> {
+        let now = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        let mut data = Cursor::new(Vec::new());
+        self.docx
+            .created_at(&now)
+            .updated_at(&now)
+            .pack(&mut data)?;
+        Ok(data.into_inner())
+    }
+}
+
+/// Build the DOCX package of a conversation.
+fn render(conversation: Option<&Conversation>, messages: &[Message]) -> Result<Vec<u8>> {
+    let title = conversation
+        .and_then(|c| c.title.clone())
+        .unwrap_or_else(|| "Conversation".to_string());
+
+    let mut builder = DocxBuilder::new();
+    let questions = messages.iter().filter(|m| m.role == "user").count();
+    let mut fields = vec![
+        (
+            "Exported",
+            chrono::Local::now().format("%Y-%m-%d %H:%M").to_string(),
+        ),
+        ("Questions", questions.to_string()),
+    ];
+    if let Some(c) = conversation {
+        fields.push(("Conversation", c.id.clone()));
+        if let Some(kb_id) = &c.kb_id {
+            fields.push(("Knowledge base", kb_id.clone()));
+        }
+        fields.push(("Started", c.created_at.clone()));
+        fields.push(("Last updated", c.updated_at.clone()));
+        if let Some(target) = &c.model_override {
+            fields.push(("Model", format!("{} / {}", target.provider, target.model)));
+        }
+    }
+    builder.metadata_page(&title, &fields);
+
+    let mut question_number = 0;
+    for message in messages {
+        match message.role.as_str() {
+            "user" => {
+                question_number += 1;
+                builder.push(
+                    Some("Heading1"),
+                    &format!("{}. {}", question_number, message.content.trim()),
+                );
+            }
+            "assistant" => builder.answer(message),
+            _ => {}
+        }
+    }
+    builder.build()
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Export a conversation to a Word document. Returns the file path.
+#[tauri::command]
+pub async fn export_conversation_docx(conv_id: String, path: String) -> Result<String, String> {
+    let mut conversations: Vec<Conversation> =
+        backend_request(Method::GET, "/api/conversations", None)
+            .await
+            .map_err(|e| e.to_string())?;
+    crate::conversation_models::annotate(&mut conversations);
+    let messages = commands::get_messages(conv_id.clone()).await?;
+    let conversation = conversations.iter().find(|c| c.id == conv_id);
+
+    let mut target = PathBuf::from(path);
+    if !target
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("docx"))
+    {
+        target.set_extension("docx");
+    }
+
+    let data = render(conversation, &messages).map_err(|e| e.to_string())?;
+    std::fs::write(&target, data)
+        .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+    tracing::info!("Exported conversation {} to {}", conv_id, target.display());
+    Ok(target.display().to_string())
+}
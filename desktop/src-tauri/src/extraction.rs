@@ -0,0 +1,898 @@
+//! Native text extraction for OpenDocument text, RTF, and EPUB files.
+//!
+//! The output is Markdown-flavored plain text: headings become `#` lines and each
+//! heading is also reported as a [`Section`], so chapter and heading structure survives
+//! into chunking and citations. It powers ingestion previews and serves as a fallback
+//! when the backend has no parser for these formats: the file is converted to Markdown
+//! under `~/.ragkit/tmp/extracted/` and that copy is ingested instead.
+
+use crate::chunking::{self, ChunkPreview};
+use crate::files::extension_of;
+use crate::metadata::DocumentMetadata;
+use anyhow::{anyhow, Result};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Formats this module can extract.
+pub const NATIVE_EXTENSIONS: &[&str] = &["odt", "rtf", "epub"];
+
+const DEFAULT_PREVIEW_CHARS: usize = 20_000;
+/// Bytes of an RTF file parsed when only its metadata is needed.
+const RTF_HEADER_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Section {
+    pub title: String,
+    /// Heading level, 1 for top-level headings
+    pub level: u8,
+    /// EPUB chapter (spine position) the heading belongs to
+    pub chapter: Option<usize>,
+    /// Character offset of the heading line in the extracted text
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExtractedDocument {
+    pub text: String,
+    pub sections: Vec<Section>,
+    pub metadata: DocumentMetadata,
+    /// Set when a preview was cut at its character limit
+    pub truncated: bool,
+}
+
+/// Whether a file can be extracted natively.
+pub fn is_native(path: &Path) -> bool {
+    NATIVE_EXTENSIONS.contains(&extension_of(path).as_str())
+}
+
+// ============================================================================
+// Text assembly
+// ============================================================================
+
+/// Accumulates inline text into paragraphs and headings.
+#[derive(Default)]
+struct TextBuilder {
+    text: String,
+    chars: usize,
+    sections: Vec<Section>,
+    current: String,
+    heading: Option<u8>,
+    chapter: Option<usize>,
+}
+
+impl TextBuilder {
+    /// Append inline text. Source whitespace (including newlines) counts as a space.
+    fn push_text(&mut self, text: &str) {
+        self.current
+            .extend(text.chars().map(|c| if c.is_whitespace() { ' ' } else { c }));
+    }
+
+    fn line_break(&mut self) {
+        self.current.push('\n');
+    }
+
+    fn start_heading(&mut self, level: u8) {
+        self.flush();
+        self.heading = Some(level.clamp(1, 6));
+    }
+
+    fn append(&mut self, text: &str) {
+        self.text.push_str(text);
+        self.chars += text.chars().count();
+    }
+
+    /// End the current paragraph or heading.
+    fn flush(&mut self) {
+        let content = std::mem::take(&mut self.current)
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let heading = self.heading.take();
+        if content.is_empty() {
+            return;
+        }
+
+        match heading {
+            Some(level) => {
+                let title = content.replace('\n', " ");
+                self.sections.push(Section {
+                    title: title.clone(),
+                    level,
+                    chapter: self.chapter,
+                    offset: self.chars,
+                });
+                self.append(&format!("{} {}\n\n", "#".repeat(level as usize), title));
+            }
+            None => self.append(&format!("{}\n\n", content)),
+        }
+    }
+
+    fn finish(mut self, metadata: DocumentMetadata) -> ExtractedDocument {
+        self.flush();
+        ExtractedDocument {
+            text: self.text.trim_end().to_string(),
+            sections: self.sections,
+            metadata,
+            truncated: false,
+        }
+    }
+}
+
+fn xml_reader(xml: &str) -> Reader<&[u8]> {
+    let mut reader = Reader::from_str(xml);
+    // EPUB content is frequently sloppy XHTML
+    reader.config_mut().check_end_names = false;
+    reader
+}
+
+fn attribute(element: &BytesStart, name: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|a| a.key.as_ref() == name)
+        .map(|a| String::from_utf8_lossy(&a.value).into_owned())
+}
+
+/// Text of the first element with the given qualified name.
+fn first_text(xml: &str, names: &[&[u8]]) -> Option<String> {
+    let mut reader = xml_reader(xml);
+    let mut inside = false;
+    let mut value = String::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) if names.contains(&e.name().as_ref()) => inside = true,
+            Ok(Event::Text(t)) if inside => value.push_str(&unescape(&t)),
+            Ok(Event::End(e)) if inside && names.contains(&e.name().as_ref()) => {
+                let value = value.trim();
+                return (!value.is_empty()).then(|| value.to_string());
+            }
+            Ok(Event::Eof) | Err(_) => return None,
+            _ => {}
+        }
+    }
+}
+
+/// Named HTML entities commonly found in EPUB content, which XML alone doesn't define.
+fn html_entity(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "amp" => "&",
+        "lt" => "<",
+        "gt" => ">",
+        "quot" => "\"",
+        "apos" => "'",
+        "nbsp" => "\u{a0}",
+        "shy" => "",
+        "mdash" => "—",
+        "ndash" => "–",
+        "hellip" => "…",
+        "lsquo" => "‘",
+        "rsquo" => "’",
+        "ldquo" => "“",
+        "rdquo" => "”",
+        "laquo" => "«",
+        "raquo" => "»",
+        "bull" => "•",
+        "middot" => "·",
+        "copy" => "©",
+        "reg" => "®",
+        "trade" => "™",
+        "deg" => "°",
+        "euro" => "€",
+        _ => return None,
+    })
+}
+
+fn unescape(text: &quick_xml::events::BytesText) -> String {
+    text.unescape_with(html_entity)
+        .map(|t| t.into_owned())
+        .unwrap_or_else(|_| String::from_utf8_lossy(text).into_owned())
+}
+
+// ============================================================================
+// ZIP containers (ODT, EPUB)
+// ============================================================================
+
+type Archive = zip::ZipArchive<std::fs::File>;
+
+fn open_archive(path: &Path) -> Result<Archive> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| anyhow!("Failed to open {}: {}", path.display(), e))?;
+    zip::ZipArchive::new(file).map_err(|e| anyhow!("Not a valid archive: {}", e))
+}
+
+fn read_entry(archive: &mut Archive, name: &str) -> Result<String> {
+    let mut entry = archive
+        .by_name(name)
+        .map_err(|e| anyhow!("Missing {}: {}", name, e))?;
+    let mut content = String::new();
+    entry
+        .read_to_string(&mut content)
+        .map_err(|e| anyhow!("Failed to read {}: {}", name, e))?;
+    Ok(content)
+}
+
+// ============================================================================
+// OpenDocument text
+// ============================================================================
+
+fn odt_metadata(archive: &mut Archive) -> DocumentMetadata {
+    let Ok(meta) = read_entry(archive, "meta.xml") else {
+        return DocumentMetadata::default();
+    };
+    DocumentMetadata {
+        title: first_text(&meta, &[b"dc:title"]),
+        author: first_text(&meta, &[b"meta:initial-creator", b"dc:creator"]),
+        created_at: first_text(&meta, &[b"meta:creation-date"]),
+    }
+}
+
+fn extract_odt(path: &Path) -> Result<ExtractedDocument> {
+    let mut archive = open_archive(path)?;
+    let content = read_entry(&mut archive, "content.xml")?;
+    let metadata = odt_metadata(&mut archive);
+
+    let mut builder = TextBuilder::default();
+    let mut reader = xml_reader(&content);
+    // Depth inside elements whose text is not part of the body (annotations, notes...)
+    let mut skip_depth = 0usize;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let name = e.name();
+                if skip_depth > 0
+                    || matches!(
+                        name.as_ref(),
+                        b"office:annotation" | b"text:note" | b"text:tracked-changes"
+                    )
+                {
+                    skip_depth += 1;
+                    continue;
+                }
+                match name.as_ref() {
+                    b"text:h" => {
+                        let level = attribute(&e, b"text:outline-level")
+                            .and_then(|l| l.parse().ok())
+                            .unwrap_or(1);
+                        builder.start_heading(level);
+                    }
+                    b"text:p" | b"table:table-row" => builder.flush(),
+                    _ => {}
+                }
+            }
+            Ok(Event::Empty(e)) if skip_depth == 0 => match e.name().as_ref() {
+                b"text:s" | b"text:tab" => builder.push_text(" "),
+                b"text:line-break" => builder.line_break(),
+                _ => {}
+            },
+            Ok(Event::Text(t)) if skip_depth == 0 => builder.push_text(&unescape(&t)),
+            Ok(Event::End(e)) => {
+                if skip_depth > 0 {
+                    skip_depth -= 1;
+                    continue;
+                }
+                match e.name().as_ref() {
+                    b"text:h" | b"text:p" => builder.flush(),
+                    b"table:table-cell" => builder.push_text(" "),
+                    _ => {}
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                tracing::warn!("Stopped reading {} early: {}", path.display(), e);
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(builder.finish(metadata))
+}
+
+// ============================================================================
+// EPUB
+// ============================================================================
+
+struct EpubPackage {
+    metadata: DocumentMetadata,
+    /// Content documents in reading order, as archive paths
+    spine: Vec<String>,
+}
+
+/// Resolve an href relative to the directory of the OPF file.
+fn resolve_href(base_dir: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or(href);
+    let href = href.replace("%20", " ");
+    let mut parts: Vec<&str> = base_dir.split('/').filter(|p| !p.is_empty()).collect();
+    for part in href.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+fn epub_package(archive: &mut Archive) -> Result<EpubPackage> {
+    let container = read_entry(archive, "META-INF/container.xml")?;
+    let mut reader = xml_reader(&container);
+    let mut opf_path = None;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) if e.local_name().as_ref() == b"rootfile" => {
+                opf_path = attribute(&e, b"full-path");
+                break;
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    let opf_path = opf_path.ok_or_else(|| anyhow!("EPUB container has no package document"))?;
+    let base_dir = opf_path.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+
+    let opf = read_entry(archive, &opf_path)?;
+    let metadata = DocumentMetadata {
+        title: first_text(&opf, &[b"dc:title", b"title"]),
+        author: first_text(&opf, &[b"dc:creator", b"creator"]),
+        created_at: first_text(&opf, &[b"dc:date", b"date"]),
+    };
+
+    let mut manifest: HashMap<String, String> = HashMap::new();
+    let mut spine_ids = Vec::new();
+    let mut reader = xml_reader(&opf);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) => match e.local_name().as_ref() {
+                b"item" => {
+                    if let (Some(id), Some(href)) = (attribute(&e, b"id"), attribute(&e, b"href")) {
+                        manifest.insert(id, resolve_href(base_dir, &href));
+                    }
+                }
+                b"itemref" => spine_ids.extend(attribute(&e, b"idref")),
+                _ => {}
+            },
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+
+    let spine = spine_ids
+        .iter()
+        .filter_map(|id| manifest.get(id).cloned())
+        .collect();
+    Ok(EpubPackage { metadata, spine })
+}
+
+/// Append the text of an XHTML content document.
+fn append_xhtml(builder: &mut TextBuilder, xhtml: &str) {
+    let mut reader = xml_reader(xhtml);
+    let mut skip_depth = 0usize;
+    let mut in_title = false;
+    let mut title = String::new();
+    let mut has_heading = false;
+    let sections_before = builder.sections.len();
+    let chapter_offset = builder.chars;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let name = e.local_name();
+                let name = name.as_ref().to_ascii_lowercase();
+                if skip_depth > 0 || matches!(name.as_slice(), b"script" | b"style" | b"head") {
+                    if name == b"title" {
+                        in_title = true;
+                    } else {
+                        skip_depth += 1;
+                    }
+                    continue;
+                }
+                match name.as_slice() {
+                    [b'h', level @ b'1'..=b'6'] => {
+                        has_heading = true;
+                        builder.start_heading(level - b'0');
+                    }
+                    b"p" | b"div" | b"li" | b"tr" | b"blockquote" | b"section" | b"pre"
+                    | b"dt" | b"dd" | b"figcaption" => builder.flush(),
+                    _ => {}
+                }
+            }
+            Ok(Event::Empty(e))
+                if skip_depth == 0 && e.local_name().as_ref().eq_ignore_ascii_case(b"br") =>
+            {
+                builder.line_break();
+            }
+            Ok(Event::Text(t)) => {
+                if in_title {
+                    title.push_str(&unescape(&t));
+                } else if skip_depth == 0 {
+                    builder.push_text(&unescape(&t));
+                }
+            }
+            Ok(Event::End(e)) => {
+                let name = e.local_name().as_ref().to_ascii_lowercase();
+                if in_title && name == b"title" {
+                    in_title = false;
+                } else if skip_depth > 0 {
+                    skip_depth -= 1;
+                } else if matches!(
+                    name.as_slice(),
+                    b"p" | b"div" | b"li" | b"h1" | b"h2" | b"h3" | b"h4" | b"h5" | b"h6"
+                ) {
+                    builder.flush();
+                } else if name == b"td" || name == b"th" {
+                    builder.push_text(" ");
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                tracing::debug!("Stopped reading EPUB chapter early: {}", e);
+                break;
+            }
+            _ => {}
+        }
+    }
+    builder.flush();
+
+    // Chapters without headings are still labelled by their <title>
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    if !has_heading && !title.is_empty() && builder.sections.len() == sections_before {
+        builder.sections.push(Section {
+            title,
+            level: 1,
+            chapter: builder.chapter,
+            offset: chapter_offset,
+        });
+    }
+}
+
+fn extract_epub(path: &Path) -> Result<ExtractedDocument> {
+    let mut archive = open_archive(path)?;
+    let package = epub_package(&mut archive)?;
+
+    let mut builder = TextBuilder::default();
+    for (index, item) in package.spine.iter().enumerate() {
+        let Ok(xhtml) = read_entry(&mut archive, item) else {
+            tracing::debug!("Skipping missing EPUB item {}", item);
+            continue;
+        };
+        builder.chapter = Some(index);
+        append_xhtml(&mut builder, &xhtml);
+    }
+
+    Ok(builder.finish(package.metadata))
+}
+
+// ============================================================================
+// RTF
+// ============================================================================
+
+/// Destinations whose content is never part of the document text.
+const RTF_SKIPPED_DESTINATIONS: &[&str] = &[
+    "fonttbl", "colortbl", "stylesheet", "listtable", "listoverridetable", "rsidtbl",
+    "generator", "pict", "object", "header", "headerl", "headerr", "headerf", "footer",
+    "footerl", "footerr", "footerf", "footnote", "fldinst", "themedata",
+    "colorschememapping", "latentstyles", "datastore", "xmlnstbl", "mmathPr", "filetbl",
+    "revtbl", "pgdsctbl", "bkmkstart", "bkmkend",
+];
+
+/// Windows-1252 characters for bytes 0x80-0x9F; other bytes map to Latin-1.
+const CP1252_HIGH: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž',
+    '\u{8f}', '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}',
+    'ž', 'Ÿ',
+];
+
+fn cp1252(byte: u8) -> char {
+    match byte {
+        0x80..=0x9F => CP1252_HIGH[(byte - 0x80) as usize],
+        _ => byte as char,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RtfDestination {
+    Body,
+    Skip,
+    Info,
+    Title,
+    Author,
+    Created,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RtfGroup {
+    destination: RtfDestination,
+    /// Fallback characters following each `\u` control word
+    unicode_skip: usize,
+}
+
+struct RtfParser<'a> {
+    data: &'a [u8],
+    pos: usize,
+    stack: Vec<RtfGroup>,
+    group: RtfGroup,
+    builder: TextBuilder,
+    outline_level: Option<u8>,
+    pending_skip: usize,
+    high_surrogate: Option<u16>,
+    title: String,
+    author: String,
+    created: [Option<i32>; 5],
+}
+
+impl<'a> RtfParser<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            stack: Vec::new(),
+            group: RtfGroup {
+                destination: RtfDestination::Body,
+                unicode_skip: 1,
+            },
+            builder: TextBuilder::default(),
+            outline_level: None,
+            pending_skip: 0,
+            high_surrogate: None,
+            title: String::new(),
+            author: String::new(),
+            created: [None; 5],
+        }
+    }
+
+    fn emit(&mut self, c: char) {
+        if self.pending_skip > 0 {
+            self.pending_skip -= 1;
+            return;
+        }
+        match self.group.destination {
+            RtfDestination::Body => {
+                let mut buf = [0u8; 4];
+                self.builder.push_text(c.encode_utf8(&mut buf));
+            }
+            RtfDestination::Title => self.title.push(c),
+            RtfDestination::Author => self.author.push(c),
+            _ => {}
+        }
+    }
+
+    fn end_paragraph(&mut self) {
+        if self.group.destination != RtfDestination::Body {
+            return;
+        }
+        if let Some(level) = self.outline_level {
+            if !self.builder.current.trim().is_empty() {
+                self.builder.heading = Some(level + 1);
+            }
+        }
+        self.builder.flush();
+    }
+
+    fn unicode(&mut self, value: i32) {
+        let unit = if value < 0 { value + 65536 } else { value } as u16;
+        let c = match (self.high_surrogate.take(), unit) {
+            (_, 0xD800..=0xDBFF) => {
+                self.high_surrogate = Some(unit);
+                None
+            }
+            (Some(high), 0xDC00..=0xDFFF) => {
+                char::decode_utf16([high, unit]).next().and_then(|r| r.ok())
+            }
+            (_, unit) => char::from_u32(unit as u32),
+        };
+        // Emit before arming the skip so the character itself isn't swallowed
+        if let Some(c) = c {
+            self.emit(c);
+        }
+        self.pending_skip = self.group.unicode_skip;
+    }
+
+    fn control_word(&mut self, word: &str, param: Option<i32>) {
+        if RTF_SKIPPED_DESTINATIONS.contains(&word) {
+            self.group.destination = RtfDestination::Skip;
+            return;
+        }
+        match (word, self.group.destination) {
+            ("info", _) => self.group.destination = RtfDestination::Info,
+            ("title", RtfDestination::Info) => self.group.destination = RtfDestination::Title,
+            ("author", RtfDestination::Info) => self.group.destination = RtfDestination::Author,
+            ("creatim", RtfDestination::Info) => self.group.destination = RtfDestination::Created,
+            ("yr" | "mo" | "dy" | "hr" | "min", RtfDestination::Created) => {
+                let index = ["yr", "mo", "dy", "hr", "min"]
+                    .iter()
+                    .position(|w| *w == word)
+                    .unwrap_or(0);
+                self.created[index] = param;
+            }
+            ("par" | "sect" | "page", _) => self.end_paragraph(),
+            ("line", RtfDestination::Body) => self.builder.line_break(),
+            ("pard", _) => self.outline_level = None,
+            ("outlinelevel", _) => {
+                self.outline_level = param.map(|l| l.clamp(0, 5) as u8);
+            }
+            ("cell", _) => self.emit(' '),
+            ("row", _) => self.end_paragraph(),
+            ("tab", _) => self.emit(' '),
+            ("emdash", _) => self.emit('—'),
+            ("endash", _) => self.emit('–'),
+            ("bullet", _) => self.emit('•'),
+            ("lquote", _) => self.emit('‘'),
+            ("rquote", _) => self.emit('’'),
+            ("ldblquote", _) => self.emit('“'),
+            ("rdblquote", _) => self.emit('”'),
+            ("u", _) => {
+                if let Some(value) = param {
+                    self.unicode(value);
+                }
+            }
+            ("uc", _) => self.group.unicode_skip = param.unwrap_or(1).max(0) as usize,
+            _ => {}
+        }
+    }
+
+    fn parse(mut self) -> ExtractedDocument {
+        while self.pos < self.data.len() {
+            let byte = self.data[self.pos];
+            self.pos += 1;
+            match byte {
+                b'{' => self.stack.push(self.group),
+                b'}' => {
+                    if let Some(group) = self.stack.pop() {
+                        self.group = group;
+                    }
+                }
+                b'\\' => self.escape(),
+                b'\r' | b'\n' => {}
+                _ => self.emit(cp1252(byte)),
+            }
+        }
+        self.end_paragraph();
+
+        let date = match self.created {
+            [Some(y), Some(m), Some(d), hour, minute] => Some(format!(
+                "{:04}-{:02}-{:02}T{:02}:{:02}:00",
+                y,
+                m,
+                d,
+                hour.unwrap_or(0),
+                minute.unwrap_or(0)
+            )),
+            _ => None,
+        };
+        let clean = |s: &str| {
+            let s = s.trim().trim_end_matches(';').trim();
+            (!s.is_empty()).then(|| s.to_string())
+        };
+        let metadata = DocumentMetadata {
+            title: clean(&self.title),
+            author: clean(&self.author),
+            created_at: date,
+        };
+        self.builder.finish(metadata)
+    }
+
+    fn escape(&mut self) {
+        let Some(&next) = self.data.get(self.pos) else {
+            return;
+        };
+        self.pos += 1;
+        match next {
+            b'\\' | b'{' | b'}' => self.emit(next as char),
+            b'~' => self.emit('\u{a0}'),
+            b'_' => self.emit('-'),
+            b'-' => {}
+            b'*' => self.group.destination = RtfDestination::Skip,
+            b'\r' | b'\n' => self.end_paragraph(),
+            b'\'' => {
+                let hex = self.data.get(self.pos..self.pos + 2).unwrap_or_default();
+                self.pos += hex.len();
+                if let Ok(byte) = u8::from_str_radix(&String::from_utf8_lossy(hex), 16) {
+                    self.emit(cp1252(byte));
+                }
+            }
+            c if c.is_ascii_alphabetic() => {
+                let start = self.pos - 1;
+                while self.data.get(self.pos).is_some_and(u8::is_ascii_alphabetic) {
+                    self.pos += 1;
+                }
+                let word = String::from_utf8_lossy(&self.data[start..self.pos]).into_owned();
+
+                let param_start = self.pos;
+                if self.data.get(self.pos) == Some(&b'-') {
+                    self.pos += 1;
+                }
+                while self.data.get(self.pos).is_some_and(u8::is_ascii_digit) {
+                    self.pos += 1;
+                }
+                let param = std::str::from_utf8(&self.data[param_start..self.pos])
+                    .ok()
+                    .and_then(|p| p.parse().ok());
+                // A single space delimits the control word and is not part of the text
+                if self.data.get(self.pos) == Some(&b' ') {
+                    self.pos += 1;
+                }
+
+                // Control words count as one fallback character after `\u`
+                if self.pending_skip > 0 && word != "u" {
+                    self.pending_skip -= 1;
+                    return;
+                }
+                self.control_word(&word, param);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn extract_rtf(data: &[u8]) -> Result<ExtractedDocument> {
+    if !data.starts_with(b"{\\rtf") {
+        return Err(anyhow!("Not an RTF document"));
+    }
+    Ok(RtfParser::new(data).parse())
+}
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Extract the text, sections, and metadata of an ODT, RTF, or EPUB file.
+pub fn extract(path: &Path) -> Result<ExtractedDocument> {
+    match extension_of(path).as_str() {
+        "odt" => extract_odt(path),
+        "epub" => extract_epub(path),
+        "rtf" => {
+            let data = std::fs::read(path)
+                .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+            extract_rtf(&data)
+        }
+        ext => Err(anyhow!("Native extraction does not support .{} files", ext)),
+    }
+}
+
+/// Convert an HTML or XHTML fragment (e.g. a wiki page body) to Markdown text.
+pub fn xhtml_to_markdown(xhtml: &str) -> String {
+    let mut builder = TextBuilder::default();
+    // A single root element keeps the reader going past the first block
+    append_xhtml(&mut builder, &format!("<div>{}</div>", xhtml));
+    builder.finish(DocumentMetadata::default()).text
+}
+
+/// Read only the metadata of an ODT, RTF, or EPUB file.
+pub fn extract_metadata(path: &Path) -> DocumentMetadata {
+    let result = match extension_of(path).as_str() {
+        "odt" => open_archive(path).map(|mut archive| odt_metadata(&mut archive)),
+        "epub" => open_archive(path)
+            .and_then(|mut archive| epub_package(&mut archive))
+            .map(|package| package.metadata),
+        "rtf" => std::fs::File::open(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| {
+                let mut head = Vec::new();
+                file.take(RTF_HEADER_BYTES).read_to_end(&mut head)?;
+                extract_rtf(&head)
+            })
+            .map(|document| document.metadata),
+        _ => return DocumentMetadata::default(),
+    };
+    result.unwrap_or_default()
+}
+
+/// Convert a file to Markdown under `~/.ragkit/tmp/extracted/<id>/` so the backend can
+/// ingest it. The caller removes the returned file's directory when done.
+pub fn extract_to_markdown(path: &Path) -> Result<PathBuf> {
+    let document = extract(path)?;
+    if document.text.trim().is_empty() {
+        return Err(anyhow!("No text found in {}", path.display()));
+    }
+
+    let dir = crate::store::tmp_dir()
+        .join("extracted")
+        .join(uuid::Uuid::new_v4().to_string());
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| anyhow!("Failed to create {}: {}", dir.display(), e))?;
+
+    // Keep the original name visible in citations, e.g. "report.odt.md"
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "document".to_string());
+    let target = dir.join(format!("{}.md", name));
+    std::fs::write(&target, document.text)
+        .map_err(|e| anyhow!("Failed to write {}: {}", target.display(), e))?;
+    Ok(target)
+}
+
+/// Read a plain text or Markdown file, with its Markdown headings as sections.
+fn read_plain_text(path: &Path) -> Result<ExtractedDocument> {
+    let data =
+        std::fs::read(path).map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+    let text = String::from_utf8_lossy(&data).into_owned();
+    let mut sections = Vec::new();
+    if extension_of(path) != "txt" {
+        let mut offset = 0;
+        for line in text.split_inclusive('\n') {
+            let hashes = line.chars().take_while(|c| *c == '#').count();
+            let title = line[hashes..].trim();
+            if (1..=6).contains(&hashes) && line[hashes..].starts_with(' ') && !title.is_empty() {
+                sections.push(Section {
+                    title: title.to_string(),
+                    level: hashes as u8,
+                    chapter: None,
+                    offset,
+                });
+            }
+            offset += line.chars().count();
+        }
+    }
+    Ok(ExtractedDocument {
+        text,
+        sections,
+        metadata: crate::metadata::extract(path),
+        truncated: false,
+    })
+}
+
+/// Extract the text of a file and split it into chunks, to tune chunking before
+/// ingesting it
+///
+/// Chunking options left out follow the settings. Only natively extracted formats and
+/// plain text or Markdown files can be previewed.
+#[tauri::command]
+pub async fn preview_document(
+    path: String,
+    chunk_strategy: Option<String>,
+    chunk_size: Option<i32>,
+    overlap: Option<i32>,
+    max_chars: Option<usize>,
+) -> Result<ChunkPreview, String> {
+    let (strategy, size, overlap) = match (chunk_strategy, chunk_size, overlap) {
+        (Some(strategy), Some(size), Some(overlap)) => (strategy, size, overlap),
+        (strategy, size, overlap) => {
+            let settings = crate::commands::get_settings().await;
+            let (default_strategy, default_size, default_overlap) = match settings {
+                Ok(s) => (
+                    s.embedding_chunk_strategy,
+                    s.embedding_chunk_size,
+                    s.embedding_chunk_overlap,
+                ),
+                // Backend defaults
+                Err(_) => ("fixed".to_string(), 512, 50),
+            };
+            (
+                strategy.unwrap_or(default_strategy),
+                size.unwrap_or(default_size),
+                overlap.unwrap_or(default_overlap),
+            )
+        }
+    };
+
+    let limit = max_chars.unwrap_or(DEFAULT_PREVIEW_CHARS);
+    let document = tauri::async_runtime::spawn_blocking(move || {
+        let path = Path::new(&path);
+        let mut document = match extension_of(path).as_str() {
+            _ if is_native(path) => extract(path),
+            "txt" | "md" | "markdown" => read_plain_text(path),
+            ext => Err(anyhow!("Previews are not available for .{} files", ext)),
+        }
+        .map_err(|e| e.to_string())?;
+        if let Some((cut, _)) = document.text.char_indices().nth(limit) {
+            document.text.truncate(cut);
+            document.sections.retain(|s| s.offset < limit);
+            document.truncated = true;
+        }
+        Ok::<_, String>(document)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    chunking::preview(document, &strategy, size, overlap)
+}
@@ -0,0 +1,182 @@
+//! Provider failover chains.
+//!
+//! Users can list fallback providers in order (e.g. Ollama → OpenAI → Anthropic). When the
+//! primary provider errors or times out, the query is retried against the next one and
+//! the response records which provider actually answered. Only provider failures
+//! (server errors, rate limits, timeouts) fail over: a request the backend rejects
+//! (e.g. an unknown knowledge base) would be rejected by every provider. The backend
+//! only saves the question once it has been answered, so failed attempts leave no
+//! orphan turn in the conversation; an attempt that times out is cancelled first.
+
+use crate::backend::{backend_request, BackendError, RateLimitError};
+use crate::capabilities::{self, Feature};
+use crate::commands::{self, QueryParams, QueryResponse};
+use crate::{network, store};
+use anyhow::{anyhow, Result};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const STATE_FILE: &str = "failover.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderTarget {
+    pub provider: String,
+    pub model: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailoverChains {
+    /// Fallback LLMs tried in order after the configured provider fails
+    pub llm: Vec<ProviderTarget>,
+    /// Fallback embedding providers, forwarded to the backend which knows which ones
+    /// produce vectors compatible with the index
+    pub embedding: Vec<ProviderTarget>,
+    /// Time allowed for each attempt before moving on to the next provider
+    pub attempt_timeout_secs: u64,
+}
+
+impl Default for FailoverChains {
+    fn default() -> Self {
+        Self {
+            llm: Vec::new(),
+            embedding: Vec::new(),
+            attempt_timeout_secs: 60,
+        }
+    }
+}
+
+async fn send_query(params: &QueryParams) -> Result<QueryResponse> {
+    backend_request(
+        Method::POST,
+        "/api/query",
+        Some(serde_json::to_value(params).unwrap()),
+    )
+    .await
+}
+
+/// An attempt ran out of time.
+#[derive(Debug)]
+struct AttemptTimeout(Duration);
+
+impl std::fmt::Display for AttemptTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Timed out after {} seconds", self.0.as_secs())
+    }
+}
+
+impl std::error::Error for AttemptTimeout {}
+
+async fn attempt(params: &QueryParams, timeout: Duration) -> Result<QueryResponse> {
+    match tokio::time::timeout(timeout, send_query(params)).await {
+        Ok(result) => result,
+        Err(_) => {
+            // The backend may still answer, and save the turn, after the next attempt
+            if let (Some(request_id), true) = (
+                &params.request_id,
+                capabilities::supports(Feature::QueryCancel),
+            ) {
+                let _ = backend_request::<serde_json::Value>(
+                    Method::POST,
+                    &format!("/api/query/{}/cancel", request_id),
+                    None,
+                )
+                .await;
+            }
+            Err(anyhow::Error::new(AttemptTimeout(timeout)))
+        }
+    }
+}
+
+/// Whether another provider may succeed where this attempt failed.
+fn should_fail_over(error: &anyhow::Error) -> bool {
+    error.is::<AttemptTimeout>()
+        || error.is::<RateLimitError>()
+        || error
+            .downcast_ref::<BackendError>()
+            .is_some_and(BackendError::is_server_error)
+}
+
+/// Provider and model of the primary attempt, as `provider/model`.
+async fn primary_target(params: &QueryParams) -> Option<String> {
+    match (&params.llm_provider, &params.llm_model) {
+        (Some(provider), Some(model)) => Some(format!("{}/{}", provider, model)),
+        _ => {
+            let settings = commands::get_settings().await.ok()?;
+            Some(format!(
+                "{}/{}",
+                params.llm_provider.as_ref().unwrap_or(&settings.llm_provider),
+                params.llm_model.as_ref().unwrap_or(&settings.llm_model)
+            ))
+        }
+    }
+}
+
+/// Run a query, falling back through the configured provider chain on failure.
+pub async fn query_with_failover(params: &QueryParams) -> Result<QueryResponse> {
+    let mut chains: FailoverChains = store::load(STATE_FILE);
+    chains.llm = network::local_targets(&chains.llm);
+    chains.embedding = network::local_targets(&chains.embedding);
+    if chains.llm.is_empty() && chains.embedding.is_empty() {
+        let mut response = send_query(params).await?;
+        response.served_by = primary_target(params).await;
+        return Ok(response);
+    }
+
+    let timeout = Duration::from_secs(chains.attempt_timeout_secs.max(1));
+    let mut params = params.clone();
+    if !chains.embedding.is_empty() {
+        params.embedding_fallbacks = Some(chains.embedding.clone());
+    }
+
+    let mut errors = Vec::new();
+    match attempt(&params, timeout).await {
+        Ok(mut response) => {
+            response.served_by = primary_target(&params).await;
+            return Ok(response);
+        }
+        Err(e) if !should_fail_over(&e) => return Err(e),
+        Err(e) => {
+            let primary = params.llm_provider.as_deref().unwrap_or("default provider");
+            tracing::warn!("Query failed on {}: {}", primary, e);
+            errors.push(format!("{}: {}", primary, e));
+        }
+    }
+
+    for target in &chains.llm {
+        params.llm_provider = Some(target.provider.clone());
+        params.llm_model = Some(target.model.clone());
+
+        match attempt(&params, timeout).await {
+            Ok(mut response) => {
+                tracing::info!("Query served by fallback provider {}", target.provider);
+                response.served_by = Some(format!("{}/{}", target.provider, target.model));
+                return Ok(response);
+            }
+            Err(e) if !should_fail_over(&e) => return Err(e),
+            Err(e) => {
+                tracing::warn!("Query failed on fallback {}: {}", target.provider, e);
+                errors.push(format!("{}: {}", target.provider, e));
+            }
+        }
+    }
+
+    Err(anyhow!("All providers failed: {}", errors.join("; ")))
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Get the provider failover chains
+#[tauri::command]
+pub async fn get_failover_chains() -> Result<FailoverChains, String> {
+    Ok(store::load(STATE_FILE))
+}
+
+/// Update the provider failover chains
+#[tauri::command]
+pub async fn set_failover_chains(chains: FailoverChains) -> Result<FailoverChains, String> {
+    store::save(STATE_FILE, &chains).map_err(|e| e.to_string())?;
+    Ok(chains)
+}
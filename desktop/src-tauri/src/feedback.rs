@@ -0,0 +1,133 @@
+//! Answer feedback (thumbs up/down).
+//!
+//! Ratings are sent to the backend with the message they rate, and also kept in
+//! feedback.json so the shell can chart answer quality over time, and still record
+//! feedback with backends that don't store it.
+
+use crate::backend::backend_request;
+use crate::capabilities::{self, Feature};
+use crate::commands::Message;
+use crate::store;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+const STATE_FILE: &str = "feedback.json";
+/// Number of recent comments returned with the summary.
+const RECENT_COMMENTS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rating {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Feedback {
+    pub message_id: String,
+    pub rating: Rating,
+    pub comment: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct RatingCounts {
+    pub up: usize,
+    pub down: usize,
+}
+
+impl RatingCounts {
+    fn add(&mut self, rating: Rating) {
+        match rating {
+            Rating::Up => self.up += 1,
+            Rating::Down => self.down += 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedbackSummary {
+    pub total: RatingCounts,
+    /// Ratings per day (YYYY-MM-DD)
+    pub by_day: BTreeMap<String, RatingCounts>,
+    /// Latest feedback with a comment, newest first
+    pub recent_comments: Vec<Feedback>,
+}
+
+/// Fill in the feedback of messages from the local store, when the backend didn't.
+pub fn annotate(messages: &mut [Message]) {
+    let stored: BTreeMap<String, Feedback> = store::load(STATE_FILE);
+    for message in messages {
+        if message.feedback.is_none() {
+            message.feedback = stored.get(&message.id).cloned();
+        }
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Rate an answer, replacing any previous rating of it
+#[tauri::command]
+pub async fn submit_feedback(
+    message_id: String,
+    rating: Rating,
+    comment: Option<String>,
+) -> Result<Feedback, String> {
+    let feedback = Feedback {
+        message_id: message_id.clone(),
+        rating,
+        comment: comment
+            .map(|c| c.trim().to_string())
+            .filter(|c| !c.is_empty()),
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    if capabilities::supports(Feature::MessageFeedback) {
+        backend_request::<serde_json::Value>(
+            Method::POST,
+            &format!("/api/messages/{}/feedback", message_id),
+            Some(json!({ "rating": feedback.rating, "comment": feedback.comment })),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    let mut stored: BTreeMap<String, Feedback> = store::load(STATE_FILE);
+    stored.insert(message_id, feedback.clone());
+    store::save(STATE_FILE, &stored).map_err(|e| e.to_string())?;
+    Ok(feedback)
+}
+
+/// Summarize the feedback given so far
+#[tauri::command]
+pub async fn get_feedback_summary() -> Result<FeedbackSummary, String> {
+    let stored: BTreeMap<String, Feedback> = store::load(STATE_FILE);
+    let mut total = RatingCounts::default();
+    let mut by_day: BTreeMap<String, RatingCounts> = BTreeMap::new();
+    for feedback in stored.values() {
+        total.add(feedback.rating);
+        let day = feedback
+            .created_at
+            .get(..10)
+            .unwrap_or_default()
+            .to_string();
+        by_day.entry(day).or_default().add(feedback.rating);
+    }
+
+    let mut recent_comments: Vec<Feedback> = stored
+        .into_values()
+        .filter(|f| f.comment.is_some())
+        .collect();
+    recent_comments.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    recent_comments.truncate(RECENT_COMMENTS);
+
+    Ok(FeedbackSummary {
+        total,
+        by_day,
+        recent_comments,
+    })
+}
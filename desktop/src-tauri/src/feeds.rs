@@ -0,0 +1,505 @@
+//! RSS and Atom feed subscriptions.
+//!
+//! A subscription polls a feed at its own interval and ingests the entries it hasn't
+//! seen into a knowledge base, for "news monitoring" knowledge bases that should stay
+//! current without imports by hand. Entries are recognised by their GUID (`<guid>` in
+//! RSS, `<id>` in Atom, else their link), and written as Markdown under
+//! `~/.ragkit/feeds/<id>/` with frontmatter holding the title, author, date, and link.
+//! An entry that fails to ingest is retried at the next poll. Subscriptions are
+//! persisted; a poll missed while the app was closed runs at the next start.
+
+use crate::jobs::{self, FileStatus, IngestOptions};
+use crate::store::{self, CachedStore};
+use crate::{backend, extraction, files, network};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use tauri::{AppHandle, Emitter};
+
+const TICK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 3600;
+const MIN_POLL_INTERVAL_SECS: u64 = 300;
+/// GUIDs remembered per feed; older ones have long left the feed.
+const MAX_SEEN_GUIDS: usize = 10_000;
+
+static FEEDS: CachedStore<Vec<StoredFeed>> = CachedStore::new("feeds.json");
+/// Feeds with a poll in progress.
+static POLLING: std::sync::Mutex<BTreeSet<String>> = std::sync::Mutex::new(BTreeSet::new());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Feed {
+    pub id: String,
+    pub kb_id: String,
+    pub url: String,
+    /// Title announced by the feed
+    pub title: Option<String>,
+    pub poll_interval_secs: u64,
+    pub created_at: DateTime<Utc>,
+    pub last_polled: Option<DateTime<Utc>>,
+    pub next_poll: DateTime<Utc>,
+    /// Error of the last poll, if it failed
+    pub last_error: Option<String>,
+    pub entries_ingested: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredFeed {
+    #[serde(flatten)]
+    feed: Feed,
+    /// GUIDs of the ingested entries, oldest first
+    #[serde(default)]
+    seen: Vec<String>,
+}
+
+/// Payload of the `feed-polled` event, sent after each poll.
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedPolledEvent {
+    pub feed_id: String,
+    pub kb_id: String,
+    pub new_entries: usize,
+    pub job_id: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct FeedEntry {
+    guid: String,
+    title: String,
+    link: String,
+    author: String,
+    published: String,
+    summary: String,
+    content: String,
+}
+
+struct ParsedFeed {
+    title: Option<String>,
+    entries: Vec<FeedEntry>,
+}
+
+fn attribute(element: &quick_xml::events::BytesStart, name: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == name)
+        .and_then(|a| a.unescape_value().ok().map(|v| v.into_owned()))
+}
+
+/// The field of an entry an element holds.
+fn field<'a>(entry: &'a mut FeedEntry, name: &[u8]) -> Option<&'a mut String> {
+    match name {
+        b"title" => Some(&mut entry.title),
+        b"link" => Some(&mut entry.link),
+        b"guid" | b"id" => Some(&mut entry.guid),
+        b"author" | b"creator" => Some(&mut entry.author),
+        b"pubdate" | b"published" | b"updated" | b"date" => Some(&mut entry.published),
+        b"description" | b"summary" => Some(&mut entry.summary),
+        b"encoded" | b"content" => Some(&mut entry.content),
+        _ => None,
+    }
+}
+
+/// Parse an RSS 2.0, RSS 1.0, or Atom document.
+fn parse_feed(xml: &str) -> Result<ParsedFeed> {
+    let mut reader = Reader::from_str(xml);
+    let config = reader.config_mut();
+    config.check_end_names = false;
+    config.trim_text(true);
+    let mut stack: Vec<Vec<u8>> = Vec::new();
+    let mut title: Option<String> = None;
+    let mut entry: Option<FeedEntry> = None;
+    let mut entries = Vec::new();
+    let mut is_feed = false;
+    let mut field_open = false;
+
+    loop {
+        let text = match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let name = e.local_name().as_ref().to_ascii_lowercase();
+                match name.as_slice() {
+                    b"rss" | b"rdf" | b"feed" | b"channel" => is_feed = true,
+                    b"item" | b"entry" => entry = Some(FeedEntry::default()),
+                    b"link" => {
+                        if let (Some(entry), Some(href)) = (entry.as_mut(), attribute(&e, b"href"))
+                        {
+                            atom_link(entry, &e, href);
+                        }
+                    }
+                    _ => {}
+                }
+                // Only the first element of a field is read (e.g. `published` over
+                // `updated` in Atom)
+                if let Some(entry) = entry.as_mut() {
+                    if stack.last().is_some_and(|p| p == b"item" || p == b"entry") {
+                        field_open = field(entry, &name).is_some_and(|f| f.is_empty());
+                    }
+                }
+                stack.push(name);
+                continue;
+            }
+            Ok(Event::Empty(e)) => {
+                if let (Some(entry), true) = (entry.as_mut(), e.local_name().as_ref() == b"link") {
+                    if let Some(href) = attribute(&e, b"href") {
+                        atom_link(entry, &e, href);
+                    }
+                }
+                continue;
+            }
+            Ok(Event::End(e)) => {
+                let name = e.local_name().as_ref().to_ascii_lowercase();
+                if matches!(name.as_slice(), b"item" | b"entry") {
+                    entries.extend(entry.take());
+                }
+                stack.pop();
+                continue;
+            }
+            Ok(Event::Text(t)) => t
+                .unescape()
+                .map(|t| t.into_owned())
+                .unwrap_or_else(|_| String::from_utf8_lossy(&t).into_owned()),
+            Ok(Event::CData(c)) => String::from_utf8_lossy(&c).into_owned(),
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(anyhow!("Invalid feed: {}", e)),
+            _ => continue,
+        };
+
+        // The field is the child of the item the text is in, at any depth
+        let Some(entry) = entry.as_mut() else {
+            if let [.., parent, name] = stack.as_slice() {
+                if name == b"title" && matches!(parent.as_slice(), b"channel" | b"feed") {
+                    title.get_or_insert_with(String::new).push_str(text.trim());
+                }
+            }
+            continue;
+        };
+        let Some(depth) = stack.iter().rposition(|n| n == b"item" || n == b"entry") else {
+            continue;
+        };
+        let Some(target) = stack.get(depth + 1).and_then(|n| field(entry, n)) else {
+            continue;
+        };
+        // Atom authors nest a name and an email: keep the name
+        if field_open && (target.is_empty() || stack.len() == depth + 2) {
+            target.push_str(&text);
+        }
+    }
+
+    if !is_feed {
+        return Err(anyhow!("Not an RSS or Atom feed"));
+    }
+    for entry in &mut entries {
+        for field in [
+            &mut entry.guid,
+            &mut entry.title,
+            &mut entry.link,
+            &mut entry.author,
+            &mut entry.published,
+        ] {
+            *field = field.trim().to_string();
+        }
+        if entry.guid.is_empty() {
+            entry.guid = if entry.link.is_empty() {
+                entry.title.clone()
+            } else {
+                entry.link.clone()
+            };
+        }
+    }
+    entries.retain(|e| !e.guid.is_empty());
+    Ok(ParsedFeed {
+        title: title.filter(|t| !t.is_empty()),
+        entries,
+    })
+}
+
+/// Take the page link of an Atom entry, ignoring enclosures and the like.
+fn atom_link(entry: &mut FeedEntry, element: &quick_xml::events::BytesStart, href: String) {
+    let rel = attribute(element, b"rel");
+    if entry.link.is_empty() && rel.as_deref().is_none_or(|r| r == "alternate") {
+        entry.link = href;
+    }
+}
+
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .or_else(|_| DateTime::parse_from_rfc2822(value))
+        .ok()
+        .map(|d| d.with_timezone(&Utc))
+}
+
+fn frontmatter_value(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "'").replace('\n', " "))
+}
+
+/// Markdown document of an entry, with frontmatter for the metadata extraction.
+fn to_markdown(entry: &FeedEntry, feed_title: Option<&str>) -> String {
+    let title = if entry.title.is_empty() {
+        "Untitled"
+    } else {
+        &entry.title
+    };
+    let mut markdown = format!("---\ntitle: {}\n", frontmatter_value(title));
+    if !entry.author.is_empty() {
+        markdown.push_str(&format!("author: {}\n", frontmatter_value(&entry.author)));
+    }
+    if let Some(date) = parse_date(&entry.published) {
+        markdown.push_str(&format!("date: {}\n", date.to_rfc3339()));
+    }
+    if !entry.link.is_empty() {
+        markdown.push_str(&format!("source: {}\n", entry.link));
+    }
+    if let Some(feed_title) = feed_title {
+        markdown.push_str(&format!("feed: {}\n", frontmatter_value(feed_title)));
+    }
+    let body = if entry.content.trim().is_empty() {
+        &entry.summary
+    } else {
+        &entry.content
+    };
+    markdown.push_str(&format!(
+        "---\n\n# {}\n\n{}\n",
+        title,
+        extraction::xhtml_to_markdown(body)
+    ));
+    markdown
+}
+
+/// File name of an entry; the GUID hash keeps it stable and unique.
+fn file_name(entry: &FeedEntry) -> String {
+    let date = parse_date(&entry.published)
+        .map(|d| d.format("%Y-%m-%d ").to_string())
+        .unwrap_or_default();
+    let name = files::safe_file_name(&format!("{}{}", date, entry.title), "Entry");
+    let hash = blake3::hash(entry.guid.as_bytes()).to_hex();
+    format!("{} ({}).md", name, &hash[..8])
+}
+
+async fn fetch(url: &str) -> Result<ParsedFeed> {
+    let response = backend::http_client()
+        .get(url)
+        .timeout(backend::TimeoutTier::Standard.timeout())
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(anyhow!("{} returned {}", url, status));
+    }
+    parse_feed(&response.text().await?)
+}
+
+/// Write the unseen entries and ingest them, returning the job and the GUIDs ingested.
+async fn ingest_new(
+    app: &AppHandle,
+    feed: &StoredFeed,
+    parsed: &ParsedFeed,
+) -> Result<(Option<String>, Vec<String>)> {
+    let seen: BTreeSet<&String> = feed.seen.iter().collect();
+    let new: Vec<&FeedEntry> = parsed
+        .entries
+        .iter()
+        .filter(|e| !seen.contains(&e.guid))
+        .collect();
+    if new.is_empty() {
+        return Ok((None, Vec::new()));
+    }
+
+    let dir = store::ragkit_dir().join("feeds").join(&feed.feed.id);
+    std::fs::create_dir_all(&dir)?;
+    let title = parsed.title.as_deref().or(feed.feed.title.as_deref());
+    let mut guids: BTreeMap<String, String> = BTreeMap::new();
+    for entry in new {
+        let path = dir.join(file_name(entry));
+        std::fs::write(&path, to_markdown(entry, title))?;
+        guids.insert(path.to_string_lossy().into_owned(), entry.guid.clone());
+    }
+
+    let paths: Vec<String> = guids.keys().cloned().collect();
+    let job_id = jobs::start_job(
+        app,
+        feed.feed.kb_id.clone(),
+        paths,
+        IngestOptions::default(),
+    )
+    .await;
+    let files = jobs::wait_for_job(&job_id)
+        .await
+        .map(|job| job.files)
+        .unwrap_or_default();
+    let ingested = files
+        .into_iter()
+        .filter(|f| matches!(f.status, FileStatus::Added | FileStatus::Duplicate))
+        .filter_map(|f| guids.remove(&f.path))
+        .collect();
+    Ok((Some(job_id), ingested))
+}
+
+async fn poll(app: &AppHandle, feed: StoredFeed) {
+    let outcome = match fetch(&feed.feed.url).await {
+        Ok(parsed) => ingest_new(app, &feed, &parsed)
+            .await
+            .map(|(job_id, ingested)| (parsed.title, job_id, ingested)),
+        Err(e) => Err(e),
+    };
+    let error = outcome.as_ref().err().map(|e| e.to_string());
+    if let Some(e) = &error {
+        tracing::warn!("Polling feed {} failed: {}", feed.feed.url, e);
+    }
+    let (job_id, new_entries) = FEEDS
+        .update(|feeds| {
+            let stored = feeds.iter_mut().find(|f| f.feed.id == feed.feed.id)?;
+            stored.feed.last_polled = Some(Utc::now());
+            stored.feed.last_error = error.clone();
+            let Ok((title, job_id, ingested)) = &outcome else {
+                return None;
+            };
+            if title.is_some() {
+                stored.feed.title = title.clone();
+            }
+            stored.feed.entries_ingested += ingested.len();
+            stored.seen.extend(ingested.iter().cloned());
+            let excess = stored.seen.len().saturating_sub(MAX_SEEN_GUIDS);
+            stored.seen.drain(..excess);
+            Some((job_id.clone(), ingested.len()))
+        })
+        .await
+        .unwrap_or((None, 0));
+    let _ = app.emit(
+        "feed-polled",
+        FeedPolledEvent {
+            feed_id: feed.feed.id.clone(),
+            kb_id: feed.feed.kb_id.clone(),
+            new_entries,
+            job_id,
+            error,
+        },
+    );
+}
+
+/// Start the background loop polling due feeds.
+pub fn start_feed_poller(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(TICK_INTERVAL).await;
+            poll_due_feeds(&app).await;
+        }
+    });
+}
+
+async fn poll_due_feeds(app: &AppHandle) {
+    if !backend::is_running() {
+        return;
+    }
+    let now = Utc::now();
+    let offline = network::is_offline();
+    let due: Vec<StoredFeed> = FEEDS
+        .update(|feeds| {
+            let mut due = Vec::new();
+            for stored in feeds.iter_mut() {
+                if stored.feed.next_poll > now || POLLING.lock().unwrap().contains(&stored.feed.id)
+                {
+                    continue;
+                }
+                if offline && !network::is_local_url(&stored.feed.url) {
+                    // Polled again once offline mode is turned off
+                    continue;
+                }
+                stored.feed.next_poll =
+                    now + Duration::seconds(stored.feed.poll_interval_secs as i64);
+                due.push(stored.clone());
+            }
+            due
+        })
+        .await;
+
+    for feed in due {
+        POLLING.lock().unwrap().insert(feed.feed.id.clone());
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let id = feed.feed.id.clone();
+            poll(&app, feed).await;
+            POLLING.lock().unwrap().remove(&id);
+        });
+    }
+}
+
+/// Delete the subscriptions of a deleted knowledge base.
+pub async fn forget_kb(kb_id: &str) {
+    FEEDS
+        .update(|feeds| feeds.retain(|f| f.feed.kb_id != kb_id))
+        .await;
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Subscribe a knowledge base to an RSS or Atom feed
+///
+/// `poll_interval` is in seconds (default one hour, at least five minutes). The feed is
+/// checked right away and its current entries ingested at the next poller tick.
+#[tauri::command]
+pub async fn subscribe_feed(
+    kb_id: String,
+    url: String,
+    poll_interval: Option<u64>,
+) -> Result<Feed, String> {
+    let poll_interval_secs = poll_interval
+        .unwrap_or(DEFAULT_POLL_INTERVAL_SECS)
+        .max(MIN_POLL_INTERVAL_SECS);
+    let url = url.trim().to_string();
+    network::check_url(&url)?;
+    let parsed = fetch(&url)
+        .await
+        .map_err(|e| format!("Failed to read the feed: {}", e))?;
+
+    let feed = Feed {
+        id: uuid::Uuid::new_v4().to_string(),
+        kb_id,
+        url,
+        title: parsed.title,
+        poll_interval_secs,
+        created_at: Utc::now(),
+        last_polled: None,
+        next_poll: Utc::now(),
+        last_error: None,
+        entries_ingested: 0,
+    };
+    FEEDS
+        .update(|feeds| {
+            feeds.push(StoredFeed {
+                feed: feed.clone(),
+                seen: Vec::new(),
+            })
+        })
+        .await;
+    Ok(feed)
+}
+
+/// List the feed subscriptions, optionally of one knowledge base
+#[tauri::command]
+pub async fn list_feeds(kb_id: Option<String>) -> Result<Vec<Feed>, String> {
+    Ok(FEEDS
+        .read(|feeds| {
+            feeds
+                .iter()
+                .filter(|f| kb_id.as_ref().is_none_or(|kb_id| &f.feed.kb_id == kb_id))
+                .map(|f| f.feed.clone())
+                .collect()
+        })
+        .await)
+}
+
+/// Unsubscribe from a feed; ingested entries stay in the knowledge base
+#[tauri::command]
+pub async fn unsubscribe_feed(id: String) -> Result<bool, String> {
+    Ok(FEEDS
+        .update(|feeds| {
+            let before = feeds.len();
+            feeds.retain(|f| f.feed.id != id);
+            feeds.len() != before
+        })
+        .await)
+}
@@ -0,0 +1,185 @@
+//! Exclusion filters for watched folders.
+//!
+//! Editors, office suites, and browsers litter folders with temporary artifacts
+//! (`~$report.docx`, `.file.swp`, `video.crdownload`, `Thumbs.db`...). Every path seen by
+//! the folder watcher is classified here so those files never reach a knowledge base,
+//! and the classification is reported back so users understand why a file was skipped.
+
+use crate::store;
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const STATE_FILE: &str = "watch_filters.json";
+
+const EDITOR_TEMP_SUFFIXES: &[&str] = &[".swp", ".swo", ".swx", ".tmp", ".temp", "~"];
+const PARTIAL_DOWNLOAD_SUFFIXES: &[&str] = &[
+    ".crdownload",
+    ".part",
+    ".partial",
+    ".download",
+    ".opdownload",
+];
+const SYSTEM_FILES: &[&str] = &[
+    "thumbs.db",
+    "ehthumbs.db",
+    "desktop.ini",
+    ".ds_store",
+    ".localized",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileClass {
+    Accepted,
+    EditorTemp,
+    OfficeLock,
+    PartialDownload,
+    Hidden,
+    System,
+    UserExcluded,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchFilters {
+    /// Skip dotfiles, dot-directories, and files with the OS hidden attribute
+    pub exclude_hidden: bool,
+    /// User-defined glob patterns matched against the path relative to the watched folder
+    pub custom_patterns: Vec<String>,
+}
+
+impl Default for WatchFilters {
+    fn default() -> Self {
+        Self {
+            exclude_hidden: true,
+            custom_patterns: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathClassification {
+    pub path: String,
+    pub class: FileClass,
+}
+
+pub fn load_filters() -> WatchFilters {
+    store::load(STATE_FILE)
+}
+
+fn classify_name(name: &str) -> Option<FileClass> {
+    let lower = name.to_lowercase();
+
+    if lower.starts_with("~$") || lower.starts_with(".~lock.") {
+        return Some(FileClass::OfficeLock);
+    }
+    if PARTIAL_DOWNLOAD_SUFFIXES.iter().any(|s| lower.ends_with(s)) {
+        return Some(FileClass::PartialDownload);
+    }
+    if EDITOR_TEMP_SUFFIXES.iter().any(|s| lower.ends_with(s))
+        || lower.starts_with(".#")
+        || (lower.starts_with('#') && lower.ends_with('#'))
+    {
+        return Some(FileClass::EditorTemp);
+    }
+    if SYSTEM_FILES.contains(&lower.as_str()) {
+        return Some(FileClass::System);
+    }
+    None
+}
+
+#[cfg(target_os = "windows")]
+fn has_hidden_attribute(path: &Path) -> Option<FileClass> {
+    use std::os::windows::fs::MetadataExt;
+
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    const FILE_ATTRIBUTE_SYSTEM: u32 = 0x4;
+
+    let attributes = std::fs::metadata(path).ok()?.file_attributes();
+    if attributes & FILE_ATTRIBUTE_SYSTEM != 0 {
+        Some(FileClass::System)
+    } else if attributes & FILE_ATTRIBUTE_HIDDEN != 0 {
+        Some(FileClass::Hidden)
+    } else {
+        None
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn has_hidden_attribute(_path: &Path) -> Option<FileClass> {
+    None
+}
+
+/// Classify a path found under a watched folder.
+pub fn classify(filters: &WatchFilters, root: &Path, path: &Path) -> FileClass {
+    if let Some(class) = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .and_then(classify_name)
+    {
+        return class;
+    }
+
+    let relative = path.strip_prefix(root).unwrap_or(path);
+
+    if filters.exclude_hidden {
+        let hidden_component = relative
+            .components()
+            .any(|c| c.as_os_str().to_string_lossy().starts_with('.'));
+        if hidden_component {
+            return FileClass::Hidden;
+        }
+        if let Some(class) = has_hidden_attribute(path) {
+            return class;
+        }
+    }
+
+    let relative_str = relative.to_string_lossy().replace('\\', "/");
+    let user_excluded = filters
+        .custom_patterns
+        .iter()
+        .filter_map(|p| Pattern::new(p).ok())
+        .any(|p| p.matches(&relative_str));
+    if user_excluded {
+        return FileClass::UserExcluded;
+    }
+
+    FileClass::Accepted
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Get the watch exclusion filters
+#[tauri::command]
+pub async fn get_watch_filters() -> Result<WatchFilters, String> {
+    Ok(load_filters())
+}
+
+/// Update the watch exclusion filters
+#[tauri::command]
+pub async fn set_watch_filters(filters: WatchFilters) -> Result<WatchFilters, String> {
+    for pattern in &filters.custom_patterns {
+        Pattern::new(pattern).map_err(|e| format!("Invalid pattern '{}': {}", pattern, e))?;
+    }
+    store::save(STATE_FILE, &filters).map_err(|e| e.to_string())?;
+    Ok(filters)
+}
+
+/// Classify paths against the current filters (used to preview exclusions)
+#[tauri::command]
+pub async fn classify_paths(
+    root: String,
+    paths: Vec<String>,
+) -> Result<Vec<PathClassification>, String> {
+    let filters = load_filters();
+    let root = Path::new(&root);
+    Ok(paths
+        .into_iter()
+        .map(|path| PathClassification {
+            class: classify(&filters, root, Path::new(&path)),
+            path,
+        })
+        .collect())
+}
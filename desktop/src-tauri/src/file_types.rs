@@ -0,0 +1,224 @@
+//! Supported file types, presets, and custom extensions.
+//!
+//! The backend parses PDF, Word, Markdown, and text files, and the shell extracts ODT,
+//! RTF, and EPUB itself. Images are read through OCR. Source code and other plain text
+//! formats are ingested with the backend's text parser. Users can register more
+//! extensions with a parser hint (e.g. `.log` as text, `.mdx` as Markdown); the hint is
+//! sent with each file so the backend picks the right parser. Presets bundle extensions
+//! ("Office docs", "Code", "Ebooks") so the UI doesn't hard-code them. Custom extensions
+//! are stored in custom_file_types.json.
+
+use crate::files::{self, extension_of};
+use crate::{extraction, ocr, store};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+const STATE_FILE: &str = "custom_file_types.json";
+
+/// Source code and plain text formats ingested with the text parser.
+const CODE_EXTENSIONS: &[&str] = &[
+    "py", "rs", "js", "jsx", "ts", "tsx", "java", "kt", "go", "c", "h", "cpp", "hpp", "cs", "rb",
+    "php", "swift", "scala", "sh", "sql", "json", "yaml", "yml", "toml", "xml", "html", "css",
+];
+
+/// Custom extensions, loaded on first use.
+static CUSTOM: Mutex<Option<BTreeMap<String, ParserHint>>> = Mutex::new(None);
+
+/// Backend parser a file type is read with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParserHint {
+    Pdf,
+    Docx,
+    Markdown,
+    Text,
+    /// Extracted by the shell (ODT, RTF, EPUB)
+    Native,
+    /// Read through OCR (images)
+    Ocr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileTypeSource {
+    Builtin,
+    Custom,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileType {
+    pub extension: String,
+    pub parser: ParserHint,
+    pub source: FileTypeSource,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileTypePreset {
+    pub id: String,
+    pub name: String,
+    pub extensions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SupportedFileTypes {
+    pub types: Vec<FileType>,
+    pub presets: Vec<FileTypePreset>,
+}
+
+fn builtin_parser(extension: &str) -> Option<ParserHint> {
+    match extension {
+        "pdf" => Some(ParserHint::Pdf),
+        "docx" | "doc" => Some(ParserHint::Docx),
+        "md" | "markdown" => Some(ParserHint::Markdown),
+        "txt" => Some(ParserHint::Text),
+        ext if extraction::NATIVE_EXTENSIONS.contains(&ext) => Some(ParserHint::Native),
+        ext if ocr::IMAGE_EXTENSIONS.contains(&ext) => Some(ParserHint::Ocr),
+        ext if CODE_EXTENSIONS.contains(&ext) => Some(ParserHint::Text),
+        _ => None,
+    }
+}
+
+fn with_custom<R>(f: impl FnOnce(&mut BTreeMap<String, ParserHint>) -> R) -> R {
+    let mut custom = CUSTOM.lock().unwrap();
+    f(custom.get_or_insert_with(|| store::load(STATE_FILE)))
+}
+
+/// Parser hint of a custom extension, sent to the backend with the file.
+pub fn custom_parser(path: &Path) -> Option<ParserHint> {
+    let extension = extension_of(path);
+    with_custom(|custom| custom.get(&extension).copied())
+}
+
+/// Whether a file is a code, plain text, or custom type (beyond the core document
+/// formats).
+pub fn is_extra(path: &Path) -> bool {
+    let extension = extension_of(path);
+    CODE_EXTENSIONS.contains(&extension.as_str())
+        || with_custom(|custom| custom.contains_key(&extension))
+}
+
+fn builtin_presets() -> Vec<FileTypePreset> {
+    let preset = |id: &str, name: &str, extensions: &[&str]| FileTypePreset {
+        id: id.to_string(),
+        name: name.to_string(),
+        extensions: extensions.iter().map(|e| e.to_string()).collect(),
+    };
+    vec![
+        preset(
+            "documents",
+            "All documents",
+            &["pdf", "docx", "doc", "odt", "rtf", "md", "markdown", "txt"],
+        ),
+        preset("office", "Office docs", &["docx", "doc", "odt", "rtf"]),
+        preset("pdf", "PDF", &["pdf"]),
+        preset("notes", "Notes", &["md", "markdown", "txt"]),
+        preset("code", "Code", CODE_EXTENSIONS),
+        preset("ebooks", "Ebooks", &["epub", "pdf"]),
+        preset(
+            "scans",
+            "Scans (OCR)",
+            &["pdf", "png", "jpg", "jpeg", "tif", "tiff"],
+        ),
+    ]
+}
+
+/// Extensions of a preset, including the custom ones registered with its parser.
+pub fn preset_extensions(id: &str) -> Result<Vec<String>, String> {
+    let mut preset = builtin_presets()
+        .into_iter()
+        .find(|p| p.id == id)
+        .ok_or_else(|| format!("Unknown file type preset: {}", id))?;
+    if preset.id == "code" || preset.id == "notes" {
+        with_custom(|custom| {
+            preset.extensions.extend(
+                custom
+                    .iter()
+                    .filter(|(_, parser)| matches!(parser, ParserHint::Text | ParserHint::Markdown))
+                    .map(|(ext, _)| ext.clone()),
+            )
+        });
+    }
+    Ok(preset.extensions)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// List the file types that can be ingested and the extension presets
+#[tauri::command]
+pub async fn get_supported_file_types() -> Result<SupportedFileTypes, String> {
+    let mut types: Vec<FileType> = files::SUPPORTED_EXTENSIONS
+        .iter()
+        .chain(extraction::NATIVE_EXTENSIONS)
+        .chain(ocr::IMAGE_EXTENSIONS)
+        .chain(CODE_EXTENSIONS)
+        .filter_map(|ext| {
+            Some(FileType {
+                extension: ext.to_string(),
+                parser: builtin_parser(ext)?,
+                source: FileTypeSource::Builtin,
+            })
+        })
+        .collect();
+    with_custom(|custom| {
+        types.extend(custom.iter().map(|(ext, parser)| FileType {
+            extension: ext.clone(),
+            parser: *parser,
+            source: FileTypeSource::Custom,
+        }))
+    });
+    Ok(SupportedFileTypes {
+        types,
+        presets: builtin_presets(),
+    })
+}
+
+/// Register an extension to ingest with one of the backend's parsers
+#[tauri::command]
+pub async fn register_custom_extension(
+    ext: String,
+    parser_hint: ParserHint,
+) -> Result<FileType, String> {
+    let extension = ext.trim().trim_start_matches('.').to_lowercase();
+    if extension.is_empty()
+        || extension.len() > 16
+        || !extension
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return Err(format!("Invalid extension: {}", ext));
+    }
+    if builtin_parser(&extension).is_some() {
+        return Err(format!(".{} files are already supported", extension));
+    }
+    if matches!(parser_hint, ParserHint::Native | ParserHint::Ocr) {
+        return Err("Custom extensions are read as PDF, Word, Markdown, or text".to_string());
+    }
+
+    with_custom(|custom| {
+        custom.insert(extension.clone(), parser_hint);
+        store::save(STATE_FILE, custom)
+    })
+    .map_err(|e| e.to_string())?;
+    Ok(FileType {
+        extension,
+        parser: parser_hint,
+        source: FileTypeSource::Custom,
+    })
+}
+
+/// Remove a custom extension
+#[tauri::command]
+pub async fn remove_custom_extension(ext: String) -> Result<bool, String> {
+    let extension = ext.trim().trim_start_matches('.').to_lowercase();
+    with_custom(|custom| {
+        if custom.remove(&extension).is_none() {
+            return Ok(false);
+        }
+        store::save(STATE_FILE, custom).map(|_| true)
+    })
+    .map_err(|e| e.to_string())
+}
@@ -0,0 +1,91 @@
+//! Filesystem helpers shared by the ingestion commands.
+
+use std::path::{Path, PathBuf};
+
+/// Extensions the backend can parse.
+pub const SUPPORTED_EXTENSIONS: &[&str] = &["pdf", "docx", "doc", "md", "markdown", "txt"];
+/// Longest generated file name, without the extension.
+pub const MAX_NAME_CHARS: usize = 80;
+
+/// Whether this file can be ingested, by the backend, through native extraction, or
+/// through OCR for images.
+pub fn is_supported(path: &Path) -> bool {
+    SUPPORTED_EXTENSIONS.contains(&extension_of(path).as_str())
+        || crate::extraction::is_native(path)
+        || crate::ocr::is_image(path)
+        || crate::file_types::is_extra(path)
+}
+
+/// Lowercase extension of a path, without the leading dot.
+pub fn extension_of(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default()
+}
+
+/// List the files under `root` whose extension is in `file_types` (e.g. `["pdf", "md"]`).
+///
+/// An empty `file_types` accepts every file. Unreadable directories are skipped.
+pub fn collect_files(root: &Path, recursive: bool, file_types: &[String]) -> Vec<PathBuf> {
+    let wanted: Vec<String> = file_types
+        .iter()
+        .map(|t| t.trim_start_matches('.').to_lowercase())
+        .collect();
+
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            tracing::warn!("Skipping unreadable directory {}", dir.display());
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                if recursive {
+                    pending.push(path);
+                }
+            } else if file_type.is_file()
+                && (wanted.is_empty() || wanted.contains(&extension_of(&path)))
+            {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    files
+}
+
+/// File name, without the extension, made from a title: characters other than letters,
+/// digits, spaces, `-`, `_` and `.` are replaced, and `fallback` is used when nothing is
+/// left.
+pub fn safe_file_name(stem: &str, fallback: &str) -> String {
+    let name: String = stem
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(MAX_NAME_CHARS)
+        .collect();
+    let name = name.trim().trim_end_matches('.');
+    if name.is_empty() {
+        fallback.to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Lowercase hex of bytes.
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,90 @@
+//! Guest mode: a throwaway session for demos and for trying the app on someone
+//! else's machine.
+//!
+//! Launching with `--guest` (or `RAGKIT_GUEST=1`) points both the shell state and the
+//! backend at a fresh temporary directory, blocks API key changes, and marks the
+//! window title. The directory is deleted when the app exits.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const WINDOW_TITLE: &str = "RAGKIT Desktop - Guest mode (data is erased on exit)";
+
+static GUEST_DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GuestModeInfo {
+    pub active: bool,
+    pub data_dir: Option<String>,
+}
+
+fn requested() -> bool {
+    std::env::args().skip(1).any(|arg| arg == "--guest")
+        || std::env::var("RAGKIT_GUEST").is_ok_and(|v| v == "1" || v == "true")
+}
+
+/// Temporary data directory of the guest session, if guest mode is active.
+pub fn data_dir() -> Option<&'static Path> {
+    GUEST_DIR
+        .get_or_init(|| {
+            if !requested() {
+                return None;
+            }
+            let dir = std::env::temp_dir().join(format!("ragkit-guest-{}", uuid::Uuid::new_v4()));
+            match std::fs::create_dir_all(&dir) {
+                Ok(()) => Some(dir),
+                Err(e) => {
+                    // Never fall back to the real data directory
+                    eprintln!("Failed to create guest directory {}: {}", dir.display(), e);
+                    std::process::exit(1);
+                }
+            }
+        })
+        .as_deref()
+}
+
+/// Whether the app was launched in guest mode.
+pub fn is_active() -> bool {
+    data_dir().is_some()
+}
+
+/// Fail with `message` when guest mode is active.
+pub fn ensure_not_guest(message: &str) -> Result<(), String> {
+    if is_active() {
+        return Err(message.to_string());
+    }
+    Ok(())
+}
+
+/// Mark the main window so a guest session is never mistaken for the owner's.
+pub fn apply_watermark(app: &tauri::AppHandle) {
+    use tauri::Manager;
+
+    if !is_active() {
+        return;
+    }
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.set_title(WINDOW_TITLE);
+    }
+}
+
+/// Delete everything the guest session wrote.
+pub fn wipe() {
+    let Some(dir) = data_dir() else {
+        return;
+    };
+    match std::fs::remove_dir_all(dir) {
+        Ok(()) => tracing::info!("Erased guest data at {}", dir.display()),
+        Err(e) => tracing::error!("Failed to erase guest data at {}: {}", dir.display(), e),
+    }
+}
+
+/// Get whether the app runs in guest mode
+#[tauri::command]
+pub fn get_guest_mode() -> GuestModeInfo {
+    GuestModeInfo {
+        active: is_active(),
+        data_dir: data_dir().map(|d| d.display().to_string()),
+    }
+}
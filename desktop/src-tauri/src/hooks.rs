@@ -0,0 +1,324 @@
+//! Scriptable hooks run after key events.
+//!
+//! A hook is an external program the shell runs when an answer is saved, an ingestion
+//! job completes, or a backup (knowledge base export) finishes, with the event as JSON
+//! on its stdin, so teams can wire RAGKIT into their own automation. Programs are run
+//! directly, not through a shell, with a timeout; by default they get an empty
+//! environment (apart from `PATH`) and a scratch working directory.
+
+use crate::store;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+
+const STATE_FILE: &str = "hooks.json";
+/// Number of runs kept in the run log.
+const MAX_RUNS: usize = 50;
+/// Longest output kept per stream in the run log.
+const MAX_OUTPUT_CHARS: usize = 4000;
+/// Variables kept in a cleared environment (Windows programs need `SYSTEMROOT`).
+const BASE_ENV: &[&str] = &["PATH", "SYSTEMROOT"];
+
+/// Recent hook runs, newest last.
+static RUNS: Mutex<Vec<HookRun>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    AnswerSaved,
+    IngestionCompleted,
+    BackupFinished,
+}
+
+impl HookEvent {
+    fn name(self) -> &'static str {
+        match self {
+            HookEvent::AnswerSaved => "answer_saved",
+            HookEvent::IngestionCompleted => "ingestion_completed",
+            HookEvent::BackupFinished => "backup_finished",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HookSandbox {
+    /// Start the program with an empty environment, apart from `PATH` and `allowed_env`
+    pub clear_env: bool,
+    /// Environment variables passed through when `clear_env` is set
+    pub allowed_env: Vec<String>,
+    /// Working directory of the program; a scratch directory, removed after the run,
+    /// when not set
+    pub working_dir: Option<String>,
+}
+
+impl Default for HookSandbox {
+    fn default() -> Self {
+        HookSandbox {
+            clear_env: true,
+            allowed_env: Vec::new(),
+            working_dir: None,
+        }
+    }
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hook {
+    /// Empty when saving a new hook
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub event: HookEvent,
+    /// Program to run, looked up in `PATH` unless absolute
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub sandbox: HookSandbox,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HookRun {
+    pub hook_id: String,
+    pub event: HookEvent,
+    pub started_at: String,
+    pub duration_ms: u64,
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub error: Option<String>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+fn tail(bytes: &[u8]) -> String {
+    let text = String::from_utf8_lossy(bytes);
+    let skip = text.chars().count().saturating_sub(MAX_OUTPUT_CHARS);
+    text.chars().skip(skip).collect()
+}
+
+async fn execute(hook: &Hook, stdin: &[u8], run: &mut HookRun) -> anyhow::Result<()> {
+    let scratch = match &hook.sandbox.working_dir {
+        Some(_) => None,
+        None => {
+            let dir = store::tmp_dir()
+                .join("hooks")
+                .join(uuid::Uuid::new_v4().to_string());
+            tokio::fs::create_dir_all(&dir).await?;
+            Some(dir)
+        }
+    };
+    let working_dir = hook
+        .sandbox
+        .working_dir
+        .as_ref()
+        .map(PathBuf::from)
+        .or_else(|| scratch.clone());
+
+    let mut command = tokio::process::Command::new(&hook.program);
+    command
+        .args(&hook.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(dir) = working_dir {
+        command.current_dir(dir);
+    }
+    if hook.sandbox.clear_env {
+        command.env_clear();
+        let allowed = hook.sandbox.allowed_env.iter().map(String::as_str);
+        for name in BASE_ENV.iter().copied().chain(allowed) {
+            if let Ok(value) = std::env::var(name) {
+                command.env(name, value);
+            }
+        }
+    }
+    command.env("RAGKIT_HOOK_EVENT", hook.event.name());
+
+    let result = async {
+        let mut child = command.spawn()?;
+        let pipe = child.stdin.take();
+        let input = async move {
+            if let Some(mut pipe) = pipe {
+                // A program that doesn't read its input closes the pipe early
+                let _ = pipe.write_all(stdin).await;
+            }
+        };
+        // The input is written while the output is read, within the timeout, so a
+        // program that neither reads it nor exits can't hang the hook
+        let output = async { tokio::join!(input, child.wait_with_output()).1 };
+        let timeout = Duration::from_secs(hook.timeout_secs.max(1));
+        match tokio::time::timeout(timeout, output).await {
+            Ok(output) => {
+                let output = output?;
+                run.exit_code = output.status.code();
+                run.stdout = tail(&output.stdout);
+                run.stderr = tail(&output.stderr);
+            }
+            // Dropping the child kills it
+            Err(_) => run.timed_out = true,
+        }
+        anyhow::Ok(())
+    }
+    .await;
+
+    if let Some(dir) = scratch {
+        let _ = tokio::fs::remove_dir_all(dir).await;
+    }
+    result
+}
+
+async fn run(hook: &Hook, payload: &serde_json::Value) -> HookRun {
+    let started = Instant::now();
+    let mut run = HookRun {
+        hook_id: hook.id.clone(),
+        event: hook.event,
+        started_at: chrono::Utc::now().to_rfc3339(),
+        duration_ms: 0,
+        exit_code: None,
+        timed_out: false,
+        error: None,
+        stdout: String::new(),
+        stderr: String::new(),
+    };
+    let stdin = json!({
+        "event": hook.event,
+        "timestamp": run.started_at,
+        "data": payload,
+    })
+    .to_string();
+    if let Err(e) = execute(hook, stdin.as_bytes(), &mut run).await {
+        run.error = Some(e.to_string());
+    }
+    run.duration_ms = started.elapsed().as_millis() as u64;
+
+    if run.timed_out {
+        tracing::warn!("Hook {} timed out after {} s", hook.name, hook.timeout_secs);
+    } else if run.error.is_some() || run.exit_code != Some(0) {
+        tracing::warn!(
+            "Hook {} failed (exit code {:?}): {}",
+            hook.name,
+            run.exit_code,
+            run.error.as_deref().unwrap_or(run.stderr.trim())
+        );
+    } else {
+        tracing::info!("Hook {} ran in {} ms", hook.name, run.duration_ms);
+    }
+
+    let mut runs = RUNS.lock().unwrap();
+    runs.push(run.clone());
+    let excess = runs.len().saturating_sub(MAX_RUNS);
+    runs.drain(..excess);
+    run
+}
+
+/// Run the enabled hooks of an event in the background.
+pub fn fire(event: HookEvent, payload: impl Serialize) {
+    let hooks: Vec<Hook> = store::load::<Vec<Hook>>(STATE_FILE)
+        .into_iter()
+        .filter(|h| h.enabled && h.event == event)
+        .collect();
+    if hooks.is_empty() {
+        return;
+    }
+    let payload = match serde_json::to_value(payload) {
+        Ok(payload) => payload,
+        Err(e) => {
+            tracing::warn!("Failed to serialize the {:?} hook payload: {}", event, e);
+            return;
+        }
+    };
+    for hook in hooks {
+        let payload = payload.clone();
+        tauri::async_runtime::spawn(async move {
+            run(&hook, &payload).await;
+        });
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// List the configured hooks
+#[tauri::command]
+pub async fn list_hooks() -> Result<Vec<Hook>, String> {
+    Ok(store::load(STATE_FILE))
+}
+
+/// Create a hook, or update it when its id is set
+#[tauri::command]
+pub async fn save_hook(mut hook: Hook) -> Result<Hook, String> {
+    hook.name = hook.name.trim().to_string();
+    hook.program = hook.program.trim().to_string();
+    if hook.name.is_empty() {
+        return Err("Hook name cannot be empty".into());
+    }
+    if hook.program.is_empty() {
+        return Err("Hook program cannot be empty".into());
+    }
+    if let Some(dir) = &hook.sandbox.working_dir {
+        if !std::path::Path::new(dir).is_dir() {
+            return Err(format!("Working directory not found: {}", dir));
+        }
+    }
+
+    let mut hooks: Vec<Hook> = store::load(STATE_FILE);
+    if hook.id.is_empty() {
+        hook.id = uuid::Uuid::new_v4().to_string();
+        hooks.push(hook.clone());
+    } else {
+        let existing = hooks
+            .iter_mut()
+            .find(|h| h.id == hook.id)
+            .ok_or_else(|| format!("Hook not found: {}", hook.id))?;
+        *existing = hook.clone();
+    }
+    store::save(STATE_FILE, &hooks).map_err(|e| e.to_string())?;
+    Ok(hook)
+}
+
+/// Delete a hook
+#[tauri::command]
+pub async fn delete_hook(id: String) -> Result<bool, String> {
+    let mut hooks: Vec<Hook> = store::load(STATE_FILE);
+    let before = hooks.len();
+    hooks.retain(|h| h.id != id);
+    if hooks.len() == before {
+        return Ok(false);
+    }
+    store::save(STATE_FILE, &hooks).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// Run a hook once with a sample payload and return the result
+#[tauri::command]
+pub async fn test_hook(id: String) -> Result<HookRun, String> {
+    let hook = store::load::<Vec<Hook>>(STATE_FILE)
+        .into_iter()
+        .find(|h| h.id == id)
+        .ok_or_else(|| format!("Hook not found: {}", id))?;
+    Ok(run(&hook, &json!({ "test": true })).await)
+}
+
+/// List recent hook runs, newest first
+#[tauri::command]
+pub async fn list_hook_runs() -> Result<Vec<HookRun>, String> {
+    Ok(RUNS.lock().unwrap().iter().rev().cloned().collect())
+}
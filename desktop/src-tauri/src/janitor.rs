@@ -0,0 +1,206 @@
+//! Cleanup of orphaned temporary files under `~/.ragkit/tmp`.
+//!
+//! Extraction directories, partially unpacked archives, download segments, export
+//! files, and transcripts never added to a knowledge base are normally removed by the
+//! operation that created them, but a crash or a killed import leaves them behind. The
+//! janitor sweeps them on startup and then periodically, only touching entries old
+//! enough that no running operation owns them.
+
+use crate::store;
+use chrono::Utc;
+use serde::Serialize;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tokio::sync::Mutex;
+
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Entries untouched for this long at startup belong to a previous session.
+const STARTUP_MIN_AGE: Duration = Duration::from_secs(10 * 60);
+/// Scheduled sweeps leave room for long-running imports and exports.
+const SCHEDULED_MIN_AGE: Duration = Duration::from_secs(6 * 60 * 60);
+
+static LAST_REPORT: Mutex<Option<CleanupReport>> = Mutex::const_new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TempCategory {
+    Extraction,
+    ArchiveUnpack,
+    Download,
+    Export,
+    Transcript,
+    Other,
+}
+
+impl TempCategory {
+    fn from_dir(name: &str) -> Self {
+        match name {
+            "extracted" => Self::Extraction,
+            "unpack" => Self::ArchiveUnpack,
+            "downloads" => Self::Download,
+            "exports" => Self::Export,
+            "transcripts" => Self::Transcript,
+            _ => Self::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanupTrigger {
+    Startup,
+    Scheduled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryCleanup {
+    pub category: TempCategory,
+    pub removed: usize,
+    pub freed_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CleanupReport {
+    pub ran_at: String,
+    pub trigger: CleanupTrigger,
+    pub removed: usize,
+    pub freed_bytes: u64,
+    pub categories: Vec<CategoryCleanup>,
+    pub errors: Vec<String>,
+}
+
+fn size_of(path: &Path) -> u64 {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|e| size_of(&e.path())).sum())
+        .unwrap_or(0)
+}
+
+fn is_stale(path: &Path, min_age: Duration) -> bool {
+    std::fs::symlink_metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age >= min_age)
+}
+
+fn remove(path: &Path) -> std::io::Result<()> {
+    if std::fs::symlink_metadata(path)?.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+/// Remove stale entries from the temp directory. Each child of a category directory
+/// (e.g. one extraction) is removed as a whole.
+fn sweep(trigger: CleanupTrigger) -> CleanupReport {
+    let min_age = match trigger {
+        CleanupTrigger::Startup => STARTUP_MIN_AGE,
+        CleanupTrigger::Scheduled => SCHEDULED_MIN_AGE,
+    };
+    let mut report = CleanupReport {
+        ran_at: Utc::now().to_rfc3339(),
+        trigger,
+        removed: 0,
+        freed_bytes: 0,
+        categories: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    let Ok(top_level) = std::fs::read_dir(store::tmp_dir()) else {
+        return report;
+    };
+
+    for entry in top_level.flatten() {
+        let path = entry.path();
+        let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let category = if is_dir {
+            TempCategory::from_dir(&name)
+        } else {
+            TempCategory::Other
+        };
+
+        // Category directories are kept; loose files and unknown directories are
+        // entries themselves.
+        let candidates: Vec<_> = if is_dir && category != TempCategory::Other {
+            std::fs::read_dir(&path)
+                .map(|entries| entries.flatten().map(|e| e.path()).collect())
+                .unwrap_or_default()
+        } else {
+            vec![path]
+        };
+
+        for candidate in candidates {
+            if !is_stale(&candidate, min_age) {
+                continue;
+            }
+            let size = size_of(&candidate);
+            match remove(&candidate) {
+                Ok(()) => {
+                    report.removed += 1;
+                    report.freed_bytes += size;
+                    match report.categories.iter_mut().find(|c| c.category == category) {
+                        Some(c) => {
+                            c.removed += 1;
+                            c.freed_bytes += size;
+                        }
+                        None => report.categories.push(CategoryCleanup {
+                            category,
+                            removed: 1,
+                            freed_bytes: size,
+                        }),
+                    }
+                }
+                Err(e) => report
+                    .errors
+                    .push(format!("{}: {}", candidate.display(), e)),
+            }
+        }
+    }
+
+    report
+}
+
+async fn run(trigger: CleanupTrigger) {
+    let report = match tauri::async_runtime::spawn_blocking(move || sweep(trigger)).await {
+        Ok(report) => report,
+        Err(e) => {
+            tracing::error!("Temp file cleanup failed: {}", e);
+            return;
+        }
+    };
+
+    if report.removed > 0 || !report.errors.is_empty() {
+        tracing::info!(
+            "Removed {} orphaned temp entries ({} bytes), {} errors",
+            report.removed,
+            report.freed_bytes,
+            report.errors.len()
+        );
+    }
+    *LAST_REPORT.lock().await = Some(report);
+}
+
+/// Sweep once now and then every `SWEEP_INTERVAL`.
+pub fn start_janitor() {
+    tauri::async_runtime::spawn(async {
+        run(CleanupTrigger::Startup).await;
+        loop {
+            tokio::time::sleep(SWEEP_INTERVAL).await;
+            run(CleanupTrigger::Scheduled).await;
+        }
+    });
+}
+
+/// Get the report of the last temp file cleanup
+#[tauri::command]
+pub async fn get_cleanup_report() -> Result<Option<CleanupReport>, String> {
+    Ok(LAST_REPORT.lock().await.clone())
+}
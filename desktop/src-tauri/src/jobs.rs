@@ -0,0 +1,992 @@
+//! Background ingestion jobs.
+//!
+//! `add_documents` and `add_folder` return a job id immediately; the files are then sent
+//! to the backend one at a time by a background task, which emits an
+//! `ingestion-progress` event after each file so the UI can show per-file status.
+//!
+//! Jobs wait in a queue and run in creation order, at most `ingestion_workers` (a
+//! preference) at a time, so bulk imports don't saturate the machine. The queue can be
+//! paused, which also holds running jobs before their next file, and jobs can be
+//! cancelled.
+//!
+//! Scanned PDFs and images are only sent when OCR is enabled; otherwise they fail with
+//! `needs_ocr` set (see [`crate::ocr`]).
+//!
+//! Jobs can be stopped between two files when the app quits. Checkpointed jobs save
+//! their remaining files and are resumed the next time the backend starts.
+
+use crate::archives::{self, ArchiveResult};
+use crate::backend::backend_request;
+use crate::capabilities::{self, Feature};
+use crate::commands::{AddFolderFailure, AddFolderResponse, SkippedDuplicate};
+use crate::dedup::{self, DuplicateOf};
+use crate::hooks::{self, HookEvent};
+use crate::kb_history::{self, KbChange};
+use crate::metadata::{self, DocumentMetadata};
+use crate::provenance::{self, FileStamp};
+use crate::{
+    commands, extraction, file_types, kb_settings, lexical_index, ocr, preferences, retry_queue,
+    store,
+};
+use chrono::Utc;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Mutex;
+
+static JOBS: Mutex<Vec<IngestionJob>> = Mutex::const_new(Vec::new());
+
+/// Set while running jobs must stop before their next file.
+static STOP: std::sync::Mutex<Option<StopMode>> = std::sync::Mutex::new(None);
+
+/// Set while the queue is paused.
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// Jobs cancelled by the user, stopped before their next file.
+static CANCELLED: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+
+/// Finished jobs kept around for `list_ingestion_jobs`.
+const MAX_FINISHED_JOBS: usize = 50;
+/// Files of checkpointed jobs, ingested once the backend is back.
+const CHECKPOINT_FILE: &str = "ingestion_checkpoint.json";
+/// How often queued and paused jobs check whether they may go on.
+const QUEUE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    /// Stopped on exit; the remaining files are resumed at the next start
+    Paused,
+    Cancelled,
+}
+
+impl JobStatus {
+    fn is_finished(self) -> bool {
+        !matches!(self, JobStatus::Queued | JobStatus::Running)
+    }
+}
+
+/// How running jobs are stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopMode {
+    /// Save the remaining files so the job resumes later
+    Checkpoint,
+    /// Drop the remaining files
+    Abort,
+}
+
+/// Options of an import.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IngestOptions {
+    /// Unpack .zip and .tar(.gz) files and ingest their supported members
+    pub extract_archives: bool,
+    /// Run OCR on scanned PDFs and images (the backend setting when unset)
+    pub ocr_enabled: Option<bool>,
+    /// OCR language, as Tesseract codes (e.g. `eng` or `fra+eng`)
+    pub ocr_language: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CheckpointedJob {
+    kb_id: String,
+    paths: Vec<String>,
+    #[serde(flatten)]
+    options: IngestOptions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileStatus {
+    Added,
+    Failed,
+    /// Skipped because its content is already in the knowledge base
+    Duplicate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileResult {
+    pub path: String,
+    pub status: FileStatus,
+    pub document_id: Option<String>,
+    pub error: Option<String>,
+    /// Document with the same content, for skipped duplicates
+    #[serde(default)]
+    pub duplicate_of: Option<DuplicateOf>,
+    /// Set when the file was not sent because it needs OCR, which is disabled
+    #[serde(default)]
+    pub needs_ocr: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngestionJob {
+    pub id: String,
+    pub kb_id: String,
+    pub status: JobStatus,
+    pub total: usize,
+    pub processed: usize,
+    pub current_file: Option<String>,
+    pub files: Vec<FileResult>,
+    pub error: Option<String>,
+    pub created_at: String,
+    /// Archives unpacked by the job, with the results of their members
+    #[serde(default)]
+    pub archives: Vec<ArchiveResult>,
+}
+
+impl IngestionJob {
+    fn percent(&self) -> f64 {
+        if self.total == 0 {
+            100.0
+        } else {
+            self.processed as f64 * 100.0 / self.total as f64
+        }
+    }
+
+    fn result(&self) -> AddFolderResponse {
+        AddFolderResponse {
+            added: self
+                .files
+                .iter()
+                .filter_map(|f| f.document_id.clone())
+                .collect(),
+            failed: self
+                .files
+                .iter()
+                .filter(|f| f.status == FileStatus::Failed)
+                .map(|f| AddFolderFailure {
+                    path: f.path.clone(),
+                    error: f.error.clone().unwrap_or_default(),
+                    needs_ocr: f.needs_ocr,
+                })
+                .collect(),
+            total_processed: self.processed,
+            skipped_duplicates: self
+                .files
+                .iter()
+                .filter_map(|f| {
+                    Some(SkippedDuplicate {
+                        path: f.path.clone(),
+                        duplicate_of: f.duplicate_of.clone()?,
+                    })
+                })
+                .collect(),
+            archives: self.archives.clone(),
+        }
+    }
+}
+
+/// Payload of the `ingestion-progress` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct IngestionProgressEvent {
+    pub job_id: String,
+    pub kb_id: String,
+    pub status: JobStatus,
+    pub processed: usize,
+    pub total: usize,
+    pub percent: f64,
+    pub current_file: Option<String>,
+    /// Result of the file that just finished, if any
+    pub file: Option<FileResult>,
+    /// Summary, only set once the job has completed
+    pub result: Option<AddFolderResponse>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobQueue {
+    pub paused: bool,
+    /// Jobs that may run at the same time
+    pub workers: usize,
+    pub running: Vec<IngestionJob>,
+    /// Waiting jobs, in the order they will start
+    pub queued: Vec<IngestionJob>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AddDocumentsResponse {
+    /// Documents that were indexed (older backends also list failed documents here)
+    added: Vec<String>,
+    /// Not reported by older backends
+    failed: Option<Vec<AddDocumentsFailure>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AddDocumentsFailure {
+    error: String,
+    /// Document left behind with an error status, if one was created
+    #[serde(default)]
+    document_id: Option<String>,
+}
+
+async fn update_job<R>(job_id: &str, f: impl FnOnce(&mut IngestionJob) -> R) -> Option<R> {
+    let mut jobs = JOBS.lock().await;
+    jobs.iter_mut().find(|j| j.id == job_id).map(f)
+}
+
+fn emit_progress(app: &AppHandle, job: &IngestionJob, file: Option<FileResult>) {
+    let finished = job.status.is_finished();
+    let _ = app.emit(
+        "ingestion-progress",
+        IngestionProgressEvent {
+            job_id: job.id.clone(),
+            kb_id: job.kb_id.clone(),
+            status: job.status,
+            processed: job.processed,
+            total: job.total,
+            percent: job.percent(),
+            current_file: job.current_file.clone(),
+            file,
+            result: finished.then(|| job.result()),
+            error: job.error.clone(),
+        },
+    );
+}
+
+/// Status of a document the backend created, for backends that don't report failures:
+/// the error message unless it was indexed.
+async fn ingestion_error(kb_id: &str, document_id: &str) -> Option<String> {
+    let documents = commands::list_documents(kb_id.to_string()).await.ok()?;
+    let document = documents.into_iter().find(|d| d.id == document_id)?;
+    match document.status.as_str() {
+        "indexed" | "ready" => None,
+        status => Some(
+            document
+                .error_message
+                .unwrap_or_else(|| format!("Document could not be ingested ({})", status)),
+        ),
+    }
+}
+
+/// Delete the document a failed ingestion left behind, so a retry doesn't add a second.
+async fn discard_document(kb_id: &str, document_id: &str) {
+    if !capabilities::supports(Feature::DocumentDelete) {
+        return;
+    }
+    if let Err(e) = backend_request::<serde_json::Value>(
+        Method::DELETE,
+        &format!("/api/knowledge-bases/{}/documents/{}", kb_id, document_id),
+        None,
+    )
+    .await
+    {
+        tracing::warn!("Failed to delete failed document {}: {}", document_id, e);
+    }
+}
+
+/// Rebuild the backend's lexical index once a job has sent its files.
+async fn refresh_index(kb_id: &str) {
+    if let Err(e) = backend_request::<serde_json::Value>(
+        Method::POST,
+        &format!("/api/knowledge-bases/{}/refresh-index", kb_id),
+        None,
+    )
+    .await
+    {
+        tracing::warn!("Failed to refresh the index of {}: {}", kb_id, e);
+    }
+}
+
+/// Send one file to the backend, returning the id of the indexed document or the error.
+///
+/// The stamp (content hash and ingestion time) is sent for the backend to store on the
+/// file's chunks. Backends that can refresh their index separately skip the refresh
+/// after each file; the job refreshes it once at the end.
+async fn send_file(
+    kb_id: &str,
+    path: &str,
+    metadata: HashMap<String, DocumentMetadata>,
+    stamp: Option<&FileStamp>,
+    options: &IngestOptions,
+) -> Result<String, String> {
+    let provenance: HashMap<&str, &FileStamp> = stamp.map(|s| (path, s)).into_iter().collect();
+    let parser_hints: HashMap<&str, file_types::ParserHint> =
+        file_types::custom_parser(Path::new(path)).map(|h| (path, h)).into_iter().collect();
+    let response = backend_request::<AddDocumentsResponse>(
+        Method::POST,
+        &format!("/api/knowledge-bases/{}/documents", kb_id),
+        Some(json!({
+            "paths": [path],
+            "metadata": metadata,
+            "provenance": provenance,
+            "parser_hints": parser_hints,
+            "chunking": kb_settings::chunking(kb_id),
+            "ocr": ocr::request(options),
+            "refresh_index": !capabilities::supports(Feature::IndexRefresh),
+        })),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let (document_id, failure) = match response.failed {
+        Some(mut failed) => match (response.added.into_iter().next(), failed.pop()) {
+            (Some(document_id), _) => (document_id, None),
+            (None, Some(failure)) => match failure.document_id {
+                Some(document_id) => (document_id, Some(failure.error)),
+                None => return Err(failure.error),
+            },
+            (None, None) => return Err("Document could not be ingested".to_string()),
+        },
+        None => {
+            let Some(document_id) = response.added.into_iter().next() else {
+                return Err("Document could not be ingested".to_string());
+            };
+            let failure = ingestion_error(kb_id, &document_id).await;
+            (document_id, failure)
+        }
+    };
+    match failure {
+        None => Ok(document_id),
+        Some(error) => {
+            discard_document(kb_id, &document_id).await;
+            Err(error)
+        }
+    }
+}
+
+/// Ingest a Markdown conversion of a file the backend could not parse.
+async fn send_extracted(
+    kb_id: &str,
+    path: &str,
+    metadata: HashMap<String, DocumentMetadata>,
+    stamp: Option<&FileStamp>,
+) -> Result<String, String> {
+    let source = PathBuf::from(path);
+    let converted =
+        tauri::async_runtime::spawn_blocking(move || extraction::extract_to_markdown(&source))
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())?;
+
+    let converted_path = converted.to_string_lossy().into_owned();
+    let metadata = metadata
+        .into_values()
+        .next()
+        .map(|m| HashMap::from([(converted_path.clone(), m)]))
+        .unwrap_or_default();
+    let result = send_file(
+        kb_id,
+        &converted_path,
+        metadata,
+        stamp,
+        &IngestOptions::default(),
+    )
+    .await;
+
+    if let Some(dir) = converted.parent() {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+    result
+}
+
+async fn ingest_file(kb_id: &str, path: &str, options: &IngestOptions, ocr: bool) -> FileResult {
+    if !ocr {
+        if let Some(error) = ocr::required(path).await {
+            return FileResult {
+                path: path.to_string(),
+                status: FileStatus::Failed,
+                document_id: None,
+                error: Some(error),
+                duplicate_of: None,
+                needs_ocr: true,
+            };
+        }
+    }
+
+    let (metadata, stamp) = {
+        let path = path.to_string();
+        tauri::async_runtime::spawn_blocking(move || {
+            let stamp = provenance::stamp_file(Path::new(&path));
+            (metadata::extract_all([path.as_str()]), stamp)
+        })
+        .await
+        .unwrap_or_default()
+    };
+
+    let mut result = send_file(kb_id, path, metadata.clone(), stamp.as_ref(), options).await;
+    if let Err(error) = &result {
+        if extraction::is_native(Path::new(path)) {
+            tracing::info!(
+                "Backend could not ingest {} ({}), retrying with native extraction",
+                path,
+                error
+            );
+            result = send_extracted(kb_id, path, metadata, stamp.as_ref()).await;
+        }
+    }
+
+    let (status, document_id, error) = match result {
+        Ok(document_id) => {
+            if let (Some(stamp), Some(filename)) = (stamp, Path::new(path).file_name()) {
+                let filename = filename.to_string_lossy();
+                provenance::record_document(kb_id, &document_id, &filename, Some(path), stamp);
+            }
+            (FileStatus::Added, Some(document_id), None)
+        }
+        Err(error) => (FileStatus::Failed, None, Some(error)),
+    };
+
+    FileResult {
+        path: path.to_string(),
+        status,
+        document_id,
+        error,
+        duplicate_of: None,
+        needs_ocr: false,
+    }
+}
+
+/// Stop a job before its next file, saving the remaining files when checkpointing.
+async fn interrupt_job(
+    app: &AppHandle,
+    job_id: &str,
+    kb_id: &str,
+    remaining: &[String],
+    archives: &[ArchiveResult],
+    options: &IngestOptions,
+    mode: StopMode,
+) {
+    if mode == StopMode::Checkpoint {
+        // Unpacked members are gone by the next start: resume from their archives, whose
+        // members already ingested are then skipped as duplicates
+        let mut paths: Vec<String> = Vec::new();
+        for path in remaining {
+            let path = archives
+                .iter()
+                .find(|a| {
+                    a.unpacked_to
+                        .as_ref()
+                        .is_some_and(|dir| Path::new(path).starts_with(dir))
+                })
+                .map_or(path, |a| &a.path);
+            if !paths.contains(path) {
+                paths.push(path.clone());
+            }
+        }
+        let _jobs = JOBS.lock().await;
+        let mut checkpoint: Vec<CheckpointedJob> = store::load(CHECKPOINT_FILE);
+        checkpoint.push(CheckpointedJob {
+            kb_id: kb_id.to_string(),
+            paths,
+            options: options.clone(),
+        });
+        if let Err(e) = store::save(CHECKPOINT_FILE, &checkpoint) {
+            tracing::error!("Failed to checkpoint ingestion job {}: {}", job_id, e);
+        }
+    }
+
+    if let Some(job) = update_job(job_id, |job| {
+        job.status = match mode {
+            StopMode::Checkpoint => JobStatus::Paused,
+            StopMode::Abort => JobStatus::Cancelled,
+        };
+        job.current_file = None;
+        job.clone()
+    })
+    .await
+    {
+        CANCELLED.lock().unwrap().retain(|id| id != job_id);
+        let added = job.files.iter().any(|f| f.status == FileStatus::Added);
+        if added && capabilities::supports(Feature::IndexRefresh) {
+            refresh_index(kb_id).await;
+        }
+        tracing::info!(
+            "Ingestion job {} stopped ({:?}) with {} files left",
+            job.id,
+            mode,
+            remaining.len()
+        );
+        emit_progress(app, &job, None);
+    }
+}
+
+/// Replace the archives among `paths` with their supported members.
+async fn expand_archives(
+    app: &AppHandle,
+    job_id: &str,
+    paths: Vec<String>,
+) -> (Vec<String>, Vec<ArchiveResult>) {
+    let mut expanded = Vec::new();
+    let mut results = Vec::new();
+    for path in paths {
+        if !archives::is_archive(Path::new(&path)) {
+            expanded.push(path);
+            continue;
+        }
+        let archive = PathBuf::from(&path);
+        let unpacked = tauri::async_runtime::spawn_blocking(move || archives::unpack(&archive))
+            .await
+            .map_err(|e| anyhow::anyhow!(e.to_string()))
+            .and_then(|result| result);
+        let mut result = ArchiveResult {
+            path: path.clone(),
+            files: 0,
+            added: 0,
+            failed: 0,
+            duplicates: 0,
+            error: None,
+            unpacked_to: None,
+        };
+        match unpacked {
+            Ok((dir, members)) => {
+                tracing::info!("Unpacked {} supported files from {}", members.len(), path);
+                result.files = members.len();
+                result.unpacked_to = Some(dir);
+                expanded.extend(members.into_iter().map(|m| m.to_string_lossy().into_owned()));
+            }
+            Err(e) => {
+                tracing::warn!("{}", e);
+                result.error = Some(e.to_string());
+            }
+        }
+        results.push(result);
+    }
+
+    if let Some(job) = update_job(job_id, |job| {
+        job.total = expanded.len();
+        job.archives = results.clone();
+        job.clone()
+    })
+    .await
+    {
+        emit_progress(app, &job, None);
+    }
+    (expanded, results)
+}
+
+fn remove_unpacked(archives: &[ArchiveResult]) {
+    for dir in archives.iter().filter_map(|a| a.unpacked_to.as_ref()) {
+        if let Err(e) = std::fs::remove_dir_all(dir) {
+            tracing::warn!("Failed to remove {}: {}", dir.display(), e);
+        }
+    }
+}
+
+/// How a job must stop before its next file, if it must.
+fn stop_mode(job_id: &str) -> Option<StopMode> {
+    let stop = *STOP.lock().unwrap();
+    stop.or_else(|| {
+        let cancelled = CANCELLED.lock().unwrap().iter().any(|id| id == job_id);
+        cancelled.then_some(StopMode::Abort)
+    })
+}
+
+/// Wait while the queue is paused, returning how the job must stop if it must.
+async fn wait_while_paused(job_id: &str) -> Option<StopMode> {
+    loop {
+        if let Some(mode) = stop_mode(job_id) {
+            return Some(mode);
+        }
+        if !PAUSED.load(Ordering::Relaxed) {
+            return None;
+        }
+        tokio::time::sleep(QUEUE_POLL_INTERVAL).await;
+    }
+}
+
+/// Wait until a worker is free and the jobs queued before this one have started, then
+/// mark it running. Returns how the job must stop if it must.
+async fn wait_for_worker(job_id: &str) -> Option<StopMode> {
+    loop {
+        if let Some(mode) = wait_while_paused(job_id).await {
+            return Some(mode);
+        }
+        let workers = preferences::load().ingestion_workers.max(1);
+        {
+            let mut jobs = JOBS.lock().await;
+            let running = jobs
+                .iter()
+                .filter(|j| j.status == JobStatus::Running)
+                .count();
+            let next = jobs.iter_mut().find(|j| j.status == JobStatus::Queued);
+            if let Some(job) = next.filter(|j| j.id == job_id && running < workers) {
+                job.status = JobStatus::Running;
+                return None;
+            }
+        }
+        tokio::time::sleep(QUEUE_POLL_INTERVAL).await;
+    }
+}
+
+async fn run_job(
+    app: AppHandle,
+    job_id: String,
+    kb_id: String,
+    paths: Vec<String>,
+    options: IngestOptions,
+) {
+    if let Some(mode) = wait_for_worker(&job_id).await {
+        interrupt_job(&app, &job_id, &kb_id, &paths, &[], &options, mode).await;
+        return;
+    }
+    let (paths, unpacked) = if options.extract_archives {
+        expand_archives(&app, &job_id, paths).await
+    } else {
+        (paths, Vec::new())
+    };
+    let mut known_hashes = dedup::known_hashes(&kb_id).await;
+    let ocr_enabled = ocr::enabled(&options).await;
+    for (index, path) in paths.iter().cloned().enumerate() {
+        if let Some(mode) = wait_while_paused(&job_id).await {
+            let remaining = &paths[index..];
+            interrupt_job(&app, &job_id, &kb_id, remaining, &unpacked, &options, mode).await;
+            remove_unpacked(&unpacked);
+            return;
+        }
+
+        if let Some(job) = update_job(&job_id, |job| {
+            job.current_file = Some(path.clone());
+            job.clone()
+        })
+        .await
+        {
+            emit_progress(&app, &job, None);
+        }
+
+        let hash = dedup::hash_file(&path).await;
+        let duplicate_of = hash.as_ref().and_then(|h| known_hashes.get(h)).cloned();
+        let file = match duplicate_of {
+            Some(duplicate_of) => {
+                tracing::info!(
+                    "Skipping {}, already ingested as {}",
+                    path,
+                    duplicate_of.filename
+                );
+                FileResult {
+                    path: path.clone(),
+                    status: FileStatus::Duplicate,
+                    document_id: None,
+                    error: None,
+                    duplicate_of: Some(duplicate_of),
+                    needs_ocr: false,
+                }
+            }
+            None => ingest_file(&kb_id, &path, &options, ocr_enabled).await,
+        };
+        if let (Some(hash), Some(document_id)) = (&hash, &file.document_id) {
+            dedup::record(&kb_id, hash, document_id, &path);
+            // Later copies in the same job are duplicates too
+            known_hashes.insert(
+                hash.clone(),
+                DuplicateOf {
+                    document_id: document_id.clone(),
+                    filename: Path::new(&path)
+                        .file_name()
+                        .map_or_else(|| path.clone(), |n| n.to_string_lossy().into_owned()),
+                },
+            );
+        }
+        match &file.error {
+            // Retrying won't help until OCR is enabled
+            Some(error) if file.needs_ocr => tracing::info!("Skipped {}: {}", path, error),
+            Some(error) => {
+                tracing::warn!("Failed to ingest {}: {}", path, error);
+                retry_queue::record_failure(&kb_id, &path, error).await;
+            }
+            None => retry_queue::record_success(&kb_id, &path).await,
+        }
+
+        if let Some(job) = update_job(&job_id, |job| {
+            job.processed += 1;
+            job.files.push(file.clone());
+            job.clone()
+        })
+        .await
+        {
+            emit_progress(&app, &job, Some(file));
+        }
+    }
+
+    if let Some(job) = update_job(&job_id, |job| {
+        let all_failed = job.total > 0 && job.files.iter().all(|f| f.status == FileStatus::Failed);
+        if all_failed {
+            job.status = JobStatus::Failed;
+            job.error = Some("No document could be ingested".to_string());
+        } else {
+            job.status = JobStatus::Completed;
+        }
+        job.current_file = None;
+        for archive in &mut job.archives {
+            let Some(dir) = &archive.unpacked_to else {
+                continue;
+            };
+            for file in job.files.iter().filter(|f| Path::new(&f.path).starts_with(dir)) {
+                match file.status {
+                    FileStatus::Added => archive.added += 1,
+                    FileStatus::Failed => archive.failed += 1,
+                    FileStatus::Duplicate => archive.duplicates += 1,
+                }
+            }
+        }
+        job.clone()
+    })
+    .await
+    {
+        remove_unpacked(&unpacked);
+        tracing::info!(
+            "Ingestion job {} finished: {}/{} files added",
+            job.id,
+            job.files.iter().filter(|f| f.status == FileStatus::Added).count(),
+            job.total
+        );
+        let documents: Vec<String> = job
+            .files
+            .iter()
+            .filter(|f| f.status == FileStatus::Added)
+            .map(|f| {
+                Path::new(&f.path)
+                    .file_name()
+                    .map_or_else(|| f.path.clone(), |n| n.to_string_lossy().into_owned())
+            })
+            .collect();
+        if !documents.is_empty() {
+            kb_history::record(&job.kb_id, KbChange::DocumentsAdded { documents });
+        }
+        let indexed: Vec<(String, String)> = job
+            .files
+            .iter()
+            .filter_map(|f| {
+                let filename = Path::new(&f.path).file_name()?.to_string_lossy().into_owned();
+                Some((f.document_id.clone()?, filename))
+            })
+            .collect();
+        if !indexed.is_empty() && capabilities::supports(Feature::IndexRefresh) {
+            refresh_index(&job.kb_id).await;
+        }
+        lexical_index::index_documents(job.kb_id.clone(), indexed);
+        if job.status == JobStatus::Completed {
+            hooks::fire(HookEvent::IngestionCompleted, &job);
+        }
+        emit_progress(&app, &job, None);
+    }
+}
+
+/// Register a new ingestion job and start processing it in the background.
+///
+/// With `extract_archives`, .zip and .tar(.gz) files are unpacked and their supported
+/// members ingested instead.
+pub async fn start_job(
+    app: &AppHandle,
+    kb_id: String,
+    paths: Vec<String>,
+    options: IngestOptions,
+) -> String {
+    let job = IngestionJob {
+        id: uuid::Uuid::new_v4().to_string(),
+        kb_id: kb_id.clone(),
+        status: JobStatus::Queued,
+        total: paths.len(),
+        processed: 0,
+        current_file: None,
+        files: Vec::new(),
+        error: None,
+        created_at: Utc::now().to_rfc3339(),
+        archives: Vec::new(),
+    };
+    let job_id = job.id.clone();
+
+    {
+        let mut jobs = JOBS.lock().await;
+        // Forget the oldest finished jobs so the list doesn't grow forever.
+        let finished = jobs.iter().filter(|j| j.status.is_finished()).count();
+        let mut to_drop = finished.saturating_sub(MAX_FINISHED_JOBS - 1);
+        jobs.retain(|j| {
+            let drop = to_drop > 0 && j.status.is_finished();
+            if drop {
+                to_drop -= 1;
+            }
+            !drop
+        });
+        jobs.push(job.clone());
+    }
+
+    emit_progress(app, &job, None);
+    tauri::async_runtime::spawn(run_job(
+        app.clone(),
+        job_id.clone(),
+        kb_id,
+        paths,
+        options,
+    ));
+    job_id
+}
+
+/// List the files of a folder import in the background and start ingesting them.
+pub async fn start_folder_job(
+    app: &AppHandle,
+    kb_id: String,
+    folder: PathBuf,
+    recursive: bool,
+    file_types: Vec<String>,
+    options: IngestOptions,
+) -> Result<String, String> {
+    if !folder.is_dir() {
+        return Err(format!("Invalid folder path: {}", folder.display()));
+    }
+
+    let extract_archives = options.extract_archives;
+    let files = tauri::async_runtime::spawn_blocking(move || {
+        let mut files = crate::files::collect_files(&folder, recursive, &file_types);
+        if extract_archives && !file_types.is_empty() {
+            files.extend(
+                crate::files::collect_files(&folder, recursive, &[])
+                    .into_iter()
+                    .filter(|f| archives::is_archive(f)),
+            );
+        }
+        files
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let paths = files
+        .into_iter()
+        .filter_map(|f| f.to_str().map(String::from))
+        .collect();
+    Ok(start_job(app, kb_id, paths, options).await)
+}
+
+/// Number of queued or running jobs.
+pub async fn active_jobs() -> usize {
+    JOBS.lock()
+        .await
+        .iter()
+        .filter(|j| !j.status.is_finished())
+        .count()
+}
+
+/// Wait for a job to finish, returning its final state (`None` for an unknown job).
+pub async fn wait_for_job(job_id: &str) -> Option<IngestionJob> {
+    loop {
+        let job = JOBS.lock().await.iter().find(|j| j.id == job_id).cloned()?;
+        if job.status.is_finished() {
+            return Some(job);
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
+/// Clears the stop request when dropped, also when the wait for jobs is cut short.
+struct StopRequest;
+
+impl Drop for StopRequest {
+    fn drop(&mut self) {
+        *STOP.lock().unwrap() = None;
+    }
+}
+
+/// Stop running jobs before their next file and wait until they have stopped.
+pub async fn stop_jobs(mode: StopMode) {
+    *STOP.lock().unwrap() = Some(mode);
+    let _request = StopRequest;
+    while active_jobs().await > 0 {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+}
+
+/// Restart the jobs checkpointed when the backend last stopped.
+pub async fn resume_checkpointed(app: &AppHandle) {
+    let checkpoint: Vec<CheckpointedJob> = {
+        let _jobs = JOBS.lock().await;
+        let checkpoint = store::load(CHECKPOINT_FILE);
+        if let Err(e) = store::save(CHECKPOINT_FILE, &Vec::<CheckpointedJob>::new()) {
+            tracing::error!("Failed to clear the ingestion checkpoint: {}", e);
+        }
+        checkpoint
+    };
+    for job in checkpoint {
+        if job.paths.is_empty() {
+            continue;
+        }
+        tracing::info!(
+            "Resuming ingestion of {} files into KB {}",
+            job.paths.len(),
+            job.kb_id
+        );
+        start_job(app, job.kb_id, job.paths, job.options).await;
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Get an ingestion job by id
+#[tauri::command]
+pub async fn get_ingestion_job(job_id: String) -> Result<IngestionJob, String> {
+    JOBS.lock()
+        .await
+        .iter()
+        .find(|j| j.id == job_id)
+        .cloned()
+        .ok_or_else(|| format!("Unknown ingestion job: {}", job_id))
+}
+
+/// List recent and running ingestion jobs
+#[tauri::command]
+pub async fn list_ingestion_jobs() -> Result<Vec<IngestionJob>, String> {
+    Ok(JOBS.lock().await.clone())
+}
+
+/// Pause the ingestion queue; running jobs stop before their next file
+#[tauri::command]
+pub async fn pause_ingestion() -> Result<(), String> {
+    PAUSED.store(true, Ordering::Relaxed);
+    tracing::info!("Ingestion queue paused");
+    Ok(())
+}
+
+/// Resume the ingestion queue
+#[tauri::command]
+pub async fn resume_ingestion() -> Result<(), String> {
+    PAUSED.store(false, Ordering::Relaxed);
+    tracing::info!("Ingestion queue resumed");
+    Ok(())
+}
+
+/// Get the running and queued ingestion jobs
+#[tauri::command]
+pub async fn get_job_queue() -> Result<JobQueue, String> {
+    let jobs = JOBS.lock().await;
+    let with_status = |status| {
+        jobs.iter()
+            .filter(|j| j.status == status)
+            .cloned()
+            .collect()
+    };
+    Ok(JobQueue {
+        paused: PAUSED.load(Ordering::Relaxed),
+        workers: preferences::load().ingestion_workers.max(1),
+        running: with_status(JobStatus::Running),
+        queued: with_status(JobStatus::Queued),
+    })
+}
+
+/// Cancel a queued or running ingestion job
+///
+/// A running job stops before its next file; the documents already ingested are kept.
+#[tauri::command]
+pub async fn cancel_job(job_id: String) -> Result<(), String> {
+    let jobs = JOBS.lock().await;
+    let job = jobs
+        .iter()
+        .find(|j| j.id == job_id)
+        .ok_or_else(|| format!("Unknown ingestion job: {}", job_id))?;
+    if job.status.is_finished() {
+        return Err("The ingestion job has already finished".to_string());
+    }
+    let mut cancelled = CANCELLED.lock().unwrap();
+    if !cancelled.contains(&job_id) {
+        cancelled.push(job_id);
+    }
+    Ok(())
+}
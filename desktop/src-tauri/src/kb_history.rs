@@ -0,0 +1,207 @@
+//! Mutation history of knowledge bases.
+//!
+//! Every change the shell makes to a knowledge base (documents added or removed, chunks
+//! edited or excluded, re-embedding, import) and every settings change is recorded with
+//! a timestamp. The version of a knowledge base is the number of changes that affected
+//! it, so an answer can be tied to the version it was generated against and the KB
+//! contents at that time can be reconstructed. Settings are global and count towards
+//! every knowledge base.
+
+use crate::store;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Mutex;
+
+const STATE_FILE: &str = "kb_history.json";
+
+/// Serializes load-modify-save cycles of the history file.
+static LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum KbChange {
+    Created,
+    DocumentsAdded { documents: Vec<String> },
+    DocumentsRemoved { documents: Vec<String> },
+    SettingsChanged { fields: Vec<String> },
+    Reembedded,
+    Imported,
+    ChunkEdited { chunk_id: String, document_id: String },
+    ChunkExcluded { chunk_id: String, document_id: String, excluded: bool },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KbEvent {
+    at: String,
+    #[serde(flatten)]
+    change: KbChange,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct HistoryStore {
+    knowledge_bases: BTreeMap<String, Vec<KbEvent>>,
+    /// Changes affecting every knowledge base (settings)
+    global: Vec<KbEvent>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KbHistoryEntry {
+    pub version: u64,
+    pub at: String,
+    #[serde(flatten)]
+    pub change: KbChange,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KbHistory {
+    pub kb_id: String,
+    pub current_version: u64,
+    pub entries: Vec<KbHistoryEntry>,
+}
+
+/// Contents of a knowledge base as of a version.
+#[derive(Debug, Serialize)]
+pub struct KbStateAt {
+    pub version: u64,
+    pub at: Option<String>,
+    pub documents: Vec<String>,
+}
+
+fn with_history<T>(f: impl FnOnce(&mut HistoryStore) -> T) -> T {
+    let _guard = LOCK.lock().unwrap();
+    let mut history: HistoryStore = store::load(STATE_FILE);
+    let result = f(&mut history);
+    if let Err(e) = store::save(STATE_FILE, &history) {
+        tracing::warn!("Failed to save knowledge base history: {}", e);
+    }
+    result
+}
+
+fn event(change: KbChange) -> KbEvent {
+    KbEvent {
+        at: Utc::now().to_rfc3339(),
+        change,
+    }
+}
+
+/// Record a change to a knowledge base.
+pub fn record(kb_id: &str, change: KbChange) {
+    with_history(|h| {
+        h.knowledge_bases
+            .entry(kb_id.to_string())
+            .or_default()
+            .push(event(change))
+    });
+}
+
+/// Record a change affecting every knowledge base.
+pub fn record_global(change: KbChange) {
+    with_history(|h| h.global.push(event(change)));
+}
+
+/// Forget the history of a deleted knowledge base.
+pub fn remove(kb_id: &str) {
+    with_history(|h| h.knowledge_bases.remove(kb_id));
+}
+
+/// Changes affecting a knowledge base in chronological order, numbered from 1.
+/// Global changes before the KB was created are left out.
+fn entries(kb_id: &str) -> Vec<KbHistoryEntry> {
+    let history: HistoryStore = store::load(STATE_FILE);
+    let own = history
+        .knowledge_bases
+        .get(kb_id)
+        .cloned()
+        .unwrap_or_default();
+    let since = own.first().map(|e| e.at.clone());
+
+    let mut events: Vec<KbEvent> = history
+        .global
+        .into_iter()
+        .filter(|e| since.as_ref().is_some_and(|since| e.at >= *since))
+        .chain(own)
+        .collect();
+    events.sort_by(|a, b| a.at.cmp(&b.at));
+
+    events
+        .into_iter()
+        .enumerate()
+        .map(|(i, e)| KbHistoryEntry {
+            version: i as u64 + 1,
+            at: e.at,
+            change: e.change,
+        })
+        .collect()
+}
+
+/// Current version of a knowledge base (0 when nothing was recorded yet).
+pub fn current_version(kb_id: &str) -> u64 {
+    entries(kb_id).len() as u64
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Get the recorded changes of a knowledge base
+#[tauri::command]
+pub async fn get_kb_history(kb_id: String) -> Result<KbHistory, String> {
+    let entries = entries(&kb_id);
+    Ok(KbHistory {
+        kb_id,
+        current_version: entries.len() as u64,
+        entries,
+    })
+}
+
+/// Get the version a knowledge base was at, at a given time (e.g. when an answer was
+/// generated)
+#[tauri::command]
+pub async fn get_kb_version_at(kb_id: String, at: String) -> Result<u64, String> {
+    let at = DateTime::parse_from_rfc3339(&at)
+        .map_err(|e| format!("Invalid timestamp {}: {}", at, e))?
+        .with_timezone(&Utc);
+    let version = entries(&kb_id)
+        .iter()
+        .filter(|e| DateTime::parse_from_rfc3339(&e.at).is_ok_and(|t| t.with_timezone(&Utc) <= at))
+        .count();
+    Ok(version as u64)
+}
+
+/// Reconstruct the documents a knowledge base contained at a version
+#[tauri::command]
+pub async fn get_kb_state_at(kb_id: String, version: u64) -> Result<KbStateAt, String> {
+    let entries = entries(&kb_id);
+    if version > entries.len() as u64 {
+        return Err(format!(
+            "Version {} does not exist (current version is {})",
+            version,
+            entries.len()
+        ));
+    }
+
+    let mut documents = BTreeSet::new();
+    let mut at = None;
+    for entry in entries.iter().take(version as usize) {
+        match &entry.change {
+            KbChange::DocumentsAdded { documents: added } => {
+                documents.extend(added.iter().cloned());
+            }
+            KbChange::DocumentsRemoved { documents: removed } => {
+                for document in removed {
+                    documents.remove(document);
+                }
+            }
+            _ => {}
+        }
+        at = Some(entry.at.clone());
+    }
+
+    Ok(KbStateAt {
+        version,
+        at,
+        documents: documents.into_iter().collect(),
+    })
+}
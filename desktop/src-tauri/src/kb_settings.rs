@@ -0,0 +1,206 @@
+//! Per-knowledge-base settings overrides.
+//!
+//! The backend `Settings` are global, but a legal-contracts knowledge base and a code
+//! knowledge base need very different chunking and retrieval tuning. A `KbSettings`
+//! overrides any of the chunking, retrieval, and LLM settings for one knowledge base;
+//! unset fields follow the global settings.
+//!
+//! Backends with per-knowledge-base settings store and apply the overrides themselves.
+//! The shell keeps its own copy either way and adds the overrides to the requests it
+//! sends: the model, `top_k`, reranking and the semantic weight to queries, chunking to
+//! ingestion. Chunking changes only affect documents ingested afterwards; re-embed the
+//! knowledge base to apply them to existing documents.
+
+use crate::backend::backend_request;
+use crate::capabilities::{self, Feature};
+use crate::commands::QueryParams;
+use crate::kb_history::{self, KbChange};
+use crate::store;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+const STATE_FILE: &str = "kb_settings.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KbSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embedding_chunk_strategy: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embedding_chunk_size: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embedding_chunk_overlap: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retrieval_architecture: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retrieval_top_k: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retrieval_semantic_weight: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retrieval_lexical_weight: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retrieval_rerank_weight: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retrieval_rerank_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retrieval_max_chunks: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub llm_provider: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub llm_model: Option<String>,
+}
+
+type Overrides = BTreeMap<String, KbSettings>;
+
+fn load(kb_id: &str) -> KbSettings {
+    let mut overrides: Overrides = store::load(STATE_FILE);
+    overrides.remove(kb_id).unwrap_or_default()
+}
+
+/// Names of the fields that differ between two sets of overrides.
+fn changed_fields(before: &KbSettings, after: &KbSettings) -> Vec<String> {
+    let before = serde_json::to_value(before).unwrap_or_default();
+    let after = serde_json::to_value(after).unwrap_or_default();
+    let mut fields: Vec<String> = before
+        .as_object()
+        .into_iter()
+        .chain(after.as_object())
+        .flat_map(|o| o.keys())
+        .filter(|key| before.get(key.as_str()) != after.get(key.as_str()))
+        .cloned()
+        .collect();
+    fields.sort();
+    fields.dedup();
+    fields
+}
+
+fn validate(settings: &KbSettings) -> Result<(), String> {
+    if settings.embedding_chunk_size.is_some_and(|size| size <= 0) {
+        return Err("The chunk size must be positive".to_string());
+    }
+    if let (Some(size), Some(overlap)) = (
+        settings.embedding_chunk_size,
+        settings.embedding_chunk_overlap,
+    ) {
+        if overlap < 0 || overlap >= size {
+            return Err("The chunk overlap must be between 0 and the chunk size".to_string());
+        }
+    }
+    if settings.retrieval_top_k.is_some_and(|k| k < 1)
+        || settings.retrieval_max_chunks.is_some_and(|k| k < 1)
+    {
+        return Err("top_k and max_chunks must be at least 1".to_string());
+    }
+    let weights = [
+        settings.retrieval_semantic_weight,
+        settings.retrieval_lexical_weight,
+        settings.retrieval_rerank_weight,
+    ];
+    if weights
+        .into_iter()
+        .flatten()
+        .any(|w| !(0.0..=1.0).contains(&w))
+    {
+        return Err("Retrieval weights must be between 0 and 1".to_string());
+    }
+    if settings.llm_model.is_some() && settings.llm_provider.is_none() {
+        return Err("An LLM model override needs its provider".to_string());
+    }
+    Ok(())
+}
+
+/// Add the knowledge base's overrides to a query, unless the caller or the
+/// conversation already chose.
+pub fn apply(params: &mut QueryParams) {
+    let settings = load(&params.kb_id);
+    if params.llm_provider.is_none() {
+        if let Some(provider) = settings.llm_provider {
+            params.llm_provider = Some(provider);
+            params.llm_model = settings.llm_model;
+        }
+    }
+    params.top_k = params.top_k.or(settings.retrieval_top_k);
+    params.rerank_enabled = params.rerank_enabled.or(settings.retrieval_rerank_enabled);
+    params.semantic_weight = params
+        .semantic_weight
+        .or(settings.retrieval_semantic_weight);
+}
+
+/// Chunking overrides of a knowledge base, sent with ingestion requests.
+pub fn chunking(kb_id: &str) -> Option<serde_json::Value> {
+    let settings = load(kb_id);
+    let chunking = json!({
+        "strategy": settings.embedding_chunk_strategy,
+        "size": settings.embedding_chunk_size,
+        "overlap": settings.embedding_chunk_overlap,
+    });
+    let any = chunking
+        .as_object()
+        .is_some_and(|o| o.values().any(|v| !v.is_null()));
+    any.then_some(chunking)
+}
+
+/// Forget the overrides of a deleted knowledge base.
+pub fn remove(kb_id: &str) {
+    let mut overrides: Overrides = store::load(STATE_FILE);
+    if overrides.remove(kb_id).is_some() {
+        if let Err(e) = store::save(STATE_FILE, &overrides) {
+            tracing::warn!("Failed to remove settings of {}: {}", kb_id, e);
+        }
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Get the settings overrides of a knowledge base
+#[tauri::command]
+pub async fn get_kb_settings(kb_id: String) -> Result<KbSettings, String> {
+    if capabilities::supports(Feature::KbSettings) {
+        match backend_request::<KbSettings>(
+            Method::GET,
+            &format!("/api/knowledge-bases/{}/settings", kb_id),
+            None,
+        )
+        .await
+        {
+            Ok(settings) => return Ok(settings),
+            Err(e) => tracing::debug!("Using the local settings of {}: {}", kb_id, e),
+        }
+    }
+    Ok(load(&kb_id))
+}
+
+/// Replace the settings overrides of a knowledge base; unset fields follow the global
+/// settings
+#[tauri::command]
+pub async fn update_kb_settings(kb_id: String, settings: KbSettings) -> Result<KbSettings, String> {
+    validate(&settings)?;
+    if capabilities::supports(Feature::KbSettings) {
+        backend_request::<serde_json::Value>(
+            Method::PUT,
+            &format!("/api/knowledge-bases/{}/settings", kb_id),
+            Some(serde_json::to_value(&settings).unwrap()),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    let mut overrides: Overrides = store::load(STATE_FILE);
+    let previous = overrides.get(&kb_id).cloned().unwrap_or_default();
+    if settings == KbSettings::default() {
+        overrides.remove(&kb_id);
+    } else {
+        overrides.insert(kb_id.clone(), settings.clone());
+    }
+    store::save(STATE_FILE, &overrides).map_err(|e| e.to_string())?;
+
+    let fields = changed_fields(&previous, &settings);
+    if !fields.is_empty() {
+        kb_history::record(&kb_id, KbChange::SettingsChanged { fields });
+    }
+    Ok(settings)
+}
@@ -0,0 +1,445 @@
+//! Signed, read-only knowledge base snapshots for audits.
+//!
+//! Regulated users must be able to prove what the model could and couldn't see at a
+//! given date. A snapshot records the documents of a knowledge base, a hash of each of
+//! its chunks, and the settings in effect, in a `.ragsnap` archive. The archive's
+//! manifest lists the SHA-256 of every other entry and is signed with this
+//! installation's Ed25519 key, so `verify_snapshot` can show that nothing was changed
+//! after export. Snapshots never contain document text.
+//!
+//! Chunk hashes come from the backend when it serves them, otherwise from the passages
+//! of the local lexical index; the manifest records which.
+
+use crate::backend::backend_request;
+use crate::capabilities::{self, Feature};
+use crate::commands::{self, KnowledgeBase};
+use crate::files::hex;
+use crate::{lexical_index, provenance, store};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::CompressionMethod;
+
+pub const SNAPSHOT_EXTENSION: &str = "ragsnap";
+
+const KEY_FILE: &str = "snapshot_signing_key.json";
+const FORMAT: &str = "ragkit-kb-snapshot";
+const FORMAT_VERSION: u32 = 1;
+const MANIFEST: &str = "manifest.json";
+const SIGNATURE: &str = "manifest.sig";
+const DOCUMENTS: &str = "documents.json";
+const CHUNKS: &str = "chunks.json";
+const SETTINGS: &str = "settings.json";
+
+/// Ed25519 key signing the snapshots of this installation, created on first export.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SnapshotKey {
+    /// Secret key seed, hex
+    secret: String,
+    created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SnapshotDocument {
+    id: String,
+    filename: String,
+    size: Option<u64>,
+    chunk_count: i32,
+    ingested_at: String,
+    status: String,
+    /// Hash of the original file when the shell ingested it
+    content_hash: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkHash {
+    pub document_id: String,
+    pub chunk_id: String,
+    /// SHA-256 of the chunk text
+    pub content_hash: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkSource {
+    /// Chunks as stored by the backend
+    Backend,
+    /// Passages of the shell's lexical index, when the backend can't list chunks
+    LocalIndex,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub format: String,
+    pub format_version: u32,
+    pub kb_id: String,
+    pub kb_name: String,
+    pub created_at: String,
+    pub app_version: String,
+    pub document_count: usize,
+    pub chunk_count: usize,
+    pub chunk_source: ChunkSource,
+    /// SHA-256 of every other entry of the archive
+    pub files: BTreeMap<String, String>,
+    /// Ed25519 public key of the signer, hex
+    pub public_key: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryStatus {
+    Unchanged,
+    Modified,
+    Missing,
+    /// In the archive but not listed in the manifest
+    Unexpected,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotEntry {
+    pub name: String,
+    pub status: EntryStatus,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotVerification {
+    /// The signature matches and every entry is unchanged
+    pub valid: bool,
+    pub signature_valid: bool,
+    /// Signed with the key of this installation
+    pub signed_here: bool,
+    /// Short fingerprint of the signing key, to compare with the exporter's
+    pub key_fingerprint: Option<String>,
+    pub manifest: Option<SnapshotManifest>,
+    pub entries: Vec<SnapshotEntry>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotExport {
+    pub path: String,
+    pub manifest: SnapshotManifest,
+    pub key_fingerprint: String,
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn fingerprint(public_key: &[u8]) -> String {
+    provenance::content_hash(public_key)[..16].to_string()
+}
+
+/// The signing key of this installation, created if needed.
+fn signing_key() -> Result<SigningKey, String> {
+    if let Some(key) = store::load::<Option<SnapshotKey>>(KEY_FILE) {
+        let seed: [u8; 32] = unhex(&key.secret)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or("The snapshot signing key is corrupt")?;
+        return Ok(SigningKey::from_bytes(&seed));
+    }
+    let mut seed = [0u8; 32];
+    getrandom::getrandom(&mut seed).map_err(|e| format!("Failed to create a key: {}", e))?;
+    let key = SnapshotKey {
+        secret: hex(&seed),
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    store::save(KEY_FILE, &Some(&key)).map_err(|e| e.to_string())?;
+    tracing::info!("Created the snapshot signing key");
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+/// Public key of this installation, if it ever signed a snapshot.
+fn own_public_key() -> Option<VerifyingKey> {
+    store::load::<Option<SnapshotKey>>(KEY_FILE)?;
+    signing_key().ok().map(|key| key.verifying_key())
+}
+
+async fn chunk_hashes(kb_id: &str) -> (ChunkSource, Vec<ChunkHash>) {
+    if capabilities::supports(Feature::ChunkHashes) {
+        let hashes = backend_request::<Vec<ChunkHash>>(
+            Method::GET,
+            &format!("/api/knowledge-bases/{}/chunks/hashes", kb_id),
+            None,
+        )
+        .await;
+        match hashes {
+            Ok(hashes) => return (ChunkSource::Backend, hashes),
+            Err(e) => tracing::warn!("Hashing the local index instead: {}", e),
+        }
+    }
+    let hashes = lexical_index::document_passages(kb_id)
+        .into_iter()
+        .flat_map(|(document_id, passages)| {
+            passages
+                .into_iter()
+                .enumerate()
+                .map(move |(i, passage)| ChunkHash {
+                    chunk_id: format!("{}:{}", document_id, i),
+                    document_id: document_id.clone(),
+                    content_hash: provenance::content_hash(passage.as_bytes()),
+                })
+        })
+        .collect();
+    (ChunkSource::LocalIndex, hashes)
+}
+
+/// Where to write the snapshot: `dest` itself, or a new file in it if it's a folder.
+fn snapshot_path(dest: &Path, kb: &KnowledgeBase) -> PathBuf {
+    if !dest.is_dir() {
+        return dest.to_path_buf();
+    }
+    let name: String = kb
+        .name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    dest.join(format!(
+        "{}-{}.{}",
+        name,
+        chrono::Utc::now().format("%Y%m%d-%H%M%S"),
+        SNAPSHOT_EXTENSION
+    ))
+}
+
+fn write_archive(path: &Path, entries: &[(&str, Vec<u8>)]) -> anyhow::Result<()> {
+    let tmp_dir = store::tmp_dir().join("snapshots");
+    std::fs::create_dir_all(&tmp_dir)?;
+    let tmp_path = tmp_dir.join(format!("{}.{}", uuid::Uuid::new_v4(), SNAPSHOT_EXTENSION));
+
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&tmp_path)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, content) in entries {
+        zip.start_file(*name, options)?;
+        zip.write_all(content)?;
+    }
+    zip.finish()?;
+
+    // Copied rather than renamed, as the destination may be on another volume
+    std::fs::copy(&tmp_path, path)?;
+    let _ = std::fs::remove_file(&tmp_path);
+    let mut permissions = std::fs::metadata(path)?.permissions();
+    permissions.set_readonly(true);
+    std::fs::set_permissions(path, permissions)?;
+    Ok(())
+}
+
+fn read_archive(path: &Path) -> anyhow::Result<BTreeMap<String, Vec<u8>>> {
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(path)?)?;
+    let mut entries = BTreeMap::new();
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content)?;
+        entries.insert(entry.name().to_string(), content);
+    }
+    Ok(entries)
+}
+
+fn verify(entries: &mut BTreeMap<String, Vec<u8>>) -> SnapshotVerification {
+    let mut verification = SnapshotVerification {
+        valid: false,
+        signature_valid: false,
+        signed_here: false,
+        key_fingerprint: None,
+        manifest: None,
+        entries: Vec::new(),
+        errors: Vec::new(),
+    };
+    let (Some(manifest_bytes), Some(signature)) =
+        (entries.remove(MANIFEST), entries.remove(SIGNATURE))
+    else {
+        verification.errors.push(
+            "Not a knowledge base snapshot: the manifest or its signature is missing".to_string(),
+        );
+        return verification;
+    };
+    let manifest: SnapshotManifest = match serde_json::from_slice(&manifest_bytes) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            verification
+                .errors
+                .push(format!("Unreadable manifest: {}", e));
+            return verification;
+        }
+    };
+    if manifest.format != FORMAT || manifest.format_version > FORMAT_VERSION {
+        verification.errors.push(format!(
+            "Unsupported snapshot format {} version {}",
+            manifest.format, manifest.format_version
+        ));
+    }
+
+    let public_key = unhex(&manifest.public_key)
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok());
+    let signature = std::str::from_utf8(&signature)
+        .ok()
+        .and_then(|text| unhex(text.trim()))
+        .and_then(|bytes| Signature::from_slice(&bytes).ok());
+    match (public_key, signature) {
+        (Some(public_key), Some(signature)) => {
+            verification.signature_valid = public_key.verify(&manifest_bytes, &signature).is_ok();
+            verification.signed_here = own_public_key() == Some(public_key);
+            verification.key_fingerprint = Some(fingerprint(public_key.as_bytes()));
+            if !verification.signature_valid {
+                verification.errors.push(
+                    "The manifest signature doesn't match; it was altered or forged".to_string(),
+                );
+            }
+        }
+        (None, _) => verification
+            .errors
+            .push("Invalid signing key in the manifest".to_string()),
+        (_, None) => verification
+            .errors
+            .push("Invalid manifest signature".to_string()),
+    }
+
+    for (name, expected) in &manifest.files {
+        let status = match entries.remove(name) {
+            Some(content) if provenance::content_hash(&content) == *expected => {
+                EntryStatus::Unchanged
+            }
+            Some(_) => EntryStatus::Modified,
+            None => EntryStatus::Missing,
+        };
+        verification.entries.push(SnapshotEntry {
+            name: name.clone(),
+            status,
+        });
+    }
+    for name in entries.keys() {
+        verification.entries.push(SnapshotEntry {
+            name: name.clone(),
+            status: EntryStatus::Unexpected,
+        });
+    }
+
+    verification.valid = verification.signature_valid
+        && verification.errors.is_empty()
+        && verification
+            .entries
+            .iter()
+            .all(|e| e.status == EntryStatus::Unchanged);
+    verification.manifest = Some(manifest);
+    verification
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Export a signed, read-only snapshot of a knowledge base for audits
+///
+/// `dest` is the snapshot file, or a folder to create it in. Existing files are never
+/// overwritten.
+#[tauri::command]
+pub async fn export_kb_snapshot(kb_id: String, dest: String) -> Result<SnapshotExport, String> {
+    let kb = commands::list_knowledge_bases()
+        .await?
+        .into_iter()
+        .find(|kb| kb.id == kb_id)
+        .ok_or_else(|| format!("Knowledge base not found: {}", kb_id))?;
+    let path = snapshot_path(Path::new(&dest), &kb);
+    if path.exists() {
+        return Err(format!("{} already exists", path.display()));
+    }
+
+    let stamps = provenance::document_stamps(&kb_id);
+    let documents: Vec<SnapshotDocument> = commands::list_documents(kb_id.clone())
+        .await?
+        .into_iter()
+        .map(|d| SnapshotDocument {
+            content_hash: stamps.get(&d.filename).map(|s| s.content_hash.clone()),
+            id: d.id,
+            filename: d.filename,
+            size: d.size,
+            chunk_count: d.chunk_count,
+            ingested_at: d.ingested_at,
+            status: d.status,
+        })
+        .collect();
+    let (chunk_source, chunks) = chunk_hashes(&kb_id).await;
+    let settings = commands::get_settings().await?;
+
+    let to_json = |value: serde_json::Value| serde_json::to_vec_pretty(&value).unwrap();
+    let entries = vec![
+        (DOCUMENTS, to_json(serde_json::json!(documents))),
+        (CHUNKS, to_json(serde_json::json!(chunks))),
+        (
+            SETTINGS,
+            to_json(serde_json::json!({ "knowledge_base": kb, "settings": settings })),
+        ),
+    ];
+
+    let key = signing_key()?;
+    let public_key = key.verifying_key();
+    let manifest = SnapshotManifest {
+        format: FORMAT.to_string(),
+        format_version: FORMAT_VERSION,
+        kb_id: kb.id.clone(),
+        kb_name: kb.name.clone(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        document_count: documents.len(),
+        chunk_count: chunks.len(),
+        chunk_source,
+        files: entries
+            .iter()
+            .map(|(name, content)| (name.to_string(), provenance::content_hash(content)))
+            .collect(),
+        public_key: hex(public_key.as_bytes()),
+    };
+    let manifest_bytes = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+    let signature = hex(&key.sign(&manifest_bytes).to_bytes());
+
+    let mut archive = entries;
+    archive.push((MANIFEST, manifest_bytes));
+    archive.push((SIGNATURE, signature.into_bytes()));
+    let archive_path = path.clone();
+    tauri::async_runtime::spawn_blocking(move || write_archive(&archive_path, &archive))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    tracing::info!(
+        "Exported snapshot of {} ({} documents, {} chunks) to {}",
+        kb_id,
+        manifest.document_count,
+        manifest.chunk_count,
+        path.display()
+    );
+    Ok(SnapshotExport {
+        path: path.display().to_string(),
+        key_fingerprint: fingerprint(public_key.as_bytes()),
+        manifest,
+    })
+}
+
+/// Check the signature and contents of a knowledge base snapshot
+#[tauri::command]
+pub async fn verify_snapshot(path: String) -> Result<SnapshotVerification, String> {
+    let archive_path = PathBuf::from(&path);
+    let mut entries = tauri::async_runtime::spawn_blocking(move || read_archive(&archive_path))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    Ok(verify(&mut entries))
+}
@@ -0,0 +1,268 @@
+//! Knowledge base statistics for the dashboard.
+//!
+//! `get_kb_stats` aggregates what the backend and the shell know about a knowledge
+//! base: documents by type and status, the size distribution of its chunks, the space
+//! taken by their embeddings, when it last changed, and its ingestion errors. Chunk
+//! sizes come from the backend's chunk listing, read up to `MAX_SAMPLED_CHUNKS`; older
+//! backends fall back to the passages of the shell's lexical index. Embedding storage
+//! is estimated from the vector count and dimensions, as 32-bit floats.
+
+use crate::capabilities::{self, Feature};
+use crate::files::extension_of;
+use crate::jobs::{self, FileStatus};
+use crate::kb_snapshots::ChunkSource;
+use crate::lexical_index;
+use crate::retry_queue::{self, FailureCategory};
+use crate::{chunks, commands};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Chunks read from the backend to measure their sizes.
+const MAX_SAMPLED_CHUNKS: usize = 20_000;
+const CHUNK_PAGE_SIZE: usize = 500;
+/// Upper bounds of the chunk size buckets, in characters.
+const SIZE_BUCKETS: &[usize] = &[250, 500, 1000, 2000, 4000];
+const BYTES_PER_DIMENSION: u64 = 4;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KbStats {
+    pub kb_id: String,
+    pub name: String,
+    pub document_count: usize,
+    pub chunk_count: u64,
+    /// Total size of the source files, in bytes
+    pub total_bytes: u64,
+    /// By extension, most documents first
+    pub documents_by_type: Vec<TypeStats>,
+    /// By backend status (e.g. "ready", "processing", "failed")
+    pub documents_by_status: BTreeMap<String, usize>,
+    pub chunk_sizes: ChunkSizeStats,
+    pub embeddings: EmbeddingStorage,
+    pub created_at: String,
+    pub updated_at: String,
+    /// Most recent document ingestion
+    pub last_ingested_at: Option<String>,
+    pub errors: IngestionErrors,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TypeStats {
+    pub extension: String,
+    pub documents: usize,
+    pub chunks: u64,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChunkSizeStats {
+    pub source: ChunkSource,
+    /// Chunks measured, fewer than the chunk count when `sampled`
+    pub measured: usize,
+    pub sampled: bool,
+    pub min_chars: usize,
+    pub max_chars: usize,
+    pub mean_chars: f64,
+    pub median_chars: usize,
+    pub mean_tokens: f64,
+    pub buckets: Vec<SizeBucket>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SizeBucket {
+    pub min_chars: usize,
+    /// Exclusive; none for the last bucket
+    pub max_chars: Option<usize>,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EmbeddingStorage {
+    pub model: String,
+    pub dimensions: u32,
+    pub vectors: u64,
+    /// Estimated size of the vectors, in bytes
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IngestionErrors {
+    /// Documents the backend reports as failed
+    pub failed_documents: usize,
+    /// Files waiting in the retry queue, by failure category
+    pub retry_queue: BTreeMap<FailureCategory, usize>,
+    /// Files that failed in the ingestion jobs of this session
+    pub failed_in_jobs: usize,
+    /// Files not sent because they need OCR
+    pub needs_ocr: usize,
+}
+
+/// Characters and tokens of the chunks of a knowledge base, and whether all were read.
+async fn chunk_sizes(kb_id: &str) -> (ChunkSource, Vec<(usize, usize)>, bool) {
+    if capabilities::supports(Feature::Chunks) {
+        let mut sizes = Vec::new();
+        let mut complete = true;
+        loop {
+            let page = chunks::fetch_page(kb_id, None, None, sizes.len(), CHUNK_PAGE_SIZE).await;
+            let page = match page {
+                Ok(page) => page,
+                Err(e) => {
+                    tracing::warn!("Measuring the local index instead: {}", e);
+                    sizes.clear();
+                    break;
+                }
+            };
+            let read = page.chunks.len();
+            sizes.extend(page.chunks.into_iter().map(|c| {
+                let chars = c.text.chars().count();
+                let tokens = c
+                    .token_count
+                    .unwrap_or_else(|| chunks::token_count(&c.text));
+                (chars, tokens)
+            }));
+            if read == 0 || sizes.len() >= page.total {
+                break;
+            }
+            if sizes.len() >= MAX_SAMPLED_CHUNKS {
+                complete = false;
+                break;
+            }
+        }
+        if !sizes.is_empty() {
+            return (ChunkSource::Backend, sizes, complete);
+        }
+    }
+    let sizes = lexical_index::document_passages(kb_id)
+        .into_iter()
+        .flat_map(|(_, passages)| passages)
+        .map(|p| (p.chars().count(), chunks::token_count(&p)))
+        .collect();
+    (ChunkSource::LocalIndex, sizes, true)
+}
+
+fn size_stats(
+    source: ChunkSource,
+    mut sizes: Vec<(usize, usize)>,
+    complete: bool,
+) -> ChunkSizeStats {
+    sizes.sort_by_key(|(chars, _)| *chars);
+    let measured = sizes.len();
+    let mut buckets: Vec<SizeBucket> = std::iter::once(0)
+        .chain(SIZE_BUCKETS.iter().copied())
+        .zip(SIZE_BUCKETS.iter().copied().map(Some).chain([None]))
+        .map(|(min_chars, max_chars)| SizeBucket {
+            min_chars,
+            max_chars,
+            count: 0,
+        })
+        .collect();
+    for (chars, _) in &sizes {
+        if let Some(bucket) = buckets
+            .iter_mut()
+            .find(|b| b.max_chars.is_none_or(|max| *chars < max))
+        {
+            bucket.count += 1;
+        }
+    }
+    let mean = |total: f64| {
+        if measured == 0 {
+            0.0
+        } else {
+            total / measured as f64
+        }
+    };
+    ChunkSizeStats {
+        source,
+        measured,
+        sampled: !complete,
+        min_chars: sizes.first().map_or(0, |(c, _)| *c),
+        max_chars: sizes.last().map_or(0, |(c, _)| *c),
+        mean_chars: mean(sizes.iter().map(|(c, _)| *c as f64).sum()),
+        median_chars: sizes.get(measured / 2).map_or(0, |(c, _)| *c),
+        mean_tokens: mean(sizes.iter().map(|(_, t)| *t as f64).sum()),
+        buckets,
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Get the statistics of a knowledge base for its dashboard
+#[tauri::command]
+pub async fn get_kb_stats(kb_id: String) -> Result<KbStats, String> {
+    let kb = commands::list_knowledge_bases()
+        .await?
+        .into_iter()
+        .find(|kb| kb.id == kb_id)
+        .ok_or_else(|| format!("Knowledge base not found: {}", kb_id))?;
+    let documents = commands::list_documents(kb_id.clone()).await?;
+
+    let mut by_type: BTreeMap<String, TypeStats> = BTreeMap::new();
+    let mut by_status: BTreeMap<String, usize> = BTreeMap::new();
+    for document in &documents {
+        let extension = extension_of(Path::new(&document.filename));
+        let stats = by_type
+            .entry(extension.clone())
+            .or_insert_with(|| TypeStats {
+                extension,
+                documents: 0,
+                chunks: 0,
+                bytes: 0,
+            });
+        stats.documents += 1;
+        stats.chunks += document.chunk_count.max(0) as u64;
+        stats.bytes += document.size.unwrap_or(0);
+        *by_status.entry(document.status.clone()).or_default() += 1;
+    }
+    let mut documents_by_type: Vec<TypeStats> = by_type.into_values().collect();
+    documents_by_type.sort_by_key(|t| std::cmp::Reverse(t.documents));
+
+    let (source, sizes, complete) = chunk_sizes(&kb_id).await;
+    let chunk_count: u64 = documents
+        .iter()
+        .map(|d| d.chunk_count.max(0) as u64)
+        .sum::<u64>()
+        .max(kb.chunk_count.max(0) as u64);
+    let dimensions = kb.embedding_dimensions.max(0) as u32;
+
+    let mut retry_queue: BTreeMap<FailureCategory, usize> = BTreeMap::new();
+    for failed in retry_queue::list_failed_documents(kb_id.clone()).await? {
+        *retry_queue.entry(failed.category).or_default() += 1;
+    }
+    let job_files: Vec<jobs::FileResult> = jobs::list_ingestion_jobs()
+        .await?
+        .into_iter()
+        .filter(|job| job.kb_id == kb_id)
+        .flat_map(|job| job.files)
+        .collect();
+
+    Ok(KbStats {
+        kb_id: kb.id,
+        name: kb.name,
+        document_count: documents.len(),
+        chunk_count,
+        total_bytes: documents.iter().filter_map(|d| d.size).sum(),
+        documents_by_type,
+        chunk_sizes: size_stats(source, sizes, complete),
+        embeddings: EmbeddingStorage {
+            model: kb.embedding_model,
+            dimensions,
+            vectors: chunk_count,
+            bytes: chunk_count * dimensions as u64 * BYTES_PER_DIMENSION,
+        },
+        created_at: kb.created_at,
+        updated_at: kb.updated_at,
+        last_ingested_at: documents.iter().map(|d| d.ingested_at.clone()).max(),
+        errors: IngestionErrors {
+            failed_documents: by_status.get("failed").copied().unwrap_or(0),
+            retry_queue,
+            failed_in_jobs: job_files
+                .iter()
+                .filter(|f| f.status == FileStatus::Failed && !f.needs_ocr)
+                .count(),
+            needs_ocr: job_files.iter().filter(|f| f.needs_ocr).count(),
+        },
+        documents_by_status: by_status,
+    })
+}
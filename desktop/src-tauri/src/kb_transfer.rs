@@ -0,0 +1,252 @@
+//! Knowledge base export and import as portable `.ragkb` archives.
+//!
+//! The backend bundles (and unbundles) documents, embeddings, and metadata; the shell
+//! streams the archive between the backend and disk so multi-gigabyte knowledge bases
+//! never sit in memory, and reports progress with `kb-transfer-progress` events.
+//! Exports are written under `~/.ragkit/tmp/exports/` and moved into place once
+//! complete, so an interrupted export never leaves a truncated archive behind.
+
+use crate::backend::{authorize, backend_client, get_backend_url};
+use crate::commands::KnowledgeBase;
+use crate::hooks::{self, HookEvent};
+use crate::kb_history::{self, KbChange};
+use anyhow::{anyhow, Result};
+use futures_util::StreamExt;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+pub const ARCHIVE_EXTENSION: &str = "ragkb";
+
+/// Archives are ZIP files.
+const ARCHIVE_MAGIC: &[u8] = b"PK\x03\x04";
+const CHUNK_SIZE: usize = 256 * 1024;
+/// Minimum number of bytes between two progress events.
+const PROGRESS_STEP: u64 = 4 * 1024 * 1024;
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(6 * 60 * 60);
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferKind {
+    Export,
+    Import,
+}
+
+/// Payload of the `kb-transfer-progress` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferProgress {
+    pub kind: TransferKind,
+    pub kb_id: Option<String>,
+    pub path: String,
+    pub bytes: u64,
+    pub total: Option<u64>,
+    pub done: bool,
+}
+
+struct ProgressReporter {
+    app: AppHandle,
+    progress: TransferProgress,
+    last_emitted: u64,
+}
+
+impl ProgressReporter {
+    fn new(app: &AppHandle, kind: TransferKind, kb_id: Option<&str>, path: &Path) -> Self {
+        Self {
+            app: app.clone(),
+            progress: TransferProgress {
+                kind,
+                kb_id: kb_id.map(String::from),
+                path: path.display().to_string(),
+                bytes: 0,
+                total: None,
+                done: false,
+            },
+            last_emitted: 0,
+        }
+    }
+
+    fn advance(&mut self, bytes: usize) {
+        self.progress.bytes += bytes as u64;
+        if self.progress.bytes - self.last_emitted >= PROGRESS_STEP {
+            self.last_emitted = self.progress.bytes;
+            let _ = self.app.emit("kb-transfer-progress", &self.progress);
+        }
+    }
+
+    fn finish(mut self) {
+        self.progress.done = true;
+        let _ = self.app.emit("kb-transfer-progress", &self.progress);
+    }
+}
+
+fn with_archive_extension(path: PathBuf) -> PathBuf {
+    let has_extension = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case(ARCHIVE_EXTENSION));
+    if has_extension {
+        path
+    } else {
+        let mut name = path.into_os_string();
+        name.push(".");
+        name.push(ARCHIVE_EXTENSION);
+        PathBuf::from(name)
+    }
+}
+
+/// Move a finished file into place, copying when source and target are on different
+/// filesystems.
+async fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if tokio::fs::rename(from, to).await.is_ok() {
+        return Ok(());
+    }
+    tokio::fs::copy(from, to)
+        .await
+        .map_err(|e| anyhow!("Failed to write {}: {}", to.display(), e))?;
+    let _ = tokio::fs::remove_file(from).await;
+    Ok(())
+}
+
+async fn export(app: &AppHandle, kb_id: &str, target: &Path) -> Result<u64> {
+    let url = format!("{}/api/knowledge-bases/{}/export", get_backend_url(), kb_id);
+    let response = authorize(backend_client().get(url))
+        .timeout(TRANSFER_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| anyhow!("Request failed: {}", e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(anyhow!("Backend error ({}): {}", status, text));
+    }
+
+    let staging_dir = crate::store::tmp_dir().join("exports");
+    tokio::fs::create_dir_all(&staging_dir)
+        .await
+        .map_err(|e| anyhow!("Failed to create {}: {}", staging_dir.display(), e))?;
+    let staging = staging_dir.join(format!("{}.{}", uuid::Uuid::new_v4(), ARCHIVE_EXTENSION));
+
+    let mut reporter = ProgressReporter::new(app, TransferKind::Export, Some(kb_id), target);
+    reporter.progress.total = response.content_length();
+
+    let result: Result<()> = async {
+        let mut file = tokio::fs::File::create(&staging)
+            .await
+            .map_err(|e| anyhow!("Failed to create {}: {}", staging.display(), e))?;
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| anyhow!("Export interrupted: {}", e))?;
+            file.write_all(&chunk)
+                .await
+                .map_err(|e| anyhow!("Failed to write archive: {}", e))?;
+            reporter.advance(chunk.len());
+        }
+        file.flush().await?;
+        drop(file);
+        move_file(&staging, target).await
+    }
+    .await;
+
+    if let Err(e) = result {
+        let _ = tokio::fs::remove_file(&staging).await;
+        return Err(e);
+    }
+    let bytes = reporter.progress.bytes;
+    reporter.finish();
+    Ok(bytes)
+}
+
+async fn import(app: &AppHandle, source: &Path) -> Result<KnowledgeBase> {
+    let mut file = tokio::fs::File::open(source)
+        .await
+        .map_err(|e| anyhow!("Failed to open {}: {}", source.display(), e))?;
+    let size = file.metadata().await?.len();
+
+    let mut magic = [0u8; 4];
+    if file.read_exact(&mut magic).await.is_err() || magic != ARCHIVE_MAGIC {
+        return Err(anyhow!("{} is not a RAGKIT archive", source.display()));
+    }
+    let file = tokio::fs::File::open(source).await?;
+
+    let mut reporter = ProgressReporter::new(app, TransferKind::Import, None, source);
+    reporter.progress.total = Some(size);
+
+    // Read the archive in chunks as the request body is sent
+    let body =
+        futures_util::stream::unfold((file, reporter), |(mut file, mut reporter)| async move {
+            let mut buffer = vec![0u8; CHUNK_SIZE];
+            match file.read(&mut buffer).await {
+                Ok(0) => None,
+                Ok(n) => {
+                    buffer.truncate(n);
+                    reporter.advance(n);
+                    Some((Ok::<_, std::io::Error>(buffer), (file, reporter)))
+                }
+                Err(e) => Some((Err(e), (file, reporter))),
+            }
+        });
+
+    let url = format!("{}/api/knowledge-bases/import", get_backend_url());
+    let response = authorize(backend_client().post(url))
+        .header(reqwest::header::CONTENT_TYPE, "application/zip")
+        .header(reqwest::header::CONTENT_LENGTH, size)
+        .timeout(TRANSFER_TIMEOUT)
+        .body(reqwest::Body::wrap_stream(body))
+        .send()
+        .await
+        .map_err(|e| anyhow!("Request failed: {}", e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(anyhow!("Backend error ({}): {}", status, text));
+    }
+
+    let knowledge_base = response.json::<KnowledgeBase>().await?;
+    let mut reporter = ProgressReporter::new(app, TransferKind::Import, None, source);
+    reporter.progress.kb_id = Some(knowledge_base.id.clone());
+    reporter.progress.bytes = size;
+    reporter.progress.total = Some(size);
+    reporter.finish();
+    Ok(knowledge_base)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Export a knowledge base (documents, embeddings, metadata) to a `.ragkb` archive.
+/// Returns the archive path.
+#[tauri::command]
+pub async fn export_knowledge_base(
+    app: AppHandle,
+    kb_id: String,
+    path: String,
+) -> Result<String, String> {
+    let target = with_archive_extension(PathBuf::from(path));
+    let bytes = export(&app, &kb_id, &target)
+        .await
+        .map_err(|e| e.to_string())?;
+    tracing::info!(
+        "Exported KB {} to {} ({} bytes)",
+        kb_id,
+        target.display(),
+        bytes
+    );
+    hooks::fire(
+        HookEvent::BackupFinished,
+        serde_json::json!({ "kb_id": kb_id, "path": target, "bytes": bytes }),
+    );
+    Ok(target.display().to_string())
+}
+
+/// Import a knowledge base from a `.ragkb` archive
+#[tauri::command]
+pub async fn import_knowledge_base(app: AppHandle, path: String) -> Result<KnowledgeBase, String> {
+    let knowledge_base = import(&app, Path::new(&path))
+        .await
+        .map_err(|e| e.to_string())?;
+    tracing::info!("Imported KB {} from {}", knowledge_base.id, path);
+    kb_history::record(&knowledge_base.id, KbChange::Imported);
+    Ok(knowledge_base)
+}
@@ -0,0 +1,220 @@
+//! User-customizable keyboard shortcuts.
+//!
+//! Accelerators are persisted in ~/.ragkit/shortcuts.json. Validation happens here rather
+//! than in the UI because only the native layer can tell whether a global hotkey is
+//! already claimed by the OS or another application.
+
+use crate::store;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use tauri::AppHandle;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+const STATE_FILE: &str = "shortcuts.json";
+
+/// Shortcuts the OS reserves for itself and which we never let users bind.
+const RESERVED_SHORTCUTS: &[&str] = &[
+    "Alt+F4",
+    "Alt+Tab",
+    "Super+Tab",
+    "Control+Alt+Delete",
+    "Super+KeyL",
+    "Super+KeyQ",
+    "Super+KeyW",
+    "Super+Space",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutScope {
+    /// Registered system-wide, works while the app is in the background
+    Global,
+    /// Menu accelerator, only active while a RAGKIT window is focused
+    Menu,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyboardShortcut {
+    pub action: String,
+    pub accelerator: String,
+    pub scope: ShortcutScope,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShortcutConflict {
+    pub action: String,
+    pub accelerator: String,
+    pub reason: String,
+}
+
+fn shortcut(action: &str, accelerator: &str, scope: ShortcutScope) -> KeyboardShortcut {
+    KeyboardShortcut {
+        action: action.to_string(),
+        accelerator: accelerator.to_string(),
+        scope,
+    }
+}
+
+fn default_shortcuts() -> Vec<KeyboardShortcut> {
+    vec![
+        shortcut("quick_ask", "CommandOrControl+Shift+Space", ShortcutScope::Global),
+        shortcut("new_conversation", "CommandOrControl+KeyN", ShortcutScope::Menu),
+        shortcut("focus_search", "CommandOrControl+KeyK", ShortcutScope::Menu),
+        shortcut("open_settings", "CommandOrControl+Comma", ShortcutScope::Menu),
+    ]
+}
+
+/// Load the saved shortcuts, filling in defaults for actions the user never customized.
+pub fn load_shortcuts() -> Vec<KeyboardShortcut> {
+    let saved: Vec<KeyboardShortcut> = store::load(STATE_FILE);
+    let mut shortcuts = default_shortcuts();
+    for shortcut in shortcuts.iter_mut() {
+        if let Some(custom) = saved.iter().find(|s| s.action == shortcut.action) {
+            shortcut.accelerator = custom.accelerator.clone();
+        }
+    }
+    shortcuts
+}
+
+/// The current shortcuts with the submitted accelerators applied. Actions left out keep
+/// their binding; unknown actions are refused.
+fn merge(submitted: &[KeyboardShortcut]) -> Result<Vec<KeyboardShortcut>, String> {
+    let mut shortcuts = load_shortcuts();
+    for entry in submitted {
+        let shortcut = shortcuts
+            .iter_mut()
+            .find(|s| s.action == entry.action)
+            .ok_or_else(|| format!("Unknown shortcut action: {}", entry.action))?;
+        shortcut.accelerator = entry.accelerator.clone();
+    }
+    Ok(shortcuts)
+}
+
+/// Detect invalid, duplicated, reserved, or externally claimed accelerators.
+fn find_conflicts(app: &AppHandle, shortcuts: &[KeyboardShortcut]) -> Vec<ShortcutConflict> {
+    let current = load_shortcuts();
+    let reserved: Vec<Shortcut> = RESERVED_SHORTCUTS
+        .iter()
+        .filter_map(|s| Shortcut::from_str(s).ok())
+        .collect();
+
+    let mut conflicts = Vec::new();
+    let mut seen: HashMap<u32, &str> = HashMap::new();
+
+    for entry in shortcuts {
+        // An empty accelerator means the action is unbound.
+        if entry.accelerator.is_empty() {
+            continue;
+        }
+
+        let conflict = |reason: String| ShortcutConflict {
+            action: entry.action.clone(),
+            accelerator: entry.accelerator.clone(),
+            reason,
+        };
+
+        let parsed = match Shortcut::from_str(&entry.accelerator) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                conflicts.push(conflict(format!("Invalid accelerator: {}", e)));
+                continue;
+            }
+        };
+
+        if let Some(other) = seen.insert(parsed.id(), &entry.action) {
+            conflicts.push(conflict(format!("Already bound to '{}'", other)));
+            continue;
+        }
+
+        if reserved.contains(&parsed) {
+            conflicts.push(conflict("Reserved by the operating system".to_string()));
+            continue;
+        }
+
+        if entry.scope != ShortcutScope::Global {
+            continue;
+        }
+
+        // Our own registrations are not conflicts; anything else is probed by trying to
+        // register it, which fails when another application already owns the hotkey.
+        let ours = current
+            .iter()
+            .any(|s| s.scope == ShortcutScope::Global && s.accelerator == entry.accelerator);
+        if ours && app.global_shortcut().is_registered(parsed) {
+            continue;
+        }
+        match app.global_shortcut().register(parsed) {
+            Ok(()) => {
+                let _ = app.global_shortcut().unregister(parsed);
+            }
+            Err(e) => conflicts.push(conflict(format!(
+                "Already in use by another application: {}",
+                e
+            ))),
+        }
+    }
+
+    conflicts
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Get the current keyboard shortcuts
+#[tauri::command]
+pub async fn get_keyboard_shortcuts() -> Result<Vec<KeyboardShortcut>, String> {
+    Ok(load_shortcuts())
+}
+
+/// Check shortcuts for conflicts, with the other actions' current bindings, without
+/// saving them
+#[tauri::command]
+pub async fn check_keyboard_shortcuts(
+    app: AppHandle,
+    shortcuts: Vec<KeyboardShortcut>,
+) -> Result<Vec<ShortcutConflict>, String> {
+    Ok(find_conflicts(&app, &merge(&shortcuts)?))
+}
+
+/// Save customized keyboard shortcuts, rejecting them if any conflict is detected
+///
+/// Only the submitted actions change; the others keep their current binding.
+#[tauri::command]
+pub async fn set_keyboard_shortcuts(
+    app: AppHandle,
+    shortcuts: Vec<KeyboardShortcut>,
+) -> Result<Vec<KeyboardShortcut>, String> {
+    let shortcuts = merge(&shortcuts)?;
+    let conflicts = find_conflicts(&app, &shortcuts);
+    if !conflicts.is_empty() {
+        let details: Vec<String> = conflicts
+            .iter()
+            .map(|c| format!("{} ({}): {}", c.action, c.accelerator, c.reason))
+            .collect();
+        return Err(format!("Shortcut conflicts: {}", details.join("; ")));
+    }
+
+    // Only customized actions are saved, so the others follow the defaults
+    let defaults = default_shortcuts();
+    let customized: Vec<&KeyboardShortcut> = shortcuts
+        .iter()
+        .filter(|s| {
+            !defaults
+                .iter()
+                .any(|d| d.action == s.action && d.accelerator == s.accelerator)
+        })
+        .collect();
+    store::save(STATE_FILE, &customized).map_err(|e| e.to_string())?;
+    crate::shortcuts::register_global_shortcuts(&app);
+    Ok(load_shortcuts())
+}
+
+/// Restore the default keyboard shortcuts
+#[tauri::command]
+pub async fn reset_keyboard_shortcuts(app: AppHandle) -> Result<Vec<KeyboardShortcut>, String> {
+    store::save(STATE_FILE, &Vec::<KeyboardShortcut>::new()).map_err(|e| e.to_string())?;
+    crate::shortcuts::register_global_shortcuts(&app);
+    Ok(load_shortcuts())
+}
@@ -0,0 +1,223 @@
+//! Latency budget: a ceiling on answer time, enforced by trimming the pipeline.
+//!
+//! With `max_answer_secs` set in the preferences, the shell keeps running averages of
+//! each pipeline stage per knowledge base (from the stage timings the backend reports,
+//! or the total latency when it reports none). Before a query it estimates the answer
+//! time and, while the estimate exceeds the budget, disables reranking, lowers
+//! `top_k`, then switches to the configured fast model. The answer lists what was
+//! trimmed, so users know it came from a reduced pipeline.
+
+use crate::commands::{self, QueryParams, QueryResponse};
+use crate::{preferences, store};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+const STATE_FILE: &str = "latency_stats.json";
+
+/// Weight of the latest measure in the running averages.
+const SMOOTHING: f64 = 0.3;
+/// Keep estimates under this share of the budget, as stage times vary.
+const SAFETY_MARGIN: f64 = 0.85;
+/// Lowest `top_k` the budget may trim to.
+const MIN_TOP_K: i32 = 3;
+/// Share of generation time assumed to depend on the amount of retrieved context.
+const CONTEXT_SHARE: f64 = 0.3;
+
+/// Serializes updates of the stored statistics.
+static UPDATES: Mutex<()> = Mutex::new(());
+
+/// Stage durations reported by the backend with an answer.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StageTimings {
+    pub embedding_ms: Option<f64>,
+    pub retrieval_ms: Option<f64>,
+    pub rerank_ms: Option<f64>,
+    pub generation_ms: Option<f64>,
+}
+
+/// Running averages of a knowledge base, in milliseconds.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct StageAverages {
+    embedding_ms: Option<f64>,
+    retrieval_ms: Option<f64>,
+    rerank_ms: Option<f64>,
+    /// Generation time by model
+    generation_ms: BTreeMap<String, f64>,
+    total_ms: Option<f64>,
+    samples: u64,
+}
+
+/// A part of the pipeline left out to stay within the budget.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PipelineTrim {
+    RerankingDisabled,
+    TopKLowered { from: i32, to: i32 },
+    FasterModel { provider: String, model: String },
+}
+
+/// What the budget did to a query, kept until its answer arrives.
+#[derive(Debug, Default)]
+pub struct BudgetPlan {
+    budget_ms: Option<f64>,
+    estimate_ms: Option<f64>,
+    trims: Vec<PipelineTrim>,
+    model: Option<String>,
+}
+
+fn average(previous: Option<f64>, value: f64) -> f64 {
+    match previous {
+        Some(previous) => previous + SMOOTHING * (value - previous),
+        None => value,
+    }
+}
+
+fn model_key(provider: &str, model: &str) -> String {
+    format!("{}/{}", provider, model)
+}
+
+/// Estimated answer time of a pipeline, if enough is known about its stages.
+fn estimate(
+    stats: &StageAverages,
+    rerank: bool,
+    top_k: i32,
+    full_top_k: i32,
+    model: &str,
+) -> Option<f64> {
+    let generation = stats.generation_ms.get(model).copied()?;
+    let context = f64::from(top_k.max(1)) / f64::from(full_top_k.max(1));
+    Some(
+        stats.embedding_ms.unwrap_or(0.0)
+            + stats.retrieval_ms.unwrap_or(0.0)
+            + if rerank {
+                stats.rerank_ms.unwrap_or(0.0)
+            } else {
+                0.0
+            }
+            + generation * (1.0 - CONTEXT_SHARE + CONTEXT_SHARE * context.min(1.0)),
+    )
+}
+
+/// Trim the pipeline of a query so its estimated answer time fits the budget.
+pub async fn apply(params: &mut QueryParams) -> BudgetPlan {
+    let prefs = preferences::load();
+    let Some(budget_secs) = prefs.max_answer_secs.filter(|s| *s > 0) else {
+        return BudgetPlan::default();
+    };
+    let budget_ms = budget_secs as f64 * 1000.0;
+    let mut plan = BudgetPlan {
+        budget_ms: Some(budget_ms),
+        ..BudgetPlan::default()
+    };
+
+    let settings = match commands::get_settings().await {
+        Ok(settings) => settings,
+        Err(e) => {
+            tracing::debug!("Latency budget skipped, settings unavailable: {}", e);
+            return plan;
+        }
+    };
+    let provider = params.llm_provider.clone().unwrap_or(settings.llm_provider);
+    let model = params.llm_model.clone().unwrap_or(settings.llm_model);
+    let mut model = model_key(&provider, &model);
+    plan.model = Some(model.clone());
+
+    let stats: BTreeMap<String, StageAverages> = store::load(STATE_FILE);
+    let Some(stats) = stats.get(&params.kb_id) else {
+        return plan;
+    };
+    let full_top_k = params.top_k.unwrap_or(settings.retrieval_top_k);
+    let mut rerank = params.rerank_enabled.unwrap_or(settings.retrieval_rerank_enabled);
+    let mut top_k = full_top_k;
+    let target = budget_ms * SAFETY_MARGIN;
+    let fits = |rerank, top_k, model: &str| {
+        estimate(stats, rerank, top_k, full_top_k, model)
+            .or(stats.total_ms)
+            .is_none_or(|ms| ms <= target)
+    };
+
+    if rerank && !fits(rerank, top_k, &model) {
+        rerank = false;
+        params.rerank_enabled = Some(false);
+        plan.trims.push(PipelineTrim::RerankingDisabled);
+    }
+    if top_k > MIN_TOP_K && !fits(rerank, top_k, &model) {
+        top_k = (full_top_k / 2).max(MIN_TOP_K);
+        params.top_k = Some(top_k);
+        plan.trims.push(PipelineTrim::TopKLowered {
+            from: full_top_k,
+            to: top_k,
+        });
+    }
+    if let Some(fast) = &prefs.latency_budget_fast_model {
+        let fast_key = model_key(&fast.provider, &fast.model);
+        // A model pinned by the caller or the conversation is left alone
+        if fast_key != model && params.llm_provider.is_none() && !fits(rerank, top_k, &model) {
+            params.llm_provider = Some(fast.provider.clone());
+            params.llm_model = Some(fast.model.clone());
+            plan.trims.push(PipelineTrim::FasterModel {
+                provider: fast.provider.clone(),
+                model: fast.model.clone(),
+            });
+            model = fast_key;
+            plan.model = Some(model.clone());
+        }
+    }
+    plan.estimate_ms = estimate(stats, rerank, top_k, full_top_k, &model).or(stats.total_ms);
+    if !plan.trims.is_empty() {
+        tracing::info!(
+            "Latency budget of {} s: trimmed {:?}, estimated {:.0} ms",
+            budget_secs,
+            plan.trims,
+            plan.estimate_ms.unwrap_or(0.0)
+        );
+    }
+    plan
+}
+
+/// Record the stage times of an answer and annotate it with the budget's trims.
+pub fn finish(kb_id: &str, plan: BudgetPlan, mut response: QueryResponse) -> QueryResponse {
+    if let Some(budget_ms) = plan.budget_ms {
+        record(kb_id, plan.model.as_deref(), &response);
+        response.budget_exceeded = Some(f64::from(response.latency_ms) > budget_ms);
+    }
+    response.trimmed = plan.trims;
+    response
+}
+
+fn record(kb_id: &str, model: Option<&str>, response: &QueryResponse) {
+    if response.latency_ms <= 0 {
+        return;
+    }
+    let _guard = UPDATES.lock().unwrap();
+    let mut stats: BTreeMap<String, StageAverages> = store::load(STATE_FILE);
+    let entry = stats.entry(kb_id.to_string()).or_default();
+    let total = f64::from(response.latency_ms);
+    entry.total_ms = Some(average(entry.total_ms, total));
+    entry.samples += 1;
+
+    let timings = response.timings.clone().unwrap_or_default();
+    if let Some(ms) = timings.embedding_ms {
+        entry.embedding_ms = Some(average(entry.embedding_ms, ms));
+    }
+    if let Some(ms) = timings.retrieval_ms {
+        entry.retrieval_ms = Some(average(entry.retrieval_ms, ms));
+    }
+    if let Some(ms) = timings.rerank_ms {
+        entry.rerank_ms = Some(average(entry.rerank_ms, ms));
+    }
+    // Without stage timings, the whole answer time is counted as generation
+    let generation = timings.generation_ms.unwrap_or(total);
+    if let Some(model) = response.served_by.as_deref().or(model) {
+        let previous = entry.generation_ms.get(model).copied();
+        entry
+            .generation_ms
+            .insert(model.to_string(), average(previous, generation));
+    }
+
+    if let Err(e) = store::save(STATE_FILE, &stats) {
+        tracing::warn!("Failed to save latency statistics: {}", e);
+    }
+}
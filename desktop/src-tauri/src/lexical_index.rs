@@ -0,0 +1,400 @@
+//! Local lexical index for instant answers.
+//!
+//! The shell keeps a BM25 index of the text of the documents it ingests, so a query can
+//! show the best-matching passages within milliseconds (`query-instant-results`) while
+//! the backend is still generating the full answer. The index is stored per knowledge
+//! base and updated as documents are added or removed; documents ingested before it
+//! existed are picked up by `rebuild_lexical_index`.
+
+use crate::commands::{self, QueryParams};
+use crate::{preferences, printing, store, windows};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tauri::AppHandle;
+
+/// Target length of an indexed passage.
+const PASSAGE_CHARS: usize = 800;
+/// BM25 term frequency saturation and length normalization.
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "de", "des", "do", "du", "en", "est", "et",
+    "for", "from", "how", "in", "is", "it", "la", "le", "les", "of", "on", "or", "que", "qui",
+    "the", "to", "un", "une", "was", "what", "which", "who", "with",
+];
+
+/// Indexes loaded in memory, keyed by knowledge base.
+static INDEXES: Mutex<BTreeMap<String, Arc<Index>>> = Mutex::new(BTreeMap::new());
+/// Serializes updates of the stored indexes.
+static UPDATES: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedDocument {
+    id: String,
+    filename: String,
+    passages: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct StoredIndex {
+    documents: Vec<IndexedDocument>,
+}
+
+/// A stored index with its postings built.
+#[derive(Default)]
+struct Index {
+    documents: Vec<IndexedDocument>,
+    /// (document, passage) of every passage
+    passages: Vec<(usize, usize)>,
+    /// Number of terms of every passage
+    lengths: Vec<usize>,
+    /// Passages containing each term, with the term frequency
+    postings: HashMap<String, Vec<(usize, u32)>>,
+    average_length: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InstantResult {
+    pub document_id: String,
+    pub filename: String,
+    pub excerpt: String,
+    pub score: f64,
+}
+
+/// Payload of the `query-instant-results` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct InstantResultsEvent {
+    pub conversation_id: String,
+    pub request_id: Option<String>,
+    pub results: Vec<InstantResult>,
+    pub elapsed_ms: u64,
+}
+
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() > 1)
+        .map(str::to_lowercase)
+        .filter(|w| !STOPWORDS.contains(&w.as_str()))
+}
+
+/// Split a document into passages of about `PASSAGE_CHARS`, on paragraph boundaries
+/// where possible.
+fn passages(text: &str) -> Vec<String> {
+    let mut passages = Vec::new();
+    let mut current = String::new();
+    for paragraph in text.split("\n\n") {
+        for word in paragraph.split_whitespace() {
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(word);
+            if current.len() >= PASSAGE_CHARS {
+                passages.push(std::mem::take(&mut current));
+            }
+        }
+        // Close the passage at a paragraph boundary once it is long enough
+        if current.len() >= PASSAGE_CHARS / 2 {
+            passages.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        passages.push(current);
+    }
+    passages
+}
+
+impl Index {
+    fn build(documents: Vec<IndexedDocument>) -> Self {
+        let mut index = Index::default();
+        for (d, document) in documents.iter().enumerate() {
+            for (p, passage) in document.passages.iter().enumerate() {
+                let id = index.passages.len();
+                let mut frequencies: HashMap<String, u32> = HashMap::new();
+                for term in tokenize(passage) {
+                    *frequencies.entry(term).or_default() += 1;
+                }
+                index.passages.push((d, p));
+                index
+                    .lengths
+                    .push(frequencies.values().sum::<u32>() as usize);
+                for (term, frequency) in frequencies {
+                    index
+                        .postings
+                        .entry(term)
+                        .or_default()
+                        .push((id, frequency));
+                }
+            }
+        }
+        index.average_length = if index.lengths.is_empty() {
+            0.0
+        } else {
+            index.lengths.iter().sum::<usize>() as f64 / index.lengths.len() as f64
+        };
+        index.documents = documents;
+        index
+    }
+
+    /// Best passages for a query by BM25 score, at most one per document.
+    fn search(&self, query: &str, limit: usize) -> Vec<InstantResult> {
+        let total = self.passages.len() as f64;
+        let mut terms: Vec<String> = tokenize(query).collect();
+        terms.sort();
+        terms.dedup();
+
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        for term in &terms {
+            let Some(postings) = self.postings.get(term) else {
+                continue;
+            };
+            let n = postings.len() as f64;
+            let idf = ((total - n + 0.5) / (n + 0.5) + 1.0).ln();
+            for &(passage, frequency) in postings {
+                let tf = frequency as f64;
+                let length = self.lengths[passage] as f64 / self.average_length.max(1.0);
+                *scores.entry(passage).or_default() +=
+                    idf * tf * (K1 + 1.0) / (tf + K1 * (1.0 - B + B * length));
+            }
+        }
+
+        let mut ranked: Vec<(usize, f64)> = scores.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let mut seen = Vec::new();
+        let mut results = Vec::new();
+        for (passage, score) in ranked {
+            let (d, p) = self.passages[passage];
+            if seen.contains(&d) {
+                continue;
+            }
+            seen.push(d);
+            let document = &self.documents[d];
+            results.push(InstantResult {
+                document_id: document.id.clone(),
+                filename: document.filename.clone(),
+                excerpt: printing::excerpt(&document.passages[p]),
+                score,
+            });
+            if results.len() >= limit {
+                break;
+            }
+        }
+        results
+    }
+
+    /// Documents closest to a document by TF-IDF cosine similarity of their terms.
+    fn similar(&self, doc_id: &str, limit: usize) -> Vec<(usize, f64)> {
+        let Some(target) = self.documents.iter().position(|d| d.id == doc_id) else {
+            return Vec::new();
+        };
+        let mut vectors: Vec<HashMap<&str, f64>> = vec![HashMap::new(); self.documents.len()];
+        for (term, postings) in &self.postings {
+            for &(passage, frequency) in postings {
+                let (d, _) = self.passages[passage];
+                *vectors[d].entry(term.as_str()).or_default() += frequency as f64;
+            }
+        }
+        let mut frequencies: HashMap<&str, usize> = HashMap::new();
+        for vector in &vectors {
+            for term in vector.keys() {
+                *frequencies.entry(term).or_default() += 1;
+            }
+        }
+        let total = self.documents.len() as f64;
+        for vector in &mut vectors {
+            for (term, weight) in vector.iter_mut() {
+                *weight *= (total / frequencies[term] as f64).ln() + 1.0;
+            }
+            let norm = vector.values().map(|w| w * w).sum::<f64>().sqrt();
+            if norm > 0.0 {
+                vector.values_mut().for_each(|w| *w /= norm);
+            }
+        }
+
+        let mut ranked: Vec<(usize, f64)> = vectors
+            .iter()
+            .enumerate()
+            .filter(|(d, _)| *d != target)
+            .map(|(d, vector)| {
+                let score = vectors[target]
+                    .iter()
+                    .filter_map(|(term, w)| vector.get(term).map(|v| w * v))
+                    .sum();
+                (d, score)
+            })
+            .filter(|(_, score)| *score > 0.0)
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranked.truncate(limit);
+        ranked
+    }
+}
+
+fn index_file(kb_id: &str) -> String {
+    let kb_id: String = kb_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("lexical_index_{}.json", kb_id)
+}
+
+fn loaded(kb_id: &str) -> Arc<Index> {
+    if let Some(index) = INDEXES.lock().unwrap().get(kb_id) {
+        return index.clone();
+    }
+    let stored: StoredIndex = store::load(&index_file(kb_id));
+    let index = Arc::new(Index::build(stored.documents));
+    INDEXES
+        .lock()
+        .unwrap()
+        .insert(kb_id.to_string(), index.clone());
+    index
+}
+
+/// Apply a change to the documents of an index, then save and reload it.
+fn update(kb_id: &str, f: impl FnOnce(&mut Vec<IndexedDocument>)) {
+    let _guard = UPDATES.lock().unwrap();
+    let mut documents = loaded(kb_id).documents.clone();
+    f(&mut documents);
+    let stored = StoredIndex { documents };
+    if let Err(e) = store::save(&index_file(kb_id), &stored) {
+        tracing::warn!("Failed to save the lexical index of KB {}: {}", kb_id, e);
+    }
+    INDEXES
+        .lock()
+        .unwrap()
+        .insert(kb_id.to_string(), Arc::new(Index::build(stored.documents)));
+}
+
+/// Fetch and index the text of documents, replacing previous versions.
+async fn index(kb_id: &str, documents: Vec<(String, String)>) -> usize {
+    let mut indexed = Vec::new();
+    for (id, filename) in documents {
+        match commands::document_text(kb_id, &id).await {
+            Ok(text) => indexed.push(IndexedDocument {
+                passages: passages(&text),
+                id,
+                filename,
+            }),
+            Err(e) => tracing::debug!("Not indexing document {}: {}", id, e),
+        }
+    }
+    let count = indexed.len();
+    if count > 0 {
+        update(kb_id, |documents| {
+            documents.retain(|d| !indexed.iter().any(|i| i.id == d.id));
+            documents.extend(indexed);
+        });
+    }
+    count
+}
+
+/// Index newly ingested documents, given as (id, filename), in the background.
+pub fn index_documents(kb_id: String, documents: Vec<(String, String)>) {
+    tauri::async_runtime::spawn(async move {
+        index(&kb_id, documents).await;
+    });
+}
+
+/// Remove a deleted document from the index.
+pub fn remove_document(kb_id: &str, doc_id: &str) {
+    if loaded(kb_id).documents.iter().any(|d| d.id == doc_id) {
+        update(kb_id, |documents| documents.retain(|d| d.id != doc_id));
+    }
+}
+
+/// Drop the index of a deleted knowledge base.
+pub fn remove_kb(kb_id: &str) {
+    INDEXES.lock().unwrap().remove(kb_id);
+    let _ = std::fs::remove_file(store::ragkit_dir().join(index_file(kb_id)));
+}
+
+/// Indexed passages of each document of a knowledge base, by document id.
+pub fn document_passages(kb_id: &str) -> Vec<(String, Vec<String>)> {
+    store::load::<StoredIndex>(&index_file(kb_id))
+        .documents
+        .into_iter()
+        .map(|d| (d.id, d.passages))
+        .collect()
+}
+
+/// Best passages of a knowledge base for a query, at most one per document.
+pub fn search(kb_id: &str, query: &str, limit: usize) -> Vec<InstantResult> {
+    loaded(kb_id).search(query, limit)
+}
+
+/// Documents of a knowledge base most similar to a document, as (id, filename, score)
+/// with scores from 0 to 1.
+pub fn similar_documents(kb_id: &str, doc_id: &str, limit: usize) -> Vec<(String, String, f64)> {
+    let index = loaded(kb_id);
+    index
+        .similar(doc_id, limit)
+        .into_iter()
+        .map(|(d, score)| {
+            let document = &index.documents[d];
+            (document.id.clone(), document.filename.clone(), score)
+        })
+        .collect()
+}
+
+/// Emit the local best matches for a query, without delaying the query itself.
+pub fn emit_instant_results(app: &AppHandle, params: &QueryParams) {
+    let prefs = preferences::load();
+    // The index doesn't know document tags, so it can't honour a tag filter
+    if !prefs.instant_results || !params.tag_filter.is_empty() {
+        return;
+    }
+    let app = app.clone();
+    let (kb_id, question) = (params.kb_id.clone(), params.question.clone());
+    let (conversation_id, request_id) = (params.conversation_id.clone(), params.request_id.clone());
+    tauri::async_runtime::spawn_blocking(move || {
+        let started = Instant::now();
+        let results = loaded(&kb_id).search(&question, prefs.instant_results_limit);
+        if results.is_empty() {
+            return;
+        }
+        let event = InstantResultsEvent {
+            conversation_id: conversation_id.clone(),
+            request_id,
+            results,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        };
+        windows::emit_to_conversation(&app, &conversation_id, "query-instant-results", event);
+    });
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Search the local lexical index of a knowledge base
+#[tauri::command]
+pub async fn search_lexical_index(
+    kb_id: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<InstantResult>, String> {
+    let limit = limit.unwrap_or_else(|| preferences::load().instant_results_limit);
+    tauri::async_runtime::spawn_blocking(move || search(&kb_id, &query, limit))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Re-index every document of a knowledge base, returning the number indexed
+#[tauri::command]
+pub async fn rebuild_lexical_index(kb_id: String) -> Result<usize, String> {
+    let documents = commands::list_documents(kb_id.clone()).await?;
+    let ids: Vec<String> = documents.iter().map(|d| d.id.clone()).collect();
+    // Forget documents deleted outside the shell
+    update(&kb_id, |indexed| indexed.retain(|d| ids.contains(&d.id)));
+    let documents = documents.into_iter().map(|d| (d.id, d.filename)).collect();
+    Ok(index(&kb_id, documents).await)
+}
@@ -0,0 +1,312 @@
+//! Mail archive ingestion.
+//!
+//! `add_mailbox` reads exported mail (.eml files, .mbox mailboxes, or a folder of them)
+//! and turns each conversation thread into a Markdown document. Messages are grouped by
+//! their `References`/`In-Reply-To` headers, falling back to the subject without its
+//! `Re:`/`Fwd:` prefixes. Each document starts with frontmatter holding the subject,
+//! first sender, and date, which the metadata extraction picks up, and lists the
+//! messages in order with their sender and date. Quoted replies are dropped since the
+//! quoted messages are in the thread already. Documents are saved under
+//! `~/.ragkit/mail/` and ingested like any other file.
+
+use crate::files::{self, extension_of};
+use crate::{jobs, store};
+use mail_parser::mailbox::mbox::MessageIterator;
+use mail_parser::{Address, HeaderValue, Message, MessageParser};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+pub const MAIL_EXTENSIONS: &[&str] = &["eml", "mbox"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MailboxImport {
+    /// Ingestion job of the thread documents
+    pub job_id: Option<String>,
+    pub messages: usize,
+    pub threads: usize,
+    /// Files or messages that could not be parsed
+    pub skipped: usize,
+    /// Directory the thread documents were written to
+    pub output_dir: String,
+}
+
+struct Mail {
+    message_id: Option<String>,
+    /// Id of the first message of the thread, from the reply headers
+    root_id: Option<String>,
+    subject: String,
+    thread_name: String,
+    from: String,
+    to: Vec<String>,
+    date: Option<String>,
+    timestamp: i64,
+    body: String,
+}
+
+fn format_address(address: Option<&Address>) -> Vec<String> {
+    address
+        .map(|a| {
+            a.iter()
+                .map(|addr| match (addr.name(), addr.address()) {
+                    (Some(name), Some(email)) => format!("{} <{}>", name, email),
+                    (Some(name), None) => name.to_string(),
+                    (None, Some(email)) => email.to_string(),
+                    (None, None) => String::new(),
+                })
+                .filter(|a| !a.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn first_id(value: &HeaderValue) -> Option<String> {
+    match value {
+        HeaderValue::Text(id) => Some(id.to_string()),
+        HeaderValue::TextList(ids) => ids.first().map(|id| id.to_string()),
+        _ => None,
+    }
+}
+
+fn parse(raw: &[u8], fallback_from: Option<&str>) -> Option<Mail> {
+    let message: Message = MessageParser::default().parse(raw)?;
+    let subject = message
+        .subject()
+        .unwrap_or("(no subject)")
+        .trim()
+        .to_string();
+    let body = message
+        .body_text(0)
+        .map(|text| {
+            text.lines()
+                .filter(|line| !line.trim_start().starts_with('>'))
+                .collect::<Vec<_>>()
+                .join("\n")
+                .trim()
+                .to_string()
+        })
+        .unwrap_or_default();
+    Some(Mail {
+        message_id: message.message_id().map(String::from),
+        root_id: first_id(message.references()).or_else(|| first_id(message.in_reply_to())),
+        thread_name: message
+            .thread_name()
+            .unwrap_or(&subject)
+            .trim()
+            .to_lowercase(),
+        from: format_address(message.from())
+            .into_iter()
+            .next()
+            .or_else(|| fallback_from.map(String::from))
+            .unwrap_or_else(|| "Unknown sender".to_string()),
+        to: format_address(message.to()),
+        date: message.date().map(|d| d.to_rfc3339()),
+        timestamp: message.date().map_or(0, |d| d.to_timestamp()),
+        subject,
+        body,
+    })
+}
+
+/// Parse the messages of .eml and .mbox files, counting the ones that fail.
+fn read_messages(paths: &[PathBuf]) -> (Vec<Mail>, usize) {
+    let mut mails = Vec::new();
+    let mut skipped = 0;
+    for path in paths {
+        if extension_of(path) == "eml" {
+            match std::fs::read(path).ok().and_then(|raw| parse(&raw, None)) {
+                Some(mail) => mails.push(mail),
+                None => skipped += 1,
+            }
+            continue;
+        }
+        let Ok(file) = std::fs::File::open(path) else {
+            skipped += 1;
+            continue;
+        };
+        for message in MessageIterator::new(std::io::BufReader::new(file)) {
+            match message
+                .ok()
+                .and_then(|m| parse(m.contents(), Some(m.from())))
+            {
+                Some(mail) => mails.push(mail),
+                None => skipped += 1,
+            }
+        }
+    }
+    (mails, skipped)
+}
+
+/// Group messages into threads, each sorted by date.
+fn threads(mails: Vec<Mail>) -> Vec<Vec<Mail>> {
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut threads: Vec<Vec<Mail>> = Vec::new();
+    for mail in mails {
+        let keys: Vec<String> = [&mail.root_id, &mail.message_id]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .chain([format!("subject:{}", mail.thread_name)])
+            .collect();
+        let thread = match keys.iter().find_map(|k| index.get(k).copied()) {
+            Some(thread) => thread,
+            None => {
+                threads.push(Vec::new());
+                threads.len() - 1
+            }
+        };
+        for key in keys {
+            index.entry(key).or_insert(thread);
+        }
+        threads[thread].push(mail);
+    }
+    for thread in &mut threads {
+        thread.sort_by_key(|m| m.timestamp);
+    }
+    threads
+}
+
+fn frontmatter_value(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "'").replace('\n', " "))
+}
+
+/// Markdown document of a thread, with frontmatter for the metadata extraction.
+fn to_markdown(thread: &[Mail]) -> String {
+    let first = &thread[0];
+    let mut markdown = format!(
+        "---\ntitle: {}\nauthor: {}\n",
+        frontmatter_value(&first.subject),
+        frontmatter_value(&first.from)
+    );
+    if let Some(date) = &first.date {
+        markdown.push_str(&format!("date: {}\n", date));
+    }
+    markdown.push_str(&format!("messages: {}\n---\n\n", thread.len()));
+    markdown.push_str(&format!("# {}\n", first.subject));
+    for mail in thread {
+        markdown.push_str(&format!("\n## {}", mail.from));
+        if let Some(date) = &mail.date {
+            markdown.push_str(&format!(" — {}", date));
+        }
+        markdown.push('\n');
+        if !mail.to.is_empty() {
+            markdown.push_str(&format!("\nTo: {}\n", mail.to.join(", ")));
+        }
+        if mail.subject != first.subject {
+            markdown.push_str(&format!("\nSubject: {}\n", mail.subject));
+        }
+        markdown.push_str(&format!("\n{}\n", mail.body));
+    }
+    markdown
+}
+
+fn file_name(thread: &[Mail]) -> String {
+    let first = &thread[0];
+    let date = first
+        .date
+        .as_deref()
+        .map_or("", |d| d.get(..10).unwrap_or(d));
+    files::safe_file_name(&format!("{} {}", date, first.subject), "Thread")
+}
+
+/// Write a document per thread to `dir`, returning their paths.
+fn write_threads(dir: &Path, threads: &[Vec<Mail>]) -> std::io::Result<Vec<String>> {
+    std::fs::create_dir_all(dir)?;
+    let mut paths = Vec::new();
+    for thread in threads {
+        let name = file_name(thread);
+        let mut path = dir.join(format!("{}.md", name));
+        let mut n = 2;
+        while path.exists() {
+            path = dir.join(format!("{} ({}).md", name, n));
+            n += 1;
+        }
+        std::fs::write(&path, to_markdown(thread))?;
+        paths.push(path.to_string_lossy().into_owned());
+    }
+    Ok(paths)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Add exported mail to a knowledge base, one document per thread
+///
+/// `path` is an .eml file, an .mbox mailbox, or a folder searched recursively for them;
+/// `formats` restricts the extensions read (default: both).
+#[tauri::command]
+pub async fn add_mailbox(
+    app: AppHandle,
+    kb_id: String,
+    path: String,
+    formats: Option<Vec<String>>,
+) -> Result<MailboxImport, String> {
+    let formats: Vec<String> = formats
+        .filter(|f| !f.is_empty())
+        .unwrap_or_else(|| MAIL_EXTENSIONS.iter().map(|e| e.to_string()).collect())
+        .iter()
+        .map(|f| f.trim_start_matches('.').to_lowercase())
+        .collect();
+    if let Some(format) = formats
+        .iter()
+        .find(|f| !MAIL_EXTENSIONS.contains(&f.as_str()))
+    {
+        return Err(format!("Unsupported mail format: {}", format));
+    }
+
+    let source = PathBuf::from(&path);
+    let name = source.file_stem().map_or_else(
+        || "Mailbox".to_string(),
+        |n| n.to_string_lossy().into_owned(),
+    );
+    let base = store::ragkit_dir().join("mail");
+    let mut dir = base.join(&name);
+    let mut n = 2;
+    while dir.exists() {
+        dir = base.join(format!("{} ({})", name, n));
+        n += 1;
+    }
+
+    let output_dir = dir.clone();
+    let (paths, messages, threads, skipped) = tauri::async_runtime::spawn_blocking(move || {
+        let inputs = if source.is_dir() {
+            files::collect_files(&source, true, &formats)
+        } else if source.is_file() && formats.contains(&extension_of(&source)) {
+            vec![source.clone()]
+        } else {
+            return Err(format!(
+                "No {} file at {}",
+                formats.join(" or "),
+                source.display()
+            ));
+        };
+        let (mails, skipped) = read_messages(&inputs);
+        let messages = mails.len();
+        let threads = threads(mails);
+        let paths = write_threads(&dir, &threads).map_err(|e| e.to_string())?;
+        Ok((paths, messages, threads.len(), skipped))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    tracing::info!(
+        "Read {} messages in {} threads from {} ({} skipped)",
+        messages,
+        threads,
+        path,
+        skipped
+    );
+
+    let job_id = if paths.is_empty() {
+        None
+    } else {
+        Some(jobs::start_job(&app, kb_id, paths, jobs::IngestOptions::default()).await)
+    };
+    Ok(MailboxImport {
+        job_id,
+        messages,
+        threads,
+        skipped,
+        output_dir: output_dir.to_string_lossy().into_owned(),
+    })
+}
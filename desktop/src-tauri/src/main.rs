@@ -1,19 +1,95 @@
 // Prevents additional console window on Windows in release
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod answer_presets;
+mod archives;
 mod backend;
+mod backend_environments;
+mod capabilities;
+mod chunking;
+mod chunks;
+mod citations;
+mod clipboard;
+mod cloud_drive;
+mod collections;
 mod commands;
+mod config;
+mod connectors;
+mod conversation_archive;
+mod conversation_forks;
+mod conversation_models;
+mod conversation_pins;
+mod conversation_titles;
+mod data_dir;
+mod dedup;
+mod devtools;
+mod document_flags;
+mod document_tags;
+mod docx_export;
+mod extraction;
+mod failover;
+mod feedback;
+mod feeds;
+mod file_filters;
+mod file_types;
+mod files;
+mod guest;
+mod hooks;
+mod janitor;
+mod jobs;
+mod kb_history;
+mod kb_settings;
+mod kb_snapshots;
+mod kb_stats;
+mod kb_transfer;
+mod keybindings;
+mod latency_budget;
+mod lexical_index;
+mod mail;
+mod media;
+mod metadata;
+mod network;
+mod oauth;
+mod ocr;
+mod ollama;
+mod os_search;
+mod preferences;
+mod printing;
+mod prompt_templates;
+mod provenance;
+mod providers;
+mod proxy;
+mod read_aloud;
+mod recommendations;
+mod reembedding;
+mod response_format;
+mod retry_queue;
+mod s3;
+mod scheduler;
+mod settings_profiles;
+mod shell_logs;
+mod shortcuts;
+mod shutdown;
+mod similar_documents;
+mod source_files;
+mod sources;
+mod startup;
+mod store;
+mod streaming;
+mod templates;
+mod tray;
+mod updates;
+mod usage;
+mod watcher;
+mod windows;
 
-use tauri::Manager;
+use tauri::{Emitter, Manager};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
 
 /// Get the log directory path (~/.ragkit/logs/)
 fn get_log_dir() -> std::path::PathBuf {
-    #[cfg(target_os = "windows")]
-    let home = std::env::var("USERPROFILE").unwrap_or_else(|_| "C:\\".to_string());
-    #[cfg(not(target_os = "windows"))]
-    let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
-
-    std::path::PathBuf::from(home).join(".ragkit").join("logs")
+    store::ragkit_dir().join("logs")
 }
 
 /// Show a native error dialog on Windows (no dependencies needed)
@@ -47,52 +123,127 @@ fn show_error_dialog(_title: &str, message: &str) {
 }
 
 fn main() {
+    startup::mark_process_start();
+
     // Initialize file-based logging (visible even in release mode on Windows)
     let log_dir = get_log_dir();
     let _ = std::fs::create_dir_all(&log_dir);
 
     let file_appender = tracing_appender::rolling::daily(&log_dir, "ragkit-desktop.log");
 
-    tracing_subscriber::fmt()
-        .with_writer(file_appender)
-        .with_ansi(false)
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::filter::LevelFilter::from_level(
+            config::log_level(),
+        ))
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(file_appender)
+                .with_ansi(false),
+        )
+        // Recent entries stay queryable from the UI with query_logs
+        .with(shell_logs::ShellLogLayer)
         .init();
 
     tracing::info!("=== RAGKIT Desktop starting ===");
     tracing::info!("Version: {}", env!("CARGO_PKG_VERSION"));
+    if let Some(dir) = guest::data_dir() {
+        tracing::info!("Guest mode: using temporary data directory {}", dir.display());
+    }
+    config::log_startup();
     tracing::info!(
         "Log directory: {}",
         log_dir.display()
     );
 
     let result = tauri::Builder::default()
+        // A second launch (e.g. a ragkit:// link) hands its arguments to this instance
+        .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            tray::show_main_window(app);
+            os_search::handle_args(app, argv);
+        }))
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .plugin(
+            tauri_plugin_updater::Builder::new()
+                .pubkey(updates::UPDATER_PUBKEY.unwrap_or_default())
+                .build(),
+        )
         .setup(|app| {
+            startup::init(app.handle());
+            config::apply_theme(app.handle());
+
             // Start Python backend on app startup
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
-                if let Err(e) = backend::start_backend(&app_handle).await {
-                    tracing::error!("Failed to start backend: {}", e);
+                match backend::start_backend(&app_handle).await {
+                    Ok(()) => watcher::start_folder_sync(&app_handle).await,
+                    Err(e) => tracing::error!("Failed to start backend: {}", e),
                 }
             });
+
+            reembedding::start_scheduler(app.handle().clone());
+            scheduler::start_scheduler(app.handle().clone());
+            feeds::start_feed_poller(app.handle().clone());
+            retry_queue::start_retry_loop(app.handle().clone());
+            janitor::start_janitor();
+            backend::start_health_watchdog(app.handle().clone());
+            guest::apply_watermark(app.handle());
+            shortcuts::register_global_shortcuts(app.handle());
+            os_search::register_scheme();
+            os_search::handle_args(app.handle(), std::env::args());
+
+            if let Err(e) = tray::create_tray(app.handle()) {
+                tracing::error!("Failed to create system tray: {}", e);
+            }
             Ok(())
         })
         .on_window_event(|window, event| {
-            if let tauri::WindowEvent::CloseRequested { .. } = event {
-                // Stop backend when window closes
-                let app_handle = window.app_handle().clone();
-                tauri::async_runtime::spawn(async move {
-                    backend::stop_backend(&app_handle).await;
-                });
+            if let tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) = event {
+                // The frontend knows the active KB and answers with `ingest_dropped_paths`
+                let paths: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+                let _ = window.emit_to(window.label(), "files-dropped", paths);
+                return;
+            }
+
+            if let tauri::WindowEvent::Destroyed = event {
+                windows::forget(window.label());
+                return;
+            }
+
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                // Secondary windows (e.g. the document viewer) just close
+                if window.label() != "main" {
+                    return;
+                }
+
+                if preferences::load().minimize_to_tray {
+                    // Keep the backend running and hide to the tray
+                    api.prevent_close();
+                    let _ = window.hide();
+                    return;
+                }
+
+                // Keep the window open until in-progress work is handled and the
+                // backend has stopped, so no orphaned backend process is left behind
+                api.prevent_close();
+                if let Some(window) = window.app_handle().get_webview_window(window.label()) {
+                    tauri::async_runtime::spawn(shutdown::request_close(window));
+                }
             }
         })
         .invoke_handler(tauri::generate_handler![
             commands::health_check,
             commands::list_knowledge_bases,
             commands::create_knowledge_base,
+            commands::update_knowledge_base,
             commands::delete_knowledge_base,
+            commands::list_documents,
+            commands::delete_document,
             commands::add_documents,
+            commands::upload_document,
             commands::add_folder,
             commands::validate_folder,
             commands::list_conversations,
@@ -100,14 +251,21 @@ fn main() {
             commands::delete_conversation,
             commands::get_messages,
             commands::query,
+            commands::query_with_options,
+            commands::query_stream,
+            commands::cancel_query,
+            commands::verify_answer,
+            commands::retrieve_chunks,
+            commands::regenerate_answer,
+            commands::edit_message,
             commands::get_settings,
             commands::update_settings,
             commands::set_api_key,
             commands::has_api_key,
             commands::delete_api_key,
-            commands::test_api_key,
             commands::get_logs,
             commands::clear_logs,
+            shell_logs::query_logs,
             commands::analyze_wizard_profile,
             commands::detect_environment,
             // Ollama commands
@@ -115,24 +273,272 @@ fn main() {
             commands::list_ollama_models,
             commands::get_recommended_models,
             commands::get_ollama_embedding_models,
-            commands::pull_ollama_model,
+            ollama::pull_ollama_model,
+            ollama::cancel_ollama_pull,
             commands::delete_ollama_model,
             commands::start_ollama_service,
             commands::get_install_instructions,
+            // Re-embedding commands
+            reembedding::estimate_reembedding,
+            reembedding::schedule_reembedding,
+            reembedding::reembed_knowledge_base,
+            reembedding::pause_reembedding,
+            reembedding::resume_reembedding,
+            reembedding::cancel_reembedding,
+            reembedding::list_reembedding_jobs,
+            // Keyboard shortcut commands
+            keybindings::get_keyboard_shortcuts,
+            keybindings::check_keyboard_shortcuts,
+            keybindings::set_keyboard_shortcuts,
+            keybindings::reset_keyboard_shortcuts,
+            // Watch filter commands
+            file_filters::get_watch_filters,
+            file_filters::set_watch_filters,
+            file_filters::classify_paths,
+            // Failover commands
+            failover::get_failover_chains,
+            failover::set_failover_chains,
+            // Preferences commands
+            preferences::get_preferences,
+            preferences::update_preferences,
+            // Developer console commands
+            devtools::get_backend_endpoints,
+            devtools::dev_backend_request,
+            // Metadata commands
+            metadata::extract_document_metadata,
+            // Ingestion job commands
+            jobs::get_ingestion_job,
+            jobs::list_ingestion_jobs,
+            jobs::pause_ingestion,
+            jobs::resume_ingestion,
+            jobs::get_job_queue,
+            jobs::cancel_job,
+            commands::ingest_dropped_paths,
+            // Read-aloud commands
+            read_aloud::start_read_aloud,
+            read_aloud::pause_read_aloud,
+            read_aloud::resume_read_aloud,
+            read_aloud::stop_read_aloud,
+            // Update commands
+            updates::check_for_updates,
+            updates::download_update,
+            updates::install_update,
+            // Guest mode commands
+            guest::get_guest_mode,
+            // Document flag commands
+            document_flags::get_document_flags,
+            document_flags::set_document_flags,
+            // Backend commands
+            backend::get_backend_info,
+            backend::get_backend_status,
+            backend::connect_remote_backend,
+            backend::disconnect_remote_backend,
+            backend::get_backend_auth_status,
+            backend::set_backend_auth,
+            backend::clear_backend_auth,
+            // Ingestion retry commands
+            retry_queue::list_failed_documents,
+            retry_queue::retry_failed_documents,
+            // Startup commands
+            startup::get_startup_state,
+            // Conversation model commands
+            conversation_models::get_conversation_model,
+            conversation_models::set_conversation_model,
+            // Extraction commands
+            extraction::preview_document,
+            // Cleanup commands
+            janitor::get_cleanup_report,
+            // Knowledge base transfer commands
+            kb_transfer::export_knowledge_base,
+            kb_transfer::import_knowledge_base,
+            // Question template commands
+            templates::list_question_templates,
+            templates::save_question_template,
+            templates::delete_question_template,
+            templates::get_template_variables,
+            templates::resolve_question_template,
+            // Printing commands
+            printing::print_conversation,
+            printing::print_document,
+            // OS search and deep link commands
+            os_search::take_pending_deep_link,
+            os_search::clear_search_stubs,
+            // Conversation export commands
+            docx_export::export_conversation_docx,
+            // Quick-ask commands
+            shortcuts::get_quick_ask_kb,
+            shortcuts::quick_query,
+            shortcuts::hide_quick_ask,
+            // Conversation window commands
+            windows::open_conversation_window,
+            windows::list_conversation_windows,
+            windows::get_window_conversation,
+            // Knowledge base history commands
+            kb_history::get_kb_history,
+            kb_history::get_kb_version_at,
+            kb_history::get_kb_state_at,
+            // Shutdown commands
+            shutdown::get_active_work,
+            // Folder sync commands
+            watcher::enable_folder_sync,
+            watcher::disable_folder_sync,
+            watcher::resync_folder,
+            watcher::list_synced_folders,
+            // Instant results commands
+            lexical_index::search_lexical_index,
+            lexical_index::rebuild_lexical_index,
+            // Answer preset commands
+            answer_presets::list_answer_presets,
+            answer_presets::save_answer_preset,
+            answer_presets::delete_answer_preset,
+            // Backend capability commands
+            capabilities::get_backend_capabilities,
+            // Knowledge base collection commands
+            collections::list_kb_collections,
+            collections::create_kb_collection,
+            collections::update_kb_collection,
+            collections::move_kb_collection,
+            collections::delete_kb_collection,
+            collections::move_knowledge_base,
+            // Document recommendation commands
+            recommendations::recommend_documents,
+            // Feedback commands
+            feedback::submit_feedback,
+            feedback::get_feedback_summary,
+            // Hook commands
+            hooks::list_hooks,
+            hooks::save_hook,
+            hooks::delete_hook,
+            hooks::test_hook,
+            hooks::list_hook_runs,
+            // Source provenance commands
+            provenance::verify_source_integrity,
+            // Source file commands
+            source_files::open_source,
+            source_files::reveal_in_folder,
+            // Conversation title commands
+            conversation_titles::generate_conversation_title,
+            conversation_titles::rename_conversation,
+            // Backend environment commands
+            backend_environments::list_backend_environments,
+            backend_environments::save_backend_environment,
+            backend_environments::delete_backend_environment,
+            backend_environments::select_backend_environment,
+            // Conversation archive commands
+            conversation_archive::archive_conversation,
+            conversation_archive::unarchive_conversation,
+            conversation_pins::pin_conversation,
+            // Knowledge base snapshot commands
+            kb_snapshots::export_kb_snapshot,
+            kb_snapshots::verify_snapshot,
+            // Knowledge base settings commands
+            kb_settings::get_kb_settings,
+            kb_settings::update_kb_settings,
+            // Usage commands
+            usage::get_usage_stats,
+            // Conversation fork commands
+            conversation_forks::fork_conversation,
+            // Prompt template commands
+            prompt_templates::list_prompt_templates,
+            prompt_templates::create_prompt_template,
+            prompt_templates::update_prompt_template,
+            prompt_templates::delete_prompt_template,
+            // Re-indexing schedule commands
+            scheduler::create_schedule,
+            scheduler::list_schedules,
+            scheduler::delete_schedule,
+            // Clipboard commands
+            clipboard::add_clipboard_content,
+            clipboard::read_clipboard_text,
+            // File type commands
+            file_types::get_supported_file_types,
+            file_types::register_custom_extension,
+            file_types::remove_custom_extension,
+            // OCR commands
+            ocr::ocr_status,
+            // Media commands
+            media::transcribe_media,
+            media::add_media,
+            // Mail commands
+            mail::add_mailbox,
+            // Connector commands
+            connectors::list_connectors,
+            connectors::configure_connector,
+            connectors::sync_connector,
+            connectors::remove_connector,
+            // Cloud drive commands
+            cloud_drive::link_cloud_drive,
+            cloud_drive::unlink_cloud_drive,
+            cloud_drive::sync_cloud_folder,
+            // S3 commands
+            s3::add_s3_bucket,
+            // Feed commands
+            feeds::subscribe_feed,
+            feeds::list_feeds,
+            feeds::unsubscribe_feed,
+            // KB statistics commands
+            kb_stats::get_kb_stats,
+            // Chunk commands
+            chunks::list_chunks,
+            chunks::update_chunk,
+            chunks::exclude_chunk,
+            // Document tag commands
+            document_tags::set_document_tags,
+            // Similar document commands
+            similar_documents::find_similar_documents,
+            // Citation commands
+            citations::export_citations,
+            // Network commands
+            network::get_network_status,
+            // Proxy commands
+            proxy::get_proxy_status,
+            // LLM provider commands
+            providers::list_llm_providers,
+            providers::add_custom_provider,
+            providers::remove_custom_provider,
+            providers::test_api_key,
+            // Settings profile commands
+            settings_profiles::list_settings_profiles,
+            settings_profiles::save_settings_profile,
+            settings_profiles::apply_settings_profile,
+            settings_profiles::delete_settings_profile,
+            settings_profiles::export_settings_profile,
+            settings_profiles::import_settings_profile,
+            // Config commands
+            config::get_effective_config,
+            // Data directory commands
+            data_dir::get_data_dir,
+            data_dir::set_data_dir,
         ])
-        .run(tauri::generate_context!());
-
-    if let Err(e) = result {
-        let error_msg = format!(
-            "RAGKIT Desktop failed to start:\n\n{}\n\n\
-            Possible fixes:\n\
-            - Install Microsoft Edge WebView2 Runtime\n\
-            - Install Visual C++ Redistributable (x64)\n\
-            - Check logs at: {}",
-            e,
-            log_dir.display()
-        );
-        tracing::error!("{}", error_msg);
-        show_error_dialog("RAGKIT Desktop - Startup Error", &error_msg);
+        .build(tauri::generate_context!());
+
+    match result {
+        Ok(app) => app.run(|app_handle, event| match event {
+            tauri::RunEvent::Exit if guest::is_active() => {
+                // The backend must release its files before the guest data is erased
+                tauri::async_runtime::block_on(backend::stop_backend(app_handle));
+                guest::wipe();
+            }
+            #[cfg(target_os = "macos")]
+            tauri::RunEvent::Opened { urls } => {
+                for url in urls {
+                    os_search::open_link(app_handle, url.as_str());
+                }
+            }
+            _ => {}
+        }),
+        Err(e) => {
+            let error_msg = format!(
+                "RAGKIT Desktop failed to start:\n\n{}\n\n\
+                Possible fixes:\n\
+                - Install Microsoft Edge WebView2 Runtime\n\
+                - Install Visual C++ Redistributable (x64)\n\
+                - Check logs at: {}",
+                e,
+                log_dir.display()
+            );
+            tracing::error!("{}", error_msg);
+            show_error_dialog("RAGKIT Desktop - Startup Error", &error_msg);
+        }
     }
 }
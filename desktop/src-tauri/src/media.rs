@@ -0,0 +1,257 @@
+//! Audio and video transcription.
+//!
+//! Media files are transcribed by the backend's Whisper endpoint, which streams the
+//! transcript as server-sent events: the media's duration first, then one event per
+//! segment. Each segment advances the `transcription-progress` event by the time it
+//! covers. `transcribe_media` returns the transcript for review and keeps it under
+//! `~/.ragkit/tmp/transcripts/`; `add_media` ingests a reviewed transcript (or a fresh
+//! one) as a Markdown document with timestamps, saved under `~/.ragkit/transcripts/`.
+
+use crate::backend::backend_send;
+use crate::capabilities::{self, Feature};
+use crate::files::extension_of;
+use crate::streaming::SseReader;
+use crate::{jobs, store};
+use anyhow::{anyhow, Result};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+
+/// Audio and video files the backend can transcribe.
+pub const MEDIA_EXTENSIONS: &[&str] = &[
+    "mp3", "wav", "m4a", "ogg", "flac", "aac", "opus", "mp4", "mkv", "mov", "webm", "avi",
+];
+
+/// Segments closer than this are joined into one paragraph, in seconds.
+const PARAGRAPH_GAP_SECS: f64 = 2.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptSegment {
+    /// Offsets in the media, in seconds
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transcript {
+    pub id: String,
+    pub path: String,
+    /// Spoken language, as requested or detected by the backend
+    pub language: Option<String>,
+    pub duration_secs: f64,
+    pub segments: Vec<TranscriptSegment>,
+    pub created_at: String,
+}
+
+/// Payload of the `transcription-progress` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptionProgressEvent {
+    pub transcript_id: String,
+    pub path: String,
+    pub processed_secs: f64,
+    pub duration_secs: f64,
+    pub percent: f64,
+}
+
+/// A single server-sent event from `/api/transcribe`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum TranscriptionStreamEvent {
+    Info {
+        duration: f64,
+        language: Option<String>,
+    },
+    Segment(TranscriptSegment),
+    Done,
+    Error {
+        message: String,
+    },
+}
+
+/// Whether a file is audio or video the backend can transcribe.
+pub fn is_media(path: &Path) -> bool {
+    MEDIA_EXTENSIONS.contains(&extension_of(path).as_str())
+}
+
+fn transcripts_dir() -> PathBuf {
+    store::tmp_dir().join("transcripts")
+}
+
+fn transcript_path(id: &str) -> Result<PathBuf, String> {
+    // Ids come from the UI; only accept the ones we generate
+    uuid::Uuid::parse_str(id).map_err(|_| format!("Unknown transcript: {}", id))?;
+    Ok(transcripts_dir().join(format!("{}.json", id)))
+}
+
+fn emit_progress(app: &AppHandle, transcript: &Transcript) {
+    let processed = transcript.segments.last().map_or(0.0, |s| s.end);
+    let percent = if transcript.duration_secs > 0.0 {
+        (processed * 100.0 / transcript.duration_secs).min(100.0)
+    } else {
+        0.0
+    };
+    let _ = app.emit(
+        "transcription-progress",
+        TranscriptionProgressEvent {
+            transcript_id: transcript.id.clone(),
+            path: transcript.path.clone(),
+            processed_secs: processed,
+            duration_secs: transcript.duration_secs,
+            percent,
+        },
+    );
+}
+
+/// Transcribe a media file, emitting progress as segments arrive.
+async fn transcribe(app: &AppHandle, path: &str, language: Option<String>) -> Result<Transcript> {
+    let response = backend_send(
+        Method::POST,
+        "/api/transcribe",
+        Some(json!({ "path": path, "language": language })),
+    )
+    .await?;
+    let mut transcript = Transcript {
+        id: uuid::Uuid::new_v4().to_string(),
+        path: path.to_string(),
+        language,
+        duration_secs: 0.0,
+        segments: Vec::new(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    let mut events = SseReader::new(response);
+    while let Some(data) = events
+        .next_event()
+        .await
+        .map_err(|e| anyhow!("Transcription interrupted: {}", e))?
+    {
+        let event: TranscriptionStreamEvent = serde_json::from_str(&data)
+            .map_err(|e| anyhow!("Invalid transcription event: {}", e))?;
+        match event {
+            TranscriptionStreamEvent::Info { duration, language } => {
+                transcript.duration_secs = duration;
+                transcript.language = language.or(transcript.language);
+            }
+            TranscriptionStreamEvent::Segment(segment) => {
+                transcript.segments.push(segment);
+            }
+            TranscriptionStreamEvent::Done => return Ok(transcript),
+            TranscriptionStreamEvent::Error { message } => return Err(anyhow!(message)),
+        }
+        emit_progress(app, &transcript);
+    }
+
+    Err(anyhow!("Stream ended before the transcript was complete"))
+}
+
+fn timestamp(secs: f64) -> String {
+    let secs = secs.max(0.0) as u64;
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Markdown document of a transcript: a paragraph per stretch of speech, each starting
+/// with its timestamp so answers can point back into the media.
+fn to_markdown(transcript: &Transcript, title: &str) -> String {
+    let mut markdown = format!("# {}\n\n", title);
+    let mut previous_end: Option<f64> = None;
+    for segment in &transcript.segments {
+        let text = segment.text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        match previous_end {
+            Some(end) if segment.start - end < PARAGRAPH_GAP_SECS => markdown.push(' '),
+            Some(_) => markdown.push_str(&format!("\n\n[{}] ", timestamp(segment.start))),
+            None => markdown.push_str(&format!("[{}] ", timestamp(segment.start))),
+        }
+        markdown.push_str(text);
+        previous_end = Some(segment.end);
+    }
+    markdown.push('\n');
+    markdown
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Transcribe an audio or video file for review before adding it
+///
+/// Progress is reported through `transcription-progress` events.
+#[tauri::command]
+pub async fn transcribe_media(
+    app: AppHandle,
+    path: String,
+    language: Option<String>,
+) -> Result<Transcript, String> {
+    capabilities::require(Feature::Transcription)?;
+    if !Path::new(&path).is_file() {
+        return Err(format!("File not found: {}", path));
+    }
+    if !is_media(Path::new(&path)) {
+        return Err(format!("Not an audio or video file: {}", path));
+    }
+
+    let transcript = transcribe(&app, &path, language)
+        .await
+        .map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(transcripts_dir()).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string(&transcript).map_err(|e| e.to_string())?;
+    std::fs::write(transcript_path(&transcript.id)?, json).map_err(|e| e.to_string())?;
+    Ok(transcript)
+}
+
+/// Add the transcript of an audio or video file to a knowledge base
+///
+/// Uses the transcript from `transcribe_media` when given its id, otherwise transcribes
+/// the file first. Returns an ingestion job id.
+#[tauri::command]
+pub async fn add_media(
+    app: AppHandle,
+    kb_id: String,
+    path: String,
+    language: Option<String>,
+    transcript_id: Option<String>,
+) -> Result<String, String> {
+    let transcript = match &transcript_id {
+        Some(id) => {
+            let json = std::fs::read_to_string(transcript_path(id)?)
+                .map_err(|_| format!("Unknown transcript: {}", id))?;
+            let transcript: Transcript = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+            if transcript.path != path {
+                return Err(format!("Transcript {} is not a transcript of {}", id, path));
+            }
+            transcript
+        }
+        None => transcribe_media(app.clone(), path.clone(), language).await?,
+    };
+    if transcript.segments.iter().all(|s| s.text.trim().is_empty()) {
+        return Err("No speech was found in the file".to_string());
+    }
+
+    let source = Path::new(&path);
+    let title = source
+        .file_name()
+        .map_or_else(|| path.clone(), |n| n.to_string_lossy().into_owned());
+    let stem = source.file_stem().map_or_else(
+        || "Transcript".to_string(),
+        |s| s.to_string_lossy().into_owned(),
+    );
+    let dir = store::ragkit_dir().join("transcripts");
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let mut target = dir.join(format!("{}.md", stem));
+    let mut n = 2;
+    while target.exists() {
+        target = dir.join(format!("{} ({}).md", stem, n));
+        n += 1;
+    }
+    std::fs::write(&target, to_markdown(&transcript, &title)).map_err(|e| e.to_string())?;
+    let _ = std::fs::remove_file(transcript_path(&transcript.id)?);
+    tracing::info!("Saved the transcript of {} to {}", path, target.display());
+
+    let paths = vec![target.to_string_lossy().into_owned()];
+    Ok(jobs::start_job(&app, kb_id, paths, jobs::IngestOptions::default()).await)
+}
@@ -0,0 +1,390 @@
+//! Document metadata enrichment.
+//!
+//! Extracts human-readable titles, authors, and creation dates from files before they are
+//! sent to the backend, so document lists and citations can show "Annual Report 2023"
+//! instead of `final_v7 (3).pdf`. Only file headers are read, keeping this cheap enough
+//! to run on every ingested file.
+
+use crate::files::extension_of;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// How much of a file is inspected when looking for metadata.
+const HEADER_BYTES: u64 = 256 * 1024;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DocumentMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub created_at: Option<String>,
+}
+
+impl DocumentMetadata {
+    fn is_empty(&self) -> bool {
+        self.title.is_none() && self.author.is_none() && self.created_at.is_none()
+    }
+}
+
+fn read_head(path: &Path) -> Option<Vec<u8>> {
+    let file = std::fs::File::open(path).ok()?;
+    let mut buffer = Vec::new();
+    file.take(HEADER_BYTES).read_to_end(&mut buffer).ok()?;
+    Some(buffer)
+}
+
+/// PDF Info dictionaries usually live at the end of the file, so read the tail too.
+fn read_tail(path: &Path) -> Option<Vec<u8>> {
+    use std::io::{Seek, SeekFrom};
+
+    let mut file = std::fs::File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(HEADER_BYTES)))
+        .ok()?;
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer).ok()?;
+    Some(buffer)
+}
+
+fn clean(value: &str) -> Option<String> {
+    let value = value.trim().trim_matches('"').trim_matches('\'').trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+// ============================================================================
+// PDF
+// ============================================================================
+
+/// Decode a PDF string object: `(literal)` or `<hex>`, possibly UTF-16BE with a BOM.
+fn decode_pdf_string(raw: &[u8]) -> Option<String> {
+    let bytes: Vec<u8> = if raw.first() == Some(&b'<') {
+        let hex: Vec<u8> = raw[1..raw.len() - 1]
+            .iter()
+            .copied()
+            .filter(|b| b.is_ascii_hexdigit())
+            .collect();
+        hex.chunks(2)
+            .filter_map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+            .collect()
+    } else {
+        let mut out = Vec::new();
+        let mut iter = raw[1..raw.len() - 1].iter().copied();
+        while let Some(b) = iter.next() {
+            if b == b'\\' {
+                match iter.next() {
+                    Some(b'n') => out.push(b'\n'),
+                    Some(b'r') => out.push(b'\r'),
+                    Some(b't') => out.push(b'\t'),
+                    Some(other) => out.push(other),
+                    None => {}
+                }
+            } else {
+                out.push(b);
+            }
+        }
+        out
+    };
+
+    if bytes.starts_with(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = bytes[2..]
+            .chunks_exact(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]))
+            .collect();
+        clean(&String::from_utf16_lossy(&units))
+    } else {
+        clean(&String::from_utf8_lossy(&bytes))
+    }
+}
+
+fn pdf_field(data: &[u8], key: &str) -> Option<String> {
+    // Matches `/Title (..)` or `/Title <..>`; nested parentheses are rare enough in
+    // metadata to be ignored.
+    let pattern = format!(r"/{}\s*(\((?:\\.|[^\\)])*\)|<[0-9A-Fa-f\s]*>)", key);
+    let re = regex::bytes::Regex::new(&pattern).ok()?;
+    let capture = re.captures_iter(data).last()?;
+    decode_pdf_string(capture.get(1)?.as_bytes())
+}
+
+/// Convert a PDF date (`D:20230415120000+02'00'`) to `2023-04-15`.
+fn pdf_date(raw: &str) -> Option<String> {
+    // Checked as bytes: the decoded string may hold multibyte characters
+    let digits = raw.trim_start_matches("D:").as_bytes().get(..8)?;
+    if !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    let digits = std::str::from_utf8(digits).ok()?;
+    Some(format!("{}-{}-{}", &digits[..4], &digits[4..6], &digits[6..8]))
+}
+
+fn extract_pdf(path: &Path) -> DocumentMetadata {
+    let mut data = read_head(path).unwrap_or_default();
+    data.extend(read_tail(path).unwrap_or_default());
+
+    DocumentMetadata {
+        title: pdf_field(&data, "Title"),
+        author: pdf_field(&data, "Author"),
+        created_at: pdf_field(&data, "CreationDate").and_then(|d| pdf_date(&d)),
+    }
+}
+
+// ============================================================================
+// HTML
+// ============================================================================
+
+fn extract_html(path: &Path) -> DocumentMetadata {
+    static TITLE_RE: OnceLock<Regex> = OnceLock::new();
+    static AUTHOR_RE: OnceLock<Regex> = OnceLock::new();
+    static DATE_RE: OnceLock<Regex> = OnceLock::new();
+
+    let Some(head) = read_head(path) else {
+        return DocumentMetadata::default();
+    };
+    let html = String::from_utf8_lossy(&head);
+
+    let title_re = TITLE_RE.get_or_init(|| Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap());
+    let author_re = AUTHOR_RE.get_or_init(|| {
+        Regex::new(r#"(?is)<meta\s+name=["']author["']\s+content=["']([^"']*)["']"#).unwrap()
+    });
+    let date_re = DATE_RE.get_or_init(|| {
+        Regex::new(
+            r#"(?is)<meta\s+(?:name|property)=["'](?:date|article:published_time|dcterms\.created)["']\s+content=["']([^"']*)["']"#,
+        )
+        .unwrap()
+    });
+
+    let capture = |re: &Regex| {
+        re.captures(&html)
+            .and_then(|c| c.get(1))
+            .and_then(|m| clean(m.as_str()))
+    };
+
+    DocumentMetadata {
+        title: capture(title_re).map(|t| t.split_whitespace().collect::<Vec<_>>().join(" ")),
+        author: capture(author_re),
+        created_at: capture(date_re).map(|d| d.chars().take(10).collect()),
+    }
+}
+
+// ============================================================================
+// Markdown
+// ============================================================================
+
+fn extract_markdown(path: &Path) -> DocumentMetadata {
+    let Some(head) = read_head(path) else {
+        return DocumentMetadata::default();
+    };
+    let text = String::from_utf8_lossy(&head);
+    let mut metadata = DocumentMetadata::default();
+
+    // YAML frontmatter: only flat `key: value` lines are needed here.
+    let mut lines = text.lines();
+    if lines.next().map(str::trim) == Some("---") {
+        for line in lines.by_ref() {
+            if line.trim() == "---" {
+                break;
+            }
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            match key.trim().to_lowercase().as_str() {
+                "title" => metadata.title = clean(value),
+                "author" | "authors" => metadata.author = clean(value),
+                "date" | "created" => metadata.created_at = clean(value),
+                _ => {}
+            }
+        }
+    }
+
+    // Otherwise fall back to the first top-level heading.
+    if metadata.title.is_none() {
+        metadata.title = text
+            .lines()
+            .find_map(|l| l.strip_prefix("# "))
+            .and_then(clean);
+    }
+
+    metadata
+}
+
+// ============================================================================
+// Images (EXIF)
+// ============================================================================
+
+struct Tiff<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl Tiff<'_> {
+    fn u16_at(&self, offset: usize) -> Option<u16> {
+        let bytes = self.data.get(offset..offset + 2)?;
+        Some(if self.little_endian {
+            u16::from_le_bytes([bytes[0], bytes[1]])
+        } else {
+            u16::from_be_bytes([bytes[0], bytes[1]])
+        })
+    }
+
+    fn u32_at(&self, offset: usize) -> Option<u32> {
+        let bytes = self.data.get(offset..offset + 4)?;
+        let array = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        Some(if self.little_endian {
+            u32::from_le_bytes(array)
+        } else {
+            u32::from_be_bytes(array)
+        })
+    }
+
+    /// Read the IFD at `offset` into a map of tag -> ASCII value, plus the raw
+    /// value of pointer tags.
+    fn read_ifd(&self, offset: usize) -> HashMap<u16, String> {
+        let mut values = HashMap::new();
+        let Some(count) = self.u16_at(offset) else {
+            return values;
+        };
+
+        for i in 0..count as usize {
+            let entry = offset + 2 + i * 12;
+            let (Some(tag), Some(kind), Some(len)) = (
+                self.u16_at(entry),
+                self.u16_at(entry + 2),
+                self.u32_at(entry + 4),
+            ) else {
+                break;
+            };
+
+            match kind {
+                // ASCII
+                2 => {
+                    let len = len as usize;
+                    let start = if len <= 4 {
+                        entry + 8
+                    } else {
+                        self.u32_at(entry + 8).unwrap_or(0) as usize
+                    };
+                    if let Some(bytes) = self.data.get(start..start + len) {
+                        let text = String::from_utf8_lossy(bytes);
+                        if let Some(value) = clean(text.trim_end_matches('\0')) {
+                            values.insert(tag, value);
+                        }
+                    }
+                }
+                // LONG (used for the Exif sub-IFD pointer)
+                4 => {
+                    if let Some(value) = self.u32_at(entry + 8) {
+                        values.insert(tag, value.to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        values
+    }
+}
+
+const TAG_IMAGE_DESCRIPTION: u16 = 0x010E;
+const TAG_ARTIST: u16 = 0x013B;
+const TAG_DATE_TIME: u16 = 0x0132;
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+
+/// Convert an EXIF date (`2023:04:15 12:00:00`) to `2023-04-15`.
+fn exif_date(raw: &str) -> Option<String> {
+    let date = raw.get(..10)?;
+    Some(date.replace(':', "-"))
+}
+
+fn extract_exif(path: &Path) -> DocumentMetadata {
+    let Some(data) = read_head(path) else {
+        return DocumentMetadata::default();
+    };
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return DocumentMetadata::default();
+    }
+
+    // Walk JPEG segments until the APP1 Exif segment.
+    let mut pos = 2;
+    while pos + 4 <= data.len() && data[pos] == 0xFF {
+        let marker = data[pos + 1];
+        let len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let segment = pos + 4;
+
+        if marker == 0xE1 && data.get(segment..segment + 6) == Some(b"Exif\0\0") {
+            let Some(tiff_data) = data.get(segment + 6..pos + 2 + len) else {
+                break;
+            };
+            let tiff = Tiff {
+                data: tiff_data,
+                little_endian: tiff_data.starts_with(b"II"),
+            };
+            let Some(ifd0_offset) = tiff.u32_at(4) else {
+                break;
+            };
+
+            let ifd0 = tiff.read_ifd(ifd0_offset as usize);
+            let exif = ifd0
+                .get(&TAG_EXIF_IFD)
+                .and_then(|o| o.parse::<usize>().ok())
+                .map(|o| tiff.read_ifd(o))
+                .unwrap_or_default();
+
+            return DocumentMetadata {
+                title: ifd0.get(&TAG_IMAGE_DESCRIPTION).cloned(),
+                author: ifd0.get(&TAG_ARTIST).cloned(),
+                created_at: exif
+                    .get(&TAG_DATE_TIME_ORIGINAL)
+                    .or_else(|| ifd0.get(&TAG_DATE_TIME))
+                    .and_then(|d| exif_date(d)),
+            };
+        }
+
+        // Start of scan: no metadata past this point.
+        if marker == 0xDA {
+            break;
+        }
+        pos += 2 + len;
+    }
+
+    DocumentMetadata::default()
+}
+
+/// Extract metadata from a file based on its extension.
+pub fn extract(path: &Path) -> DocumentMetadata {
+    match extension_of(path).as_str() {
+        "pdf" => extract_pdf(path),
+        "html" | "htm" => extract_html(path),
+        "md" | "markdown" => extract_markdown(path),
+        "jpg" | "jpeg" => extract_exif(path),
+        "odt" | "rtf" | "epub" => crate::extraction::extract_metadata(path),
+        _ => DocumentMetadata::default(),
+    }
+}
+
+/// Extract metadata for many files, keyed by path, omitting files with nothing found.
+pub fn extract_all<'a>(
+    paths: impl IntoIterator<Item = &'a str>,
+) -> HashMap<String, DocumentMetadata> {
+    paths
+        .into_iter()
+        .filter_map(|p| {
+            let metadata = extract(Path::new(p));
+            (!metadata.is_empty()).then(|| (p.to_string(), metadata))
+        })
+        .collect()
+}
+
+/// Extract metadata from a single file (used for previews)
+#[tauri::command]
+pub async fn extract_document_metadata(path: String) -> Result<DocumentMetadata, String> {
+    tauri::async_runtime::spawn_blocking(move || extract(Path::new(&path)))
+        .await
+        .map_err(|e| e.to_string())
+}
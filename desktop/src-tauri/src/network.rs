@@ -0,0 +1,249 @@
+//! Offline mode.
+//!
+//! With `offline_mode` on, the shell guarantees nothing leaves the machine. Every backend
+//! request to an endpoint that calls the LLM, embedding, reranking or transcription
+//! providers (queries, retrieval, regeneration, verification, titles, ingestion,
+//! re-embedding, transcription) is refused unless those providers run locally (Ollama,
+//! ONNX, sentence-transformers, custom providers on localhost); `backend::check_offline`
+//! runs before every backend request, so no command can bypass it. Offline mode can't be
+//! turned on while the settings use a cloud provider, settings can't switch to one,
+//! cloud fallbacks are skipped, and the shell's own outbound requests (remote backend,
+//! connectors, cloud drives, S3, feeds, updates) are blocked unless they target the
+//! machine itself. `get_network_status` reports whether the internet is reachable,
+//! without probing it in offline mode, and which settings offline mode blocks.
+
+use crate::backend;
+use crate::commands::{self, Settings};
+use crate::failover::ProviderTarget;
+use crate::{preferences, providers};
+use serde::Serialize;
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// Providers that run on the machine; every other provider is a cloud service.
+const LOCAL_PROVIDERS: &[&str] = &[
+    "ollama",
+    "onnx_local",
+    "sentence_transformers",
+    "huggingface",
+    "local",
+    "none",
+];
+/// Backend endpoints that call the LLM, embedding, reranking or transcription providers,
+/// with path parameters written `{}`.
+const PROVIDER_ENDPOINTS: &[(&str, &str)] = &[
+    ("POST", "/api/query"),
+    ("POST", "/api/query/stream"),
+    ("POST", "/api/retrieve"),
+    ("POST", "/api/conversations/{}/messages/{}/regenerate"),
+    ("PUT", "/api/conversations/{}/messages/{}"),
+    ("POST", "/api/messages/{}/verify"),
+    ("POST", "/api/conversations/{}/title"),
+    ("POST", "/api/conversations/{}/recommendations"),
+    ("POST", "/api/knowledge-bases/{}/documents"),
+    ("POST", "/api/knowledge-bases/{}/documents/upload"),
+    ("POST", "/api/knowledge-bases/{}/folders"),
+    ("POST", "/api/knowledge-bases/{}/reembed"),
+    ("POST", "/api/transcribe"),
+];
+/// Address probed to tell whether the internet is reachable (Cloudflare DNS over TLS
+/// port, which answers without a DNS lookup).
+const PROBE_ADDRESS: &str = "1.1.1.1:443";
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkStatus {
+    pub offline_mode: bool,
+    /// Not probed in offline mode
+    pub internet_reachable: Option<bool>,
+    /// Whether the backend runs on this machine
+    pub backend_local: bool,
+    /// Settings that offline mode refuses, e.g. `llm_provider: openai`
+    pub blocked: Vec<String>,
+}
+
+pub fn is_offline() -> bool {
+    preferences::load().offline_mode
+}
+
+pub fn is_local_provider(provider: &str) -> bool {
+    let provider = provider.trim().to_lowercase();
+    LOCAL_PROVIDERS.contains(&provider.as_str()) || providers::is_local_custom(&provider)
+}
+
+/// Whether a URL points at the machine itself.
+pub fn is_local_url(url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    match url.host_str() {
+        Some(host) => {
+            let host = host.trim_start_matches('[').trim_end_matches(']');
+            host == "localhost"
+                || host.ends_with(".localhost")
+                || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+        }
+        None => false,
+    }
+}
+
+/// Fail when offline mode is on, for an action that needs the internet.
+pub fn ensure_online(action: &str) -> Result<(), String> {
+    if is_offline() {
+        return Err(format!("{} is blocked in offline mode", action));
+    }
+    Ok(())
+}
+
+/// Fail when offline mode is on and `url` isn't on this machine.
+pub fn check_url(url: &str) -> Result<(), String> {
+    if is_offline() && !is_local_url(url) {
+        return Err(format!(
+            "Offline mode blocks requests to {}: only localhost is allowed",
+            url
+        ));
+    }
+    Ok(())
+}
+
+/// Settings that send data to a cloud provider, as `field: provider`.
+fn cloud_settings(settings: &Settings) -> Vec<String> {
+    let mut providers = vec![
+        ("llm_provider", &settings.llm_provider),
+        ("embedding_provider", &settings.embedding_provider),
+    ];
+    if settings.retrieval_rerank_enabled {
+        providers.push((
+            "retrieval_rerank_provider",
+            &settings.retrieval_rerank_provider,
+        ));
+    }
+    providers
+        .into_iter()
+        .filter(|(_, provider)| !is_local_provider(provider))
+        .map(|(field, provider)| format!("{}: {}", field, provider))
+        .collect()
+}
+
+/// Refuse settings that use a cloud provider in offline mode.
+pub fn check_settings(settings: &Settings) -> Result<(), String> {
+    if !is_offline() {
+        return Ok(());
+    }
+    let blocked = cloud_settings(settings);
+    if !blocked.is_empty() {
+        return Err(format!(
+            "Offline mode blocks cloud providers ({})",
+            blocked.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Whether a backend request calls the LLM, embedding or transcription providers.
+pub fn uses_providers(method: &reqwest::Method, path: &str) -> bool {
+    let path = path.split('?').next().unwrap_or(path);
+    let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+    PROVIDER_ENDPOINTS.iter().any(|(m, pattern)| {
+        let pattern: Vec<&str> = pattern.split('/').collect();
+        *m == method.as_str()
+            && pattern.len() == segments.len()
+            && pattern
+                .iter()
+                .zip(&segments)
+                .all(|(p, s)| *p == "{}" || p == s)
+    })
+}
+
+/// Refuse a backend request that would reach a cloud provider in offline mode, given
+/// the current settings and the request body, which may pick other providers.
+pub fn check_request(
+    mut settings: Settings,
+    body: Option<&serde_json::Value>,
+) -> Result<(), String> {
+    if backend::is_remote() && !is_local_url(&backend::get_backend_url()) {
+        return Err("Offline mode blocks requests to a remote backend".to_string());
+    }
+    if let Some(body) = body {
+        if let Some(provider) = body["llm_provider"].as_str() {
+            settings.llm_provider = provider.to_string();
+        }
+        if let Some(rerank) = body["rerank_enabled"].as_bool() {
+            settings.retrieval_rerank_enabled = rerank;
+        }
+        let fallbacks: Vec<ProviderTarget> =
+            serde_json::from_value(body["embedding_fallbacks"].clone()).unwrap_or_default();
+        if let Some(target) = fallbacks.iter().find(|t| !is_local_provider(&t.provider)) {
+            return Err(format!(
+                "Offline mode blocks cloud providers (embedding fallback: {})",
+                target.provider
+            ));
+        }
+    }
+    check_settings(&settings)
+}
+
+/// Refuse to turn offline mode on while the settings or the backend aren't local.
+pub async fn check_can_enable() -> Result<(), String> {
+    if backend::is_remote() && !is_local_url(&backend::get_backend_url()) {
+        return Err("Disconnect from the remote backend before turning on offline mode".into());
+    }
+    let settings = commands::get_settings().await?;
+    let blocked = cloud_settings(&settings);
+    if !blocked.is_empty() {
+        return Err(format!(
+            "Switch to local providers before turning on offline mode ({})",
+            blocked.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Drop the cloud providers of a fallback chain in offline mode.
+pub fn local_targets(targets: &[ProviderTarget]) -> Vec<ProviderTarget> {
+    let offline = is_offline();
+    targets
+        .iter()
+        .filter(|t| !offline || is_local_provider(&t.provider))
+        .cloned()
+        .collect()
+}
+
+async fn internet_reachable() -> bool {
+    tokio::time::timeout(PROBE_TIMEOUT, tokio::net::TcpStream::connect(PROBE_ADDRESS))
+        .await
+        .is_ok_and(|connected| connected.is_ok())
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Get whether offline mode is on, whether the internet is reachable, and what offline
+/// mode blocks in the current settings
+#[tauri::command]
+pub async fn get_network_status() -> Result<NetworkStatus, String> {
+    let offline_mode = is_offline();
+    let internet_reachable = if offline_mode {
+        None
+    } else {
+        Some(internet_reachable().await)
+    };
+    let backend_local = !backend::is_remote() || is_local_url(&backend::get_backend_url());
+    let mut blocked = match commands::get_settings().await {
+        Ok(settings) => cloud_settings(&settings),
+        Err(e) => {
+            tracing::debug!("Could not read the settings: {}", e);
+            Vec::new()
+        }
+    };
+    if !backend_local {
+        blocked.insert(0, format!("backend: {}", backend::get_backend_url()));
+    }
+    Ok(NetworkStatus {
+        offline_mode,
+        internet_reachable,
+        backend_local,
+        blocked,
+    })
+}
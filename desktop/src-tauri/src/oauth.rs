@@ -0,0 +1,265 @@
+//! OAuth 2.0 device authorization flow for Google and Microsoft accounts.
+//!
+//! The device flow suits a desktop app without a redirect server: the user opens the
+//! verification page in any browser and types the code shown by the app, while the app
+//! polls the token endpoint until the grant is approved, denied, or expires. Client ids
+//! come from the preferences, or from the release build (`RAGKIT_GOOGLE_CLIENT_ID`,
+//! `RAGKIT_GOOGLE_CLIENT_SECRET`, `RAGKIT_MICROSOFT_CLIENT_ID`). Tokens are kept in
+//! oauth_tokens.json and refreshed when they expire.
+
+use crate::backend::{http_client, TimeoutTier};
+use crate::{preferences, store};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+const STATE_FILE: &str = "oauth_tokens.json";
+
+/// Tokens are refreshed this long before they expire.
+const EXPIRY_MARGIN_SECS: i64 = 60;
+
+const GOOGLE_CLIENT_ID: Option<&str> = option_env!("RAGKIT_GOOGLE_CLIENT_ID");
+const GOOGLE_CLIENT_SECRET: Option<&str> = option_env!("RAGKIT_GOOGLE_CLIENT_SECRET");
+const MICROSOFT_CLIENT_ID: Option<&str> = option_env!("RAGKIT_MICROSOFT_CLIENT_ID");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OAuthProvider {
+    Google,
+    Microsoft,
+}
+
+impl OAuthProvider {
+    fn device_code_url(self) -> &'static str {
+        match self {
+            Self::Google => "https://oauth2.googleapis.com/device/code",
+            Self::Microsoft => "https://login.microsoftonline.com/common/oauth2/v2.0/devicecode",
+        }
+    }
+
+    fn token_url(self) -> &'static str {
+        match self {
+            Self::Google => "https://oauth2.googleapis.com/token",
+            Self::Microsoft => "https://login.microsoftonline.com/common/oauth2/v2.0/token",
+        }
+    }
+
+    /// Client id and, for Google, the client secret of installed apps.
+    fn client(self) -> Result<(String, Option<String>)> {
+        let prefs = preferences::load();
+        let (id, secret) = match self {
+            Self::Google => (
+                prefs
+                    .google_client_id
+                    .or(GOOGLE_CLIENT_ID.map(String::from)),
+                prefs
+                    .google_client_secret
+                    .or(GOOGLE_CLIENT_SECRET.map(String::from)),
+            ),
+            Self::Microsoft => (
+                prefs
+                    .microsoft_client_id
+                    .or(MICROSOFT_CLIENT_ID.map(String::from)),
+                None,
+            ),
+        };
+        let id = id.ok_or_else(|| anyhow!("No OAuth client id is configured for {:?}", self))?;
+        Ok((id, secret))
+    }
+}
+
+/// What the user needs to approve a device authorization.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceAuthorization {
+    pub provider: OAuthProvider,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub expires_in: u64,
+    #[serde(skip)]
+    device_code: String,
+    #[serde(skip)]
+    interval: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredToken {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_at: DateTime<Utc>,
+    scope: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_in: i64,
+}
+
+fn load() -> BTreeMap<OAuthProvider, StoredToken> {
+    store::load(STATE_FILE)
+}
+
+fn save(tokens: &BTreeMap<OAuthProvider, StoredToken>) -> Result<()> {
+    store::save(STATE_FILE, tokens)
+}
+
+/// Post a form to the token endpoint. Errors carry the OAuth error code.
+async fn token_request(
+    provider: OAuthProvider,
+    form: &[(&str, &str)],
+) -> Result<TokenResponse, String> {
+    let response = http_client()
+        .post(provider.token_url())
+        .form(form)
+        .timeout(TimeoutTier::Standard.timeout())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = response.status();
+    let body: Value = response.json().await.map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        return Err(body["error"]
+            .as_str()
+            .unwrap_or("request_failed")
+            .to_string());
+    }
+    serde_json::from_value(body).map_err(|e| e.to_string())
+}
+
+fn store_token(provider: OAuthProvider, scope: &str, token: TokenResponse) -> Result<String> {
+    let mut tokens = load();
+    // Refresh responses may omit the refresh token, which then stays valid
+    let refresh_token = token
+        .refresh_token
+        .or_else(|| tokens.get(&provider).and_then(|t| t.refresh_token.clone()));
+    tokens.insert(
+        provider,
+        StoredToken {
+            access_token: token.access_token.clone(),
+            refresh_token,
+            expires_at: Utc::now() + Duration::seconds(token.expires_in),
+            scope: scope.to_string(),
+        },
+    );
+    save(&tokens)?;
+    Ok(token.access_token)
+}
+
+/// Ask for a device code to approve `scope`.
+pub async fn start_device_flow(
+    provider: OAuthProvider,
+    scope: &str,
+) -> Result<DeviceAuthorization> {
+    let (client_id, _) = provider.client()?;
+    let response = http_client()
+        .post(provider.device_code_url())
+        .form(&[("client_id", client_id.as_str()), ("scope", scope)])
+        .timeout(TimeoutTier::Standard.timeout())
+        .send()
+        .await?;
+    let status = response.status();
+    let body: Value = response.json().await?;
+    if !status.is_success() {
+        return Err(anyhow!(
+            "{}",
+            body["error_description"]
+                .as_str()
+                .or(body["error"].as_str())
+                .unwrap_or("The device authorization was refused")
+        ));
+    }
+    let field = |name: &str| body[name].as_str().map(String::from);
+    Ok(DeviceAuthorization {
+        provider,
+        device_code: field("device_code").ok_or_else(|| anyhow!("No device code returned"))?,
+        user_code: field("user_code").ok_or_else(|| anyhow!("No user code returned"))?,
+        // Google names it verification_url
+        verification_uri: field("verification_uri")
+            .or_else(|| field("verification_url"))
+            .ok_or_else(|| anyhow!("No verification page returned"))?,
+        expires_in: body["expires_in"].as_u64().unwrap_or(900),
+        interval: body["interval"].as_u64().unwrap_or(5),
+    })
+}
+
+/// Poll until the user approves the device authorization, then store the tokens.
+pub async fn complete_device_flow(authorization: &DeviceAuthorization, scope: &str) -> Result<()> {
+    let provider = authorization.provider;
+    let (client_id, client_secret) = provider.client()?;
+    let mut form = vec![
+        ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+        ("client_id", client_id.as_str()),
+        ("device_code", authorization.device_code.as_str()),
+    ];
+    if let Some(secret) = &client_secret {
+        form.push(("client_secret", secret.as_str()));
+    }
+
+    let deadline = Utc::now() + Duration::seconds(authorization.expires_in as i64);
+    let mut interval = authorization.interval.max(1);
+    while Utc::now() < deadline {
+        tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+        match token_request(provider, &form).await {
+            Ok(token) => {
+                store_token(provider, scope, token)?;
+                return Ok(());
+            }
+            Err(e) if e == "authorization_pending" => {}
+            Err(e) if e == "slow_down" => interval += 5,
+            Err(e) if e == "access_denied" || e == "authorization_declined" => {
+                return Err(anyhow!("The authorization was denied"));
+            }
+            Err(e) if e == "expired_token" => break,
+            Err(e) => return Err(anyhow!("Authorization failed: {}", e)),
+        }
+    }
+    Err(anyhow!("The code expired before it was entered"))
+}
+
+/// A valid access token for the provider, refreshed if needed.
+pub async fn access_token(provider: OAuthProvider) -> Result<String> {
+    let token = load()
+        .remove(&provider)
+        .ok_or_else(|| anyhow!("No {:?} account is linked", provider))?;
+    if token.expires_at - Duration::seconds(EXPIRY_MARGIN_SECS) > Utc::now() {
+        return Ok(token.access_token);
+    }
+    let refresh_token = token
+        .refresh_token
+        .ok_or_else(|| anyhow!("The {:?} session expired: link the account again", provider))?;
+    let (client_id, client_secret) = provider.client()?;
+    let mut form = vec![
+        ("grant_type", "refresh_token"),
+        ("client_id", client_id.as_str()),
+        ("refresh_token", refresh_token.as_str()),
+    ];
+    if let Some(secret) = &client_secret {
+        form.push(("client_secret", secret.as_str()));
+    }
+    let refreshed = token_request(provider, &form).await.map_err(|e| {
+        anyhow!(
+            "The {:?} session expired ({}): link the account again",
+            provider,
+            e
+        )
+    })?;
+    store_token(provider, &token.scope, refreshed)
+}
+
+/// Whether an account is linked for the provider.
+pub fn is_linked(provider: OAuthProvider) -> bool {
+    load().contains_key(&provider)
+}
+
+/// Forget the tokens of a provider.
+pub fn unlink(provider: OAuthProvider) -> Result<bool> {
+    let mut tokens = load();
+    let removed = tokens.remove(&provider).is_some();
+    if removed {
+        save(&tokens)?;
+    }
+    Ok(removed)
+}
@@ -0,0 +1,155 @@
+//! OCR of scanned PDFs and images.
+//!
+//! The backend runs Tesseract on files without a text layer when OCR is enabled, either
+//! in its settings (`ingestion_ocr_enabled`) or for one import (`ocr_enabled`,
+//! `ocr_language`). When it is off, scanned PDFs and images are not sent: they would
+//! become empty documents. They are reported as failed with `needs_ocr` set instead, so
+//! the UI can offer to enable OCR and import them again.
+
+use crate::backend::backend_request;
+use crate::capabilities::{self, Feature};
+use crate::commands;
+use crate::files::extension_of;
+use crate::jobs::IngestOptions;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::Path;
+
+/// Images ingested through OCR.
+pub const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "tif", "tiff", "bmp", "webp"];
+
+/// Scanned PDFs larger than this are not inspected and are sent as is.
+const MAX_INSPECTED_BYTES: u64 = 200 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OcrStatus {
+    pub available: bool,
+    pub engine: Option<String>,
+    pub version: Option<String>,
+    /// Installed language packs, as Tesseract codes (e.g. `eng`, `fra`)
+    #[serde(default)]
+    pub languages: Vec<String>,
+    /// What is missing when OCR is unavailable
+    pub error: Option<String>,
+}
+
+/// Whether a file is an image that needs OCR to be ingested.
+pub fn is_image(path: &Path) -> bool {
+    IMAGE_EXTENSIONS.contains(&extension_of(path).as_str())
+}
+
+/// Whether a PDF has pages drawn from images but no fonts, i.e. no text layer.
+fn is_scanned_pdf(path: &Path) -> bool {
+    if extension_of(path) != "pdf"
+        || std::fs::metadata(path).map_or(true, |m| m.len() > MAX_INSPECTED_BYTES)
+    {
+        return false;
+    }
+    let Ok(data) = std::fs::read(path) else {
+        return false;
+    };
+    let contains = |needle: &[u8]| data.windows(needle.len()).any(|w| w == needle);
+    contains(b"/Image") && !contains(b"/Font")
+}
+
+/// Whether OCR is enabled for an import: its own option, else the backend setting.
+pub async fn enabled(options: &IngestOptions) -> bool {
+    match options.ocr_enabled {
+        Some(enabled) => enabled,
+        None => commands::get_settings()
+            .await
+            .ok()
+            .and_then(|s| s.ingestion_ocr_enabled)
+            .unwrap_or(false),
+    }
+}
+
+/// Why a file can't be ingested without OCR, if it can't.
+pub async fn required(path: &str) -> Option<String> {
+    if is_image(Path::new(path)) {
+        return Some("Images can only be ingested with OCR enabled".to_string());
+    }
+    let pdf = path.to_string();
+    let scanned = tauri::async_runtime::spawn_blocking(move || is_scanned_pdf(Path::new(&pdf)))
+        .await
+        .unwrap_or(false);
+    scanned.then(|| "Scanned PDF without a text layer: enable OCR to ingest it".to_string())
+}
+
+/// OCR options sent to the backend with a file, when the import sets them.
+pub fn request(options: &IngestOptions) -> Option<serde_json::Value> {
+    if options.ocr_enabled.is_none() && options.ocr_language.is_none() {
+        return None;
+    }
+    Some(json!({
+        "enabled": options.ocr_enabled,
+        "language": options.ocr_language,
+    }))
+}
+
+/// Check the Tesseract install the backend runs.
+async fn local_status() -> OcrStatus {
+    let run = |arg: &'static str| async move {
+        tokio::process::Command::new("tesseract")
+            .arg(arg)
+            .output()
+            .await
+            .ok()
+            .filter(|output| output.status.success())
+            // Older releases print to stderr
+            .map(|output| {
+                let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+                text.push_str(&String::from_utf8_lossy(&output.stderr));
+                text
+            })
+    };
+    let Some(version) = run("--version").await else {
+        return OcrStatus {
+            available: false,
+            engine: None,
+            version: None,
+            languages: Vec::new(),
+            error: Some("Tesseract is not installed or not on the PATH".to_string()),
+        };
+    };
+    let languages: Vec<String> = run("--list-langs")
+        .await
+        .unwrap_or_default()
+        .lines()
+        // The first line is a header ("List of available languages ...")
+        .skip(1)
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && *l != "osd")
+        .map(String::from)
+        .collect();
+    OcrStatus {
+        available: !languages.is_empty(),
+        engine: Some("tesseract".to_string()),
+        version: version
+            .lines()
+            .next()
+            .and_then(|l| l.split_whitespace().nth(1))
+            .map(|v| v.trim_start_matches('v').to_string()),
+        error: languages
+            .is_empty()
+            .then(|| "No Tesseract language pack is installed".to_string()),
+        languages,
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Check whether OCR is available to the backend, and in which languages
+#[tauri::command]
+pub async fn ocr_status() -> Result<OcrStatus, String> {
+    if capabilities::supports(Feature::OcrStatus) {
+        match backend_request::<OcrStatus>(Method::GET, "/api/ocr/status", None).await {
+            Ok(status) => return Ok(status),
+            Err(e) => tracing::debug!("Checking the local OCR install instead: {}", e),
+        }
+    }
+    Ok(local_status().await)
+}
@@ -0,0 +1,157 @@
+//! Ollama model downloads with progress reporting.
+//!
+//! Pulls go straight to the local Ollama server, whose `/api/pull` endpoint streams one
+//! JSON status line per progress update. The backend's own pull endpoint blocks until
+//! the download finishes, which leaves users without feedback for minutes.
+
+use crate::backend::{cancel_request, cancellable, http_client};
+use crate::streaming::LineReader;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+
+const DEFAULT_OLLAMA_HOST: &str = "http://localhost:11434";
+
+/// Models currently being pulled.
+static ACTIVE_PULLS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// A progress line from Ollama's `/api/pull` stream.
+#[derive(Debug, Deserialize)]
+struct PullStatus {
+    #[serde(default)]
+    status: String,
+    digest: Option<String>,
+    total: Option<u64>,
+    completed: Option<u64>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OllamaPullProgress {
+    pub model: String,
+    /// Ollama's status text, e.g. "pulling manifest" or "verifying sha256 digest"
+    pub status: String,
+    /// Layer currently downloading
+    pub digest: Option<String>,
+    pub layer_completed: Option<u64>,
+    pub layer_total: Option<u64>,
+    /// Bytes downloaded and total size across all layers seen so far
+    pub downloaded: u64,
+    pub total: u64,
+}
+
+/// Base URL of the local Ollama server, honoring Ollama's own `OLLAMA_HOST` variable.
+pub fn ollama_url() -> String {
+    match std::env::var("OLLAMA_HOST") {
+        Ok(host) if !host.trim().is_empty() => {
+            let host = host.trim().trim_end_matches('/');
+            if host.starts_with("http://") || host.starts_with("https://") {
+                host.to_string()
+            } else {
+                format!("http://{}", host.replace("0.0.0.0", "127.0.0.1"))
+            }
+        }
+        _ => DEFAULT_OLLAMA_HOST.to_string(),
+    }
+}
+
+fn pull_request_id(model_name: &str) -> String {
+    format!("ollama-pull:{}", model_name)
+}
+
+async fn pull(app: &AppHandle, model_name: &str) -> Result<()> {
+    let response = http_client()
+        .post(format!("{}/api/pull", ollama_url()))
+        .json(&json!({ "model": model_name, "stream": true }))
+        .timeout(std::time::Duration::from_secs(24 * 60 * 60))
+        .send()
+        .await
+        .map_err(|e| anyhow!("Failed to reach Ollama: {}", e))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(anyhow!("Ollama error {}: {}", status, text));
+    }
+
+    // (completed, total) per layer digest
+    let mut layers: HashMap<String, (u64, u64)> = HashMap::new();
+    let mut lines = LineReader::new(response);
+
+    while let Some(line) = lines
+        .next_line()
+        .await
+        .map_err(|e| anyhow!("Download interrupted: {}", e))?
+    {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let status: PullStatus = serde_json::from_str(line)
+            .map_err(|e| anyhow!("Invalid progress line from Ollama: {}", e))?;
+        if let Some(error) = status.error {
+            return Err(anyhow!(error));
+        }
+
+        if let (Some(digest), Some(total)) = (&status.digest, status.total) {
+            layers.insert(digest.clone(), (status.completed.unwrap_or(0), total));
+        }
+        let _ = app.emit(
+            "ollama-pull-progress",
+            OllamaPullProgress {
+                model: model_name.to_string(),
+                downloaded: layers.values().map(|(completed, _)| completed).sum(),
+                total: layers.values().map(|(_, total)| total).sum(),
+                status: status.status.clone(),
+                digest: status.digest,
+                layer_completed: status.completed,
+                layer_total: status.total,
+            },
+        );
+
+        if status.status == "success" {
+            return Ok(());
+        }
+    }
+
+    Err(anyhow!("Ollama closed the connection before the pull finished"))
+}
+
+/// Number of model pulls in progress.
+pub fn active_pulls() -> usize {
+    ACTIVE_PULLS.lock().unwrap().len()
+}
+
+/// Cancel every pull in progress. Ollama keeps the layers downloaded so far, so pulling
+/// the model again resumes where it stopped.
+pub fn cancel_pulls() {
+    let models: Vec<String> = ACTIVE_PULLS.lock().unwrap().iter().cloned().collect();
+    for model in models {
+        cancel_request(&pull_request_id(&model));
+    }
+}
+
+/// Pull (download) an Ollama model, emitting `ollama-pull-progress` events
+#[tauri::command]
+pub async fn pull_ollama_model(app: AppHandle, model_name: String) -> Result<(), String> {
+    tracing::info!("Pulling Ollama model {}", model_name);
+    let request_id = pull_request_id(&model_name);
+    ACTIVE_PULLS.lock().unwrap().insert(model_name.clone());
+    let result = cancellable(Some(&request_id), pull(&app, &model_name)).await;
+    ACTIVE_PULLS.lock().unwrap().remove(&model_name);
+    result.map_err(|e| e.to_string())
+}
+
+/// Cancel a running Ollama model pull
+#[tauri::command]
+pub async fn cancel_ollama_pull(model_name: String) -> Result<bool, String> {
+    let cancelled = cancel_request(&pull_request_id(&model_name));
+    if cancelled {
+        tracing::info!("Cancelled pull of Ollama model {}", model_name);
+    }
+    Ok(cancelled)
+}
@@ -0,0 +1,322 @@
+//! OS search integration and `ragkit://` deep links.
+//!
+//! The shell keeps one small stub per knowledge base and titled conversation in a
+//! location the OS launcher indexes, so searching "contracts ragkit" in Spotlight,
+//! Windows Search, or the desktop's app search finds it:
+//!
+//! - macOS: `.webloc` files under `~/Library/Caches/Metadata/RAGKIT/`
+//! - Windows: `.url` shortcuts under the Start Menu's `RAGKIT Search` folder
+//! - Linux: `.desktop` entries under `~/.local/share/applications/ragkit-search/`
+//!
+//! Each stub opens `ragkit://kb/<id>` or `ragkit://conversation/<id>`. The scheme is
+//! registered by the installer on Windows, by `Info.plist` on macOS, and at runtime on
+//! Linux. Incoming links show the main window and emit a `deep-link` event.
+
+use crate::commands::{Conversation, KnowledgeBase};
+use crate::{guest, preferences, store};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+
+const SCHEME: &str = "ragkit";
+/// Stub filenames written by the last sync, keyed by `<kind>:<id>`.
+const MANIFEST_FILE: &str = "os_search_stubs.json";
+
+/// Link received before the UI was ready to handle it.
+static PENDING: Mutex<Option<DeepLink>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkTarget {
+    Kb,
+    Conversation,
+}
+
+impl LinkTarget {
+    fn as_str(self) -> &'static str {
+        match self {
+            LinkTarget::Kb => "kb",
+            LinkTarget::Conversation => "conversation",
+        }
+    }
+}
+
+/// Payload of the `deep-link` event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeepLink {
+    pub target: LinkTarget,
+    pub id: String,
+}
+
+struct Stub {
+    key: String,
+    title: String,
+    description: Option<String>,
+    url: String,
+}
+
+type Manifest = BTreeMap<String, String>;
+
+/// Parse `ragkit://kb/<id>` or `ragkit://conversation/<id>`.
+pub fn parse_link(url: &str) -> Option<DeepLink> {
+    let url = reqwest::Url::parse(url).ok()?;
+    if url.scheme() != SCHEME {
+        return None;
+    }
+    let target = match url.host_str()? {
+        "kb" => LinkTarget::Kb,
+        "conversation" => LinkTarget::Conversation,
+        _ => return None,
+    };
+    let id = url.path().trim_matches('/');
+    if id.is_empty() || id.contains('/') {
+        return None;
+    }
+    Some(DeepLink {
+        target,
+        id: id.to_string(),
+    })
+}
+
+/// Bring the app forward and route a `ragkit://` link to the UI.
+pub fn open_link(app: &AppHandle, url: &str) {
+    let Some(link) = parse_link(url) else {
+        tracing::warn!("Ignoring unsupported link: {}", url);
+        return;
+    };
+    tracing::info!("Opening {} {}", link.target.as_str(), link.id);
+    *PENDING.lock().unwrap() = Some(link.clone());
+    crate::tray::show_main_window(app);
+    let _ = app.emit("deep-link", &link);
+}
+
+/// Handle a link passed on the command line (Windows and Linux launch the app with it),
+/// either to this process or to a second launch forwarded to it.
+pub fn handle_args(app: &AppHandle, args: impl IntoIterator<Item = String>) {
+    if let Some(url) = args.into_iter().find(|a| a.starts_with("ragkit://")) {
+        open_link(app, &url);
+    }
+}
+
+fn home() -> PathBuf {
+    #[cfg(target_os = "windows")]
+    let home = std::env::var("USERPROFILE").unwrap_or_else(|_| "C:\\".to_string());
+    #[cfg(not(target_os = "windows"))]
+    let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+    PathBuf::from(home)
+}
+
+#[cfg(target_os = "macos")]
+fn stub_dir() -> PathBuf {
+    home().join("Library/Caches/Metadata/RAGKIT")
+}
+
+#[cfg(target_os = "windows")]
+fn stub_dir() -> PathBuf {
+    let appdata = std::env::var("APPDATA")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| home().join("AppData").join("Roaming"));
+    appdata.join(r"Microsoft\Windows\Start Menu\Programs\RAGKIT Search")
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn stub_dir() -> PathBuf {
+    home().join(".local/share/applications/ragkit-search")
+}
+
+fn sanitize_filename(title: &str) -> String {
+    let name: String = title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => ' ',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect();
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    name.chars().take(80).collect()
+}
+
+#[cfg(target_os = "macos")]
+fn stub_file(stub: &Stub, _exe: &Path) -> (String, String) {
+    let url = stub.url.replace('&', "&amp;").replace('<', "&lt;");
+    let content = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \
+         \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+         <plist version=\"1.0\"><dict><key>URL</key><string>{}</string></dict></plist>\n",
+        url
+    );
+    (format!("{} (RAGKIT).webloc", stub.title), content)
+}
+
+#[cfg(target_os = "windows")]
+fn stub_file(stub: &Stub, exe: &Path) -> (String, String) {
+    let content = format!(
+        "[InternetShortcut]\r\nURL={}\r\nIconFile={}\r\nIconIndex=0\r\n",
+        stub.url,
+        exe.display()
+    );
+    (format!("{} (RAGKIT).url", stub.title), content)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn stub_file(stub: &Stub, exe: &Path) -> (String, String) {
+    let single_line = |s: &str| s.replace(['\n', '\r'], " ");
+    let mut content = format!(
+        "[Desktop Entry]\nType=Application\nName={} (RAGKIT)\nExec=\"{}\" {}\n\
+         Icon=ragkit-desktop\nKeywords=ragkit;\nTerminal=false\n",
+        single_line(&stub.title),
+        exe.display(),
+        stub.url
+    );
+    if let Some(description) = &stub.description {
+        content.push_str(&format!("Comment={}\n", single_line(description)));
+    }
+    (format!("{}.desktop", stub.key.replace(':', "-")), content)
+}
+
+/// Replace the stubs of one kind with `stubs`, removing those no longer listed.
+fn sync(target: LinkTarget, stubs: Vec<Stub>) {
+    let prefix = format!("{}:", target.as_str());
+    let enabled = preferences::load().os_search_integration && !guest::is_active();
+    let dir = stub_dir();
+    let mut manifest: Manifest = store::load(MANIFEST_FILE);
+
+    let exe = std::env::current_exe().unwrap_or_default();
+    let mut written: Manifest = BTreeMap::new();
+    if enabled {
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            tracing::warn!("Failed to create {}: {}", dir.display(), e);
+            return;
+        }
+        for stub in stubs {
+            let (mut filename, content) = stub_file(&stub, &exe);
+            // Two items with the same title get distinct stubs
+            if written.values().any(|f| f.eq_ignore_ascii_case(&filename)) {
+                let short_id: String = stub.key[prefix.len()..].chars().take(8).collect();
+                filename = filename.replacen(" (RAGKIT)", &format!(" {} (RAGKIT)", short_id), 1);
+            }
+            match std::fs::write(dir.join(&filename), content) {
+                Ok(()) => {
+                    written.insert(stub.key, filename);
+                }
+                Err(e) => tracing::warn!("Failed to write search stub {}: {}", filename, e),
+            }
+        }
+    }
+
+    let stale: Vec<String> = manifest
+        .iter()
+        .filter(|(key, filename)| {
+            key.starts_with(&prefix) && !written.values().any(|f| f == *filename)
+        })
+        .map(|(_, filename)| filename.clone())
+        .collect();
+    for filename in stale {
+        let _ = std::fs::remove_file(dir.join(filename));
+    }
+
+    manifest.retain(|key, _| !key.starts_with(&prefix));
+    manifest.extend(written);
+    if let Err(e) = store::save(MANIFEST_FILE, &manifest) {
+        tracing::warn!("Failed to save search stub manifest: {}", e);
+    }
+}
+
+/// Refresh the search stubs of knowledge bases.
+pub fn sync_knowledge_bases(knowledge_bases: &[KnowledgeBase]) {
+    let stubs = knowledge_bases
+        .iter()
+        .map(|kb| Stub {
+            key: format!("kb:{}", kb.id),
+            title: sanitize_filename(&kb.name),
+            description: kb.description.clone(),
+            url: format!("{}://kb/{}", SCHEME, kb.id),
+        })
+        .filter(|s| !s.title.is_empty())
+        .collect();
+    sync(LinkTarget::Kb, stubs);
+}
+
+/// Refresh the search stubs of titled conversations.
+pub fn sync_conversations(conversations: &[Conversation]) {
+    let stubs = conversations
+        .iter()
+        .filter_map(|c| {
+            let title = sanitize_filename(c.title.as_deref()?);
+            Some(Stub {
+                key: format!("conversation:{}", c.id),
+                title,
+                description: None,
+                url: format!("{}://conversation/{}", SCHEME, c.id),
+            })
+        })
+        .filter(|s| !s.title.is_empty())
+        .collect();
+    sync(LinkTarget::Conversation, stubs);
+}
+
+/// Register the app as the `ragkit://` handler with the desktop environment.
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub fn register_scheme() {
+    if guest::is_active() {
+        return;
+    }
+    let Ok(exe) = std::env::current_exe() else {
+        return;
+    };
+    let applications = home().join(".local/share/applications");
+    let entry = format!(
+        "[Desktop Entry]\nType=Application\nName=RAGKIT Desktop\nExec=\"{}\" %u\n\
+         MimeType=x-scheme-handler/{};\nNoDisplay=true\nTerminal=false\n",
+        exe.display(),
+        SCHEME
+    );
+    let path = applications.join("ragkit-url-handler.desktop");
+    if std::fs::read_to_string(&path).is_ok_and(|existing| existing == entry) {
+        return;
+    }
+    if let Err(e) =
+        std::fs::create_dir_all(&applications).and_then(|_| std::fs::write(&path, entry))
+    {
+        tracing::warn!("Failed to register the {} scheme: {}", SCHEME, e);
+        return;
+    }
+    let _ = std::process::Command::new("xdg-mime")
+        .args([
+            "default",
+            "ragkit-url-handler.desktop",
+            &format!("x-scheme-handler/{}", SCHEME),
+        ])
+        .status();
+}
+
+/// The scheme is registered by the installer (Windows) or the bundle's `Info.plist` (macOS).
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+pub fn register_scheme() {}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Take the last `ragkit://` link received, if the UI has not handled it yet
+#[tauri::command]
+pub async fn take_pending_deep_link() -> Result<Option<DeepLink>, String> {
+    Ok(PENDING.lock().unwrap().take())
+}
+
+/// Remove every OS search stub written by the shell
+#[tauri::command]
+pub async fn clear_search_stubs() -> Result<usize, String> {
+    let manifest: Manifest = store::load(MANIFEST_FILE);
+    let dir = stub_dir();
+    let removed = manifest
+        .values()
+        .filter(|filename| std::fs::remove_file(dir.join(filename)).is_ok())
+        .count();
+    store::save(MANIFEST_FILE, &Manifest::new()).map_err(|e| e.to_string())?;
+    Ok(removed)
+}
@@ -0,0 +1,179 @@
+//! Desktop shell preferences.
+//!
+//! These settings only affect the native shell (not the RAG pipeline), so they are stored
+//! locally in ~/.ragkit/preferences.json rather than in the backend `Settings`.
+
+use crate::failover::ProviderTarget;
+use crate::proxy::{ManualProxy, ProxyMode};
+use crate::store;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+
+const STATE_FILE: &str = "preferences.json";
+
+/// Preferences read from disk, kept as they are read on every request.
+static CACHE: RwLock<Option<Preferences>> = RwLock::new(None);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preferences {
+    /// Enables developer tooling such as the in-app REST console
+    pub developer_mode: bool,
+    /// Hide the window to the system tray on close instead of quitting
+    pub minimize_to_tray: bool,
+    /// Maximum time to establish a connection to the backend (applied on restart)
+    pub connect_timeout_secs: u64,
+    /// Default maximum duration of a backend request or outbound API call (downloads are
+    /// not capped)
+    pub request_timeout_secs: u64,
+    /// Maximum duration of quick requests (health, settings, logs, API keys)
+    pub fast_timeout_secs: u64,
+    /// Maximum duration of queries and answer verification
+    pub query_timeout_secs: u64,
+    /// Maximum duration of ingestion and re-embedding batches
+    pub long_timeout_secs: u64,
+    /// Collapse near-identical chunks among the sources of an answer
+    pub suppress_duplicate_sources: bool,
+    /// Word-shingle similarity (0-1) above which two sources count as duplicates
+    pub duplicate_source_threshold: f64,
+    /// Release channel checked for application updates
+    pub update_channel: UpdateChannel,
+    /// Inclusive port range scanned for a free backend port
+    pub backend_port_min: u16,
+    pub backend_port_max: u16,
+    /// Always start the backend on this port instead of scanning the range
+    /// (overridden by `backend_port` in config.toml and `RAGKIT_BACKEND_PORT`)
+    pub backend_port: Option<u16>,
+    /// Show cached knowledge bases and conversations while the backend starts
+    pub fast_start: bool,
+    /// Seconds between two backend health checks
+    pub health_check_interval_secs: u64,
+    /// Keep knowledge bases and conversations findable from the OS search
+    pub os_search_integration: bool,
+    /// Attempts per backend request when the backend is unreachable or unavailable
+    pub request_retry_attempts: u32,
+    /// Delay before the first retry, doubled (with jitter) for each further attempt
+    pub request_retry_base_delay_ms: u64,
+    /// Also retry non-idempotent requests (POST, PATCH) that the backend rejected as
+    /// unavailable. Requests that never reached the backend are always retried.
+    pub retry_non_idempotent: bool,
+    /// Retries of a request rate-limited by the LLM or embedding provider (requests that
+    /// could duplicate a conversation turn are never retried)
+    pub rate_limit_retries: u32,
+    /// Longest wait before retrying a rate-limited request; longer limits are reported
+    /// to the user instead
+    pub rate_limit_max_wait_secs: u64,
+    /// Show the best passages from the local index while an answer is generated
+    pub instant_results: bool,
+    /// Number of instant results per query
+    pub instant_results_limit: usize,
+    /// Answer preset used by queries that don't choose one
+    pub default_answer_preset: Option<String>,
+    /// Suggest related documents that haven't been cited after each answer
+    pub document_recommendations: bool,
+    /// Number of documents suggested after each answer
+    pub document_recommendations_limit: usize,
+    /// Longest acceptable answer time; the pipeline is trimmed to stay under it
+    pub max_answer_secs: Option<u64>,
+    /// Model switched to when trimming retrieval isn't enough to meet the budget
+    pub latency_budget_fast_model: Option<ProviderTarget>,
+    /// Ingestion jobs run at the same time; 1 ingests one import at a time
+    pub ingestion_workers: usize,
+    /// OAuth client used to link Google Drive, instead of the one of the release build
+    pub google_client_id: Option<String>,
+    pub google_client_secret: Option<String>,
+    /// OAuth client used to link OneDrive, instead of the one of the release build
+    pub microsoft_client_id: Option<String>,
+    /// Block everything that would leave the machine: cloud LLM and embedding
+    /// providers, remote backends and the shell's own outbound requests
+    pub offline_mode: bool,
+    /// Proxy for outbound requests of the shell and the backend (applied to the
+    /// backend on restart)
+    pub proxy_mode: ProxyMode,
+    /// Proxy used when `proxy_mode` is manual
+    pub manual_proxy: Option<ManualProxy>,
+    /// PEM bundle of extra CA certificates to trust, e.g. a TLS-intercepting proxy's
+    pub ca_bundle_path: Option<String>,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            developer_mode: false,
+            minimize_to_tray: false,
+            connect_timeout_secs: 5,
+            request_timeout_secs: 300,
+            fast_timeout_secs: 10,
+            query_timeout_secs: 600,
+            long_timeout_secs: 3600,
+            suppress_duplicate_sources: true,
+            duplicate_source_threshold: 0.85,
+            update_channel: UpdateChannel::Stable,
+            backend_port_min: 8100,
+            backend_port_max: 8199,
+            backend_port: None,
+            fast_start: true,
+            health_check_interval_secs: 10,
+            os_search_integration: true,
+            request_retry_attempts: 4,
+            request_retry_base_delay_ms: 250,
+            retry_non_idempotent: false,
+            rate_limit_retries: 3,
+            rate_limit_max_wait_secs: 120,
+            instant_results: true,
+            instant_results_limit: 5,
+            default_answer_preset: None,
+            document_recommendations: true,
+            document_recommendations_limit: 3,
+            max_answer_secs: None,
+            latency_budget_fast_model: None,
+            ingestion_workers: 2,
+            google_client_id: None,
+            google_client_secret: None,
+            microsoft_client_id: None,
+            offline_mode: false,
+            proxy_mode: ProxyMode::System,
+            manual_proxy: None,
+            ca_bundle_path: None,
+        }
+    }
+}
+
+pub fn load() -> Preferences {
+    if let Some(preferences) = CACHE.read().unwrap().as_ref() {
+        return preferences.clone();
+    }
+    let preferences: Preferences = store::load(STATE_FILE);
+    CACHE.write().unwrap().get_or_insert(preferences).clone()
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Get the shell preferences
+#[tauri::command]
+pub async fn get_preferences() -> Result<Preferences, String> {
+    Ok(load())
+}
+
+/// Update the shell preferences
+#[tauri::command]
+pub async fn update_preferences(preferences: Preferences) -> Result<Preferences, String> {
+    if preferences.offline_mode && !load().offline_mode {
+        crate::network::check_can_enable().await?;
+    }
+    store::save(STATE_FILE, &preferences).map_err(|e| e.to_string())?;
+    *CACHE.write().unwrap() = Some(preferences.clone());
+    // Pick up proxy and certificate changes on the next request
+    crate::backend::reset_http_client();
+    Ok(preferences)
+}
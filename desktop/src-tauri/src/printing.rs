@@ -0,0 +1,184 @@
+//! Printing of conversations and documents.
+//!
+//! The shell renders a standalone HTML page with a print stylesheet (citations become
+//! numbered footnotes) and prints it through a hidden iframe in the calling webview, so
+//! the OS print dialog opens without disturbing the current view.
+
+use crate::backend::backend_request;
+use crate::commands::{self, Conversation, Message};
+use reqwest::Method;
+use tauri::WebviewWindow;
+
+/// Longest excerpt of a cited chunk shown in a footnote.
+const FOOTNOTE_EXCERPT_CHARS: usize = 300;
+
+const PRINT_STYLESHEET: &str = r#"
+@page { margin: 2cm; }
+body { font-family: Georgia, "Times New Roman", serif; font-size: 11pt; line-height: 1.5; color: #000; }
+header { border-bottom: 1px solid #000; margin-bottom: 1.5em; }
+h1 { font-size: 16pt; margin: 0 0 0.25em; }
+.meta { font-size: 9pt; color: #444; margin: 0 0 0.75em; }
+.message { margin: 0 0 1.25em; page-break-inside: avoid; }
+.role { font-weight: bold; font-size: 9pt; text-transform: uppercase; letter-spacing: 0.05em; }
+.message p, .document p { margin: 0.25em 0; white-space: pre-wrap; }
+sup { font-size: 7pt; }
+.footnotes { border-top: 1px solid #000; margin-top: 2em; padding-top: 0.5em; font-size: 9pt; }
+.footnotes li { margin-bottom: 0.5em; }
+.footnotes .excerpt { color: #444; font-style: italic; }
+"#;
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// One `<p>` per blank-line separated paragraph.
+fn paragraphs(text: &str) -> String {
+    text.split("\n\n")
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(|p| format!("<p>{}</p>", escape_html(p)))
+        .collect()
+}
+
+/// Whitespace-normalized start of a cited chunk.
+pub fn excerpt(chunk: &str) -> String {
+    let chunk = chunk.split_whitespace().collect::<Vec<_>>().join(" ");
+    if chunk.chars().count() <= FOOTNOTE_EXCERPT_CHARS {
+        return chunk;
+    }
+    let cut: String = chunk.chars().take(FOOTNOTE_EXCERPT_CHARS).collect();
+    format!("{}…", cut.trim_end())
+}
+
+fn page(title: &str, meta: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{title}</title>\
+         <style>{PRINT_STYLESHEET}</style></head><body><header><h1>{title}</h1>\
+         <p class=\"meta\">{meta}</p></header>{body}</body></html>",
+        title = escape_html(title),
+        meta = escape_html(meta),
+    )
+}
+
+/// Render a conversation transcript. Sources of each answer are numbered across the
+/// whole transcript and listed as footnotes at the end.
+fn render_conversation(conversation: Option<&Conversation>, messages: &[Message]) -> String {
+    let mut body = String::new();
+    let mut footnotes = String::new();
+    let mut count = 0;
+
+    for message in messages {
+        let role = match message.role.as_str() {
+            "user" => "Question",
+            "assistant" => "Answer",
+            other => other,
+        };
+        let mut refs = String::new();
+        for source in message.sources.iter().flatten() {
+            count += 1;
+            refs.push_str(&format!("<sup>[{}]</sup>", count));
+            footnotes.push_str(&format!(
+                "<li>{} <span class=\"excerpt\">“{}”</span></li>",
+                escape_html(&source.filename),
+                escape_html(&excerpt(&source.chunk))
+            ));
+        }
+        body.push_str(&format!(
+            "<section class=\"message\"><div class=\"role\">{}</div>{}{}</section>",
+            escape_html(role),
+            paragraphs(&message.content),
+            refs
+        ));
+    }
+    if !footnotes.is_empty() {
+        body.push_str(&format!(
+            "<section class=\"footnotes\"><h2>Sources</h2><ol>{}</ol></section>",
+            footnotes
+        ));
+    }
+
+    let title = conversation
+        .and_then(|c| c.title.clone())
+        .unwrap_or_else(|| "Conversation".to_string());
+    let meta = match conversation {
+        Some(c) => format!(
+            "{} messages · started {} · printed {}",
+            messages.len(),
+            c.created_at,
+            chrono::Local::now().format("%Y-%m-%d %H:%M")
+        ),
+        None => format!("{} messages", messages.len()),
+    };
+    page(&title, &meta, &body)
+}
+
+/// Print an HTML page from a hidden iframe in `window`.
+fn print_html(window: &WebviewWindow, html: &str) -> Result<(), String> {
+    let html = serde_json::to_string(html).map_err(|e| e.to_string())?;
+    let script = format!(
+        r#"(() => {{
+  const frame = document.createElement("iframe");
+  frame.style.cssText = "position:fixed;width:0;height:0;border:0;visibility:hidden";
+  frame.onload = () => {{
+    frame.contentWindow.focus();
+    frame.contentWindow.print();
+    setTimeout(() => frame.remove(), 1000);
+  }};
+  frame.srcdoc = {html};
+  document.body.appendChild(frame);
+}})();"#
+    );
+    window
+        .eval(&script)
+        .map_err(|e| format!("Failed to open the print dialog: {}", e))
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Print a conversation transcript with its citations as footnotes
+#[tauri::command]
+pub async fn print_conversation(window: WebviewWindow, conv_id: String) -> Result<(), String> {
+    let conversations: Vec<Conversation> = backend_request(Method::GET, "/api/conversations", None)
+        .await
+        .map_err(|e| e.to_string())?;
+    let messages = commands::get_messages(conv_id.clone()).await?;
+
+    let conversation = conversations.iter().find(|c| c.id == conv_id);
+    print_html(&window, &render_conversation(conversation, &messages))
+}
+
+/// Print the text of a document
+#[tauri::command]
+pub async fn print_document(
+    window: WebviewWindow,
+    kb_id: String,
+    doc_id: String,
+) -> Result<(), String> {
+    let documents = commands::list_documents(kb_id.clone()).await?;
+    let document = documents
+        .iter()
+        .find(|d| d.id == doc_id)
+        .ok_or_else(|| format!("Document not found: {}", doc_id))?;
+    let text = commands::document_text(&kb_id, &doc_id).await?;
+
+    let meta = format!(
+        "Ingested {} · printed {}",
+        document.ingested_at,
+        chrono::Local::now().format("%Y-%m-%d %H:%M")
+    );
+    let body = format!("<div class=\"document\">{}</div>", paragraphs(&text));
+    print_html(&window, &page(&document.filename, &meta, &body))
+}
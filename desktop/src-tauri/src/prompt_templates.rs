@@ -0,0 +1,222 @@
+//! Prompt templates.
+//!
+//! A template is a named, reusable system prompt ("Answer as a lawyer, cite clauses").
+//! A query names its template with `template_id`; otherwise it uses the template its
+//! conversation defaults to, then the one its knowledge base defaults to. The shell
+//! resolves the template into the `system_prompt` sent to the backend. Templates are
+//! stored in prompt_templates.json.
+
+use crate::commands::QueryParams;
+use crate::store;
+use serde::{Deserialize, Serialize};
+
+const STATE_FILE: &str = "prompt_templates.json";
+
+/// Longest system prompt accepted, in characters.
+const MAX_PROMPT_CHARS: usize = 8000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    pub id: String,
+    pub name: String,
+    pub system_prompt: String,
+    /// Knowledge bases whose queries use this template by default
+    #[serde(default)]
+    pub kb_ids: Vec<String>,
+    /// Conversations whose queries use this template by default
+    #[serde(default)]
+    pub conversation_ids: Vec<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+fn validate(name: &str, system_prompt: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Template name cannot be empty".into());
+    }
+    if system_prompt.trim().is_empty() {
+        return Err("Template prompt cannot be empty".into());
+    }
+    if system_prompt.chars().count() > MAX_PROMPT_CHARS {
+        return Err(format!(
+            "Template prompt is longer than {} characters",
+            MAX_PROMPT_CHARS
+        ));
+    }
+    Ok(())
+}
+
+/// Make `id` the only template used by default for these knowledge bases and
+/// conversations.
+fn take_defaults(
+    templates: &mut [PromptTemplate],
+    id: &str,
+    kb_ids: &[String],
+    conv_ids: &[String],
+) {
+    for template in templates.iter_mut().filter(|t| t.id != id) {
+        template.kb_ids.retain(|kb| !kb_ids.contains(kb));
+        template.conversation_ids.retain(|c| !conv_ids.contains(c));
+    }
+}
+
+/// Resolve the query's template, or its conversation's or knowledge base's default,
+/// into a system prompt, unless the caller already gave one.
+pub fn apply(params: &mut QueryParams) {
+    if params.system_prompt.is_some() {
+        return;
+    }
+    let templates: Vec<PromptTemplate> = store::load(STATE_FILE);
+    let template = match &params.template_id {
+        Some(id) => {
+            let found = templates.iter().find(|t| &t.id == id);
+            if found.is_none() {
+                tracing::warn!("Unknown prompt template: {}", id);
+            }
+            found
+        }
+        None => templates
+            .iter()
+            .find(|t| t.conversation_ids.contains(&params.conversation_id))
+            .or_else(|| templates.iter().find(|t| t.kb_ids.contains(&params.kb_id))),
+    };
+    if let Some(template) = template {
+        params.system_prompt = Some(template.system_prompt.clone());
+    }
+}
+
+fn forget(matches: impl Fn(&mut PromptTemplate) -> bool) {
+    let mut templates: Vec<PromptTemplate> = store::load(STATE_FILE);
+    let mut changed = false;
+    for template in &mut templates {
+        changed |= matches(template);
+    }
+    if changed {
+        if let Err(e) = store::save(STATE_FILE, &templates) {
+            tracing::warn!("Failed to update prompt templates: {}", e);
+        }
+    }
+}
+
+/// Stop using templates by default for a deleted knowledge base.
+pub fn remove_kb(kb_id: &str) {
+    forget(|t| {
+        let before = t.kb_ids.len();
+        t.kb_ids.retain(|id| id != kb_id);
+        t.kb_ids.len() != before
+    });
+}
+
+/// Stop using templates by default for a deleted conversation.
+pub fn remove_conversation(conv_id: &str) {
+    forget(|t| {
+        let before = t.conversation_ids.len();
+        t.conversation_ids.retain(|id| id != conv_id);
+        t.conversation_ids.len() != before
+    });
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// List prompt templates, or only those available to a knowledge base
+///
+/// With `kb_id`, templates that are the default of other knowledge bases only are left
+/// out.
+#[tauri::command]
+pub async fn list_prompt_templates(kb_id: Option<String>) -> Result<Vec<PromptTemplate>, String> {
+    let mut templates: Vec<PromptTemplate> = store::load(STATE_FILE);
+    if let Some(kb_id) = kb_id {
+        templates.retain(|t| t.kb_ids.is_empty() || t.kb_ids.contains(&kb_id));
+    }
+    Ok(templates)
+}
+
+/// Create a prompt template, optionally as the default of knowledge bases and
+/// conversations
+#[tauri::command]
+pub async fn create_prompt_template(
+    name: String,
+    system_prompt: String,
+    kb_ids: Option<Vec<String>>,
+    conversation_ids: Option<Vec<String>>,
+) -> Result<PromptTemplate, String> {
+    validate(&name, &system_prompt)?;
+    let now = chrono::Utc::now();
+    let template = PromptTemplate {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.trim().to_string(),
+        system_prompt: system_prompt.trim().to_string(),
+        kb_ids: kb_ids.unwrap_or_default(),
+        conversation_ids: conversation_ids.unwrap_or_default(),
+        created_at: now,
+        updated_at: now,
+    };
+
+    let mut templates: Vec<PromptTemplate> = store::load(STATE_FILE);
+    take_defaults(
+        &mut templates,
+        &template.id,
+        &template.kb_ids,
+        &template.conversation_ids,
+    );
+    templates.push(template.clone());
+    store::save(STATE_FILE, &templates).map_err(|e| e.to_string())?;
+    Ok(template)
+}
+
+/// Update a prompt template; fields left out are unchanged
+#[tauri::command]
+pub async fn update_prompt_template(
+    id: String,
+    name: Option<String>,
+    system_prompt: Option<String>,
+    kb_ids: Option<Vec<String>>,
+    conversation_ids: Option<Vec<String>>,
+) -> Result<PromptTemplate, String> {
+    let mut templates: Vec<PromptTemplate> = store::load(STATE_FILE);
+    let index = templates
+        .iter()
+        .position(|t| t.id == id)
+        .ok_or_else(|| format!("Template not found: {}", id))?;
+
+    let mut template = templates[index].clone();
+    if let Some(name) = name {
+        template.name = name.trim().to_string();
+    }
+    if let Some(system_prompt) = system_prompt {
+        template.system_prompt = system_prompt.trim().to_string();
+    }
+    if let Some(kb_ids) = kb_ids {
+        template.kb_ids = kb_ids;
+    }
+    if let Some(conversation_ids) = conversation_ids {
+        template.conversation_ids = conversation_ids;
+    }
+    validate(&template.name, &template.system_prompt)?;
+    template.updated_at = chrono::Utc::now();
+
+    take_defaults(
+        &mut templates,
+        &template.id,
+        &template.kb_ids,
+        &template.conversation_ids,
+    );
+    templates[index] = template.clone();
+    store::save(STATE_FILE, &templates).map_err(|e| e.to_string())?;
+    Ok(template)
+}
+
+/// Delete a prompt template
+#[tauri::command]
+pub async fn delete_prompt_template(id: String) -> Result<bool, String> {
+    let mut templates: Vec<PromptTemplate> = store::load(STATE_FILE);
+    let before = templates.len();
+    templates.retain(|t| t.id != id);
+    if templates.len() == before {
+        return Ok(false);
+    }
+    store::save(STATE_FILE, &templates).map_err(|e| e.to_string())?;
+    Ok(true)
+}
@@ -0,0 +1,308 @@
+//! Document provenance of answers.
+//!
+//! The shell hashes every file it ingests (SHA-256 of the content) and sends the hash
+//! and ingestion time with the file, so the backend can stamp its chunks with them.
+//! The hashes are also kept locally, with the hashes of the files each answer cited, so
+//! `verify_source_integrity` can warn when a cited file has changed or disappeared
+//! since the answer was generated.
+
+use crate::commands::Source;
+use crate::files::hex;
+use crate::store;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Mutex;
+
+const STATE_FILE: &str = "provenance.json";
+
+/// Serializes updates of the provenance store.
+static UPDATES: Mutex<()> = Mutex::new(());
+
+/// Hash and ingestion time of a file, sent with it to the backend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub content_hash: String,
+    pub ingested_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DocumentProvenance {
+    document_id: String,
+    /// Original file, `None` for uploaded content
+    path: Option<String>,
+    #[serde(flatten)]
+    stamp: FileStamp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CitedFile {
+    filename: String,
+    path: Option<String>,
+    content_hash: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AnswerProvenance {
+    kb_id: String,
+    answered_at: String,
+    sources: Vec<CitedFile>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct ProvenanceStore {
+    /// Ingested documents by knowledge base, then filename
+    documents: BTreeMap<String, BTreeMap<String, DocumentProvenance>>,
+    /// Files cited by each answer, by message id
+    answers: BTreeMap<String, AnswerProvenance>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntegrityStatus {
+    Unchanged,
+    Modified,
+    Missing,
+    /// The file wasn't hashed when it was ingested, or has no path (uploads)
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceIntegrity {
+    pub filename: String,
+    pub path: Option<String>,
+    pub status: IntegrityStatus,
+    /// Hash of the file when the answer was generated
+    pub content_hash: Option<String>,
+    pub current_hash: Option<String>,
+}
+
+fn update(f: impl FnOnce(&mut ProvenanceStore)) {
+    let _guard = UPDATES.lock().unwrap();
+    let mut provenance: ProvenanceStore = store::load(STATE_FILE);
+    f(&mut provenance);
+    if let Err(e) = store::save(STATE_FILE, &provenance) {
+        tracing::warn!("Failed to save document provenance: {}", e);
+    }
+}
+
+/// SHA-256 of a file's content, as lowercase hex.
+pub fn file_hash(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex(&hasher.finalize()))
+}
+
+/// Stamp a file about to be ingested.
+pub fn stamp_file(path: &Path) -> Option<FileStamp> {
+    match file_hash(path) {
+        Ok(content_hash) => Some(FileStamp {
+            content_hash,
+            ingested_at: chrono::Utc::now().to_rfc3339(),
+        }),
+        Err(e) => {
+            tracing::warn!("Failed to hash {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Stamp content uploaded from memory.
+pub fn stamp_bytes(bytes: &[u8]) -> FileStamp {
+    FileStamp {
+        content_hash: content_hash(bytes),
+        ingested_at: chrono::Utc::now().to_rfc3339(),
+    }
+}
+
+/// SHA-256 of some content, as lowercase hex.
+pub fn content_hash(bytes: &[u8]) -> String {
+    hex(&Sha256::digest(bytes))
+}
+
+/// Stamps of the documents ingested in a knowledge base, by filename.
+pub fn document_stamps(kb_id: &str) -> BTreeMap<String, FileStamp> {
+    let mut provenance: ProvenanceStore = store::load(STATE_FILE);
+    provenance
+        .documents
+        .remove(kb_id)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(filename, document)| (filename, document.stamp))
+        .collect()
+}
+
+/// Remember the stamp of an ingested document.
+pub fn record_document(
+    kb_id: &str,
+    document_id: &str,
+    filename: &str,
+    path: Option<&str>,
+    stamp: FileStamp,
+) {
+    update(|provenance| {
+        provenance
+            .documents
+            .entry(kb_id.to_string())
+            .or_default()
+            .insert(
+                filename.to_string(),
+                DocumentProvenance {
+                    document_id: document_id.to_string(),
+                    path: path.map(String::from),
+                    stamp,
+                },
+            );
+    });
+}
+
+/// Forget a deleted document.
+pub fn remove_document(kb_id: &str, document_id: &str) {
+    update(|provenance| {
+        if let Some(documents) = provenance.documents.get_mut(kb_id) {
+            documents.retain(|_, d| d.document_id != document_id);
+        }
+    });
+}
+
+/// Forget the documents of a deleted knowledge base.
+pub fn remove_kb(kb_id: &str) {
+    update(|provenance| {
+        provenance.documents.remove(kb_id);
+    });
+}
+
+/// Path of an ingested file, searching every knowledge base unless one is given.
+pub fn find_path(kb_id: Option<&str>, filename: &str) -> Option<String> {
+    let provenance: ProvenanceStore = store::load(STATE_FILE);
+    provenance
+        .documents
+        .iter()
+        .filter(|(kb, _)| kb_id.is_none_or(|id| id == kb.as_str()))
+        .find_map(|(_, documents)| documents.get(filename)?.path.clone())
+}
+
+/// Whether a path is the original file of an ingested document, in any knowledge base
+/// unless one is given.
+pub fn is_ingested_path(kb_id: Option<&str>, path: &str) -> bool {
+    let provenance: ProvenanceStore = store::load(STATE_FILE);
+    provenance
+        .documents
+        .iter()
+        .filter(|(kb, _)| kb_id.is_none_or(|id| id == kb.as_str()))
+        .flat_map(|(_, documents)| documents.values())
+        .any(|document| document.path.as_deref() == Some(path))
+}
+
+/// Fill in the stamps of sources the backend returned without them.
+pub fn annotate(kb_id: &str, sources: &mut [Source]) {
+    let provenance: ProvenanceStore = store::load(STATE_FILE);
+    let Some(documents) = provenance.documents.get(kb_id) else {
+        return;
+    };
+    for source in sources {
+        if source.content_hash.is_some() {
+            continue;
+        }
+        if let Some(document) = documents.get(&source.filename) {
+            source.content_hash = Some(document.stamp.content_hash.clone());
+            source.ingested_at = Some(document.stamp.ingested_at.clone());
+        }
+    }
+}
+
+/// Remember the files an answer cited, with their hashes at that time.
+pub fn record_answer(message_id: &str, kb_id: &str, sources: &[Source]) {
+    update(|provenance| {
+        let documents = provenance.documents.get(kb_id);
+        let mut cited: Vec<CitedFile> = Vec::new();
+        for source in sources {
+            if cited.iter().any(|c| c.filename == source.filename) {
+                continue;
+            }
+            let document = documents.and_then(|d| d.get(&source.filename));
+            cited.push(CitedFile {
+                filename: source.filename.clone(),
+                path: document.and_then(|d| d.path.clone()),
+                content_hash: source
+                    .content_hash
+                    .clone()
+                    .or_else(|| document.map(|d| d.stamp.content_hash.clone())),
+            });
+        }
+        provenance.answers.insert(
+            message_id.to_string(),
+            AnswerProvenance {
+                kb_id: kb_id.to_string(),
+                answered_at: chrono::Utc::now().to_rfc3339(),
+                sources: cited,
+            },
+        );
+    });
+}
+
+fn check(cited: CitedFile) -> SourceIntegrity {
+    let (status, current_hash) = match (&cited.path, &cited.content_hash) {
+        (Some(path), Some(hash)) => match file_hash(Path::new(path)) {
+            Ok(current) if current == *hash => (IntegrityStatus::Unchanged, Some(current)),
+            Ok(current) => (IntegrityStatus::Modified, Some(current)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (IntegrityStatus::Missing, None),
+            Err(e) => {
+                tracing::warn!("Failed to hash {}: {}", path, e);
+                (IntegrityStatus::Unknown, None)
+            }
+        },
+        _ => (IntegrityStatus::Unknown, None),
+    };
+    SourceIntegrity {
+        filename: cited.filename,
+        path: cited.path,
+        status,
+        content_hash: cited.content_hash,
+        current_hash,
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Check whether the files cited by an answer changed since it was generated
+#[tauri::command]
+pub async fn verify_source_integrity(message_id: String) -> Result<Vec<SourceIntegrity>, String> {
+    let answer = store::load::<ProvenanceStore>(STATE_FILE)
+        .answers
+        .remove(&message_id)
+        .ok_or_else(|| format!("No provenance recorded for message {}", message_id))?;
+    tauri::async_runtime::spawn_blocking(move || {
+        answer.sources.into_iter().map(check).collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .inspect(|results| {
+        for result in results {
+            if matches!(
+                result.status,
+                IntegrityStatus::Modified | IntegrityStatus::Missing
+            ) {
+                tracing::warn!(
+                    "Source {} of message {} is {:?}",
+                    result.filename,
+                    message_id,
+                    result.status
+                );
+            }
+        }
+    })
+}
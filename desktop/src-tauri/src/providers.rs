@@ -0,0 +1,367 @@
+//! LLM provider registry.
+//!
+//! The built-in providers are the ones the backend knows by name. Users can register
+//! any other endpoint that speaks one of their APIs (LM Studio, vLLM or LiteLLM with the
+//! OpenAI API, Azure OpenAI deployments) without a code change: custom providers are
+//! stored in llm_providers.json and forwarded to the backend, again each time it starts
+//! since the shell's registry is the reference. A custom provider is then selected by
+//! its id like any other, and its API key set with `set_api_key`.
+//!
+//! `test_api_key` checks a key with the cheapest authenticated call each provider has,
+//! listing its models, and reports the latency, the models the key can use and the
+//! rate limit headers the provider sent.
+
+use crate::backend::{backend_request, http_client};
+use crate::capabilities::{self, Feature};
+use crate::{network, ollama, store};
+use reqwest::header::HeaderMap;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::{Duration, Instant};
+
+const STATE_FILE: &str = "llm_providers.json";
+const KEY_TEST_TIMEOUT: Duration = Duration::from_secs(15);
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+const AZURE_API_VERSION: &str = "2024-10-21";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ApiStyle {
+    /// OpenAI chat completions, also served by LM Studio, vLLM and LiteLLM
+    #[serde(rename = "openai")]
+    OpenAi,
+    #[serde(rename = "azure_openai")]
+    AzureOpenAi,
+    #[serde(rename = "anthropic")]
+    Anthropic,
+    #[serde(rename = "ollama")]
+    Ollama,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmProvider {
+    /// Name used in the settings and API keys, e.g. `openai` or `lm_studio`
+    pub id: String,
+    pub name: String,
+    /// Endpoint of a custom provider; built-ins use the backend's defaults
+    pub base_url: Option<String>,
+    pub api_style: ApiStyle,
+    #[serde(default)]
+    pub builtin: bool,
+    /// Whether the provider runs on this machine, so offline mode allows it
+    #[serde(default)]
+    pub local: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QuotaHints {
+    pub remaining_requests: Option<u64>,
+    pub remaining_tokens: Option<u64>,
+    /// When the request limit resets, as the provider wrote it (e.g. `20s`)
+    pub reset: Option<String>,
+    /// The key is valid but rate limited or out of credit
+    pub exhausted: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TestApiKeyResponse {
+    pub ok: bool,
+    pub error: Option<String>,
+    pub latency_ms: Option<u64>,
+    /// Models the key can use
+    pub models: Vec<String>,
+    /// Whether the requested model is among them
+    pub model_available: Option<bool>,
+    pub quota: QuotaHints,
+}
+
+fn builtin(id: &str, name: &str, api_style: ApiStyle, local: bool) -> LlmProvider {
+    LlmProvider {
+        id: id.to_string(),
+        name: name.to_string(),
+        base_url: None,
+        api_style,
+        builtin: true,
+        local,
+    }
+}
+
+fn builtin_providers() -> Vec<LlmProvider> {
+    vec![
+        builtin("openai", "OpenAI", ApiStyle::OpenAi, false),
+        builtin("anthropic", "Anthropic", ApiStyle::Anthropic, false),
+        builtin("mistral", "Mistral", ApiStyle::OpenAi, false),
+        builtin("groq", "Groq", ApiStyle::OpenAi, false),
+        builtin("deepseek", "DeepSeek", ApiStyle::OpenAi, false),
+        builtin("gemini", "Gemini", ApiStyle::OpenAi, false),
+        builtin("cohere", "Cohere", ApiStyle::OpenAi, false),
+        builtin("ollama", "Ollama", ApiStyle::Ollama, true),
+    ]
+}
+
+fn custom_providers() -> Vec<LlmProvider> {
+    store::load(STATE_FILE)
+}
+
+/// Whether a provider id is a custom provider running on this machine.
+pub fn is_local_custom(id: &str) -> bool {
+    custom_providers().iter().any(|p| p.id == id && p.local)
+}
+
+/// Provider id derived from its name: lowercase letters, digits and underscores.
+fn provider_id(name: &str) -> String {
+    let id: String = name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    id.split('_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+async fn forward(provider: &LlmProvider) -> Result<(), String> {
+    backend_request::<serde_json::Value>(
+        Method::PUT,
+        &format!("/api/providers/custom/{}", provider.id),
+        Some(json!({
+            "name": provider.name,
+            "base_url": provider.base_url,
+            "api_style": provider.api_style,
+        })),
+    )
+    .await
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+/// Register the custom providers with a backend that just started.
+pub async fn sync() {
+    let providers = custom_providers();
+    if providers.is_empty() || !capabilities::supports(Feature::CustomProviders) {
+        return;
+    }
+    for provider in &providers {
+        if let Err(e) = forward(provider).await {
+            tracing::warn!("Failed to register provider {}: {}", provider.id, e);
+        }
+    }
+}
+
+/// Model listing endpoint of a provider, the cheapest call that needs a valid key.
+fn models_url(provider: &LlmProvider) -> Option<String> {
+    if let Some(base_url) = &provider.base_url {
+        return Some(match provider.api_style {
+            ApiStyle::OpenAi => format!("{}/models", base_url),
+            ApiStyle::AzureOpenAi => format!(
+                "{}/openai/models?api-version={}",
+                base_url, AZURE_API_VERSION
+            ),
+            ApiStyle::Anthropic => format!("{}/v1/models", base_url),
+            ApiStyle::Ollama => format!("{}/api/tags", base_url),
+        });
+    }
+    let url = match provider.id.as_str() {
+        "openai" => "https://api.openai.com/v1/models",
+        "anthropic" => "https://api.anthropic.com/v1/models",
+        "mistral" => "https://api.mistral.ai/v1/models",
+        "groq" => "https://api.groq.com/openai/v1/models",
+        "deepseek" => "https://api.deepseek.com/models",
+        "gemini" => "https://generativelanguage.googleapis.com/v1beta/openai/models",
+        "cohere" => "https://api.cohere.com/v1/models",
+        "ollama" => return Some(format!("{}/api/tags", ollama::ollama_url())),
+        _ => return None,
+    };
+    Some(url.to_string())
+}
+
+fn header_value(headers: &HeaderMap, names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .find_map(|name| headers.get(*name)?.to_str().ok())
+        .map(str::to_string)
+}
+
+fn quota_hints(headers: &HeaderMap) -> QuotaHints {
+    let number = |names: &[&str]| header_value(headers, names).and_then(|v| v.parse().ok());
+    QuotaHints {
+        remaining_requests: number(&[
+            "x-ratelimit-remaining-requests",
+            "anthropic-ratelimit-requests-remaining",
+        ]),
+        remaining_tokens: number(&[
+            "x-ratelimit-remaining-tokens",
+            "anthropic-ratelimit-tokens-remaining",
+        ]),
+        reset: header_value(
+            headers,
+            &[
+                "x-ratelimit-reset-requests",
+                "anthropic-ratelimit-requests-reset",
+            ],
+        ),
+        exhausted: false,
+    }
+}
+
+/// Model ids of a listing: `data[].id` (OpenAI, Anthropic, Azure) or `models[].name`
+/// (Ollama, Cohere).
+fn model_names(body: &serde_json::Value) -> Vec<String> {
+    let listed = body["data"]
+        .as_array()
+        .or_else(|| body["models"].as_array());
+    listed
+        .into_iter()
+        .flatten()
+        .filter_map(|m| m["id"].as_str().or_else(|| m["name"].as_str()))
+        .map(|name| name.trim_start_matches("models/").to_string())
+        .collect()
+}
+
+async fn check_key(
+    provider: &LlmProvider,
+    api_key: &str,
+    model: Option<&str>,
+) -> Result<TestApiKeyResponse, String> {
+    let url = models_url(provider)
+        .ok_or_else(|| format!("Don't know how to test {} keys", provider.name))?;
+    network::check_url(&url)?;
+    let mut request = http_client().get(&url).timeout(KEY_TEST_TIMEOUT);
+    request = match provider.api_style {
+        ApiStyle::OpenAi => request.bearer_auth(api_key),
+        ApiStyle::AzureOpenAi => request.header("api-key", api_key),
+        ApiStyle::Anthropic => request
+            .header("x-api-key", api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION),
+        ApiStyle::Ollama => request,
+    };
+
+    let started = Instant::now();
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Could not reach {}: {}", provider.name, e))?;
+    let latency_ms = Some(started.elapsed().as_millis() as u64);
+    let status = response.status();
+    let mut quota = quota_hints(response.headers());
+    let text = response.text().await.unwrap_or_default();
+    let mut result = TestApiKeyResponse {
+        ok: status.is_success(),
+        error: None,
+        latency_ms,
+        models: Vec::new(),
+        model_available: None,
+        quota: QuotaHints::default(),
+    };
+
+    if status.is_success() {
+        let body: serde_json::Value = serde_json::from_str(&text).unwrap_or_default();
+        result.models = model_names(&body);
+        result.model_available = model.map(|m| result.models.iter().any(|n| n == m));
+    } else {
+        let lower = text.to_lowercase();
+        result.error = Some(match status.as_u16() {
+            401 | 403 => "The API key was rejected".to_string(),
+            402 => "The account is out of credit".to_string(),
+            429 if lower.contains("quota") || lower.contains("billing") => {
+                "The key's quota is exhausted".to_string()
+            }
+            429 => "The key is rate limited".to_string(),
+            _ => format!("{} answered {}", provider.name, status),
+        });
+        quota.exhausted = matches!(status.as_u16(), 402 | 429);
+    }
+    result.quota = quota;
+    Ok(result)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// List the built-in and custom LLM providers
+#[tauri::command]
+pub async fn list_llm_providers() -> Result<Vec<LlmProvider>, String> {
+    let mut providers = builtin_providers();
+    providers.extend(custom_providers());
+    Ok(providers)
+}
+
+/// Register an endpoint speaking the OpenAI, Azure OpenAI, Anthropic or Ollama API as
+/// a provider, or update the custom provider of the same name
+#[tauri::command]
+pub async fn add_custom_provider(
+    name: String,
+    base_url: String,
+    api_style: ApiStyle,
+) -> Result<LlmProvider, String> {
+    capabilities::require(Feature::CustomProviders)?;
+    let name = name.trim().to_string();
+    let id = provider_id(&name);
+    if id.is_empty() {
+        return Err("Provider name cannot be empty".into());
+    }
+    if builtin_providers().iter().any(|p| p.id == id) {
+        return Err(format!("{} is a built-in provider", name));
+    }
+    let url = reqwest::Url::parse(base_url.trim()).map_err(|e| format!("Invalid URL: {}", e))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err("The base URL must use http or https".to_string());
+    }
+    let base_url = url.as_str().trim_end_matches('/').to_string();
+
+    let provider = LlmProvider {
+        id,
+        name,
+        local: network::is_local_url(&base_url),
+        base_url: Some(base_url),
+        api_style,
+        builtin: false,
+    };
+    forward(&provider).await?;
+
+    let mut providers = custom_providers();
+    providers.retain(|p| p.id != provider.id);
+    providers.push(provider.clone());
+    store::save(STATE_FILE, &providers).map_err(|e| e.to_string())?;
+    Ok(provider)
+}
+
+/// Remove a custom provider
+#[tauri::command]
+pub async fn remove_custom_provider(id: String) -> Result<bool, String> {
+    let mut providers = custom_providers();
+    let before = providers.len();
+    providers.retain(|p| p.id != id);
+    if providers.len() == before {
+        return Ok(false);
+    }
+    if capabilities::supports(Feature::CustomProviders) {
+        backend_request::<serde_json::Value>(
+            Method::DELETE,
+            &format!("/api/providers/custom/{}", id),
+            None,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    store::save(STATE_FILE, &providers).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// Check an API key against its provider, optionally checking a model is available
+#[tauri::command]
+pub async fn test_api_key(
+    provider: String,
+    api_key: String,
+    model: Option<String>,
+) -> Result<TestApiKeyResponse, String> {
+    let provider = builtin_providers()
+        .into_iter()
+        .chain(custom_providers())
+        .find(|p| p.id == provider)
+        .ok_or_else(|| format!("Unknown provider: {}", provider))?;
+    check_key(&provider, api_key.trim(), model.as_deref()).await
+}
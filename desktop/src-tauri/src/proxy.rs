@@ -0,0 +1,298 @@
+//! HTTP(S) proxy and custom CA certificates.
+//!
+//! Corporate networks often only reach the internet through a proxy that intercepts
+//! TLS with a private certificate authority. The preferences choose the system proxy
+//! (the `HTTPS_PROXY` family of variables, then the OS settings on macOS and Windows),
+//! a manual proxy with optional credentials, or no proxy, plus a PEM bundle of extra CA
+//! certificates. Both apply to the shell's shared HTTP client and are passed to the
+//! backend, which makes the calls to the LLM and embedding providers, through the usual
+//! proxy and CA variables. Requests to the machine itself never go through the proxy.
+//! A `proxy` set in config.toml or `RAGKIT_PROXY` takes precedence over the preferences.
+
+use crate::preferences::{self, Preferences};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// Hosts always reached directly, on top of the user's `NO_PROXY`.
+const LOCAL_HOSTS: &str = "localhost,127.0.0.1,::1";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyMode {
+    /// The proxy of the environment or the OS settings, if any
+    #[default]
+    System,
+    Manual,
+    /// Always connect directly
+    Off,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManualProxy {
+    /// Host, optionally with a scheme (`http://` when omitted)
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxySource {
+    /// config.toml or `RAGKIT_PROXY`
+    Config,
+    Manual,
+    Environment,
+    Os,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyStatus {
+    pub mode: ProxyMode,
+    /// Proxy in use, without credentials
+    pub proxy_url: Option<String>,
+    pub source: Option<ProxySource>,
+    /// Certificates loaded from the CA bundle
+    pub ca_certificates: usize,
+    /// Why the proxy or CA bundle can't be used
+    pub error: Option<String>,
+}
+
+/// A proxy to use, with its credentials in the URL.
+struct ResolvedProxy {
+    url: reqwest::Url,
+    source: ProxySource,
+}
+
+pub fn parse_proxy_url(value: &str) -> Option<reqwest::Url> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    if value.contains("://") {
+        reqwest::Url::parse(value).ok()
+    } else {
+        reqwest::Url::parse(&format!("http://{}", value)).ok()
+    }
+}
+
+fn environment_proxy() -> Option<reqwest::Url> {
+    [
+        "HTTPS_PROXY",
+        "https_proxy",
+        "ALL_PROXY",
+        "all_proxy",
+        "HTTP_PROXY",
+        "http_proxy",
+    ]
+    .iter()
+    .find_map(|name| std::env::var(name).ok().and_then(|v| parse_proxy_url(&v)))
+}
+
+/// HTTPS proxy of the network settings, from `scutil --proxy`.
+#[cfg(target_os = "macos")]
+fn os_proxy() -> Option<reqwest::Url> {
+    let output = std::process::Command::new("scutil")
+        .arg("--proxy")
+        .output()
+        .ok()?;
+    let output = String::from_utf8_lossy(&output.stdout);
+    let value = |key: &str| {
+        output.lines().find_map(|line| {
+            let (k, v) = line.split_once(':')?;
+            (k.trim() == key).then(|| v.trim().to_string())
+        })
+    };
+    if value("HTTPSEnable").as_deref() != Some("1") {
+        return None;
+    }
+    parse_proxy_url(&format!("{}:{}", value("HTTPSProxy")?, value("HTTPSPort")?))
+}
+
+/// Proxy of the Internet Settings, from the registry.
+#[cfg(target_os = "windows")]
+fn os_proxy() -> Option<reqwest::Url> {
+    let output = std::process::Command::new("reg")
+        .args([
+            "query",
+            r"HKCU\Software\Microsoft\Windows\CurrentVersion\Internet Settings",
+        ])
+        .output()
+        .ok()?;
+    let output = String::from_utf8_lossy(&output.stdout);
+    let value = |key: &str| {
+        output.lines().find_map(|line| {
+            // `name    REG_TYPE    value`
+            let mut parts = line.split_whitespace();
+            if parts.next() != Some(key) {
+                return None;
+            }
+            parts.nth(1).map(str::to_string)
+        })
+    };
+    if value("ProxyEnable").as_deref() != Some("0x1") {
+        return None;
+    }
+    // Either `host:port` or per-protocol entries like `http=host:port;https=host:port`
+    let server = value("ProxyServer")?;
+    let server = server
+        .split(';')
+        .find_map(|entry| entry.strip_prefix("https="))
+        .or_else(|| {
+            server
+                .split(';')
+                .find_map(|entry| entry.strip_prefix("http="))
+        })
+        .unwrap_or(&server);
+    parse_proxy_url(server)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn os_proxy() -> Option<reqwest::Url> {
+    None
+}
+
+fn manual_proxy(manual: &ManualProxy) -> Result<reqwest::Url> {
+    let mut url = parse_proxy_url(&format!("{}:{}", manual.host, manual.port))
+        .ok_or_else(|| anyhow!("Invalid proxy host: {}", manual.host))?;
+    if let Some(username) = manual.username.as_deref().filter(|u| !u.is_empty()) {
+        url.set_username(username)
+            .map_err(|_| anyhow!("Invalid proxy username"))?;
+        url.set_password(manual.password.as_deref())
+            .map_err(|_| anyhow!("Invalid proxy password"))?;
+    }
+    Ok(url)
+}
+
+fn resolve(prefs: &Preferences) -> Result<Option<ResolvedProxy>> {
+    if let Some(proxy) = &crate::config::get().proxy {
+        return Ok(Some(ResolvedProxy {
+            url: proxy.value.clone(),
+            source: ProxySource::Config,
+        }));
+    }
+    Ok(match prefs.proxy_mode {
+        ProxyMode::Off => None,
+        ProxyMode::Manual => {
+            let manual = prefs
+                .manual_proxy
+                .as_ref()
+                .ok_or_else(|| anyhow!("The manual proxy isn't configured"))?;
+            Some(ResolvedProxy {
+                url: manual_proxy(manual)?,
+                source: ProxySource::Manual,
+            })
+        }
+        ProxyMode::System => environment_proxy()
+            .map(|url| ResolvedProxy {
+                url,
+                source: ProxySource::Environment,
+            })
+            .or_else(|| {
+                os_proxy().map(|url| ResolvedProxy {
+                    url,
+                    source: ProxySource::Os,
+                })
+            }),
+    })
+}
+
+fn no_proxy_list() -> String {
+    match std::env::var("NO_PROXY").or_else(|_| std::env::var("no_proxy")) {
+        Ok(list) if !list.trim().is_empty() => format!("{},{}", LOCAL_HOSTS, list.trim()),
+        _ => LOCAL_HOSTS.to_string(),
+    }
+}
+
+fn ca_certificates(path: &str) -> Result<Vec<reqwest::Certificate>> {
+    let pem =
+        std::fs::read(path).map_err(|e| anyhow!("Failed to read CA bundle {}: {}", path, e))?;
+    let certificates = reqwest::Certificate::from_pem_bundle(&pem)
+        .map_err(|e| anyhow!("Invalid CA bundle {}: {}", path, e))?;
+    if certificates.is_empty() {
+        return Err(anyhow!("No certificate found in {}", path));
+    }
+    Ok(certificates)
+}
+
+/// Apply the proxy and CA bundle of the preferences to an HTTP client.
+pub fn configure(
+    mut builder: reqwest::ClientBuilder,
+    prefs: &Preferences,
+) -> Result<reqwest::ClientBuilder> {
+    builder = match resolve(prefs)? {
+        Some(proxy) => {
+            // Credentials in the URL are sent as basic auth
+            let proxy = reqwest::Proxy::all(proxy.url.as_str())?
+                .no_proxy(reqwest::NoProxy::from_string(&no_proxy_list()));
+            builder.proxy(proxy)
+        }
+        None => builder.no_proxy(),
+    };
+    if let Some(path) = prefs.ca_bundle_path.as_deref().filter(|p| !p.is_empty()) {
+        for certificate in ca_certificates(path)? {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    Ok(builder)
+}
+
+/// Proxy and CA variables for the backend process.
+///
+/// Python's HTTP clients replace their trust store with the CA bundle, so the bundle
+/// should also contain the public roots when only some hosts are intercepted.
+pub fn backend_env(prefs: &Preferences) -> Vec<(&'static str, String)> {
+    let mut env = Vec::new();
+    match resolve(prefs) {
+        Ok(Some(proxy)) => {
+            for name in ["HTTPS_PROXY", "HTTP_PROXY", "https_proxy", "http_proxy"] {
+                env.push((name, proxy.url.to_string()));
+            }
+            env.push(("NO_PROXY", no_proxy_list()));
+            env.push(("no_proxy", no_proxy_list()));
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Not passing a proxy to the backend: {}", e),
+    }
+    if let Some(path) = prefs.ca_bundle_path.as_deref().filter(|p| !p.is_empty()) {
+        for name in ["SSL_CERT_FILE", "REQUESTS_CA_BUNDLE", "CURL_CA_BUNDLE"] {
+            env.push((name, path.to_string()));
+        }
+    }
+    env
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Get the proxy and CA bundle in use, as resolved from the preferences
+#[tauri::command]
+pub async fn get_proxy_status() -> Result<ProxyStatus, String> {
+    let prefs = preferences::load();
+    let mut status = ProxyStatus {
+        mode: prefs.proxy_mode,
+        proxy_url: None,
+        source: None,
+        ca_certificates: 0,
+        error: None,
+    };
+    match resolve(&prefs) {
+        Ok(Some(proxy)) => {
+            let mut url = proxy.url;
+            let _ = url.set_username("");
+            let _ = url.set_password(None);
+            status.proxy_url = Some(url.to_string());
+            status.source = Some(proxy.source);
+        }
+        Ok(None) => {}
+        Err(e) => status.error = Some(e.to_string()),
+    }
+    if let Some(path) = prefs.ca_bundle_path.as_deref().filter(|p| !p.is_empty()) {
+        match ca_certificates(path) {
+            Ok(certificates) => status.ca_certificates = certificates.len(),
+            Err(e) => status.error = Some(e.to_string()),
+        }
+    }
+    Ok(status)
+}
@@ -0,0 +1,267 @@
+//! Read-aloud and follow-along mode for cited documents.
+//!
+//! The shell fetches the section surrounding a cited chunk, splits it into sentences,
+//! opens the document viewer window, and paces playback by emitting one
+//! `read-aloud-segment` event per sentence. The viewer speaks each segment with the
+//! webview's speech synthesis and highlights it, so pausing, resuming, and stopping are
+//! controlled from here regardless of which window issued the command.
+
+use crate::backend::backend_request;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
+use tokio::sync::{watch, Mutex};
+
+const VIEWER_LABEL: &str = "viewer";
+const DEFAULT_WORDS_PER_MINUTE: u32 = 170;
+/// Pause inserted between sentences so speech never overlaps.
+const SEGMENT_GAP: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Playback {
+    Playing,
+    Paused,
+    Stopped,
+}
+
+struct Session {
+    id: String,
+    control: watch::Sender<Playback>,
+}
+
+static SESSION: Mutex<Option<Session>> = Mutex::const_new(None);
+
+/// Section of a document surrounding a cited chunk, as returned by the backend.
+#[derive(Debug, Deserialize)]
+struct SourceContext {
+    text: String,
+    /// Character offsets of the cited chunk within `text`
+    chunk_start: usize,
+    chunk_end: usize,
+    page: Option<i32>,
+}
+
+/// A sentence to speak. Offsets are in UTF-16 code units so the webview can map them
+/// directly onto its text nodes.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadAloudSegment {
+    pub index: usize,
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+    /// Whether this sentence belongs to the cited chunk (vs. surrounding context)
+    pub in_chunk: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadAloudSession {
+    pub session_id: String,
+    pub filename: String,
+    pub page: Option<i32>,
+    pub text: String,
+    pub segments: Vec<ReadAloudSegment>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SegmentEvent {
+    session_id: String,
+    segment: ReadAloudSegment,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SessionEndedEvent {
+    session_id: String,
+    completed: bool,
+}
+
+/// Split text into sentences, keeping offsets relative to the whole text.
+fn split_sentences(text: &str, chunk_start: usize, chunk_end: usize) -> Vec<ReadAloudSegment> {
+    let utf16_offset = |byte: usize| text[..byte].encode_utf16().count();
+    let char_offset = |byte: usize| text[..byte].chars().count();
+
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let boundary = match c {
+            '.' | '!' | '?' => chars.peek().is_none_or(|(_, next)| next.is_whitespace()),
+            '\n' => chars.peek().is_some_and(|(_, next)| *next == '\n'),
+            _ => false,
+        };
+        let end = i + c.len_utf8();
+        if boundary || chars.peek().is_none() {
+            let sentence = text[start..end].trim();
+            if !sentence.is_empty() {
+                let (first, last) = (char_offset(start), char_offset(end));
+                segments.push(ReadAloudSegment {
+                    index: segments.len(),
+                    text: sentence.to_string(),
+                    start: utf16_offset(start),
+                    end: utf16_offset(end),
+                    in_chunk: first < chunk_end && last > chunk_start,
+                });
+            }
+            start = end;
+        }
+    }
+
+    segments
+}
+
+fn estimated_duration(text: &str, words_per_minute: u32) -> Duration {
+    let words = text.split_whitespace().count().max(1) as u64;
+    Duration::from_millis(words * 60_000 / words_per_minute.max(1) as u64) + SEGMENT_GAP
+}
+
+fn open_viewer(app: &AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(VIEWER_LABEL) {
+        let _ = window.show();
+        let _ = window.set_focus();
+        return Ok(());
+    }
+
+    WebviewWindowBuilder::new(
+        app,
+        VIEWER_LABEL,
+        WebviewUrl::App("index.html#/viewer".into()),
+    )
+    .title("RAGKIT - Document Viewer")
+    .inner_size(900.0, 700.0)
+    .build()
+    .map(|_| ())
+    .map_err(|e| format!("Failed to open viewer: {}", e))
+}
+
+async fn play(
+    app: AppHandle,
+    session_id: String,
+    segments: Vec<ReadAloudSegment>,
+    words_per_minute: u32,
+    mut control: watch::Receiver<Playback>,
+) {
+    let mut completed = true;
+
+    'segments: for segment in segments {
+        // Wait while paused; bail out when stopped or replaced by a new session.
+        loop {
+            match *control.borrow_and_update() {
+                Playback::Playing => break,
+                Playback::Stopped => {
+                    completed = false;
+                    break 'segments;
+                }
+                Playback::Paused => {}
+            }
+            if control.changed().await.is_err() {
+                completed = false;
+                break 'segments;
+            }
+        }
+
+        let duration = estimated_duration(&segment.text, words_per_minute);
+        let _ = app.emit_to(
+            VIEWER_LABEL,
+            "read-aloud-segment",
+            SegmentEvent {
+                session_id: session_id.clone(),
+                segment,
+            },
+        );
+        tokio::time::sleep(duration).await;
+    }
+
+    let _ = app.emit_to(
+        VIEWER_LABEL,
+        "read-aloud-ended",
+        SessionEndedEvent {
+            session_id: session_id.clone(),
+            completed,
+        },
+    );
+
+    let mut session = SESSION.lock().await;
+    if session.as_ref().is_some_and(|s| s.id == session_id) {
+        *session = None;
+    }
+}
+
+async fn set_playback(state: Playback) -> Result<bool, String> {
+    let session = SESSION.lock().await;
+    match session.as_ref() {
+        Some(session) => Ok(session.control.send(state).is_ok()),
+        None => Ok(false),
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Open a cited document in the viewer and read the chunk and its section aloud
+#[tauri::command]
+pub async fn start_read_aloud(
+    app: AppHandle,
+    filename: String,
+    chunk: String,
+    words_per_minute: Option<u32>,
+) -> Result<ReadAloudSession, String> {
+    let context: SourceContext = backend_request(
+        Method::POST,
+        "/api/sources/context",
+        Some(json!({ "filename": filename, "chunk": chunk })),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let segments = split_sentences(&context.text, context.chunk_start, context.chunk_end);
+    let session = ReadAloudSession {
+        session_id: uuid::Uuid::new_v4().to_string(),
+        filename,
+        page: context.page,
+        text: context.text,
+        segments: segments.clone(),
+    };
+
+    open_viewer(&app)?;
+    let _ = app.emit_to(VIEWER_LABEL, "read-aloud-started", &session);
+
+    // Starting a new session stops the previous one.
+    let (control, receiver) = watch::channel(Playback::Playing);
+    if let Some(previous) = SESSION.lock().await.replace(Session {
+        id: session.session_id.clone(),
+        control,
+    }) {
+        let _ = previous.control.send(Playback::Stopped);
+    }
+
+    tauri::async_runtime::spawn(play(
+        app.clone(),
+        session.session_id.clone(),
+        segments,
+        words_per_minute.unwrap_or(DEFAULT_WORDS_PER_MINUTE),
+        receiver,
+    ));
+
+    Ok(session)
+}
+
+/// Pause the current read-aloud session
+#[tauri::command]
+pub async fn pause_read_aloud() -> Result<bool, String> {
+    set_playback(Playback::Paused).await
+}
+
+/// Resume the current read-aloud session
+#[tauri::command]
+pub async fn resume_read_aloud() -> Result<bool, String> {
+    set_playback(Playback::Playing).await
+}
+
+/// Stop the current read-aloud session
+#[tauri::command]
+pub async fn stop_read_aloud() -> Result<bool, String> {
+    set_playback(Playback::Stopped).await
+}
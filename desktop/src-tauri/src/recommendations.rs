@@ -0,0 +1,142 @@
+//! Document recommendations while chatting.
+//!
+//! After each answer, the shell suggests documents of the knowledge base that are
+//! related to the conversation but haven't been cited yet ("you may also want to look
+//! at…"), to help discovery in large corpora. The backend ranks documents against the
+//! conversation's embeddings; backends without that endpoint fall back to the local
+//! lexical index, searched with the conversation's text.
+
+use crate::backend::backend_request;
+use crate::capabilities::{self, Feature};
+use crate::commands::{self, Message};
+use crate::{lexical_index, preferences, windows};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::AppHandle;
+
+/// Number of recent messages used to search the local index.
+const CONTEXT_MESSAGES: usize = 6;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentRecommendation {
+    pub document_id: String,
+    pub filename: String,
+    /// Passage that relates the document to the conversation
+    pub excerpt: Option<String>,
+    pub score: f64,
+}
+
+/// Payload of the `document-recommendations` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecommendationsEvent {
+    pub conversation_id: String,
+    pub recommendations: Vec<DocumentRecommendation>,
+}
+
+fn cited_filenames(messages: &[Message]) -> Vec<String> {
+    let mut cited: Vec<String> = messages
+        .iter()
+        .filter_map(|m| m.sources.as_ref())
+        .flatten()
+        .map(|s| s.filename.clone())
+        .collect();
+    cited.sort();
+    cited.dedup();
+    cited
+}
+
+/// Rank documents with the local index, using the latest messages as the query.
+fn recommend_locally(
+    kb_id: &str,
+    messages: &[Message],
+    cited: &[String],
+    limit: usize,
+) -> Vec<DocumentRecommendation> {
+    let query = messages
+        .iter()
+        .rev()
+        .take(CONTEXT_MESSAGES)
+        .map(|m| m.content.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    // Cited documents usually rank first, so search past them
+    lexical_index::search(kb_id, &query, limit + cited.len())
+        .into_iter()
+        .filter(|r| !cited.contains(&r.filename))
+        .take(limit)
+        .map(|r| DocumentRecommendation {
+            document_id: r.document_id,
+            filename: r.filename,
+            excerpt: Some(r.excerpt),
+            score: r.score,
+        })
+        .collect()
+}
+
+async fn recommend(
+    kb_id: &str,
+    conv_id: &str,
+    limit: usize,
+) -> Result<Vec<DocumentRecommendation>, String> {
+    let messages = commands::get_messages(conv_id.to_string()).await?;
+    let cited = cited_filenames(&messages);
+
+    if capabilities::supports(Feature::DocumentRecommendations) {
+        let recommended = backend_request::<Vec<DocumentRecommendation>>(
+            Method::POST,
+            &format!("/api/conversations/{}/recommendations", conv_id),
+            Some(json!({ "exclude_filenames": cited, "limit": limit })),
+        )
+        .await;
+        match recommended {
+            Ok(recommended) => return Ok(recommended),
+            Err(e) => tracing::debug!("Falling back to local recommendations: {}", e),
+        }
+    }
+
+    let kb_id = kb_id.to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        recommend_locally(&kb_id, &messages, &cited, limit)
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Emit recommendations for a conversation that just got an answer, in the background.
+pub fn emit_after_answer(app: &AppHandle, kb_id: &str, conv_id: &str) {
+    let prefs = preferences::load();
+    if !prefs.document_recommendations {
+        return;
+    }
+    let app = app.clone();
+    let (kb_id, conv_id) = (kb_id.to_string(), conv_id.to_string());
+    tauri::async_runtime::spawn(async move {
+        match recommend(&kb_id, &conv_id, prefs.document_recommendations_limit).await {
+            Ok(recommendations) if !recommendations.is_empty() => {
+                let event = RecommendationsEvent {
+                    conversation_id: conv_id.clone(),
+                    recommendations,
+                };
+                windows::emit_to_conversation(&app, &conv_id, "document-recommendations", event);
+            }
+            Ok(_) => {}
+            Err(e) => tracing::debug!("No recommendations for conversation {}: {}", conv_id, e),
+        }
+    });
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Suggest documents related to a conversation that it hasn't cited yet
+#[tauri::command]
+pub async fn recommend_documents(
+    conv_id: String,
+    limit: Option<usize>,
+) -> Result<Vec<DocumentRecommendation>, String> {
+    let kb_id = commands::conversation_kb(&conv_id).await?;
+    let limit = limit.unwrap_or_else(|| preferences::load().document_recommendations_limit);
+    recommend(&kb_id, &conv_id, limit).await
+}
@@ -0,0 +1,419 @@
+//! Background re-embedding scheduler.
+//!
+//! Settings changes (new embedding model, new chunking) can require re-embedding a whole
+//! knowledge base. Instead of blocking the app, the work is queued here and processed in
+//! small batches by a low-priority background task that only runs inside the job's
+//! allowed window. Progress is persisted so jobs survive restarts and can be paused.
+//!
+//! `reembed_knowledge_base` runs a job right away, batch after batch, or with `dry_run`
+//! only estimates its time and cost. When the embedding model changes in the settings,
+//! every knowledge base is queued for re-embedding while the app is idle, so no index
+//! is left silently mixing two models.
+
+use crate::backend::{self, backend_request};
+use crate::commands;
+use crate::jobs::StopMode;
+use crate::kb_history::{self, KbChange};
+use crate::store::CachedStore;
+use chrono::{Local, Timelike, Utc};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Mutex;
+
+const BATCH_SIZE: usize = 20;
+const TICK_INTERVAL: Duration = Duration::from_secs(30);
+/// Minimum time without user queries before "idle" jobs may run.
+const IDLE_THRESHOLD_SECS: i64 = 5 * 60;
+/// Local hours (start inclusive, end exclusive) considered "night".
+const NIGHT_HOURS: (u32, u32) = (0, 6);
+
+static JOBS: CachedStore<Vec<ReembeddingJob>> = CachedStore::new("reembedding.json");
+static LAST_ACTIVITY: AtomicI64 = AtomicI64::new(0);
+/// Held while batches are sent to the backend.
+static BATCHES: Mutex<()> = Mutex::const_new(());
+/// Set while the app stops the backend, so no new batch starts.
+static STOPPING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReembeddingWindow {
+    Anytime,
+    Idle,
+    Night,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReembeddingStatus {
+    Pending,
+    Running,
+    Paused,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReembeddingJob {
+    pub kb_id: String,
+    pub window: ReembeddingWindow,
+    pub status: ReembeddingStatus,
+    pub processed_documents: usize,
+    pub total_documents: usize,
+    pub error: Option<String>,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReembeddingEstimate {
+    pub document_count: usize,
+    pub chunk_count: usize,
+    pub estimated_seconds: f64,
+    pub estimated_cost: Option<f64>,
+}
+
+/// Result of `reembed_knowledge_base`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReembedResult {
+    DryRun { estimate: ReembeddingEstimate },
+    Started { job: ReembeddingJob },
+}
+
+/// Payload of the `reembedding-required` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReembeddingRequiredEvent {
+    pub embedding_provider: String,
+    pub embedding_model: String,
+    /// Knowledge bases queued for re-embedding
+    pub kb_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchResponse {
+    processed: usize,
+    total: usize,
+    done: bool,
+}
+
+/// Record user activity so "idle" jobs back off while the app is in use.
+pub fn mark_activity() {
+    LAST_ACTIVITY.store(Utc::now().timestamp(), Ordering::Relaxed);
+}
+
+fn window_open(window: ReembeddingWindow) -> bool {
+    match window {
+        ReembeddingWindow::Anytime => true,
+        ReembeddingWindow::Idle => {
+            Utc::now().timestamp() - LAST_ACTIVITY.load(Ordering::Relaxed) >= IDLE_THRESHOLD_SECS
+        }
+        ReembeddingWindow::Night => {
+            let hour = Local::now().hour();
+            hour >= NIGHT_HOURS.0 && hour < NIGHT_HOURS.1
+        }
+    }
+}
+
+/// Start the background scheduler loop.
+pub fn start_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        // Jobs interrupted mid-batch resume from their last persisted offset.
+        JOBS.update(|jobs| {
+            for job in jobs.iter_mut() {
+                if job.status == ReembeddingStatus::Running {
+                    job.status = ReembeddingStatus::Pending;
+                }
+            }
+        })
+        .await;
+
+        loop {
+            tokio::time::sleep(TICK_INTERVAL).await;
+            run_pending_batches(&app).await;
+        }
+    });
+}
+
+async fn run_pending_batches(app: &AppHandle) {
+    let _batches = BATCHES.lock().await;
+    if STOPPING.load(Ordering::Relaxed) || !backend::is_running() {
+        return;
+    }
+
+    let runnable: Vec<ReembeddingJob> = JOBS
+        .read(|jobs| {
+            jobs.iter()
+                .filter(|j| {
+                    matches!(
+                        j.status,
+                        ReembeddingStatus::Pending | ReembeddingStatus::Running
+                    ) && window_open(j.window)
+                })
+                .cloned()
+                .collect()
+        })
+        .await;
+
+    for job in runnable {
+        if STOPPING.load(Ordering::Relaxed) {
+            break;
+        }
+        run_batch(app, &job).await;
+    }
+}
+
+/// Send the next batch of a job to the backend and record its progress.
+async fn run_batch(app: &AppHandle, job: &ReembeddingJob) -> Option<ReembeddingJob> {
+    let result = backend_request::<BatchResponse>(
+        Method::POST,
+        &format!("/api/knowledge-bases/{}/reembed", job.kb_id),
+        Some(json!({
+            "offset": job.processed_documents,
+            "batch_size": BATCH_SIZE,
+        })),
+    )
+    .await;
+
+    let updated = JOBS
+        .update(|jobs| {
+            let entry = jobs.iter_mut().find(|j| j.kb_id == job.kb_id)?;
+            // The job may have been paused or cancelled while the batch was running.
+            if entry.status == ReembeddingStatus::Paused {
+                return Some(entry.clone());
+            }
+            match &result {
+                Ok(batch) => {
+                    entry.processed_documents = batch.processed;
+                    entry.total_documents = batch.total;
+                    entry.status = if batch.done {
+                        ReembeddingStatus::Completed
+                    } else {
+                        ReembeddingStatus::Running
+                    };
+                    entry.error = None;
+                }
+                Err(e) => {
+                    entry.status = ReembeddingStatus::Failed;
+                    entry.error = Some(e.to_string());
+                }
+            }
+            entry.updated_at = Utc::now().to_rfc3339();
+            Some(entry.clone())
+        })
+        .await;
+
+    if let Some(job) = &updated {
+        if let Some(error) = &job.error {
+            tracing::error!("Re-embedding of KB {} failed: {}", job.kb_id, error);
+        }
+        if job.status == ReembeddingStatus::Completed {
+            kb_history::record(&job.kb_id, KbChange::Reembedded);
+        }
+        let _ = app.emit("reembedding-progress", job);
+    }
+    updated
+}
+
+/// Run a job's batches back to back until it completes, fails, or is paused.
+async fn run_until_done(app: AppHandle, kb_id: String) {
+    loop {
+        let _batches = BATCHES.lock().await;
+        if STOPPING.load(Ordering::Relaxed) || !backend::is_running() {
+            return;
+        }
+        let job = JOBS
+            .read(|jobs| jobs.iter().find(|j| j.kb_id == kb_id).cloned())
+            .await;
+        let Some(job) = job.filter(|j| {
+            matches!(
+                j.status,
+                ReembeddingStatus::Pending | ReembeddingStatus::Running
+            )
+        }) else {
+            return;
+        };
+        run_batch(&app, &job).await;
+    }
+}
+
+async fn set_status(kb_id: &str, status: ReembeddingStatus) -> Result<ReembeddingJob, String> {
+    JOBS.update(|jobs| {
+        let job = jobs
+            .iter_mut()
+            .find(|j| j.kb_id == kb_id)
+            .ok_or_else(|| format!("No re-embedding job for knowledge base {}", kb_id))?;
+        job.status = status;
+        job.updated_at = Utc::now().to_rfc3339();
+        Ok(job.clone())
+    })
+    .await
+}
+
+/// Queue a knowledge base for re-embedding, replacing any previous job.
+async fn queue(kb_id: &str, window: ReembeddingWindow) -> ReembeddingJob {
+    let job = ReembeddingJob {
+        kb_id: kb_id.to_string(),
+        window,
+        status: ReembeddingStatus::Pending,
+        processed_documents: 0,
+        total_documents: 0,
+        error: None,
+        updated_at: Utc::now().to_rfc3339(),
+    };
+
+    JOBS.update(|jobs| {
+        jobs.retain(|j| j.kb_id != kb_id);
+        jobs.push(job.clone());
+        job
+    })
+    .await
+}
+
+/// Queue every knowledge base for re-embedding after the embedding model changed.
+pub async fn on_embedding_model_changed(app: &AppHandle, settings: &commands::Settings) {
+    let kbs = match commands::list_knowledge_bases().await {
+        Ok(kbs) => kbs,
+        Err(e) => {
+            tracing::warn!("Failed to list knowledge bases to re-embed: {}", e);
+            return;
+        }
+    };
+    let mut kb_ids = Vec::new();
+    for kb in kbs {
+        queue(&kb.id, ReembeddingWindow::Idle).await;
+        kb_ids.push(kb.id);
+    }
+    tracing::info!(
+        "Embedding model changed to {}/{}: {} knowledge bases queued for re-embedding",
+        settings.embedding_provider,
+        settings.embedding_model,
+        kb_ids.len()
+    );
+    let _ = app.emit(
+        "reembedding-required",
+        ReembeddingRequiredEvent {
+            embedding_provider: settings.embedding_provider.clone(),
+            embedding_model: settings.embedding_model.clone(),
+            kb_ids,
+        },
+    );
+}
+
+/// Number of re-embedding jobs in progress.
+pub async fn active_jobs() -> usize {
+    JOBS.read(|jobs| {
+        jobs.iter()
+            .filter(|j| j.status == ReembeddingStatus::Running)
+            .count()
+    })
+    .await
+}
+
+/// Clears `STOPPING` when dropped, also when the wait for the batch is cut short.
+struct StopRequest;
+
+impl Drop for StopRequest {
+    fn drop(&mut self) {
+        STOPPING.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Wait for the batch in flight and stop running jobs. Checkpointed jobs resume from
+/// their last persisted offset at the next start; aborted ones stay paused.
+pub async fn stop_batches(mode: StopMode) {
+    STOPPING.store(true, Ordering::Relaxed);
+    let _stopping = StopRequest;
+    let _batches = BATCHES.lock().await;
+    JOBS.update(|jobs| {
+        for job in jobs
+            .iter_mut()
+            .filter(|j| j.status == ReembeddingStatus::Running)
+        {
+            job.status = match mode {
+                StopMode::Checkpoint => ReembeddingStatus::Pending,
+                StopMode::Abort => ReembeddingStatus::Paused,
+            };
+            job.updated_at = Utc::now().to_rfc3339();
+        }
+    })
+    .await;
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Estimate the scope of re-embedding a knowledge base before committing to it
+#[tauri::command]
+pub async fn estimate_reembedding(kb_id: String) -> Result<ReembeddingEstimate, String> {
+    backend_request(
+        Method::GET,
+        &format!("/api/knowledge-bases/{}/reembed/estimate", kb_id),
+        None,
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Queue a knowledge base for background re-embedding
+#[tauri::command]
+pub async fn schedule_reembedding(
+    kb_id: String,
+    window: ReembeddingWindow,
+) -> Result<ReembeddingJob, String> {
+    Ok(queue(&kb_id, window).await)
+}
+
+/// Re-embed a knowledge base now, or with `dry_run` only estimate the time and cost
+///
+/// Progress is reported through `reembedding-progress` events.
+#[tauri::command]
+pub async fn reembed_knowledge_base(
+    app: AppHandle,
+    kb_id: String,
+    dry_run: Option<bool>,
+) -> Result<ReembedResult, String> {
+    if dry_run.unwrap_or(false) {
+        let estimate = estimate_reembedding(kb_id).await?;
+        return Ok(ReembedResult::DryRun { estimate });
+    }
+    if !backend::is_running() {
+        return Err("The backend is not running".to_string());
+    }
+    let job = queue(&kb_id, ReembeddingWindow::Anytime).await;
+    tauri::async_runtime::spawn(run_until_done(app, kb_id));
+    Ok(ReembedResult::Started { job })
+}
+
+/// Pause a scheduled re-embedding job
+#[tauri::command]
+pub async fn pause_reembedding(kb_id: String) -> Result<ReembeddingJob, String> {
+    set_status(&kb_id, ReembeddingStatus::Paused).await
+}
+
+/// Resume a paused (or failed) re-embedding job from its last checkpoint
+#[tauri::command]
+pub async fn resume_reembedding(kb_id: String) -> Result<ReembeddingJob, String> {
+    set_status(&kb_id, ReembeddingStatus::Pending).await
+}
+
+/// Cancel and forget a re-embedding job
+#[tauri::command]
+pub async fn cancel_reembedding(kb_id: String) -> Result<bool, String> {
+    Ok(JOBS
+        .update(|jobs| {
+            let before = jobs.len();
+            jobs.retain(|j| j.kb_id != kb_id);
+            jobs.len() != before
+        })
+        .await)
+}
+
+/// List all re-embedding jobs with their progress
+#[tauri::command]
+pub async fn list_reembedding_jobs() -> Result<Vec<ReembeddingJob>, String> {
+    Ok(JOBS.read(|jobs| jobs.clone()).await)
+}
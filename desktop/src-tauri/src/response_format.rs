@@ -0,0 +1,155 @@
+//! Answer format control.
+//!
+//! A query can ask for its answer as Markdown (the default), plain text, JSON, or a
+//! Markdown table. The format is forwarded to the backend and also spelled out in the
+//! answer instructions, since LLMs (and older backends) don't always follow it. JSON
+//! answers are then parsed by the shell, repairing the usual slips (code fences, prose
+//! around the value, trailing commas), and tables are read into rows, both returned as
+//! `QueryResponse.structured` for structured extraction tasks.
+
+use crate::commands::{QueryParams, QueryResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseFormat {
+    #[default]
+    Markdown,
+    Plain,
+    Json,
+    /// A Markdown table, read into one JSON object per row
+    Table,
+}
+
+impl ResponseFormat {
+    fn instructions(self) -> Option<&'static str> {
+        match self {
+            ResponseFormat::Markdown => None,
+            ResponseFormat::Plain => Some("Answer in plain text, without Markdown formatting."),
+            ResponseFormat::Json => Some(
+                "Answer with a single valid JSON value only: no code fences, comments or \
+                 text around it.",
+            ),
+            ResponseFormat::Table => Some(
+                "Answer with a single Markdown table only, with a header row, and no text \
+                 around it.",
+            ),
+        }
+    }
+}
+
+/// Add the instructions of the query's response format to its answer instructions.
+pub fn apply(params: &mut QueryParams) {
+    let Some(instructions) = params
+        .response_format
+        .and_then(ResponseFormat::instructions)
+    else {
+        return;
+    };
+    params.answer_instructions = Some(match params.answer_instructions.take() {
+        Some(existing) => format!("{}\n\n{}", existing, instructions),
+        None => instructions.to_string(),
+    });
+}
+
+/// Drop commas directly followed by a closing bracket, outside strings.
+fn remove_trailing_commas(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut repaired = String::with_capacity(text.len());
+    let (mut in_string, mut escaped) = (false, false);
+    for (i, &c) in chars.iter().enumerate() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' {
+            let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+            if matches!(next, Some('}' | ']')) {
+                continue;
+            }
+        }
+        repaired.push(c);
+    }
+    repaired
+}
+
+/// Parse a JSON answer, repairing code fences, surrounding prose and trailing commas.
+fn parse_json(answer: &str) -> Option<Value> {
+    let mut text = answer.trim();
+    if let Some(fenced) = text.strip_prefix("```") {
+        let fenced = fenced.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+        text = fenced.trim_end().trim_end_matches("```").trim();
+    }
+    if let Ok(value) = serde_json::from_str(text) {
+        return Some(value);
+    }
+    let start = text.find(['{', '['])?;
+    let end = text.rfind(['}', ']'])?;
+    let text = text.get(start..=end)?;
+    serde_json::from_str(text)
+        .or_else(|_| serde_json::from_str(&remove_trailing_commas(text)))
+        .ok()
+}
+
+fn table_cells(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line.strip_suffix('|').unwrap_or(line);
+    line.split('|').map(|c| c.trim().to_string()).collect()
+}
+
+/// Read the first Markdown table of an answer into one object per row, keyed by header.
+fn parse_table(answer: &str) -> Option<Value> {
+    let mut lines = answer
+        .lines()
+        .skip_while(|l| !l.trim_start().starts_with('|'))
+        .take_while(|l| l.trim_start().starts_with('|'));
+    let headers = table_cells(lines.next()?);
+    let separator = table_cells(lines.next()?);
+    let is_separator = separator
+        .iter()
+        .all(|c| !c.is_empty() && c.chars().all(|c| matches!(c, '-' | ':')));
+    if !is_separator {
+        return None;
+    }
+    let rows = lines
+        .map(|line| {
+            let cells = table_cells(line);
+            let row: Map<String, Value> = headers
+                .iter()
+                .enumerate()
+                .map(|(i, header)| {
+                    let cell = cells.get(i).cloned().unwrap_or_default();
+                    (header.clone(), Value::String(cell))
+                })
+                .collect();
+            Value::Object(row)
+        })
+        .collect();
+    Some(Value::Array(rows))
+}
+
+/// Fill in the structured form of a JSON or table answer.
+pub fn finish(params: &QueryParams, mut response: QueryResponse) -> QueryResponse {
+    if response.structured.is_some() {
+        return response;
+    }
+    response.structured = match params.response_format {
+        Some(ResponseFormat::Json) => parse_json(&response.answer),
+        Some(ResponseFormat::Table) => parse_table(&response.answer),
+        _ => return response,
+    };
+    if response.structured.is_none() {
+        tracing::warn!(
+            "The answer isn't valid {:?}",
+            params.response_format.unwrap_or_default()
+        );
+    }
+    response
+}
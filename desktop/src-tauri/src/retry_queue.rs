@@ -0,0 +1,249 @@
+//! Retry queue for files that failed to ingest.
+//!
+//! Every failed file is recorded with a category derived from its error. Transient
+//! failures (network hiccups, locked files on a flaky share) are retried automatically
+//! with exponential backoff; the others wait for the user to fix the file and call
+//! `retry_failed_documents`. The queue is persisted so nothing is forgotten on restart.
+
+use crate::jobs::IngestOptions;
+use crate::store;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::sync::Mutex;
+
+const STATE_FILE: &str = "retry_queue.json";
+const TICK_INTERVAL: Duration = Duration::from_secs(60);
+const MAX_AUTO_RETRIES: u32 = 5;
+const RETRY_BASE_DELAY_SECS: i64 = 60;
+const RETRY_MAX_DELAY_SECS: i64 = 60 * 60;
+
+static QUEUE: Mutex<Option<Vec<FailedDocument>>> = Mutex::const_new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCategory {
+    /// The file is corrupt, encrypted, or in a format the parser can't read
+    ParserError,
+    /// Another process holds the file or access was denied
+    FileLocked,
+    /// The file exceeds the backend's size limit
+    TooLarge,
+    /// Network, timeout, or backend availability problem
+    Transient,
+    Unknown,
+}
+
+impl FailureCategory {
+    /// Whether failures of this category are worth retrying without user action.
+    fn auto_retry(self) -> bool {
+        matches!(self, Self::Transient | Self::FileLocked)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedDocument {
+    pub kb_id: String,
+    pub path: String,
+    pub category: FailureCategory,
+    pub error: String,
+    pub attempts: u32,
+    pub last_failed_at: String,
+    /// When the file will be retried automatically, if it will be
+    pub next_retry_at: Option<String>,
+}
+
+/// Categorize an ingestion error from its message.
+pub fn categorize(error: &str) -> FailureCategory {
+    let error = error.to_lowercase();
+    let has = |needles: &[&str]| needles.iter().any(|n| error.contains(n));
+
+    if has(&["too large", "file size", "size limit", "exceeds", "413"]) {
+        FailureCategory::TooLarge
+    } else if has(&[
+        "locked",
+        "being used by another process",
+        "permission denied",
+        "access is denied",
+        "resource busy",
+        "os error 32",
+        "os error 13",
+    ]) {
+        FailureCategory::FileLocked
+    } else if has(&[
+        "timed out",
+        "timeout",
+        "connection",
+        "network",
+        "temporarily",
+        "unavailable",
+        "backend error (502",
+        "backend error (503",
+        "backend error (504",
+        "request failed",
+    ]) {
+        FailureCategory::Transient
+    } else if has(&[
+        "parse",
+        "decode",
+        "corrupt",
+        "encrypted",
+        "unsupported",
+        "invalid",
+        "extract",
+        "no text",
+    ]) {
+        FailureCategory::ParserError
+    } else {
+        FailureCategory::Unknown
+    }
+}
+
+fn retry_delay(attempts: u32) -> chrono::Duration {
+    let secs = RETRY_BASE_DELAY_SECS.saturating_mul(1 << attempts.saturating_sub(1).min(10));
+    chrono::Duration::seconds(secs.min(RETRY_MAX_DELAY_SECS))
+}
+
+/// Run a closure against the queue, loading it from disk on first use and persisting
+/// it afterwards.
+async fn with_queue<R>(f: impl FnOnce(&mut Vec<FailedDocument>) -> R) -> R {
+    let mut guard = QUEUE.lock().await;
+    let queue = guard.get_or_insert_with(|| store::load(STATE_FILE));
+    let result = f(queue);
+    if let Err(e) = store::save(STATE_FILE, queue) {
+        tracing::error!("Failed to persist ingestion retry queue: {}", e);
+    }
+    result
+}
+
+/// Record a failed ingestion, scheduling an automatic retry for transient errors.
+pub async fn record_failure(kb_id: &str, path: &str, error: &str) {
+    let category = categorize(error);
+    let now = Utc::now();
+
+    with_queue(|queue| {
+        let index = match queue.iter().position(|d| d.kb_id == kb_id && d.path == path) {
+            Some(index) => index,
+            None => {
+                queue.push(FailedDocument {
+                    kb_id: kb_id.to_string(),
+                    path: path.to_string(),
+                    category,
+                    error: String::new(),
+                    attempts: 0,
+                    last_failed_at: String::new(),
+                    next_retry_at: None,
+                });
+                queue.len() - 1
+            }
+        };
+
+        let entry = &mut queue[index];
+        entry.category = category;
+        entry.error = error.to_string();
+        entry.attempts += 1;
+        entry.last_failed_at = now.to_rfc3339();
+        entry.next_retry_at = (category.auto_retry() && entry.attempts <= MAX_AUTO_RETRIES)
+            .then(|| (now + retry_delay(entry.attempts)).to_rfc3339());
+    })
+    .await;
+}
+
+/// Forget a file once it has been ingested.
+pub async fn record_success(kb_id: &str, path: &str) {
+    with_queue(|queue| queue.retain(|d| !(d.kb_id == kb_id && d.path == path))).await;
+}
+
+/// Take the entries due for an automatic retry, grouped by knowledge base.
+async fn take_due() -> Vec<(String, Vec<String>)> {
+    let now = Utc::now();
+    with_queue(|queue| {
+        let mut due: Vec<(String, Vec<String>)> = Vec::new();
+        for entry in queue.iter_mut() {
+            let is_due = entry
+                .next_retry_at
+                .as_deref()
+                .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                .is_some_and(|t| t <= now);
+            if !is_due {
+                continue;
+            }
+            // Cleared until the retry fails again
+            entry.next_retry_at = None;
+            match due.iter_mut().find(|(kb_id, _)| *kb_id == entry.kb_id) {
+                Some((_, paths)) => paths.push(entry.path.clone()),
+                None => due.push((entry.kb_id.clone(), vec![entry.path.clone()])),
+            }
+        }
+        due
+    })
+    .await
+}
+
+/// Start the background loop that retries transient failures.
+pub fn start_retry_loop(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        // Retries interrupted by the last shutdown are due again.
+        with_queue(|queue| {
+            let now = Utc::now().to_rfc3339();
+            for entry in queue.iter_mut() {
+                if entry.next_retry_at.is_none()
+                    && entry.category.auto_retry()
+                    && entry.attempts <= MAX_AUTO_RETRIES
+                {
+                    entry.next_retry_at = Some(now.clone());
+                }
+            }
+        })
+        .await;
+
+        loop {
+            tokio::time::sleep(TICK_INTERVAL).await;
+            for (kb_id, paths) in take_due().await {
+                tracing::info!("Retrying {} failed files for KB {}", paths.len(), kb_id);
+                crate::jobs::start_job(&app, kb_id, paths, IngestOptions::default()).await;
+            }
+        }
+    });
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// List the files of a knowledge base that failed to ingest
+#[tauri::command]
+pub async fn list_failed_documents(kb_id: String) -> Result<Vec<FailedDocument>, String> {
+    Ok(with_queue(|queue| {
+        queue
+            .iter()
+            .filter(|d| d.kb_id == kb_id)
+            .cloned()
+            .collect()
+    })
+    .await)
+}
+
+/// Retry every failed file of a knowledge base. Returns the ingestion job id, if any.
+#[tauri::command]
+pub async fn retry_failed_documents(app: AppHandle, kb_id: String) -> Result<Option<String>, String> {
+    let paths: Vec<String> = with_queue(|queue| {
+        queue
+            .iter_mut()
+            .filter(|d| d.kb_id == kb_id)
+            .map(|d| {
+                // A manual retry gives transient failures a fresh retry budget
+                d.attempts = 0;
+                d.next_retry_at = None;
+                d.path.clone()
+            })
+            .collect()
+    })
+    .await;
+
+    if paths.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(crate::jobs::start_job(&app, kb_id, paths, IngestOptions::default()).await))
+}
@@ -0,0 +1,483 @@
+//! S3 and MinIO bucket ingestion.
+//!
+//! `add_s3_bucket` lists the objects under a prefix, downloads the supported ones under
+//! `~/.ragkit/s3/`, and ingests them. Requests go through a small S3 client signing
+//! with AWS Signature V4 and path-style URLs (`{endpoint}/{bucket}/{key}`), which both
+//! AWS and MinIO accept. Objects larger than the size limit are skipped. Progress is
+//! saved in s3_imports.json after each object, so calling the command again with the
+//! same bucket and prefix resumes an interrupted import instead of downloading
+//! everything again. Credentials are not saved and must be given again to resume.
+
+use crate::backend::{http_client, TimeoutTier};
+use crate::files::hex;
+use crate::jobs::{self, IngestOptions};
+use crate::{files, store};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use hmac::{Hmac, Mac};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+use tokio::io::AsyncWriteExt;
+
+const STATE_FILE: &str = "s3_imports.json";
+const DEFAULT_REGION: &str = "us-east-1";
+/// Objects larger than this are skipped unless the import sets its own limit.
+const DEFAULT_MAX_OBJECT_BYTES: u64 = 100 * 1024 * 1024;
+/// SHA-256 of an empty payload, for GET requests.
+const EMPTY_PAYLOAD_SHA256: &str =
+    "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct S3Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// For temporary credentials
+    #[serde(default)]
+    pub session_token: Option<String>,
+    /// Defaults to `us-east-1`, which MinIO accepts too
+    #[serde(default)]
+    pub region: Option<String>,
+}
+
+/// Progress of a bucket import, saved after each object.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct S3ImportState {
+    id: String,
+    kb_id: String,
+    endpoint: String,
+    bucket: String,
+    prefix: String,
+    /// Downloaded objects: key -> ETag
+    downloaded: BTreeMap<String, String>,
+    started_at: DateTime<Utc>,
+}
+
+/// Payload of the `s3-import-progress` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct S3ImportProgressEvent {
+    pub import_id: String,
+    pub key: String,
+    pub downloaded: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct S3Import {
+    pub import_id: String,
+    /// Ingestion job of the downloaded objects
+    pub job_id: Option<String>,
+    /// Supported objects under the prefix
+    pub objects: usize,
+    pub downloaded: usize,
+    /// Objects already downloaded by an interrupted run of the same import
+    pub resumed: usize,
+    pub skipped_too_large: usize,
+    pub skipped_unsupported: usize,
+    pub failed: usize,
+    pub output_dir: String,
+}
+
+struct S3Object {
+    key: String,
+    size: u64,
+    etag: String,
+}
+
+fn load() -> Vec<S3ImportState> {
+    store::load(STATE_FILE)
+}
+
+fn save_state(state: &S3ImportState) {
+    let mut imports = load();
+    imports.retain(|i| i.id != state.id);
+    imports.push(state.clone());
+    if let Err(e) = store::save(STATE_FILE, &imports) {
+        tracing::warn!("Failed to save the S3 import progress: {}", e);
+    }
+}
+
+fn remove_state(id: &str) {
+    let mut imports = load();
+    imports.retain(|i| i.id != id);
+    if let Err(e) = store::save(STATE_FILE, &imports) {
+        tracing::warn!("Failed to save the S3 import progress: {}", e);
+    }
+}
+
+// ============================================================================
+// Client
+// ============================================================================
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    // HMAC takes keys of any length
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+/// Percent-encode as SigV4 expects, keeping `/` in paths.
+fn uri_encode(value: &str, keep_slash: bool) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            b'/' if keep_slash => "/".to_string(),
+            b => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+struct S3Client {
+    endpoint: reqwest::Url,
+    bucket: String,
+    region: String,
+    credentials: S3Credentials,
+}
+
+impl S3Client {
+    fn new(endpoint: &str, bucket: &str, credentials: S3Credentials) -> Result<Self> {
+        let endpoint = reqwest::Url::parse(endpoint.trim_end_matches('/'))
+            .map_err(|e| anyhow!("Invalid endpoint {}: {}", endpoint, e))?;
+        if endpoint.host_str().is_none() {
+            return Err(anyhow!("Invalid endpoint: {}", endpoint));
+        }
+        Ok(Self {
+            endpoint,
+            bucket: bucket.to_string(),
+            region: credentials
+                .region
+                .clone()
+                .unwrap_or_else(|| DEFAULT_REGION.to_string()),
+            credentials,
+        })
+    }
+
+    /// Signed GET of `key` (or of the bucket itself when empty).
+    fn get(&self, key: &str, query: &[(&str, &str)]) -> reqwest::RequestBuilder {
+        let base_path = self.endpoint.path().trim_end_matches('/');
+        let path = format!(
+            "{}/{}/{}",
+            base_path,
+            uri_encode(&self.bucket, false),
+            uri_encode(key, true)
+        );
+        let mut query: Vec<(String, String)> = query
+            .iter()
+            .map(|(k, v)| (uri_encode(k, false), uri_encode(v, false)))
+            .collect();
+        query.sort();
+        let canonical_query = query
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let host = match self.endpoint.port() {
+            Some(port) => format!("{}:{}", self.endpoint.host_str().unwrap_or(""), port),
+            None => self.endpoint.host_str().unwrap_or("").to_string(),
+        };
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+
+        let mut headers = vec![
+            ("host", host.clone()),
+            ("x-amz-content-sha256", EMPTY_PAYLOAD_SHA256.to_string()),
+            ("x-amz-date", amz_date.clone()),
+        ];
+        if let Some(token) = &self.credentials.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+            .collect();
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_request = format!(
+            "GET\n{}\n{}\n{}\n{}\n{}",
+            path, canonical_query, canonical_headers, signed_headers, EMPTY_PAYLOAD_SHA256
+        );
+
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+        let secret = format!("AWS4{}", self.credentials.secret_access_key);
+        let key = [self.region.as_str(), "s3", "aws4_request"].iter().fold(
+            hmac_sha256(secret.as_bytes(), date.as_bytes()),
+            |key, part| hmac_sha256(&key, part.as_bytes()),
+        );
+        let signature = hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.credentials.access_key_id, scope, signed_headers, signature
+        );
+
+        let mut url = self.endpoint.clone();
+        url.set_path(&path);
+        url.set_query((!canonical_query.is_empty()).then_some(canonical_query.as_str()));
+        let mut request = http_client()
+            .get(url)
+            .header("Authorization", authorization);
+        for (name, value) in headers.into_iter().filter(|(name, _)| *name != "host") {
+            request = request.header(name, value);
+        }
+        request
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let response = request.send().await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let body = response.text().await.unwrap_or_default();
+        let message = xml_field(&body, b"Message").unwrap_or(body);
+        Err(anyhow!("{}: {}", status, message))
+    }
+
+    /// All objects under a prefix.
+    async fn list(&self, prefix: &str) -> Result<Vec<S3Object>> {
+        let mut objects = Vec::new();
+        let mut continuation: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type", "2"), ("prefix", prefix)];
+            if let Some(token) = &continuation {
+                query.push(("continuation-token", token));
+            }
+            let request = self
+                .get("", &query)
+                .timeout(TimeoutTier::Standard.timeout());
+            let body = self.send(request).await?.text().await?;
+            let (page, next) = parse_list(&body)?;
+            objects.extend(page);
+            match next {
+                Some(token) => continuation = Some(token),
+                None => break,
+            }
+        }
+        Ok(objects)
+    }
+
+    /// Stream an object to a file, giving up past `max_bytes`.
+    async fn download(&self, key: &str, target: &Path, max_bytes: u64) -> Result<()> {
+        let response = self.send(self.get(key, &[])).await?;
+        if let Some(parent) = target.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut file = tokio::fs::File::create(target).await?;
+        let mut written = 0u64;
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            written += chunk.len() as u64;
+            if written > max_bytes {
+                drop(file);
+                let _ = tokio::fs::remove_file(target).await;
+                return Err(anyhow!("Object larger than the size limit"));
+            }
+            file.write_all(&chunk).await?;
+        }
+        file.flush().await?;
+        Ok(())
+    }
+}
+
+/// Text of the first `name` element of an XML document.
+fn xml_field(xml: &str, name: &[u8]) -> Option<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut inside = false;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) if e.name().as_ref() == name => inside = true,
+            Ok(Event::Text(t)) if inside => return t.unescape().ok().map(|t| t.into_owned()),
+            Ok(Event::Eof) | Err(_) => return None,
+            _ => {}
+        }
+    }
+}
+
+/// Objects of a ListObjectsV2 page, and the token of the next page.
+fn parse_list(xml: &str) -> Result<(Vec<S3Object>, Option<String>)> {
+    let mut reader = Reader::from_str(xml);
+    let mut objects = Vec::new();
+    let mut next = None;
+    let mut truncated = false;
+    let mut element = Vec::new();
+    let mut current: Option<S3Object> = None;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                element = e.name().as_ref().to_vec();
+                if element == b"Contents" {
+                    current = Some(S3Object {
+                        key: String::new(),
+                        size: 0,
+                        etag: String::new(),
+                    });
+                }
+            }
+            Ok(Event::Text(t)) => {
+                let text = t.unescape()?.into_owned();
+                match (element.as_slice(), current.as_mut()) {
+                    (b"Key", Some(object)) => object.key.push_str(&text),
+                    (b"Size", Some(object)) => object.size = text.trim().parse().unwrap_or(0),
+                    (b"ETag", Some(object)) => object.etag = text.trim_matches('"').to_string(),
+                    (b"IsTruncated", _) => truncated = text.trim() == "true",
+                    (b"NextContinuationToken", _) => next = Some(text),
+                    _ => {}
+                }
+            }
+            Ok(Event::End(e)) => {
+                if e.name().as_ref() == b"Contents" {
+                    objects.extend(current.take());
+                }
+                element.clear();
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(anyhow!("Invalid bucket listing: {}", e)),
+            _ => {}
+        }
+    }
+    Ok((objects, next.filter(|_| truncated)))
+}
+
+/// Local path of an object, keeping its folders but no `..` or absolute parts.
+fn local_path(dir: &Path, key: &str) -> PathBuf {
+    key.split('/')
+        .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+        .fold(dir.to_path_buf(), |path, part| path.join(part))
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Ingest the objects of an S3 or MinIO bucket into a knowledge base
+///
+/// Only objects whose key starts with `prefix` are read, and objects larger than
+/// `max_object_bytes` (default 100 MB) are skipped. Calling it again for the same
+/// bucket and prefix resumes an interrupted import. Download progress is reported
+/// through `s3-import-progress` events; returns once the ingestion job has started.
+#[tauri::command]
+pub async fn add_s3_bucket(
+    app: AppHandle,
+    kb_id: String,
+    endpoint: String,
+    bucket: String,
+    prefix: Option<String>,
+    credentials: S3Credentials,
+    max_object_bytes: Option<u64>,
+) -> Result<S3Import, String> {
+    let prefix = prefix.unwrap_or_default();
+    let max_bytes = max_object_bytes.unwrap_or(DEFAULT_MAX_OBJECT_BYTES);
+    crate::network::check_url(&endpoint)?;
+    let client = S3Client::new(&endpoint, &bucket, credentials).map_err(|e| e.to_string())?;
+    let objects = client
+        .list(&prefix)
+        .await
+        .map_err(|e| format!("Failed to list {}: {}", bucket, e))?;
+
+    let mut state = load()
+        .into_iter()
+        .find(|i| {
+            i.kb_id == kb_id && i.endpoint == endpoint && i.bucket == bucket && i.prefix == prefix
+        })
+        .unwrap_or_else(|| S3ImportState {
+            id: uuid::Uuid::new_v4().to_string(),
+            kb_id: kb_id.clone(),
+            endpoint: endpoint.clone(),
+            bucket: bucket.clone(),
+            prefix: prefix.clone(),
+            downloaded: BTreeMap::new(),
+            started_at: Utc::now(),
+        });
+    let dir = store::ragkit_dir().join("s3").join(&state.id);
+
+    let (supported, unsupported): (Vec<S3Object>, Vec<S3Object>) = objects
+        .into_iter()
+        .filter(|o| !o.key.ends_with('/'))
+        .partition(|o| files::is_supported(Path::new(&o.key)));
+    let (objects, too_large): (Vec<S3Object>, Vec<S3Object>) =
+        supported.into_iter().partition(|o| o.size <= max_bytes);
+    let mut result = S3Import {
+        import_id: state.id.clone(),
+        job_id: None,
+        objects: objects.len() + too_large.len(),
+        downloaded: 0,
+        resumed: 0,
+        skipped_too_large: too_large.len(),
+        skipped_unsupported: unsupported.len(),
+        failed: 0,
+        output_dir: dir.to_string_lossy().into_owned(),
+    };
+
+    let mut paths = Vec::new();
+    for (n, object) in objects.iter().enumerate() {
+        let target = local_path(&dir, &object.key);
+        if state.downloaded.get(&object.key) == Some(&object.etag) && target.is_file() {
+            result.resumed += 1;
+            paths.push(target.to_string_lossy().into_owned());
+            continue;
+        }
+        match client.download(&object.key, &target, max_bytes).await {
+            Ok(()) => {
+                state
+                    .downloaded
+                    .insert(object.key.clone(), object.etag.clone());
+                save_state(&state);
+                result.downloaded += 1;
+                paths.push(target.to_string_lossy().into_owned());
+            }
+            Err(e) => {
+                tracing::warn!("Failed to download s3://{}/{}: {}", bucket, object.key, e);
+                result.failed += 1;
+            }
+        }
+        let _ = app.emit(
+            "s3-import-progress",
+            S3ImportProgressEvent {
+                import_id: state.id.clone(),
+                key: object.key.clone(),
+                downloaded: n + 1,
+                total: objects.len(),
+            },
+        );
+    }
+
+    // Failed downloads keep the import open so the next call retries them; the
+    // ingestion job checkpoints its own progress
+    if result.failed == 0 {
+        remove_state(&state.id);
+    }
+    if !paths.is_empty() {
+        result.job_id =
+            Some(jobs::start_job(&app, kb_id.clone(), paths, IngestOptions::default()).await);
+    }
+    tracing::info!(
+        "Read {} objects from s3://{}/{}: {} downloaded, {} resumed, {} too large, {} failed",
+        result.objects,
+        bucket,
+        prefix,
+        result.downloaded,
+        result.resumed,
+        result.skipped_too_large,
+        result.failed
+    );
+    Ok(result)
+}
@@ -0,0 +1,239 @@
+//! Scheduled folder re-indexing.
+//!
+//! A schedule re-ingests a folder into a knowledge base on a cron expression, for
+//! knowledge bases backed by directories that are updated on a regular basis (e.g.
+//! nightly reports). Each run syncs the folder like folder sync does: new and modified
+//! files are ingested, deleted ones removed. Expressions use the five standard fields
+//! (`minute hour day-of-month month day-of-week`) in local time; a leading seconds field
+//! is accepted too. Days of the week are numbered 0-7 as in crontab, Sunday being 0 or 7,
+//! or named (`MON-FRI`). Schedules are persisted, and a run missed while the app was closed
+//! fires once at the next start.
+
+use crate::store::CachedStore;
+use crate::{backend, watcher};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::str::FromStr;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+const TICK_INTERVAL: Duration = Duration::from_secs(30);
+
+static SCHEDULES: CachedStore<Vec<Schedule>> = CachedStore::new("schedules.json");
+/// Schedules with a run in progress.
+static RUNNING: std::sync::Mutex<BTreeSet<String>> = std::sync::Mutex::new(BTreeSet::new());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    pub id: String,
+    pub kb_id: String,
+    pub folder: String,
+    pub cron_expr: String,
+    pub created_at: DateTime<Utc>,
+    pub last_run: Option<DateTime<Utc>>,
+    pub next_run: Option<DateTime<Utc>>,
+    /// Error of the last run, if it failed
+    pub last_error: Option<String>,
+}
+
+/// Payload of the `schedule-run` event, sent when a scheduled run finishes.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduleRunEvent {
+    pub schedule_id: String,
+    pub kb_id: String,
+    pub folder: String,
+    pub error: Option<String>,
+}
+
+/// Standard day-of-week field (0-7, Sunday being 0 or 7) in the numbering of the cron
+/// crate (1-7, Sunday being 1). Numeric items are expanded to a list of days; day names
+/// and anything else are left for the crate to parse.
+fn day_of_week(field: &str) -> String {
+    let mut days = BTreeSet::new();
+    let mut others = Vec::new();
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok()),
+            None => (item, Some(1)),
+        };
+        let bounds = match range.split_once('-') {
+            _ if range == "*" => Some((0, 6)),
+            Some((start, end)) => start.parse::<u32>().ok().zip(end.parse::<u32>().ok()),
+            // `n/step` runs from n to the end of the week
+            None if item.contains('/') => range.parse::<u32>().ok().map(|start| (start, 6)),
+            None => range.parse::<u32>().ok().map(|day| (day, day)),
+        };
+        match (bounds, step) {
+            (Some((start, end)), Some(step)) if start <= end && end <= 7 && step > 0 => {
+                days.extend((start..=end).step_by(step as usize).map(|day| day % 7 + 1));
+            }
+            _ => others.push(item.to_string()),
+        }
+    }
+    days.iter()
+        .map(u32::to_string)
+        .chain(others)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn parse(cron_expr: &str) -> Result<cron::Schedule, String> {
+    let mut fields: Vec<String> = cron_expr.split_whitespace().map(str::to_string).collect();
+    // The cron crate starts with a seconds field
+    let day_of_week_index = match fields.len() {
+        5 => {
+            fields.insert(0, "0".to_string());
+            5
+        }
+        6 | 7 => 5,
+        _ => return Err(format!("Invalid cron expression: {}", cron_expr)),
+    };
+    fields[day_of_week_index] = day_of_week(&fields[day_of_week_index]);
+    cron::Schedule::from_str(&fields.join(" "))
+        .map_err(|e| format!("Invalid cron expression: {}", e))
+}
+
+fn next_run(cron_expr: &str, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let schedule = parse(cron_expr).ok()?;
+    let next = schedule.after(&after.with_timezone(&Local)).next()?;
+    Some(next.with_timezone(&Utc))
+}
+
+/// Start the background loop firing due schedules.
+pub fn start_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(TICK_INTERVAL).await;
+            run_due_schedules(&app).await;
+        }
+    });
+}
+
+async fn run_due_schedules(app: &AppHandle) {
+    if !backend::is_running() {
+        return;
+    }
+    let now = Utc::now();
+    let due: Vec<Schedule> = SCHEDULES
+        .update(|schedules| {
+            let mut due = Vec::new();
+            for schedule in schedules.iter_mut() {
+                if schedule.next_run.is_none_or(|next| next > now)
+                    || RUNNING.lock().unwrap().contains(&schedule.id)
+                {
+                    continue;
+                }
+                // Runs missed while the app was closed collapse into this one
+                schedule.next_run = next_run(&schedule.cron_expr, now);
+                due.push(schedule.clone());
+            }
+            due
+        })
+        .await;
+
+    for schedule in due {
+        RUNNING.lock().unwrap().insert(schedule.id.clone());
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            run_schedule(&app, &schedule).await;
+            RUNNING.lock().unwrap().remove(&schedule.id);
+        });
+    }
+}
+
+async fn run_schedule(app: &AppHandle, schedule: &Schedule) {
+    tracing::info!(
+        "Scheduled re-indexing of {} into KB {}",
+        schedule.folder,
+        schedule.kb_id
+    );
+    let error = watcher::rescan_folder(app, &schedule.kb_id, &schedule.folder)
+        .await
+        .err();
+    if let Some(e) = &error {
+        tracing::warn!("Scheduled re-indexing of {} failed: {}", schedule.folder, e);
+    }
+    SCHEDULES
+        .update(|schedules| {
+            if let Some(s) = schedules.iter_mut().find(|s| s.id == schedule.id) {
+                s.last_run = Some(Utc::now());
+                s.last_error = error.clone();
+            }
+        })
+        .await;
+    let _ = app.emit(
+        "schedule-run",
+        ScheduleRunEvent {
+            schedule_id: schedule.id.clone(),
+            kb_id: schedule.kb_id.clone(),
+            folder: schedule.folder.clone(),
+            error,
+        },
+    );
+}
+
+/// Delete the schedules of a deleted knowledge base.
+pub async fn forget_kb(kb_id: &str) {
+    SCHEDULES
+        .update(|schedules| schedules.retain(|s| s.kb_id != kb_id))
+        .await;
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Re-index a folder into a knowledge base on a cron schedule
+#[tauri::command]
+pub async fn create_schedule(
+    kb_id: String,
+    folder: String,
+    cron_expr: String,
+) -> Result<Schedule, String> {
+    parse(&cron_expr)?;
+    if !std::path::Path::new(&folder).is_dir() {
+        return Err(format!("Invalid folder path: {}", folder));
+    }
+    let now = Utc::now();
+    let schedule = Schedule {
+        id: uuid::Uuid::new_v4().to_string(),
+        kb_id,
+        folder,
+        next_run: next_run(&cron_expr, now),
+        cron_expr: cron_expr.trim().to_string(),
+        created_at: now,
+        last_run: None,
+        last_error: None,
+    };
+    SCHEDULES
+        .update(|schedules| schedules.push(schedule.clone()))
+        .await;
+    Ok(schedule)
+}
+
+/// List the re-indexing schedules, optionally of one knowledge base
+#[tauri::command]
+pub async fn list_schedules(kb_id: Option<String>) -> Result<Vec<Schedule>, String> {
+    Ok(SCHEDULES
+        .read(|schedules| {
+            schedules
+                .iter()
+                .filter(|s| kb_id.as_ref().is_none_or(|kb_id| &s.kb_id == kb_id))
+                .cloned()
+                .collect()
+        })
+        .await)
+}
+
+/// Delete a re-indexing schedule; a run in progress finishes
+#[tauri::command]
+pub async fn delete_schedule(id: String) -> Result<bool, String> {
+    Ok(SCHEDULES
+        .update(|schedules| {
+            let before = schedules.len();
+            schedules.retain(|s| s.id != id);
+            schedules.len() != before
+        })
+        .await)
+}
@@ -0,0 +1,196 @@
+//! Settings profiles.
+//!
+//! A profile is a named copy of the backend settings (providers, models, chunking,
+//! retrieval), so users can switch between e.g. "fast local" and "high quality cloud"
+//! in one click. Profiles are stored one per file in ~/.ragkit/profiles/ and can be
+//! exported to share a configuration; API keys live in the backend's key store, not in
+//! the settings, so they are never exported. Applying a profile goes through
+//! `update_settings`, so its checks and follow-ups (re-embedding, history) still apply.
+
+use crate::commands::{self, Settings};
+use crate::store;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+const PROFILES_DIR: &str = "profiles";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SettingsProfile {
+    pub name: String,
+    pub settings: Settings,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProfileSummary {
+    pub name: String,
+    pub llm_provider: String,
+    pub llm_model: String,
+    pub embedding_provider: String,
+    pub embedding_model: String,
+    pub created_at: String,
+    /// Whether the current settings are exactly the profile's
+    pub active: bool,
+}
+
+/// Profile names are case-insensitive.
+fn name_key(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// File of a profile, relative to the ragkit directory: a readable stem, and a hash of
+/// the name so that names differing only by punctuation or script get their own file.
+fn profile_file(name: &str) -> String {
+    let key = name_key(name);
+    let stem: String = key
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let hash = blake3::hash(key.as_bytes()).to_hex();
+    format!("{}/{}-{}.json", PROFILES_DIR, stem, &hash[..8])
+}
+
+/// Saved profiles with their files.
+fn read_profiles() -> Vec<(PathBuf, SettingsProfile)> {
+    let Ok(entries) = std::fs::read_dir(store::ragkit_dir().join(PROFILES_DIR)) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|e| e == "json"))
+        .filter_map(|entry| {
+            let content = std::fs::read_to_string(entry.path()).ok()?;
+            let profile = serde_json::from_str(&content)
+                .inspect_err(|e| {
+                    tracing::warn!("Skipping profile {}: {}", entry.path().display(), e)
+                })
+                .ok()?;
+            Some((entry.path(), profile))
+        })
+        .collect()
+}
+
+fn load_profiles() -> Vec<SettingsProfile> {
+    let mut profiles: Vec<SettingsProfile> = read_profiles().into_iter().map(|(_, p)| p).collect();
+    profiles.sort_by_key(|p| name_key(&p.name));
+    profiles
+}
+
+fn find(name: &str) -> Result<SettingsProfile, String> {
+    load_profiles()
+        .into_iter()
+        .find(|p| name_key(&p.name) == name_key(name))
+        .ok_or_else(|| format!("Profile not found: {}", name))
+}
+
+/// Remove the files of a profile, including those named by earlier versions.
+fn remove_profile_files(name: &str, keep: Option<&Path>) -> Result<bool, String> {
+    let mut removed = false;
+    for (path, profile) in read_profiles() {
+        if name_key(&profile.name) == name_key(name) && Some(path.as_path()) != keep {
+            std::fs::remove_file(&path).map_err(|e| e.to_string())?;
+            removed = true;
+        }
+    }
+    Ok(removed)
+}
+
+fn summary(profile: SettingsProfile, active: bool) -> ProfileSummary {
+    ProfileSummary {
+        name: profile.name,
+        llm_provider: profile.settings.llm_provider,
+        llm_model: profile.settings.llm_model,
+        embedding_provider: profile.settings.embedding_provider,
+        embedding_model: profile.settings.embedding_model,
+        created_at: profile.created_at,
+        active,
+    }
+}
+
+fn save_profile(profile: &SettingsProfile) -> Result<(), String> {
+    if !profile.name.chars().any(|c| c.is_alphanumeric()) {
+        return Err("Profile name cannot be empty".into());
+    }
+    std::fs::create_dir_all(store::ragkit_dir().join(PROFILES_DIR)).map_err(|e| e.to_string())?;
+    let file = profile_file(&profile.name);
+    store::save(&file, profile).map_err(|e| e.to_string())?;
+    remove_profile_files(&profile.name, Some(&store::ragkit_dir().join(&file)))?;
+    Ok(())
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// List the saved settings profiles, marking the one matching the current settings
+#[tauri::command]
+pub async fn list_settings_profiles() -> Result<Vec<ProfileSummary>, String> {
+    let current = commands::get_settings()
+        .await
+        .ok()
+        .and_then(|s| serde_json::to_value(s).ok());
+    Ok(load_profiles()
+        .into_iter()
+        .map(|p| {
+            let active = current.is_some() && serde_json::to_value(&p.settings).ok() == current;
+            summary(p, active)
+        })
+        .collect())
+}
+
+/// Save the current settings as a profile, replacing the profile of the same name
+#[tauri::command]
+pub async fn save_settings_profile(name: String) -> Result<ProfileSummary, String> {
+    let profile = SettingsProfile {
+        name: name.trim().to_string(),
+        settings: commands::get_settings().await?,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    save_profile(&profile)?;
+    Ok(summary(profile, true))
+}
+
+/// Replace the current settings with a profile's
+#[tauri::command]
+pub async fn apply_settings_profile(app: AppHandle, name: String) -> Result<Settings, String> {
+    let profile = find(&name)?;
+    let settings = commands::update_settings(app, profile.settings).await?;
+    tracing::info!("Applied settings profile {}", profile.name);
+    Ok(settings)
+}
+
+/// Delete a settings profile
+#[tauri::command]
+pub async fn delete_settings_profile(name: String) -> Result<bool, String> {
+    remove_profile_files(&name, None)
+}
+
+/// Export a profile to a JSON file. Returns the file path.
+#[tauri::command]
+pub async fn export_settings_profile(name: String, path: String) -> Result<String, String> {
+    let profile = find(&name)?;
+    let mut target = PathBuf::from(path);
+    if !target
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("json"))
+    {
+        target.set_extension("json");
+    }
+    let content = serde_json::to_string_pretty(&profile).map_err(|e| e.to_string())?;
+    std::fs::write(&target, content)
+        .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+    Ok(target.display().to_string())
+}
+
+/// Import a profile exported with `export_settings_profile`, replacing the profile of
+/// the same name
+#[tauri::command]
+pub async fn import_settings_profile(path: String) -> Result<ProfileSummary, String> {
+    let content =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let profile: SettingsProfile =
+        serde_json::from_str(&content).map_err(|e| format!("Invalid profile {}: {}", path, e))?;
+    save_profile(&profile)?;
+    Ok(summary(profile, false))
+}
@@ -0,0 +1,177 @@
+//! Recent log entries of the shell, queryable from the UI.
+//!
+//! A tracing layer keeps the last entries of this session in memory alongside the log
+//! file. Backend output forwarded by the sidecar supervisor carries its module and
+//! request id as fields, so `query_logs` filters shell and backend entries the same way.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// Number of entries kept in memory.
+const CAPACITY: usize = 5000;
+
+/// Default number of entries returned by `query_logs`.
+const DEFAULT_LIMIT: usize = 200;
+
+static ENTRIES: Mutex<VecDeque<ShellLogEntry>> = Mutex::new(VecDeque::new());
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShellLogEntry {
+    pub timestamp: String,
+    pub level: String,
+    /// "backend" for forwarded backend output, the Rust module path otherwise
+    pub target: String,
+    pub module: Option<String>,
+    pub line: Option<u32>,
+    pub request_id: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct LogQuery {
+    /// Minimum level: error, warn, info, debug or trace
+    pub level: Option<String>,
+    /// Prefix of the entry target, e.g. "backend" or "ragkit_desktop::jobs"
+    pub target: Option<String>,
+    /// Prefix of the module the entry was logged from
+    pub module: Option<String>,
+    pub request_id: Option<String>,
+    /// Case-insensitive text searched in the message
+    pub contains: Option<String>,
+    /// Maximum number of entries, most recent last
+    pub limit: Option<usize>,
+}
+
+/// Tracing layer recording events into the in-memory log.
+pub struct ShellLogLayer;
+
+impl<S: Subscriber> Layer<S> for ShellLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut fields = EventFields::default();
+        event.record(&mut fields);
+
+        let entry = ShellLogEntry {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            level: metadata.level().to_string(),
+            target: metadata.target().to_string(),
+            module: fields
+                .module
+                .or_else(|| metadata.module_path().map(str::to_string)),
+            line: fields.line.or(metadata.line()),
+            request_id: fields.request_id.filter(|id| !id.is_empty()),
+            message: fields.message,
+        };
+
+        let mut entries = ENTRIES.lock().unwrap();
+        if entries.len() == CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+}
+
+#[derive(Default)]
+struct EventFields {
+    message: String,
+    module: Option<String>,
+    line: Option<u32>,
+    request_id: Option<String>,
+}
+
+impl Visit for EventFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "module" => self.module = Some(value.to_string()),
+            "request_id" => self.request_id = Some(value.to_string()),
+            _ => self.record_debug(field, &value),
+        }
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match field.name() {
+            "line" => self.line = u32::try_from(value).ok().filter(|line| *line > 0),
+            _ => self.record_debug(field, &value),
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            if !self.message.is_empty() {
+                self.message.push(' ');
+            }
+            self.message
+                .push_str(&format!("{}={:?}", field.name(), value));
+        }
+    }
+}
+
+fn parse_level(level: &str) -> Option<Level> {
+    match level.to_lowercase().as_str() {
+        "error" => Some(Level::ERROR),
+        "warn" | "warning" => Some(Level::WARN),
+        "info" => Some(Level::INFO),
+        "debug" => Some(Level::DEBUG),
+        "trace" => Some(Level::TRACE),
+        _ => None,
+    }
+}
+
+/// Recent shell and backend log entries matching every given filter, oldest first.
+#[tauri::command]
+pub fn query_logs(query: LogQuery) -> Result<Vec<ShellLogEntry>, String> {
+    let min_level = match query.level.as_deref() {
+        Some(level) => {
+            Some(parse_level(level).ok_or_else(|| format!("Unknown log level: {}", level))?)
+        }
+        None => None,
+    };
+    let contains = query.contains.map(|text| text.to_lowercase());
+
+    let entries = ENTRIES.lock().unwrap();
+    let mut matching: Vec<ShellLogEntry> = entries
+        .iter()
+        .rev()
+        .filter(|entry| {
+            // More verbose levels compare greater
+            min_level.is_none_or(|min| parse_level(&entry.level).is_some_and(|level| level <= min))
+        })
+        .filter(|entry| {
+            query
+                .target
+                .as_deref()
+                .is_none_or(|target| entry.target.starts_with(target))
+        })
+        .filter(|entry| {
+            query.module.as_deref().is_none_or(|module| {
+                entry
+                    .module
+                    .as_deref()
+                    .is_some_and(|m| m.starts_with(module))
+            })
+        })
+        .filter(|entry| {
+            query
+                .request_id
+                .as_deref()
+                .is_none_or(|id| entry.request_id.as_deref() == Some(id))
+        })
+        .filter(|entry| {
+            contains
+                .as_deref()
+                .is_none_or(|text| entry.message.to_lowercase().contains(text))
+        })
+        .take(query.limit.unwrap_or(DEFAULT_LIMIT))
+        .cloned()
+        .collect();
+    matching.reverse();
+    Ok(matching)
+}
@@ -0,0 +1,210 @@
+//! Global hotkeys and the quick-ask window.
+//!
+//! Global shortcuts from [`crate::keybindings`] are registered with the OS here. The
+//! `quick_ask` action toggles a small always-on-top window that answers questions
+//! against the last knowledge base used in the main window, Spotlight-style; other
+//! global actions are forwarded to the UI as `shortcut-triggered` events.
+
+use crate::backend::{backend_request, BackendError};
+use crate::commands::{self, QueryParams, QueryResponse};
+use crate::keybindings::{self, ShortcutScope};
+use crate::store;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+const QUICK_ASK_LABEL: &str = "quick-ask";
+const QUICK_ASK_ACTION: &str = "quick_ask";
+const STATE_FILE: &str = "quick_ask.json";
+
+/// Shortcuts currently registered by the app.
+static REGISTERED: Mutex<Vec<Shortcut>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuickAskState {
+    /// Knowledge base last queried from the main window
+    pub kb_id: Option<String>,
+    /// Conversation collecting quick-ask questions, per knowledge base
+    pub conversations: std::collections::BTreeMap<String, String>,
+}
+
+/// Payload of the `shortcut-triggered` event.
+#[derive(Debug, Clone, Serialize)]
+struct ShortcutTriggered {
+    action: String,
+}
+
+/// Remember the knowledge base of a query so the quick-ask window can use it.
+pub fn remember_kb(kb_id: &str) {
+    let mut state: QuickAskState = store::load(STATE_FILE);
+    if state.kb_id.as_deref() == Some(kb_id) {
+        return;
+    }
+    state.kb_id = Some(kb_id.to_string());
+    if let Err(e) = store::save(STATE_FILE, &state) {
+        tracing::warn!("Failed to save quick-ask state: {}", e);
+    }
+}
+
+fn toggle_quick_ask(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(QUICK_ASK_LABEL) {
+        if window.is_visible().unwrap_or(false) && window.is_focused().unwrap_or(false) {
+            let _ = window.hide();
+        } else {
+            let _ = window.show();
+            let _ = window.set_focus();
+            let _ = window.emit("quick-ask-opened", ());
+        }
+        return;
+    }
+
+    let result = WebviewWindowBuilder::new(
+        app,
+        QUICK_ASK_LABEL,
+        WebviewUrl::App("index.html#/quick-ask".into()),
+    )
+    .title("RAGKIT - Quick Ask")
+    .inner_size(680.0, 420.0)
+    .resizable(false)
+    .decorations(false)
+    .always_on_top(true)
+    .skip_taskbar(true)
+    .center()
+    .focused(true)
+    .build();
+    if let Err(e) = result {
+        tracing::error!("Failed to open quick-ask window: {}", e);
+    }
+}
+
+/// Register the global shortcuts from the saved keybindings, replacing the previous
+/// registrations. Called at startup and whenever the keybindings change.
+pub fn register_global_shortcuts(app: &AppHandle) {
+    let manager = app.global_shortcut();
+    for shortcut in REGISTERED.lock().unwrap().drain(..) {
+        let _ = manager.unregister(shortcut);
+    }
+
+    let mut registered = Vec::new();
+    for binding in keybindings::load_shortcuts() {
+        if binding.scope != ShortcutScope::Global || binding.accelerator.is_empty() {
+            continue;
+        }
+        let Ok(shortcut) = Shortcut::from_str(&binding.accelerator) else {
+            tracing::warn!(
+                "Invalid accelerator for {}: {}",
+                binding.action,
+                binding.accelerator
+            );
+            continue;
+        };
+
+        let action = binding.action.clone();
+        let result = manager.on_shortcut(shortcut, move |app, _, event| {
+            if event.state() != ShortcutState::Pressed {
+                return;
+            }
+            if action == QUICK_ASK_ACTION {
+                toggle_quick_ask(app);
+            } else {
+                let _ = app.emit(
+                    "shortcut-triggered",
+                    ShortcutTriggered {
+                        action: action.clone(),
+                    },
+                );
+            }
+        });
+        match result {
+            Ok(()) => registered.push(shortcut),
+            Err(e) => tracing::warn!(
+                "Failed to register {} for {}: {}",
+                binding.accelerator,
+                binding.action,
+                e
+            ),
+        }
+    }
+    *REGISTERED.lock().unwrap() = registered;
+}
+
+/// Conversation used for quick-ask questions on a knowledge base, created on first use.
+/// Whether a conversation still exists in the backend.
+async fn conversation_exists(conv_id: &str) -> Result<bool, String> {
+    let messages = backend_request::<serde_json::Value>(
+        Method::GET,
+        &format!("/api/conversations/{}/messages", conv_id),
+        None,
+    )
+    .await;
+    match messages {
+        Ok(_) => Ok(true),
+        Err(e) => match e.downcast_ref::<BackendError>() {
+            Some(error) if error.status == 404 => Ok(false),
+            _ => Err(e.to_string()),
+        },
+    }
+}
+
+async fn quick_ask_conversation(kb_id: &str) -> Result<String, String> {
+    let state: QuickAskState = store::load(STATE_FILE);
+    if let Some(conv_id) = state.conversations.get(kb_id) {
+        // It may have been deleted from the main window; a new one is started then
+        if conversation_exists(conv_id).await? {
+            return Ok(conv_id.clone());
+        }
+    }
+
+    let conversation = commands::create_conversation(Some(kb_id.to_string())).await?;
+    let mut state: QuickAskState = store::load(STATE_FILE);
+    state
+        .conversations
+        .insert(kb_id.to_string(), conversation.id.clone());
+    store::save(STATE_FILE, &state).map_err(|e| e.to_string())?;
+    Ok(conversation.id)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Get the knowledge base the quick-ask window queries
+#[tauri::command]
+pub async fn get_quick_ask_kb() -> Result<Option<String>, String> {
+    Ok(store::load::<QuickAskState>(STATE_FILE).kb_id)
+}
+
+/// Answer a question from the quick-ask window, against the last-used knowledge base
+/// unless `kb_id` is given
+#[tauri::command]
+pub async fn quick_query(
+    app: AppHandle,
+    question: String,
+    kb_id: Option<String>,
+) -> Result<QueryResponse, String> {
+    let kb_id = kb_id
+        .or_else(|| store::load::<QuickAskState>(STATE_FILE).kb_id)
+        .ok_or("No knowledge base used yet; ask a question in the main window first")?;
+
+    let conversation_id = quick_ask_conversation(&kb_id).await?;
+    let params = QueryParams {
+        kb_id,
+        conversation_id,
+        question,
+        ..Default::default()
+    };
+    commands::query(app, params).await
+}
+
+/// Hide the quick-ask window
+#[tauri::command]
+pub async fn hide_quick_ask(app: AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(QUICK_ASK_LABEL) {
+        window.hide().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
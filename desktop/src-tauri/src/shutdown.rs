@@ -0,0 +1,181 @@
+//! Closing the app while work is in progress.
+//!
+//! When the main window closes during ingestion, re-embedding, or downloads, a native
+//! dialog lets the user keep the work going in the background (the app stays in the
+//! tray and quits once everything is done), pause it at a safe point so it resumes at
+//! the next launch, or abort it. [`checkpoint_all`] also runs before the backend stops,
+//! so the backend is never stopped in the middle of a write.
+
+use crate::jobs::{self, StopMode};
+use crate::{backend, ollama, reembedding, updates};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, WebviewWindow};
+use tauri_plugin_dialog::{
+    DialogExt, MessageDialogButtons, MessageDialogKind, MessageDialogResult,
+};
+
+const PAUSE_LABEL: &str = "Pause and quit";
+const ABORT_LABEL: &str = "Quit now";
+const BACKGROUND_LABEL: &str = "Finish in background";
+/// How often background work is polled while finishing in the tray.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Set while the close dialog is open, so repeated close clicks don't stack dialogs.
+static PROMPTING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct ActiveWork {
+    pub ingestion_jobs: usize,
+    pub reembedding_jobs: usize,
+    pub downloads: usize,
+}
+
+impl ActiveWork {
+    pub fn is_empty(&self) -> bool {
+        self.ingestion_jobs == 0 && self.reembedding_jobs == 0 && self.downloads == 0
+    }
+
+    fn describe(&self) -> String {
+        let plural =
+            |n: usize, what: &str| format!("{} {}{}", n, what, if n > 1 { "s" } else { "" });
+        let mut parts = Vec::new();
+        if self.ingestion_jobs > 0 {
+            parts.push(plural(self.ingestion_jobs, "ingestion job"));
+        }
+        if self.reembedding_jobs > 0 {
+            parts.push(plural(self.reembedding_jobs, "re-embedding job"));
+        }
+        if self.downloads > 0 {
+            parts.push(plural(self.downloads, "download"));
+        }
+        parts.join(", ")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CloseChoice {
+    Background,
+    Pause,
+    Abort,
+}
+
+impl From<MessageDialogResult> for CloseChoice {
+    fn from(result: MessageDialogResult) -> Self {
+        match result {
+            MessageDialogResult::Custom(label) if label == PAUSE_LABEL => CloseChoice::Pause,
+            MessageDialogResult::Custom(label) if label == ABORT_LABEL => CloseChoice::Abort,
+            MessageDialogResult::Yes => CloseChoice::Pause,
+            MessageDialogResult::No => CloseChoice::Abort,
+            // Dismissing the dialog keeps the work going
+            _ => CloseChoice::Background,
+        }
+    }
+}
+
+/// Work that would be interrupted by quitting.
+pub async fn active_work() -> ActiveWork {
+    ActiveWork {
+        ingestion_jobs: jobs::active_jobs().await,
+        reembedding_jobs: reembedding::active_jobs().await,
+        downloads: ollama::active_pulls() + usize::from(updates::is_downloading()),
+    }
+}
+
+/// Stop in-progress work at a safe point, saving what is needed to resume it later.
+///
+/// Ingestion stops after the file being sent and re-embedding after the batch in
+/// flight. Model pulls are cancelled; Ollama keeps the downloaded layers, so pulling
+/// again resumes them.
+pub async fn checkpoint_all() {
+    stop_all(StopMode::Checkpoint).await;
+}
+
+async fn stop_all(mode: StopMode) {
+    ollama::cancel_pulls();
+    tokio::join!(jobs::stop_jobs(mode), reembedding::stop_batches(mode));
+}
+
+/// Keep working with the window hidden, and quit once everything is done unless the
+/// window was reopened in the meantime.
+async fn finish_in_background(app: AppHandle, window: WebviewWindow) {
+    let _ = window.hide();
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        if window.is_visible().unwrap_or(false) {
+            tracing::info!("Main window reopened, not quitting after background work");
+            return;
+        }
+        if active_work().await.is_empty() {
+            break;
+        }
+    }
+    tracing::info!("Background work finished, quitting");
+    backend::shutdown_and_exit(app).await;
+}
+
+/// Handle a close request of the main window, asking what to do with in-progress work.
+pub async fn request_close(window: WebviewWindow) {
+    let app = window.app_handle().clone();
+    let work = active_work().await;
+    if work.is_empty() {
+        let _ = window.set_title("RAGKIT Desktop - Shutting down…");
+        backend::shutdown_and_exit(app).await;
+        return;
+    }
+
+    if PROMPTING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .message(format!(
+            "RAGKIT is still working: {}.\n\n\
+             Pause the work and resume it at the next launch, quit now and abort it, \
+             or keep RAGKIT running in the tray until it finishes.",
+            work.describe()
+        ))
+        .title("Work in progress")
+        .kind(MessageDialogKind::Warning)
+        .parent(&window)
+        .buttons(MessageDialogButtons::YesNoCancelCustom(
+            PAUSE_LABEL.to_string(),
+            ABORT_LABEL.to_string(),
+            BACKGROUND_LABEL.to_string(),
+        ))
+        .show_with_result(move |result| {
+            let _ = tx.send(CloseChoice::from(result));
+        });
+    let choice = rx.await.unwrap_or(CloseChoice::Background);
+    PROMPTING.store(false, Ordering::SeqCst);
+
+    tracing::info!(
+        "Closing with work in progress ({}): {:?}",
+        work.describe(),
+        choice
+    );
+    match choice {
+        CloseChoice::Background => finish_in_background(app, window).await,
+        CloseChoice::Pause => {
+            let _ = window.set_title("RAGKIT Desktop - Saving progress…");
+            // `stop_backend` checkpoints before stopping the backend
+            backend::shutdown_and_exit(app).await;
+        }
+        CloseChoice::Abort => {
+            let _ = window.set_title("RAGKIT Desktop - Shutting down…");
+            stop_all(StopMode::Abort).await;
+            backend::shutdown_and_exit(app).await;
+        }
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Get the work that quitting the app would interrupt
+#[tauri::command]
+pub async fn get_active_work() -> Result<ActiveWork, String> {
+    Ok(active_work().await)
+}
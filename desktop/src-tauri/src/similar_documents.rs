@@ -0,0 +1,101 @@
+//! Similar documents.
+//!
+//! `find_similar_documents` lists the documents of a knowledge base closest to a given
+//! one, to spot near-duplicates worth cleaning up or related material worth reading.
+//! The backend compares the documents' embeddings; older backends fall back to the
+//! TF-IDF similarity of the documents' terms in the shell's lexical index, which finds
+//! copies and shared vocabulary but not paraphrases.
+
+use crate::backend::backend_request;
+use crate::capabilities::{self, Feature};
+use crate::kb_snapshots::ChunkSource;
+use crate::lexical_index;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_TOP_K: usize = 10;
+const MAX_TOP_K: usize = 100;
+/// Similarity from which a document is flagged as a near-duplicate.
+const NEAR_DUPLICATE_SCORE: f64 = 0.95;
+
+#[derive(Debug, Clone, Deserialize)]
+struct BackendSimilarDocument {
+    document_id: String,
+    filename: String,
+    /// Cosine similarity of the document embeddings
+    score: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SimilarDocument {
+    pub document_id: String,
+    pub filename: String,
+    /// From 0 to 1, higher is closer
+    pub score: f64,
+    pub near_duplicate: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SimilarDocuments {
+    /// `LocalIndex` when scores are lexical rather than semantic
+    pub source: ChunkSource,
+    pub documents: Vec<SimilarDocument>,
+}
+
+fn similar_document(document_id: String, filename: String, score: f64) -> SimilarDocument {
+    SimilarDocument {
+        document_id,
+        filename,
+        score,
+        near_duplicate: score >= NEAR_DUPLICATE_SCORE,
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Find the documents of a knowledge base most similar to a document, closest first
+#[tauri::command]
+pub async fn find_similar_documents(
+    kb_id: String,
+    doc_id: String,
+    top_k: Option<usize>,
+) -> Result<SimilarDocuments, String> {
+    let top_k = top_k.unwrap_or(DEFAULT_TOP_K).clamp(1, MAX_TOP_K);
+
+    if capabilities::supports(Feature::SimilarDocuments) {
+        let similar = backend_request::<Vec<BackendSimilarDocument>>(
+            Method::GET,
+            &format!("/api/documents/{}/similar?top_k={}", doc_id, top_k),
+            None,
+        )
+        .await;
+        match similar {
+            Ok(similar) => {
+                return Ok(SimilarDocuments {
+                    source: ChunkSource::Backend,
+                    documents: similar
+                        .into_iter()
+                        .filter(|d| d.document_id != doc_id)
+                        .map(|d| similar_document(d.document_id, d.filename, d.score))
+                        .collect(),
+                })
+            }
+            Err(e) => tracing::warn!("Comparing with the local index instead: {}", e),
+        }
+    }
+
+    let documents = tauri::async_runtime::spawn_blocking(move || {
+        lexical_index::similar_documents(&kb_id, &doc_id, top_k)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(SimilarDocuments {
+        source: ChunkSource::LocalIndex,
+        documents: documents
+            .into_iter()
+            .map(|(id, filename, score)| similar_document(id, filename, score))
+            .collect(),
+    })
+}
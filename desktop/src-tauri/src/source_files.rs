@@ -0,0 +1,106 @@
+//! Opening the original files of cited sources.
+//!
+//! Clicking a citation opens the file it came from with the system's default app.
+//! PDFs are opened at the cited page, with the start of the chunk as a search, using
+//! the standard PDF open parameters (`#page=N&search="…"`); viewers that don't support
+//! them just open the file. The file is found from its ingestion record, so files
+//! uploaded from memory can't be opened, and only files that were ingested are opened.
+
+use crate::provenance;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use tauri_plugin_opener::OpenerExt;
+
+/// Number of words of the chunk used as the PDF search.
+const SEARCH_WORDS: usize = 6;
+
+/// Original file of a source, from its filename or its path. Paths not recorded at
+/// ingestion are refused.
+fn resolve(kb_id: Option<&str>, filename: &str) -> Result<PathBuf, String> {
+    let path = if Path::new(filename).is_absolute() {
+        provenance::is_ingested_path(kb_id, filename)
+            .then(|| filename.to_string())
+            .ok_or_else(|| format!("{} is not the original file of a source", filename))?
+    } else {
+        provenance::find_path(kb_id, filename)
+            .ok_or_else(|| format!("The original file of {} is unknown", filename))?
+    };
+    let path = PathBuf::from(path);
+    if path.is_file() {
+        Ok(path)
+    } else {
+        Err(format!(
+            "{} no longer exists; it may have been moved or deleted",
+            path.display()
+        ))
+    }
+}
+
+/// `file://` URL of a PDF with page and search open parameters.
+fn pdf_url(path: &Path, page: Option<u32>, chunk_text: Option<&str>) -> Option<String> {
+    let mut url = reqwest::Url::from_file_path(path).ok()?;
+    let mut parameters = Vec::new();
+    if let Some(page) = page {
+        parameters.push(format!("page={}", page.max(1)));
+    }
+    if let Some(text) = chunk_text {
+        let words: Vec<&str> = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .take(SEARCH_WORDS)
+            .collect();
+        if !words.is_empty() {
+            parameters.push(format!("search=\"{}\"", words.join(" ")));
+        }
+    }
+    if parameters.is_empty() {
+        return None;
+    }
+    url.set_fragment(Some(&parameters.join("&")));
+    Some(url.to_string())
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Open the original file of a source with the default app, at the cited page for PDFs
+#[tauri::command]
+pub async fn open_source(
+    app: AppHandle,
+    filename: String,
+    page: Option<u32>,
+    chunk_text: Option<String>,
+    kb_id: Option<String>,
+) -> Result<String, String> {
+    let path = resolve(kb_id.as_deref(), &filename)?;
+    let is_pdf = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("pdf"));
+    let anchored = is_pdf
+        .then(|| pdf_url(&path, page, chunk_text.as_deref()))
+        .flatten();
+
+    tracing::info!("Opening source {}", path.display());
+    match anchored {
+        Some(url) => app.opener().open_url(url, None::<&str>),
+        None => app.opener().open_path(path.to_string_lossy(), None::<&str>),
+    }
+    .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    Ok(path.display().to_string())
+}
+
+/// Show a file in the system file manager
+///
+/// `path` is the path of an ingested file, or the filename of a source.
+#[tauri::command]
+pub async fn reveal_in_folder(
+    app: AppHandle,
+    path: String,
+    kb_id: Option<String>,
+) -> Result<(), String> {
+    let path = resolve(kb_id.as_deref(), &path)?;
+    app.opener()
+        .reveal_item_in_dir(&path)
+        .map_err(|e| format!("Failed to reveal {}: {}", path.display(), e))
+}
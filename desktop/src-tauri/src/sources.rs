@@ -0,0 +1,59 @@
+//! Post-processing of the sources returned with an answer.
+
+use crate::commands::Source;
+use std::collections::HashSet;
+
+/// Number of consecutive words per shingle used to compare chunks.
+const SHINGLE_SIZE: usize = 3;
+
+fn shingles(text: &str) -> HashSet<String> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect();
+
+    if words.len() < SHINGLE_SIZE {
+        return std::iter::once(words.join(" ")).collect();
+    }
+    words.windows(SHINGLE_SIZE).map(|w| w.join(" ")).collect()
+}
+
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// Mark near-identical chunks (e.g. the same paragraph from mirrored or versioned
+/// documents) as duplicates of the highest-scoring copy.
+///
+/// Sources keep their order, so citation numbers in the answer still match; the UI can
+/// collapse the marked ones. Returns the number of sources marked.
+pub fn mark_near_duplicates(sources: &mut [Source], threshold: f64) -> usize {
+    let shingled: Vec<HashSet<String>> = sources.iter().map(|s| shingles(&s.chunk)).collect();
+    let mut by_score: Vec<usize> = (0..sources.len()).collect();
+    by_score.sort_by(|a, b| sources[*b].score.total_cmp(&sources[*a].score));
+
+    let mut kept: Vec<usize> = Vec::new();
+    let mut marked = 0;
+    for index in by_score {
+        match kept
+            .iter()
+            .find(|k| jaccard(&shingled[**k], &shingled[index]) >= threshold)
+        {
+            Some(&original) => {
+                sources[index].duplicate_of = Some(original);
+                marked += 1;
+            }
+            None => kept.push(index),
+        }
+    }
+
+    if marked > 0 {
+        tracing::debug!("Marked {} near-duplicate sources", marked);
+    }
+    marked
+}
@@ -0,0 +1,142 @@
+//! Cold-start instrumentation and the fast-start path.
+//!
+//! Each startup phase is timed from process start and reported as a `startup-phase`
+//! event; `backend-ready` fires once the backend answers its health check. In fast-start
+//! mode the last known knowledge bases and conversations are cached on disk so the UI
+//! can render them immediately while the backend is still warming up.
+
+use crate::commands::{Conversation, KnowledgeBase};
+use crate::{preferences, store};
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter};
+
+const SNAPSHOT_FILE: &str = "startup_snapshot.json";
+
+static PROCESS_START: OnceLock<Instant> = OnceLock::new();
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static TIMINGS: Mutex<Vec<PhaseTiming>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupPhase {
+    /// Tauri runtime and main window created
+    TauriInit,
+    /// Backend process spawned
+    BackendSpawned,
+    /// First output from the backend, roughly when Python finished importing
+    BackendFirstOutput,
+    /// Backend answered its health check
+    BackendReady,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PhaseTiming {
+    pub phase: StartupPhase,
+    /// Milliseconds since the process started
+    pub elapsed_ms: u64,
+}
+
+/// Data shown while the backend is warming up.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StartupSnapshot {
+    pub knowledge_bases: Vec<KnowledgeBase>,
+    pub conversations: Vec<Conversation>,
+    pub saved_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StartupState {
+    pub backend_ready: bool,
+    pub timings: Vec<PhaseTiming>,
+    /// Cached data from the previous session, only in fast-start mode
+    pub snapshot: Option<StartupSnapshot>,
+}
+
+/// Start the startup clock. Call first thing in `main`.
+pub fn mark_process_start() {
+    PROCESS_START.get_or_init(Instant::now);
+}
+
+/// Record the end of Tauri initialization and keep the handle used to emit events.
+pub fn init(app: &AppHandle) {
+    let _ = APP.set(app.clone());
+    record(StartupPhase::TauriInit);
+}
+
+/// Handle of the running app, once Tauri is initialized.
+pub fn app() -> Option<&'static AppHandle> {
+    APP.get()
+}
+
+/// Record a startup phase. Only the first occurrence counts, so backend restarts
+/// don't overwrite cold-start timings.
+pub fn record(phase: StartupPhase) {
+    let timing = {
+        let mut timings = TIMINGS.lock().unwrap();
+        if timings.iter().any(|t| t.phase == phase) {
+            return;
+        }
+        let start = *PROCESS_START.get_or_init(Instant::now);
+        let timing = PhaseTiming {
+            phase,
+            elapsed_ms: start.elapsed().as_millis() as u64,
+        };
+        timings.push(timing.clone());
+        timing
+    };
+
+    tracing::info!("Startup phase {:?} reached after {} ms", phase, timing.elapsed_ms);
+    if let Some(app) = APP.get() {
+        let _ = app.emit("startup-phase", &timing);
+        if phase == StartupPhase::BackendReady {
+            let _ = app.emit("backend-ready", &timing);
+        }
+    }
+}
+
+fn update_snapshot(f: impl FnOnce(&mut StartupSnapshot)) {
+    if !preferences::load().fast_start {
+        return;
+    }
+    let mut snapshot: StartupSnapshot = store::load(SNAPSHOT_FILE);
+    f(&mut snapshot);
+    snapshot.saved_at = Some(chrono::Utc::now().to_rfc3339());
+    if let Err(e) = store::save(SNAPSHOT_FILE, &snapshot) {
+        tracing::warn!("Failed to save startup snapshot: {}", e);
+    }
+}
+
+/// Cache the knowledge base list for the next fast start.
+pub fn cache_knowledge_bases(knowledge_bases: &[KnowledgeBase]) {
+    update_snapshot(|s| s.knowledge_bases = knowledge_bases.to_vec());
+}
+
+/// Cache the conversation list for the next fast start.
+pub fn cache_conversations(conversations: &[Conversation]) {
+    update_snapshot(|s| s.conversations = conversations.to_vec());
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Get startup timings, backend readiness, and cached data for a fast start
+#[tauri::command]
+pub async fn get_startup_state() -> Result<StartupState, String> {
+    let timings = TIMINGS.lock().unwrap().clone();
+    let backend_ready = crate::backend::is_running()
+        && timings
+            .iter()
+            .any(|t| t.phase == StartupPhase::BackendReady);
+    let snapshot = preferences::load()
+        .fast_start
+        .then(|| store::load::<StartupSnapshot>(SNAPSHOT_FILE));
+
+    Ok(StartupState {
+        backend_ready,
+        timings,
+        snapshot,
+    })
+}
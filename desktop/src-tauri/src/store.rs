@@ -0,0 +1,107 @@
+//! Local persistence for state owned by the desktop shell.
+//!
+//! Everything lives as small JSON files under ~/.ragkit/ so it survives
+//! backend restarts and app updates.
+
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::PathBuf;
+use tokio::sync::Mutex;
+
+/// Get the RAGKIT home directory: the temporary directory in guest mode, then the
+/// configured data directory, then ~/.ragkit/
+pub fn ragkit_dir() -> PathBuf {
+    if let Some(dir) = crate::guest::data_dir() {
+        return dir.to_path_buf();
+    }
+    if let Some(dir) = &crate::config::get().data_dir {
+        return dir.value.clone();
+    }
+    default_dir()
+}
+
+/// Get the user's home directory
+pub fn home_dir() -> PathBuf {
+    #[cfg(target_os = "windows")]
+    let home = std::env::var("USERPROFILE").unwrap_or_else(|_| "C:\\".to_string());
+    #[cfg(not(target_os = "windows"))]
+    let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+
+    PathBuf::from(home)
+}
+
+/// Get the default RAGKIT home directory (~/.ragkit/), which also holds config.toml
+pub fn default_dir() -> PathBuf {
+    home_dir().join(".ragkit")
+}
+
+/// Get the directory for temporary files (~/.ragkit/tmp/)
+pub fn tmp_dir() -> PathBuf {
+    ragkit_dir().join("tmp")
+}
+
+/// Load a JSON file from the RAGKIT home directory, falling back to the default value
+/// when the file is missing or unreadable.
+pub fn load<T: DeserializeOwned + Default>(name: &str) -> T {
+    let path = ragkit_dir().join(name);
+    match std::fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring corrupt state file {}: {}", path.display(), e);
+            T::default()
+        }),
+        Err(_) => T::default(),
+    }
+}
+
+/// Save a value as JSON in the RAGKIT home directory.
+///
+/// Writes to a temporary file first so a crash never leaves a half-written state file.
+pub fn save<T: Serialize>(name: &str, value: &T) -> Result<()> {
+    let dir = ragkit_dir();
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| anyhow!("Failed to create {}: {}", dir.display(), e))?;
+
+    let path = dir.join(name);
+    let tmp_path = dir.join(format!("{}.tmp", name));
+    let content = serde_json::to_string_pretty(value)?;
+    std::fs::write(&tmp_path, content)
+        .map_err(|e| anyhow!("Failed to write {}: {}", tmp_path.display(), e))?;
+    std::fs::rename(&tmp_path, &path)
+        .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+/// A state file kept in memory after its first use.
+///
+/// Reads are served from memory; the file is only written after an update.
+pub struct CachedStore<T> {
+    name: &'static str,
+    value: Mutex<Option<T>>,
+}
+
+impl<T: Serialize + DeserializeOwned + Default> CachedStore<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            value: Mutex::const_new(None),
+        }
+    }
+
+    /// Run a closure against the value, loading it from disk on first use.
+    pub async fn read<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        let mut guard = self.value.lock().await;
+        f(guard.get_or_insert_with(|| load(self.name)))
+    }
+
+    /// Run a closure changing the value, then persist it.
+    pub async fn update<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let mut guard = self.value.lock().await;
+        let value = guard.get_or_insert_with(|| load(self.name));
+        let result = f(value);
+        if let Err(e) = save(self.name, value) {
+            tracing::error!("Failed to persist {}: {}", self.name, e);
+        }
+        result
+    }
+}
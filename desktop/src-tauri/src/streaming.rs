@@ -0,0 +1,91 @@
+//! Readers for streamed responses: server-sent events from the backend and
+//! newline-delimited JSON from Ollama.
+//!
+//! Chunks are buffered as bytes and only complete lines are decoded, so a multibyte
+//! character split across two chunks survives. Lines may end with `\n`, `\r\n` or `\r`.
+
+/// Complete lines of a streamed response body.
+pub struct LineReader {
+    response: reqwest::Response,
+    buffer: Vec<u8>,
+    finished: bool,
+}
+
+impl LineReader {
+    pub fn new(response: reqwest::Response) -> Self {
+        Self {
+            response,
+            buffer: Vec::new(),
+            finished: false,
+        }
+    }
+
+    /// Next line without its line ending, or `None` once the body has ended.
+    pub async fn next_line(&mut self) -> Result<Option<String>, reqwest::Error> {
+        loop {
+            if let Some(line) = self.take_line() {
+                return Ok(Some(line));
+            }
+            if self.finished {
+                if self.buffer.is_empty() {
+                    return Ok(None);
+                }
+                let rest = std::mem::take(&mut self.buffer);
+                return Ok(Some(String::from_utf8_lossy(&rest).into_owned()));
+            }
+            match self.response.chunk().await? {
+                Some(chunk) => self.buffer.extend_from_slice(&chunk),
+                None => self.finished = true,
+            }
+        }
+    }
+
+    fn take_line(&mut self) -> Option<String> {
+        let end = self
+            .buffer
+            .iter()
+            .position(|b| *b == b'\n' || *b == b'\r')?;
+        let ending = match self.buffer.get(end + 1) {
+            Some(b'\n') if self.buffer[end] == b'\r' => 2,
+            // A trailing \r may be the first half of \r\n
+            None if self.buffer[end] == b'\r' && !self.finished => return None,
+            _ => 1,
+        };
+        let line: Vec<u8> = self.buffer.drain(..end + ending).take(end).collect();
+        Some(String::from_utf8_lossy(&line).into_owned())
+    }
+}
+
+/// Data of the server-sent events of a response.
+pub struct SseReader {
+    lines: LineReader,
+}
+
+impl SseReader {
+    pub fn new(response: reqwest::Response) -> Self {
+        Self {
+            lines: LineReader::new(response),
+        }
+    }
+
+    /// Data of the next event, its `data:` lines joined with newlines. Events without
+    /// data (comments, keep-alives) are skipped.
+    pub async fn next_event(&mut self) -> Result<Option<String>, reqwest::Error> {
+        let mut data: Vec<String> = Vec::new();
+        loop {
+            let Some(line) = self.lines.next_line().await? else {
+                // An unterminated last event is still dispatched
+                return Ok((!data.is_empty()).then(|| data.join("\n")));
+            };
+            if line.is_empty() {
+                if !data.is_empty() {
+                    return Ok(Some(data.join("\n")));
+                }
+                continue;
+            }
+            if let Some(value) = line.strip_prefix("data:") {
+                data.push(value.strip_prefix(' ').unwrap_or(value).to_string());
+            }
+        }
+    }
+}
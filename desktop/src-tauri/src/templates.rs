@@ -0,0 +1,310 @@
+//! Saved question templates with variables.
+//!
+//! A template is a question with `{placeholders}`, e.g. `Summarize {document} for
+//! {audience}`, so recurring analyses can be rerun with the same wording. Variables
+//! named `document` (or ending in `_document`) are resolved against the documents of
+//! the target knowledge base; the others are free text. A literal brace is written `{{`
+//! or `}}`.
+
+use crate::commands::{self, Document};
+use crate::store;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const STATE_FILE: &str = "question_templates.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuestionTemplate {
+    pub id: String,
+    pub name: String,
+    pub template: String,
+    /// Knowledge base the template is meant for, if any
+    pub kb_id: Option<String>,
+    pub variables: Vec<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VariableKind {
+    Text,
+    Document,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateVariable {
+    pub name: String,
+    pub kind: VariableKind,
+    /// Document filenames of the knowledge base, for document variables
+    pub options: Vec<String>,
+}
+
+enum Segment<'a> {
+    Text(&'a str),
+    Variable(&'a str),
+}
+
+/// Split a template into literal text and variables.
+fn parse(template: &str) -> Result<Vec<Segment<'_>>> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+    while let Some(pos) = rest.find(['{', '}']) {
+        let (text, tail) = rest.split_at(pos);
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        if let Some(after) = tail.strip_prefix("{{") {
+            segments.push(Segment::Text("{"));
+            rest = after;
+        } else if let Some(after) = tail.strip_prefix("}}") {
+            segments.push(Segment::Text("}"));
+            rest = after;
+        } else if tail.starts_with('}') {
+            return Err(anyhow!(
+                "Unmatched '}}' in template (use '}}}}' for a literal brace)"
+            ));
+        } else {
+            let end = tail[1..]
+                .find('}')
+                .ok_or_else(|| anyhow!("Unclosed '{{' in template"))?;
+            let name = tail[1..=end].trim();
+            let valid = !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == ' ');
+            if !valid {
+                return Err(anyhow!("Invalid variable name '{}'", &tail[1..=end]));
+            }
+            segments.push(Segment::Variable(name));
+            rest = &tail[end + 2..];
+        }
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest));
+    }
+    Ok(segments)
+}
+
+/// Variable names in order of first appearance.
+fn variables(template: &str) -> Result<Vec<String>> {
+    let mut names: Vec<String> = Vec::new();
+    for segment in parse(template)? {
+        if let Segment::Variable(name) = segment {
+            if !names.iter().any(|n| n == name) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    Ok(names)
+}
+
+fn variable_kind(name: &str) -> VariableKind {
+    let name = name.to_lowercase();
+    if name == "document" || name.ends_with("_document") || name.ends_with(" document") {
+        VariableKind::Document
+    } else {
+        VariableKind::Text
+    }
+}
+
+/// Match a document variable value against the KB documents. Exact filenames win;
+/// otherwise a case-insensitive match, then a unique partial match is accepted.
+fn resolve_document(value: &str, documents: &[Document]) -> Result<String> {
+    let value = value.trim();
+    if let Some(doc) = documents.iter().find(|d| d.filename == value) {
+        return Ok(doc.filename.clone());
+    }
+    let lower = value.to_lowercase();
+    if let Some(doc) = documents
+        .iter()
+        .find(|d| d.filename.to_lowercase() == lower)
+    {
+        return Ok(doc.filename.clone());
+    }
+    let partial: Vec<_> = documents
+        .iter()
+        .filter(|d| d.filename.to_lowercase().contains(&lower))
+        .collect();
+    match partial.as_slice() {
+        [doc] => Ok(doc.filename.clone()),
+        [] => Err(anyhow!("No document matches '{}'", value)),
+        _ => Err(anyhow!(
+            "'{}' matches several documents: {}",
+            value,
+            partial
+                .iter()
+                .map(|d| d.filename.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+async fn render(
+    template: &QuestionTemplate,
+    kb_id: Option<&str>,
+    values: &HashMap<String, String>,
+) -> Result<String> {
+    let segments = parse(&template.template)?;
+
+    let needs_documents = template
+        .variables
+        .iter()
+        .any(|v| variable_kind(v) == VariableKind::Document);
+    let documents = match (needs_documents, kb_id) {
+        (false, _) => Vec::new(),
+        (true, Some(kb_id)) => commands::list_documents(kb_id.to_string())
+            .await
+            .map_err(|e| anyhow!("Failed to list documents: {}", e))?,
+        (true, None) => return Err(anyhow!("A knowledge base is required to resolve documents")),
+    };
+
+    let mut question = String::new();
+    for segment in segments {
+        match segment {
+            Segment::Text(text) => question.push_str(text),
+            Segment::Variable(name) => {
+                let value = values
+                    .get(name)
+                    .map(|v| v.trim())
+                    .filter(|v| !v.is_empty())
+                    .ok_or_else(|| anyhow!("Missing value for '{}'", name))?;
+                match variable_kind(name) {
+                    VariableKind::Document => {
+                        question.push_str(&resolve_document(value, &documents)?)
+                    }
+                    VariableKind::Text => question.push_str(value),
+                }
+            }
+        }
+    }
+    Ok(question)
+}
+
+fn find(id: &str) -> Result<QuestionTemplate, String> {
+    let templates: Vec<QuestionTemplate> = store::load(STATE_FILE);
+    templates
+        .into_iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| format!("Template not found: {}", id))
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// List saved question templates
+#[tauri::command]
+pub async fn list_question_templates() -> Result<Vec<QuestionTemplate>, String> {
+    Ok(store::load(STATE_FILE))
+}
+
+/// Create a question template, or update it when `id` is given
+#[tauri::command]
+pub async fn save_question_template(
+    id: Option<String>,
+    name: String,
+    template: String,
+    kb_id: Option<String>,
+) -> Result<QuestionTemplate, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Template name cannot be empty".into());
+    }
+    let variables = variables(&template).map_err(|e| e.to_string())?;
+
+    let mut templates: Vec<QuestionTemplate> = store::load(STATE_FILE);
+    let now = Utc::now().to_rfc3339();
+    let saved = match id {
+        Some(id) => {
+            let existing = templates
+                .iter_mut()
+                .find(|t| t.id == id)
+                .ok_or_else(|| format!("Template not found: {}", id))?;
+            existing.name = name;
+            existing.template = template;
+            existing.kb_id = kb_id;
+            existing.variables = variables;
+            existing.updated_at = now;
+            existing.clone()
+        }
+        None => {
+            let created = QuestionTemplate {
+                id: uuid::Uuid::new_v4().to_string(),
+                name,
+                template,
+                kb_id,
+                variables,
+                created_at: now.clone(),
+                updated_at: now,
+            };
+            templates.push(created.clone());
+            created
+        }
+    };
+
+    store::save(STATE_FILE, &templates).map_err(|e| e.to_string())?;
+    Ok(saved)
+}
+
+/// Delete a question template
+#[tauri::command]
+pub async fn delete_question_template(id: String) -> Result<bool, String> {
+    let mut templates: Vec<QuestionTemplate> = store::load(STATE_FILE);
+    let before = templates.len();
+    templates.retain(|t| t.id != id);
+    if templates.len() == before {
+        return Ok(false);
+    }
+    store::save(STATE_FILE, &templates).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// Get the variables of a template, with the document choices of a knowledge base
+#[tauri::command]
+pub async fn get_template_variables(
+    id: String,
+    kb_id: Option<String>,
+) -> Result<Vec<TemplateVariable>, String> {
+    let template = find(&id)?;
+    let kb_id = kb_id.or(template.kb_id);
+
+    let mut documents: Option<Vec<String>> = None;
+    let mut result = Vec::new();
+    for name in template.variables {
+        let kind = variable_kind(&name);
+        let options = match (kind, &kb_id) {
+            (VariableKind::Document, Some(kb_id)) => {
+                if documents.is_none() {
+                    let list = commands::list_documents(kb_id.clone()).await?;
+                    documents = Some(list.into_iter().map(|d| d.filename).collect());
+                }
+                documents.clone().unwrap_or_default()
+            }
+            _ => Vec::new(),
+        };
+        result.push(TemplateVariable {
+            name,
+            kind,
+            options,
+        });
+    }
+    Ok(result)
+}
+
+/// Fill in a template's variables and return the resulting question
+#[tauri::command]
+pub async fn resolve_question_template(
+    id: String,
+    kb_id: Option<String>,
+    values: HashMap<String, String>,
+) -> Result<String, String> {
+    let template = find(&id)?;
+    let kb_id = kb_id.or_else(|| template.kb_id.clone());
+    render(&template, kb_id.as_deref(), &values)
+        .await
+        .map_err(|e| e.to_string())
+}
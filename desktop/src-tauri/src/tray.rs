@@ -0,0 +1,91 @@
+//! System tray icon with quick actions.
+
+use crate::backend;
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Emitter, Manager};
+
+const PAUSE_LABEL: &str = "Pause backend";
+const RESUME_LABEL: &str = "Resume backend";
+
+/// Show and focus the main window.
+pub fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+/// Create the tray icon and its menu.
+pub fn create_tray(app: &AppHandle) -> tauri::Result<()> {
+    let open = MenuItem::with_id(app, "open", "Open RAGKIT", true, None::<&str>)?;
+    let new_conversation = MenuItem::with_id(
+        app,
+        "new_conversation",
+        "New conversation",
+        true,
+        None::<&str>,
+    )?;
+    let toggle_backend = MenuItem::with_id(app, "toggle_backend", PAUSE_LABEL, true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &open,
+            &new_conversation,
+            &PredefinedMenuItem::separator(app)?,
+            &toggle_backend,
+            &PredefinedMenuItem::separator(app)?,
+            &quit,
+        ],
+    )?;
+
+    let mut builder = TrayIconBuilder::with_id("main")
+        .tooltip("RAGKIT Desktop")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(move |app, event| match event.id.as_ref() {
+            "open" => show_main_window(app),
+            "new_conversation" => {
+                show_main_window(app);
+                let _ = app.emit("tray-new-conversation", ());
+            }
+            "toggle_backend" => {
+                let app = app.clone();
+                let toggle_backend = toggle_backend.clone();
+                tauri::async_runtime::spawn(async move {
+                    if backend::is_running() {
+                        backend::stop_backend(&app).await;
+                        let _ = toggle_backend.set_text(RESUME_LABEL);
+                    } else {
+                        let _ = toggle_backend.set_text(PAUSE_LABEL);
+                        if let Err(e) = backend::start_backend(&app).await {
+                            tracing::error!("Failed to resume backend: {}", e);
+                            let _ = toggle_backend.set_text(RESUME_LABEL);
+                        }
+                    }
+                });
+            }
+            "quit" => {
+                tauri::async_runtime::spawn(backend::shutdown_and_exit(app.clone()));
+            }
+            _ => {}
+        })
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_main_window(tray.app_handle());
+            }
+        });
+
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+    Ok(())
+}
@@ -0,0 +1,129 @@
+//! In-app updates through the Tauri updater plugin.
+//!
+//! The release channel (stable or beta) selects the update manifest that is checked.
+//! Updates are downloaded and installed in separate steps so the UI can ask before
+//! restarting the app.
+
+use crate::preferences::{self, UpdateChannel};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_updater::{Update, UpdaterExt};
+use tokio::sync::Mutex;
+
+/// Public key used to verify update signatures, set by the release workflows from the
+/// `TAURI_UPDATER_PUBKEY` repository variable. Other builds can't update.
+pub const UPDATER_PUBKEY: Option<&str> = option_env!("RAGKIT_UPDATER_PUBKEY");
+
+const STABLE_ENDPOINT: &str =
+    "https://github.com/henribesnard/ragkit/releases/latest/download/latest.json";
+const BETA_ENDPOINT: &str =
+    "https://github.com/henribesnard/ragkit/releases/download/desktop-beta/latest.json";
+
+/// Update found by the last check, and its payload once downloaded.
+static PENDING_UPDATE: Mutex<Option<(Update, Option<Vec<u8>>)>> = Mutex::const_new(None);
+/// Set while an update is downloading.
+static DOWNLOADING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub notes: Option<String>,
+    pub date: Option<String>,
+    pub channel: UpdateChannel,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct DownloadProgress {
+    downloaded: usize,
+    total: Option<u64>,
+}
+
+/// Whether an update is downloading.
+pub fn is_downloading() -> bool {
+    DOWNLOADING.load(Ordering::Relaxed)
+}
+
+fn endpoint(channel: UpdateChannel) -> &'static str {
+    match channel {
+        UpdateChannel::Stable => STABLE_ENDPOINT,
+        UpdateChannel::Beta => BETA_ENDPOINT,
+    }
+}
+
+/// Check the configured release channel for a newer version
+#[tauri::command]
+pub async fn check_for_updates(app: AppHandle) -> Result<Option<UpdateInfo>, String> {
+    if UPDATER_PUBKEY.is_none_or(str::is_empty) {
+        return Err("Updates are not available for this build".to_string());
+    }
+    crate::network::ensure_online("Checking for updates")?;
+
+    let channel = preferences::load().update_channel;
+    let url = endpoint(channel).parse().map_err(|e| format!("{}", e))?;
+    let updater = app
+        .updater_builder()
+        .endpoints(vec![url])
+        .map_err(|e| e.to_string())?
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let update = updater.check().await.map_err(|e| e.to_string())?;
+    let info = update.as_ref().map(|u| UpdateInfo {
+        version: u.version.clone(),
+        current_version: u.current_version.clone(),
+        notes: u.body.clone(),
+        date: u.date.map(|d| d.to_string()),
+        channel,
+    });
+
+    if let Some(info) = &info {
+        tracing::info!("Update available on {:?} channel: {}", channel, info.version);
+    }
+    *PENDING_UPDATE.lock().await = update.map(|u| (u, None));
+    Ok(info)
+}
+
+/// Download the update found by `check_for_updates`, emitting `update-download-progress`
+#[tauri::command]
+pub async fn download_update(app: AppHandle) -> Result<(), String> {
+    let mut pending = PENDING_UPDATE.lock().await;
+    let (update, payload) = pending
+        .as_mut()
+        .ok_or_else(|| "No update available, check for updates first".to_string())?;
+
+    let mut downloaded = 0;
+    DOWNLOADING.store(true, Ordering::Relaxed);
+    let result = update
+        .download(
+            |chunk_length, total| {
+                downloaded += chunk_length;
+                let _ = app.emit(
+                    "update-download-progress",
+                    DownloadProgress { downloaded, total },
+                );
+            },
+            || tracing::info!("Update download finished"),
+        )
+        .await;
+    DOWNLOADING.store(false, Ordering::Relaxed);
+    let bytes = result.map_err(|e| e.to_string())?;
+
+    *payload = Some(bytes);
+    Ok(())
+}
+
+/// Install the downloaded update and restart the app
+#[tauri::command]
+pub async fn install_update(app: AppHandle) -> Result<(), String> {
+    let pending = PENDING_UPDATE.lock().await.take();
+    let Some((update, Some(bytes))) = pending else {
+        return Err("No downloaded update to install".to_string());
+    };
+
+    tracing::info!("Installing update {}", update.version);
+    crate::backend::stop_backend(&app).await;
+    update.install(bytes).map_err(|e| e.to_string())?;
+    app.restart();
+}
@@ -0,0 +1,310 @@
+//! Token usage and cost tracking.
+//!
+//! Each answer carries its prompt and completion token counts (as reported by the
+//! backend, or estimated from the text when it reports none) and an estimated cost
+//! from approximate list prices. Totals are accumulated per day and per provider and
+//! model in usage.json, so spend survives backend restarts and can be charted with
+//! `get_usage_stats`.
+
+use crate::commands::{self, Message, QueryParams, QueryResponse};
+use crate::failover::ProviderTarget;
+use crate::store;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+const STATE_FILE: &str = "usage.json";
+
+/// Rough number of characters per token, to estimate counts the backend didn't report.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Approximate list prices in USD per million tokens (prompt, completion), matched on
+/// the start of the model name. Longer prefixes come first.
+const PRICES: &[(&str, f64, f64)] = &[
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-4o", 2.50, 10.00),
+    ("gpt-4.1-nano", 0.10, 0.40),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1", 2.00, 8.00),
+    ("gpt-4-turbo", 10.00, 30.00),
+    ("gpt-3.5-turbo", 0.50, 1.50),
+    ("o3-mini", 1.10, 4.40),
+    ("o4-mini", 1.10, 4.40),
+    ("claude-3-5-haiku", 0.80, 4.00),
+    ("claude-3-haiku", 0.25, 1.25),
+    ("claude-3-5-sonnet", 3.00, 15.00),
+    ("claude-3-7-sonnet", 3.00, 15.00),
+    ("claude-sonnet-4", 3.00, 15.00),
+    ("claude-3-opus", 15.00, 75.00),
+    ("claude-opus-4", 15.00, 75.00),
+    ("mistral-small", 0.20, 0.60),
+    ("mistral-medium", 0.40, 2.00),
+    ("mistral-large", 2.00, 6.00),
+    ("open-mistral-nemo", 0.15, 0.15),
+    ("deepseek-chat", 0.27, 1.10),
+];
+
+/// Providers running on this machine, which cost nothing per token.
+const LOCAL_PROVIDERS: &[&str] = &["ollama", "local"];
+
+/// Serializes updates of the usage store.
+static UPDATES: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageTotals {
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Estimated cost in USD of the requests with a known price
+    pub cost: f64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, other: &UsageTotals) {
+        self.requests += other.requests;
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.cost += other.cost;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MessageUsage {
+    prompt_tokens: u32,
+    completion_tokens: u32,
+    estimated_cost: Option<f64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct UsageStore {
+    /// Totals per day (YYYY-MM-DD, local time), then per `provider/model`
+    days: BTreeMap<String, BTreeMap<String, UsageTotals>>,
+    /// Usage of each answer, by message id
+    messages: BTreeMap<String, MessageUsage>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsagePeriod {
+    Today,
+    Week,
+    Month,
+    Year,
+    All,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelUsage {
+    pub provider: String,
+    pub model: String,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderUsage {
+    pub provider: String,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+    pub models: Vec<ModelUsage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageStats {
+    pub period: UsagePeriod,
+    /// First day included, `None` for all time
+    pub since: Option<String>,
+    pub total: UsageTotals,
+    /// Providers by descending cost
+    pub by_provider: Vec<ProviderUsage>,
+    /// Totals per day (YYYY-MM-DD)
+    pub by_day: BTreeMap<String, UsageTotals>,
+}
+
+fn estimate_tokens(text: &str) -> u32 {
+    (text.chars().count().div_ceil(CHARS_PER_TOKEN)) as u32
+}
+
+/// Estimated cost in USD, `None` for models without a known price.
+fn estimate_cost(
+    model: &ProviderTarget,
+    prompt_tokens: u32,
+    completion_tokens: u32,
+) -> Option<f64> {
+    if LOCAL_PROVIDERS.contains(&model.provider.to_lowercase().as_str()) {
+        return Some(0.0);
+    }
+    let name = model.model.to_lowercase();
+    // Provider prefixes such as "openai/gpt-4o" are ignored
+    let name = name.rsplit('/').next().unwrap_or(&name);
+    let (_, prompt_price, completion_price) = PRICES
+        .iter()
+        .find(|(prefix, _, _)| name.starts_with(prefix))?;
+    Some(
+        (f64::from(prompt_tokens) * prompt_price + f64::from(completion_tokens) * completion_price)
+            / 1_000_000.0,
+    )
+}
+
+/// Provider and model a query will be answered by: the one it names, or the settings'.
+pub async fn resolve_model(params: &QueryParams) -> Option<ProviderTarget> {
+    if let (Some(provider), Some(model)) = (&params.llm_provider, &params.llm_model) {
+        return Some(ProviderTarget {
+            provider: provider.clone(),
+            model: model.clone(),
+        });
+    }
+    let settings = commands::get_settings().await.ok()?;
+    Some(ProviderTarget {
+        provider: params.llm_provider.clone().unwrap_or(settings.llm_provider),
+        model: settings.llm_model,
+    })
+}
+
+/// Fill in the token counts and cost of an answer, and add them to the totals.
+pub fn record(
+    params: &QueryParams,
+    model: Option<&ProviderTarget>,
+    mut response: QueryResponse,
+) -> QueryResponse {
+    // A failover chain may have answered with another model
+    let served_by = response.served_by.as_deref().and_then(|s| {
+        let (provider, model) = s.split_once('/')?;
+        Some(ProviderTarget {
+            provider: provider.to_string(),
+            model: model.to_string(),
+        })
+    });
+    let model = served_by.as_ref().or(model);
+
+    let prompt_tokens = response.prompt_tokens.unwrap_or_else(|| {
+        estimate_tokens(&params.question)
+            + response
+                .sources
+                .iter()
+                .map(|s| estimate_tokens(&s.chunk))
+                .sum::<u32>()
+    });
+    let completion_tokens = response
+        .completion_tokens
+        .unwrap_or_else(|| estimate_tokens(&response.answer));
+    let cost = response
+        .estimated_cost
+        .or_else(|| model.and_then(|m| estimate_cost(m, prompt_tokens, completion_tokens)));
+    response.prompt_tokens = Some(prompt_tokens);
+    response.completion_tokens = Some(completion_tokens);
+    response.estimated_cost = cost;
+
+    let key = model.map_or_else(
+        || "unknown/unknown".to_string(),
+        |m| format!("{}/{}", m.provider, m.model),
+    );
+    let _guard = UPDATES.lock().unwrap();
+    let mut usage: UsageStore = store::load(STATE_FILE);
+    let day = chrono::Local::now().format("%Y-%m-%d").to_string();
+    usage
+        .days
+        .entry(day)
+        .or_default()
+        .entry(key)
+        .or_default()
+        .add(&UsageTotals {
+            requests: 1,
+            prompt_tokens: u64::from(prompt_tokens),
+            completion_tokens: u64::from(completion_tokens),
+            cost: cost.unwrap_or(0.0),
+        });
+    if let Some(message_id) = &response.message_id {
+        usage.messages.insert(
+            message_id.clone(),
+            MessageUsage {
+                prompt_tokens,
+                completion_tokens,
+                estimated_cost: cost,
+            },
+        );
+    }
+    if let Err(e) = store::save(STATE_FILE, &usage) {
+        tracing::warn!("Failed to save token usage: {}", e);
+    }
+    response
+}
+
+/// Fill in the token counts and cost of stored answers, when the backend didn't.
+pub fn annotate(messages: &mut [Message]) {
+    let usage: UsageStore = store::load(STATE_FILE);
+    for message in messages {
+        let Some(recorded) = usage.messages.get(&message.id) else {
+            continue;
+        };
+        message.prompt_tokens = message.prompt_tokens.or(Some(recorded.prompt_tokens));
+        message.completion_tokens = message
+            .completion_tokens
+            .or(Some(recorded.completion_tokens));
+        message.estimated_cost = message.estimated_cost.or(recorded.estimated_cost);
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Get token usage and estimated spend over a period, per provider and per day
+#[tauri::command]
+pub async fn get_usage_stats(period: UsagePeriod) -> Result<UsageStats, String> {
+    let today = chrono::Local::now().date_naive();
+    let since = match period {
+        UsagePeriod::Today => Some(today),
+        UsagePeriod::Week => Some(today - chrono::Days::new(6)),
+        UsagePeriod::Month => Some(today - chrono::Days::new(29)),
+        UsagePeriod::Year => Some(today - chrono::Days::new(364)),
+        UsagePeriod::All => None,
+    }
+    .map(|day| day.format("%Y-%m-%d").to_string());
+
+    let usage: UsageStore = store::load(STATE_FILE);
+    let mut total = UsageTotals::default();
+    let mut by_day = BTreeMap::new();
+    let mut by_model: BTreeMap<String, UsageTotals> = BTreeMap::new();
+    for (day, models) in &usage.days {
+        if since.as_ref().is_some_and(|since| day < since) {
+            continue;
+        }
+        let day_total: &mut UsageTotals = by_day.entry(day.clone()).or_default();
+        for (key, totals) in models {
+            day_total.add(totals);
+            total.add(totals);
+            by_model.entry(key.clone()).or_default().add(totals);
+        }
+    }
+
+    let mut providers: BTreeMap<String, ProviderUsage> = BTreeMap::new();
+    for (key, totals) in by_model {
+        let (provider, model) = key.split_once('/').unwrap_or((key.as_str(), ""));
+        let entry = providers
+            .entry(provider.to_string())
+            .or_insert_with(|| ProviderUsage {
+                provider: provider.to_string(),
+                totals: UsageTotals::default(),
+                models: Vec::new(),
+            });
+        entry.totals.add(&totals);
+        entry.models.push(ModelUsage {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            totals,
+        });
+    }
+    let mut by_provider: Vec<ProviderUsage> = providers.into_values().collect();
+    by_provider.sort_by(|a, b| b.totals.cost.total_cmp(&a.totals.cost));
+
+    Ok(UsageStats {
+        period,
+        since,
+        total,
+        by_provider,
+        by_day,
+    })
+}
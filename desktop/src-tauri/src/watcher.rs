@@ -0,0 +1,658 @@
+//! Continuous sync of folders into knowledge bases.
+//!
+//! Folders added with `add_folder` can be kept in sync: a filesystem watcher collects
+//! changes, and once the folder has been quiet for `DEBOUNCE` the batch is applied —
+//! new and modified files are ingested, deleted ones are removed from the knowledge
+//! base. Paths go through the same exclusion filters as dropped files. Each synced
+//! folder tracks the document created for every file, and is fully reconciled when
+//! sync starts so changes made while the app was closed are picked up too. Files whose
+//! modification time changed but whose content hash didn't are not ingested again.
+//! `resync_folder` reconciles a folder once, whether or not it is kept in sync.
+//! Files left out by the exclusion filters are counted by class, with the most recent
+//! ones, both in each `folder-sync` event and in the folder's status.
+
+use crate::commands::{self, AddFolderParams};
+use crate::file_filters::{self, FileClass, WatchFilters};
+use crate::jobs::{self, FileStatus};
+use crate::store::CachedStore;
+use crate::{dedup, files};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Mutex;
+
+/// Quiet period after the last change before a batch is synced.
+const DEBOUNCE: Duration = Duration::from_secs(2);
+/// Skipped files listed in an event or a folder's status.
+const RECENT_SKIPPED: usize = 20;
+
+/// (kb_id, folder)
+type FolderKey = (String, String);
+
+static FOLDERS: CachedStore<Vec<SyncedFolder>> = CachedStore::new("folder_sync.json");
+/// Active watchers. Dropping one stops its sync task.
+static WATCHERS: std::sync::Mutex<BTreeMap<FolderKey, RecommendedWatcher>> =
+    std::sync::Mutex::new(BTreeMap::new());
+/// Held while a folder is synced, so that watcher batches, resyncs and scheduled
+/// rescans of a folder run one at a time.
+static SYNC_LOCKS: std::sync::Mutex<BTreeMap<FolderKey, Arc<Mutex<()>>>> =
+    std::sync::Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SyncedFile {
+    document_id: String,
+    /// Modification time (Unix seconds) when the file was ingested
+    modified: u64,
+    /// BLAKE3 of the content when the file was ingested
+    #[serde(default)]
+    hash: Option<String>,
+}
+
+/// A file left out by the exclusion filters.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedFile {
+    pub path: String,
+    pub class: FileClass,
+}
+
+/// Files left out by the exclusion filters.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SkippedFiles {
+    /// Number of skipped files by class
+    pub counts: BTreeMap<FileClass, usize>,
+    /// Most recently skipped files, oldest first
+    pub recent: Vec<SkippedFile>,
+}
+
+impl SkippedFiles {
+    fn record(&mut self, path: String, class: FileClass) {
+        *self.counts.entry(class).or_default() += 1;
+        self.recent.retain(|f| f.path != path);
+        self.recent.push(SkippedFile { path, class });
+        if self.recent.len() > RECENT_SKIPPED {
+            self.recent.drain(..self.recent.len() - RECENT_SKIPPED);
+        }
+    }
+
+    fn merge(&mut self, other: &SkippedFiles) {
+        for (class, count) in &other.counts {
+            *self.counts.entry(*class).or_default() += count;
+        }
+        for file in &other.recent {
+            self.recent.retain(|f| f.path != file.path);
+            self.recent.push(file.clone());
+        }
+        if self.recent.len() > RECENT_SKIPPED {
+            self.recent.drain(..self.recent.len() - RECENT_SKIPPED);
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SyncedFolder {
+    kb_id: String,
+    path: String,
+    recursive: bool,
+    file_types: Vec<String>,
+    enabled: bool,
+    /// Document created for each file, keyed by path
+    #[serde(default)]
+    documents: BTreeMap<String, SyncedFile>,
+    /// Files left out by the exclusion filters since the folder was added
+    #[serde(default)]
+    skipped: SkippedFiles,
+}
+
+/// A folder added to a knowledge base, and whether it is kept in sync.
+#[derive(Debug, Clone, Serialize)]
+pub struct FolderSync {
+    pub kb_id: String,
+    pub path: String,
+    pub recursive: bool,
+    pub enabled: bool,
+    pub tracked_files: usize,
+    pub skipped: SkippedFiles,
+}
+
+impl From<&SyncedFolder> for FolderSync {
+    fn from(folder: &SyncedFolder) -> Self {
+        Self {
+            kb_id: folder.kb_id.clone(),
+            path: folder.path.clone(),
+            recursive: folder.recursive,
+            enabled: folder.enabled,
+            tracked_files: folder.documents.len(),
+            skipped: folder.skipped.clone(),
+        }
+    }
+}
+
+/// Payload of the `folder-sync` event, sent after each synced batch.
+#[derive(Debug, Clone, Serialize)]
+pub struct FolderSyncEvent {
+    pub kb_id: String,
+    pub path: String,
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    pub failed: usize,
+    /// Files skipped because their content is already in the knowledge base
+    pub duplicates: usize,
+    /// Files left out by the exclusion filters
+    pub skipped: SkippedFiles,
+}
+
+async fn find_folder(kb_id: &str, path: &str) -> Option<SyncedFolder> {
+    FOLDERS
+        .read(|folders| {
+            folders
+                .iter()
+                .find(|f| f.kb_id == kb_id && f.path == path)
+                .cloned()
+        })
+        .await
+}
+
+fn modified(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_secs())
+}
+
+/// Class of a path under the folder: `Accepted` if the file should be in the knowledge
+/// base, the reason it is excluded otherwise. `None` for paths that aren't files of the
+/// folder's types. Excluded files are classified even once gone (e.g. lock files).
+fn classify(folder: &SyncedFolder, filters: &WatchFilters, path: &Path) -> Option<FileClass> {
+    let root = Path::new(&folder.path);
+    if !folder.recursive && path.parent() != Some(root) {
+        return None;
+    }
+    let class = file_filters::classify(filters, root, path);
+    if class != FileClass::Accepted {
+        return Some(class);
+    }
+    let extension = files::extension_of(path);
+    let wanted = folder.file_types.is_empty()
+        || folder
+            .file_types
+            .iter()
+            .any(|t| t.trim_start_matches('.').eq_ignore_ascii_case(&extension));
+    (path.is_file() && wanted && files::is_supported(path)).then_some(FileClass::Accepted)
+}
+
+/// Link untracked files to documents of the same name already in the knowledge base
+/// (e.g. ingested by `add_folder` before sync was enabled), so they aren't ingested twice.
+async fn adopt_existing(folder: &mut SyncedFolder, candidates: &[PathBuf]) {
+    let untracked: Vec<&PathBuf> = candidates
+        .iter()
+        .filter(|p| !folder.documents.contains_key(&p.display().to_string()))
+        .collect();
+    if untracked.is_empty() {
+        return;
+    }
+    let Ok(documents) = commands::list_documents(folder.kb_id.clone()).await else {
+        return;
+    };
+
+    let tracked: BTreeSet<&str> = folder
+        .documents
+        .values()
+        .map(|f| f.document_id.as_str())
+        .collect();
+    let mut by_name: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for document in documents
+        .iter()
+        .filter(|d| !tracked.contains(d.id.as_str()))
+    {
+        by_name
+            .entry(document.filename.as_str())
+            .or_default()
+            .push(document.id.as_str());
+    }
+
+    let mut adopted = Vec::new();
+    for path in untracked {
+        let Some(name) = path.file_name().map(|n| n.to_string_lossy()) else {
+            continue;
+        };
+        // Only unambiguous matches; anything else is ingested again
+        if let (Some([document_id]), Some(modified)) = (
+            by_name.get(name.as_ref()).map(Vec::as_slice),
+            modified(path),
+        ) {
+            adopted.push((
+                path.display().to_string(),
+                SyncedFile {
+                    document_id: document_id.to_string(),
+                    modified,
+                    hash: None,
+                },
+            ));
+        }
+    }
+    folder.documents.extend(adopted);
+}
+
+/// Bring the knowledge base in line with the current state of `paths` under a folder.
+/// A path that no longer exists removes every tracked file at or below it.
+/// Folders without sync enabled are only synced when `force` is set. Syncs of the same
+/// folder wait for each other.
+async fn sync_paths(
+    app: &AppHandle,
+    kb_id: &str,
+    folder_path: &str,
+    paths: BTreeSet<PathBuf>,
+    force: bool,
+) -> Option<FolderSyncEvent> {
+    let lock = SYNC_LOCKS
+        .lock()
+        .unwrap()
+        .entry((kb_id.to_string(), folder_path.to_string()))
+        .or_default()
+        .clone();
+    let _guard = lock.lock().await;
+    let mut folder = find_folder(kb_id, folder_path).await?;
+    if !folder.enabled && !force {
+        return None;
+    }
+    let filters = file_filters::load_filters();
+
+    let mut candidates: Vec<PathBuf> = Vec::new();
+    let mut skipped = SkippedFiles::default();
+    for path in &paths {
+        match classify(&folder, &filters, path) {
+            Some(FileClass::Accepted) => candidates.push(path.clone()),
+            Some(class) => skipped.record(path.display().to_string(), class),
+            None => {}
+        }
+    }
+    adopt_existing(&mut folder, &candidates).await;
+
+    let mut to_ingest: Vec<String> = Vec::new();
+    for path in &candidates {
+        let key = path.display().to_string();
+        let current = modified(path);
+        let Some(tracked) = folder.documents.get_mut(&key) else {
+            to_ingest.push(key);
+            continue;
+        };
+        if Some(tracked.modified) == current {
+            continue;
+        }
+        // Touched without changing the content: only the modification time is updated
+        if let (Some(hash), Some(current)) = (&tracked.hash, current) {
+            if dedup::hash_file(&key).await.as_ref() == Some(hash) {
+                tracked.modified = current;
+                continue;
+            }
+        }
+        to_ingest.push(key);
+    }
+    let to_remove: Vec<String> = folder
+        .documents
+        .keys()
+        .filter(|tracked| {
+            let tracked = Path::new(tracked.as_str());
+            !tracked.is_file() && paths.iter().any(|p| !p.exists() && tracked.starts_with(p))
+        })
+        .cloned()
+        .collect();
+    let changed = !to_ingest.is_empty() || !to_remove.is_empty();
+
+    let mut event = FolderSyncEvent {
+        kb_id: kb_id.to_string(),
+        path: folder_path.to_string(),
+        added: 0,
+        updated: 0,
+        removed: 0,
+        failed: 0,
+        duplicates: 0,
+        skipped,
+    };
+
+    for path in to_remove {
+        let Some(file) = folder.documents.get(&path) else {
+            continue;
+        };
+        match commands::delete_document(kb_id.to_string(), file.document_id.clone()).await {
+            Ok(_) => {
+                folder.documents.remove(&path);
+                event.removed += 1;
+            }
+            Err(e) => tracing::warn!("Failed to remove {} from KB {}: {}", path, kb_id, e),
+        }
+    }
+
+    if !to_ingest.is_empty() {
+        let options = jobs::IngestOptions::default();
+        let job_id = jobs::start_job(app, kb_id.to_string(), to_ingest, options).await;
+        let files = jobs::wait_for_job(&job_id)
+            .await
+            .map(|job| job.files)
+            .unwrap_or_default();
+        for file in files {
+            let document_id = match (file.status, file.document_id, file.duplicate_of) {
+                (FileStatus::Added, Some(document_id), _) => document_id,
+                (FileStatus::Duplicate, _, Some(duplicate_of)) => {
+                    // Saved again without changes: the tracked document is still current
+                    if let Some(tracked) = folder
+                        .documents
+                        .get_mut(&file.path)
+                        .filter(|f| f.document_id == duplicate_of.document_id)
+                    {
+                        tracked.modified = modified(Path::new(&file.path)).unwrap_or_default();
+                        tracked.hash = dedup::hash_file(&file.path).await;
+                    } else {
+                        event.duplicates += 1;
+                    }
+                    continue;
+                }
+                _ => {
+                    event.failed += 1;
+                    continue;
+                }
+            };
+            let synced = SyncedFile {
+                document_id,
+                modified: modified(Path::new(&file.path)).unwrap_or_default(),
+                hash: dedup::hash_file(&file.path).await,
+            };
+            match folder.documents.insert(file.path.clone(), synced) {
+                // The previous version is only removed once the new one is in
+                Some(previous) => {
+                    if let Err(e) =
+                        commands::delete_document(kb_id.to_string(), previous.document_id).await
+                    {
+                        tracing::warn!(
+                            "Failed to remove the previous version of {}: {}",
+                            file.path,
+                            e
+                        );
+                    }
+                    event.updated += 1;
+                }
+                None => event.added += 1,
+            }
+        }
+    }
+
+    FOLDERS
+        .update(|folders| {
+            if let Some(entry) = folders
+                .iter_mut()
+                .find(|f| f.kb_id == kb_id && f.path == folder_path)
+            {
+                entry.documents = folder.documents;
+                entry.skipped.merge(&event.skipped);
+            }
+        })
+        .await;
+    if !changed && event.skipped.is_empty() {
+        return Some(event);
+    }
+
+    tracing::info!(
+        "Synced {} into KB {}: {} added, {} updated, {} removed, {} failed, {} duplicates, \
+         {} skipped",
+        folder_path,
+        kb_id,
+        event.added,
+        event.updated,
+        event.removed,
+        event.failed,
+        event.duplicates,
+        event.skipped.counts.values().sum::<usize>()
+    );
+    let _ = app.emit("folder-sync", &event);
+    Some(event)
+}
+
+/// Sync every file of the folder and every tracked file.
+async fn reconcile(
+    app: &AppHandle,
+    kb_id: &str,
+    folder_path: &str,
+    force: bool,
+) -> Option<FolderSyncEvent> {
+    let folder = find_folder(kb_id, folder_path).await?;
+    let root = PathBuf::from(&folder.path);
+    let (recursive, file_types) = (folder.recursive, folder.file_types.clone());
+    let mut paths: BTreeSet<PathBuf> = tauri::async_runtime::spawn_blocking(move || {
+        files::collect_files(&root, recursive, &file_types)
+    })
+    .await
+    .unwrap_or_default()
+    .into_iter()
+    .collect();
+    paths.extend(folder.documents.keys().map(PathBuf::from));
+    sync_paths(app, kb_id, folder_path, paths, force).await
+}
+
+/// Start watching a folder and reconcile it, replacing any previous watcher.
+fn start_watching(app: &AppHandle, folder: &SyncedFolder) -> notify::Result<()> {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<PathBuf>();
+    let mut watcher =
+        notify::recommended_watcher(move |result: notify::Result<notify::Event>| match result {
+            Ok(event) if !event.kind.is_access() => {
+                for path in event.paths {
+                    let _ = tx.send(path);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Folder watcher error: {}", e),
+        })?;
+    let mode = if folder.recursive {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    watcher.watch(Path::new(&folder.path), mode)?;
+    WATCHERS
+        .lock()
+        .unwrap()
+        .insert((folder.kb_id.clone(), folder.path.clone()), watcher);
+
+    let app = app.clone();
+    let (kb_id, path) = (folder.kb_id.clone(), folder.path.clone());
+    tauri::async_runtime::spawn(async move {
+        reconcile(&app, &kb_id, &path, false).await;
+        // Ends when the watcher, which owns the sender, is dropped
+        while let Some(first) = rx.recv().await {
+            let mut batch = BTreeSet::from([first]);
+            loop {
+                match tokio::time::timeout(DEBOUNCE, rx.recv()).await {
+                    Ok(Some(path)) => {
+                        batch.insert(path);
+                    }
+                    Ok(None) => return,
+                    Err(_) => break,
+                }
+            }
+            sync_paths(&app, &kb_id, &path, batch, false).await;
+        }
+    });
+    tracing::info!("Watching {} for KB {}", folder.path, folder.kb_id);
+    Ok(())
+}
+
+fn stop_watching(kb_id: &str, path: &str) {
+    WATCHERS
+        .lock()
+        .unwrap()
+        .remove(&(kb_id.to_string(), path.to_string()));
+}
+
+/// Remember a folder added with `add_folder`, so sync can reuse its options.
+pub async fn remember_folder(params: &AddFolderParams) {
+    FOLDERS
+        .update(|folders| {
+            match folders
+                .iter_mut()
+                .find(|f| f.kb_id == params.kb_id && f.path == params.folder_path)
+            {
+                Some(folder) => {
+                    folder.recursive = params.recursive;
+                    folder.file_types = params.file_types.clone();
+                }
+                None => folders.push(SyncedFolder {
+                    kb_id: params.kb_id.clone(),
+                    path: params.folder_path.clone(),
+                    recursive: params.recursive,
+                    file_types: params.file_types.clone(),
+                    enabled: false,
+                    documents: BTreeMap::new(),
+                    skipped: SkippedFiles::default(),
+                }),
+            }
+        })
+        .await;
+}
+
+/// Bring a knowledge base in line with a folder once, whether or not it is kept in sync:
+/// new and modified files are ingested, deleted ones removed. Folders not added before
+/// are tracked recursively with every supported file type.
+pub async fn rescan_folder(
+    app: &AppHandle,
+    kb_id: &str,
+    path: &str,
+) -> Result<FolderSyncEvent, String> {
+    if !Path::new(path).is_dir() {
+        return Err(format!("Invalid folder path: {}", path));
+    }
+    FOLDERS
+        .update(|folders| {
+            if !folders.iter().any(|f| f.kb_id == kb_id && f.path == path) {
+                folders.push(SyncedFolder {
+                    kb_id: kb_id.to_string(),
+                    path: path.to_string(),
+                    recursive: true,
+                    file_types: Vec::new(),
+                    enabled: false,
+                    documents: BTreeMap::new(),
+                    skipped: SkippedFiles::default(),
+                });
+            }
+        })
+        .await;
+    reconcile(app, kb_id, path, true)
+        .await
+        .ok_or_else(|| format!("Unknown folder: {}", path))
+}
+
+/// Forget the folders of a deleted knowledge base.
+pub async fn forget_kb(kb_id: &str) {
+    WATCHERS.lock().unwrap().retain(|(kb, _), _| kb != kb_id);
+    SYNC_LOCKS.lock().unwrap().retain(|(kb, _), _| kb != kb_id);
+    FOLDERS
+        .update(|folders| folders.retain(|f| f.kb_id != kb_id))
+        .await;
+}
+
+/// Resume watching the synced folders. Called once the backend is up.
+pub async fn start_folder_sync(app: &AppHandle) {
+    let folders: Vec<SyncedFolder> = FOLDERS
+        .read(|folders| folders.iter().filter(|f| f.enabled).cloned().collect())
+        .await;
+    for folder in folders {
+        if let Err(e) = start_watching(app, &folder) {
+            tracing::warn!("Failed to watch {}: {}", folder.path, e);
+        }
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Keep a folder in sync with a knowledge base
+#[tauri::command]
+pub async fn enable_folder_sync(
+    app: AppHandle,
+    kb_id: String,
+    path: String,
+) -> Result<FolderSync, String> {
+    if !Path::new(&path).is_dir() {
+        return Err(format!("Invalid folder path: {}", path));
+    }
+    let folder = FOLDERS
+        .update(|folders| {
+            let index = match folders
+                .iter()
+                .position(|f| f.kb_id == kb_id && f.path == path)
+            {
+                Some(index) => index,
+                None => {
+                    folders.push(SyncedFolder {
+                        kb_id: kb_id.clone(),
+                        path: path.clone(),
+                        recursive: true,
+                        file_types: Vec::new(),
+                        enabled: false,
+                        documents: BTreeMap::new(),
+                        skipped: SkippedFiles::default(),
+                    });
+                    folders.len() - 1
+                }
+            };
+            folders[index].enabled = true;
+            folders[index].clone()
+        })
+        .await;
+
+    start_watching(&app, &folder).map_err(|e| format!("Failed to watch {}: {}", path, e))?;
+    Ok(FolderSync::from(&folder))
+}
+
+/// Stop syncing a folder; documents already ingested stay in the knowledge base
+#[tauri::command]
+pub async fn disable_folder_sync(kb_id: String, path: String) -> Result<bool, String> {
+    stop_watching(&kb_id, &path);
+    Ok(FOLDERS
+        .update(|folders| {
+            match folders
+                .iter_mut()
+                .find(|f| f.kb_id == kb_id && f.path == path && f.enabled)
+            {
+                Some(folder) => {
+                    folder.enabled = false;
+                    true
+                }
+                None => false,
+            }
+        })
+        .await)
+}
+
+/// Re-ingest the files of a folder that changed since it was added
+///
+/// Files are compared by modification time and content hash; new and changed files are
+/// ingested and deleted ones removed from the knowledge base.
+#[tauri::command]
+pub async fn resync_folder(
+    app: AppHandle,
+    kb_id: String,
+    folder_path: String,
+) -> Result<FolderSyncEvent, String> {
+    rescan_folder(&app, &kb_id, &folder_path).await
+}
+
+/// List the folders added to knowledge bases and their sync state
+#[tauri::command]
+pub async fn list_synced_folders(kb_id: Option<String>) -> Result<Vec<FolderSync>, String> {
+    Ok(FOLDERS
+        .read(|folders| {
+            folders
+                .iter()
+                .filter(|f| kb_id.as_ref().is_none_or(|kb_id| &f.kb_id == kb_id))
+                .map(FolderSync::from)
+                .collect()
+        })
+        .await)
+}
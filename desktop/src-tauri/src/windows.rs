@@ -0,0 +1,133 @@
+//! Additional windows bound to a conversation.
+//!
+//! Each conversation window shows a single conversation, so chats against different
+//! knowledge bases can sit side by side. Conversation-scoped events (e.g. streamed
+//! answer tokens) are delivered to the main window and to the windows bound to that
+//! conversation only, instead of being broadcast to every window.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tauri::{
+    AppHandle, Emitter, EventTarget, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder,
+};
+
+const MAIN_LABEL: &str = "main";
+const LABEL_PREFIX: &str = "conversation-";
+
+/// Conversation bound to each open conversation window, keyed by window label.
+static BOUND: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversationWindow {
+    pub label: String,
+    pub conversation_id: String,
+}
+
+/// Window labels only allow alphanumerics, `-`, `/`, `:` and `_`.
+fn window_label(conv_id: &str) -> String {
+    let id: String = conv_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}{}", LABEL_PREFIX, id)
+}
+
+/// Emit a conversation-scoped event to the main window and the windows bound to the
+/// conversation.
+pub fn emit_to_conversation<S: Serialize + Clone>(
+    app: &AppHandle,
+    conv_id: &str,
+    event: &str,
+    payload: S,
+) {
+    let labels: Vec<String> = BOUND
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, bound)| bound.as_str() == conv_id)
+        .map(|(label, _)| label.clone())
+        .collect();
+    let result = app.emit_filter(event, payload, |target| match target {
+        EventTarget::WebviewWindow { label }
+        | EventTarget::Webview { label }
+        | EventTarget::Window { label } => label == MAIN_LABEL || labels.contains(label),
+        _ => false,
+    });
+    if let Err(e) = result {
+        tracing::warn!("Failed to emit {}: {}", event, e);
+    }
+}
+
+/// Forget a conversation window once it has been destroyed.
+pub fn forget(label: &str) {
+    if BOUND.lock().unwrap().remove(label).is_some() {
+        tracing::debug!("Closed conversation window {}", label);
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Open a conversation in its own window, or focus the window already showing it
+#[tauri::command]
+pub async fn open_conversation_window(
+    app: AppHandle,
+    conv_id: String,
+    title: Option<String>,
+) -> Result<ConversationWindow, String> {
+    let label = window_label(&conv_id);
+    if let Some(window) = app.get_webview_window(&label) {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    } else {
+        let title = match title.filter(|t| !t.trim().is_empty()) {
+            Some(title) => format!("RAGKIT - {}", title),
+            None => "RAGKIT - Conversation".to_string(),
+        };
+        WebviewWindowBuilder::new(
+            &app,
+            &label,
+            WebviewUrl::App(format!("index.html#/conversation/{}", conv_id).into()),
+        )
+        .title(title)
+        .inner_size(800.0, 700.0)
+        .min_inner_size(480.0, 400.0)
+        .build()
+        .map_err(|e| format!("Failed to open conversation window: {}", e))?;
+    }
+
+    BOUND.lock().unwrap().insert(label.clone(), conv_id.clone());
+    Ok(ConversationWindow {
+        label,
+        conversation_id: conv_id,
+    })
+}
+
+/// List the open conversation windows
+#[tauri::command]
+pub async fn list_conversation_windows() -> Result<Vec<ConversationWindow>, String> {
+    Ok(BOUND
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(label, conv_id)| ConversationWindow {
+            label: label.clone(),
+            conversation_id: conv_id.clone(),
+        })
+        .collect())
+}
+
+/// Get the conversation bound to the calling window (`None` for the main window)
+#[tauri::command]
+pub async fn get_window_conversation(window: WebviewWindow) -> Result<Option<String>, String> {
+    Ok(BOUND.lock().unwrap().get(window.label()).cloned())
+}
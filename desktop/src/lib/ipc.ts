@@ -5,6 +5,7 @@
  */
 
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { open } from "@tauri-apps/plugin-dialog";
 
 // Response types
@@ -26,6 +27,53 @@ interface AddFolderResponse {
   total_processed: number;
 }
 
+interface IngestionProgressEvent {
+  job_id: string;
+  kb_id: string;
+  status: "queued" | "running" | "completed" | "failed";
+  processed: number;
+  total: number;
+  percent: number;
+  current_file: string | null;
+  file: { path: string; status: "added" | "failed"; document_id: string | null; error: string | null } | null;
+  result: AddFolderResponse | null;
+  error: string | null;
+}
+
+/**
+ * Start an ingestion job and resolve with its summary once the job has finished.
+ */
+async function waitForIngestionJob(
+  start: () => Promise<string>,
+  onProgress?: (event: IngestionProgressEvent) => void
+): Promise<AddFolderResponse> {
+  let jobId: string | null = null;
+  const pending: IngestionProgressEvent[] = [];
+  let resolveResult: (result: AddFolderResponse) => void = () => {};
+  const finished = new Promise<AddFolderResponse>((resolve) => {
+    resolveResult = resolve;
+  });
+
+  const handle = (event: IngestionProgressEvent) => {
+    onProgress?.(event);
+    if (event.result) resolveResult(event.result);
+  };
+
+  // Events can arrive before `start` resolves with the job id.
+  const unlisten = await listen<IngestionProgressEvent>("ingestion-progress", ({ payload }) => {
+    if (jobId === null) pending.push(payload);
+    else if (payload.job_id === jobId) handle(payload);
+  });
+
+  try {
+    jobId = await start();
+    pending.filter((event) => event.job_id === jobId).forEach(handle);
+    return await finished;
+  } finally {
+    unlisten();
+  }
+}
+
 interface FolderValidationStats {
   files: number;
   size_mb: number;
@@ -57,6 +105,48 @@ interface KnowledgeBase {
   updated_at: string;
 }
 
+interface KbDocument {
+  id: string;
+  filename: string;
+  size: number | null;
+  chunk_count: number;
+  ingested_at: string;
+  status: string;
+}
+
+interface SentenceVerification {
+  text: string;
+  start: number;
+  end: number;
+  verdict: "supported" | "partially_supported" | "unsupported";
+  supporting_sources: number[];
+  explanation: string | null;
+}
+
+interface AnswerVerification {
+  message_id: string;
+  sentences: SentenceVerification[];
+  supported_ratio: number;
+}
+
+type BackendFeature =
+  | "query_stream"
+  | "query_cancel"
+  | "answer_verification"
+  | "document_upload"
+  | "document_content"
+  | "document_delete"
+  | "reembedding"
+  | "source_context"
+  | "graceful_shutdown";
+
+interface BackendCapabilities {
+  backend_version: string | null;
+  features: Partial<Record<BackendFeature, boolean>>;
+  endpoint_count: number;
+  checked_at: string;
+}
+
 interface Conversation {
   id: string;
   kb_id: string | null;
@@ -209,17 +299,41 @@ export const ipc = {
     return invoke<boolean>("delete_knowledge_base", { kbId });
   },
 
-  async addDocuments(kbId: string, paths: string[]): Promise<void> {
-    return invoke("add_documents", { kbId, paths });
+  async listDocuments(kbId: string): Promise<KbDocument[]> {
+    return invoke<KbDocument[]>("list_documents", { kbId });
   },
 
-  async addFolder(params: {
-    kbId: string;
-    folderPath: string;
-    recursive: boolean;
-    fileTypes: string[];
-  }): Promise<AddFolderResponse> {
-    return invoke<AddFolderResponse>("add_folder", { params });
+  async deleteDocument(kbId: string, docId: string): Promise<boolean> {
+    return invoke<boolean>("delete_document", { kbId, docId });
+  },
+
+  async addDocuments(
+    kbId: string,
+    paths: string[],
+    onProgress?: (event: IngestionProgressEvent) => void
+  ): Promise<AddFolderResponse> {
+    return waitForIngestionJob(() => invoke<string>("add_documents", { kbId, paths }), onProgress);
+  },
+
+  async uploadDocument(kbId: string, filename: string, data: Blob | ArrayBuffer): Promise<string> {
+    const buffer = data instanceof Blob ? await data.arrayBuffer() : data;
+    return invoke<string>("upload_document", {
+      kbId,
+      filename,
+      bytes: Array.from(new Uint8Array(buffer)),
+    });
+  },
+
+  async addFolder(
+    params: {
+      kbId: string;
+      folderPath: string;
+      recursive: boolean;
+      fileTypes: string[];
+    },
+    onProgress?: (event: IngestionProgressEvent) => void
+  ): Promise<AddFolderResponse> {
+    return waitForIngestionJob(() => invoke<string>("add_folder", { params }), onProgress);
   },
 
   async validateFolder(path: string): Promise<FolderValidationResult> {
@@ -252,6 +366,10 @@ export const ipc = {
     return invoke<QueryResponse>("query", { params });
   },
 
+  async verifyAnswer(messageId: string): Promise<AnswerVerification> {
+    return invoke<AnswerVerification>("verify_answer", { messageId });
+  },
+
   // Settings
   async getSettings(): Promise<Settings> {
     return invoke<Settings>("get_settings");
@@ -286,6 +404,17 @@ export const ipc = {
     await invoke("clear_logs");
   },
 
+  async queryLogs(query: {
+    level?: string;
+    target?: string;
+    module?: string;
+    request_id?: string;
+    contains?: string;
+    limit?: number;
+  } = {}): Promise<any[]> {
+    return invoke("query_logs", { query });
+  },
+
   // File dialogs (via Tauri)
   async selectFiles(filters?: { name: string; extensions: string[] }[]): Promise<string[] | null> {
     const result = await open({
@@ -348,6 +477,11 @@ export const ipc = {
   async detectEnvironment(): Promise<EnvironmentDetection> {
     return invoke<EnvironmentDetection>("detect_environment");
   },
+
+  // Backend
+  async getBackendCapabilities(): Promise<BackendCapabilities | null> {
+    return invoke<BackendCapabilities | null>("get_backend_capabilities");
+  },
 };
 
 // Export types
@@ -355,11 +489,15 @@ export type {
   HealthCheckResponse,
   QueryResponse,
   AddFolderResponse,
+  IngestionProgressEvent,
   FolderValidationResult,
   Source,
   KnowledgeBase,
+  KbDocument,
   Conversation,
   Message,
+  SentenceVerification,
+  AnswerVerification,
   Settings,
   OllamaStatus,
   OllamaModel,
@@ -369,4 +507,6 @@ export type {
   WizardAnswers,
   WizardProfileResponse,
   EnvironmentDetection,
+  BackendFeature,
+  BackendCapabilities,
 };
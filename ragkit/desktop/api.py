@@ -41,6 +41,8 @@ class CreateKnowledgeBaseRequest(BaseModel):
 
 class AddDocumentsRequest(BaseModel):
     paths: list[str]
+    # Clients ingesting files one request at a time refresh the index once at the end
+    refresh_index: bool = True
 
 
 class AddFolderRequest(BaseModel):
@@ -259,12 +261,12 @@ async def add_documents(request: Request, kb_id: str, body: AddDocumentsRequest)
     settings = state.get_settings()
 
     # Add each document
-    added = []
+    added: list[str] = []
+    failed: list[dict[str, str | None]] = []
     for path in body.paths:
         doc = None
         try:
             doc = await state.kb_manager.add_document(kb_id, path)
-            added.append(doc.id)
             chunk_count = await _ingest_document(
                 path=Path(path),
                 document_id=doc.id,
@@ -279,8 +281,12 @@ async def add_documents(request: Request, kb_id: str, body: AddDocumentsRequest)
                 status="indexed",
                 chunk_count=chunk_count,
             )
+            added.append(doc.id)
         except (FileNotFoundError, ValueError, RuntimeError) as e:
             logger.warning(f"Failed to ingest document {path}: {e}")
+            failed.append(
+                {"path": path, "error": str(e), "document_id": doc.id if doc else None}
+            )
             if doc is not None:
                 await state.kb_manager.update_document_status(
                     doc.id,
@@ -289,14 +295,29 @@ async def add_documents(request: Request, kb_id: str, body: AddDocumentsRequest)
                 )
 
     await state.kb_manager.update_stats(kb_id)
+    if body.refresh_index:
+        await _refresh_lexical_index(state, kb_id)
+
+    return {"added": added, "failed": failed}
+
+
+@router.post("/knowledge-bases/{kb_id}/refresh-index")
+async def refresh_index(request: Request, kb_id: str) -> bool:
+    """Rebuild the lexical index of a knowledge base after documents were added."""
+    state = get_state(request)
+    if not await state.kb_manager.get(kb_id):
+        raise HTTPException(status_code=404, detail="Knowledge base not found")
+    await _refresh_lexical_index(state, kb_id)
+    return True
+
+
+async def _refresh_lexical_index(state: Any, kb_id: str) -> None:
     try:
         orchestrator = await state.get_orchestrator(kb_id)
         await orchestrator.retrieval.refresh_lexical_index()
     except Exception:  # noqa: BLE001
         pass
 
-    return {"added": added}
-
 
 @router.post("/knowledge-bases/{kb_id}/folders")
 async def add_folder(request: Request, kb_id: str, body: AddFolderRequest) -> dict[str, Any]:
@@ -334,7 +355,6 @@ async def add_folder(request: Request, kb_id: str, body: AddFolderRequest) -> di
         doc = None
         try:
             doc = await state.kb_manager.add_document(kb_id, str(file_path))
-            added.append(doc.id)
             chunk_count = await _ingest_document(
                 path=file_path,
                 document_id=doc.id,
@@ -349,6 +369,7 @@ async def add_folder(request: Request, kb_id: str, body: AddFolderRequest) -> di
                 status="indexed",
                 chunk_count=chunk_count,
             )
+            added.append(doc.id)
         except Exception as e:  # noqa: BLE001
             logger.warning(f"Failed to ingest document {file_path}: {e}")
             failed.append({"path": str(file_path), "error": str(e)})
@@ -360,11 +381,7 @@ async def add_folder(request: Request, kb_id: str, body: AddFolderRequest) -> di
                 )
 
     await state.kb_manager.update_stats(kb_id)
-    try:
-        orchestrator = await state.get_orchestrator(kb_id)
-        await orchestrator.retrieval.refresh_lexical_index()
-    except Exception:  # noqa: BLE001
-        pass
+    await _refresh_lexical_index(state, kb_id)
 
     return {
         "added": added,
@@ -464,18 +481,19 @@ async def query(request: Request, body: QueryRequest) -> dict[str, Any]:
         if msg.role in {"user", "assistant"}
     ]
 
-    # Add user message to conversation
+    orchestrator = await state.get_orchestrator(body.kb_id)
+    start = time.perf_counter()
+    result = await orchestrator.process(body.question, history)
+    latency_ms = int((time.perf_counter() - start) * 1000)
+
+    # Save the turn only once answered, so a failed attempt retried by the client (or on
+    # another provider) leaves no orphan question in the conversation
     await state.conversation_manager.add_message(
         conversation_id=body.conversation_id,
         role="user",
         content=body.question,
     )
 
-    orchestrator = await state.get_orchestrator(body.kb_id)
-    start = time.perf_counter()
-    result = await orchestrator.process(body.question, history)
-    latency_ms = int((time.perf_counter() - start) * 1000)
-
     sources_payload = []
     for item in result.context or []:
         sources_payload.append(
@@ -9,10 +9,12 @@ from __future__ import annotations
 import argparse
 import asyncio
 import logging
+import os
 import signal
 import sys
 from collections.abc import AsyncGenerator
 from contextlib import asynccontextmanager
+from pathlib import Path
 from typing import Any
 
 import uvicorn
@@ -44,8 +46,9 @@ async def lifespan(app: FastAPI) -> AsyncGenerator[None, None]:
 
     logger.info("Starting RAGKIT Desktop backend...")
 
-    # Initialize app state
-    app_state = AppState()
+    # Initialize app state (the desktop shell overrides the data dir in guest mode)
+    data_dir = os.environ.get("RAGKIT_DATA_DIR")
+    app_state = AppState(Path(data_dir) if data_dir else None)
     await app_state.initialize()
     app.state.app_state = app_state
 